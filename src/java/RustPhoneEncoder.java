@@ -0,0 +1,64 @@
+import java.io.BufferedReader;
+import java.io.FileReader;
+import java.io.IOException;
+import java.io.PrintStream;
+
+import static java.nio.charset.StandardCharsets.US_ASCII;
+
+/**
+ * Runs the Rust phone encoder through its JNI bindings (see src/rust/phone_encoder_jni).
+ * <p>
+ * The native library must be on the {@code java.library.path}.
+ */
+public class RustPhoneEncoder {
+
+    static {
+        System.loadLibrary("phone_encoder_jni");
+    }
+
+    /**
+     * Load the dictionary used by {@link #encode(String)} from the given words file.
+     */
+    public static native void loadDictionary(String path);
+
+    /**
+     * @return all encodings of the phone number, each as the words (or digits) separated by spaces
+     */
+    public static native String[] encode(String number);
+
+    /**
+     * Load a dictionary from the given words file, or share the one loaded already from a file with the same
+     * contents, e.g. by another thread.
+     *
+     * @return the handle of the dictionary, to give to {@link #encodeWith(long, String)}, and to
+     * {@link #releaseDictionary(long)} once done with it
+     */
+    public static native long acquireDictionary(String path);
+
+    /**
+     * Release a dictionary acquired with {@link #acquireDictionary(String)}, which is unloaded once released as
+     * many times as it was acquired.
+     */
+    public static native void releaseDictionary(long dictionary);
+
+    /**
+     * @return all encodings of the phone number with the given dictionary, like {@link #encode(String)}
+     */
+    public static native String[] encodeWith(long dictionary, String number);
+
+    public static void main(String[] args) throws IOException {
+        String words = args.length > 0 ? args[0] : "tests/words.txt";
+        String numbers = args.length > 1 ? args[1] : "tests/numbers.txt";
+        loadDictionary(words);
+        PrintStream out = new PrintStream(System.out, false, US_ASCII);
+        try (BufferedReader reader = new BufferedReader(new FileReader(numbers, US_ASCII))) {
+            String number;
+            while ((number = reader.readLine()) != null) {
+                for (String solution : encode(number)) {
+                    out.println(solution.isEmpty() ? number + ":" : number + ": " + solution);
+                }
+            }
+        }
+        out.flush();
+    }
+}
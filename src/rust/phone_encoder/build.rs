@@ -0,0 +1,46 @@
+use std::env;
+use std::fs;
+use std::path::Path;
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    embed_words();
+    #[cfg(feature = "grpc")]
+    generate_grpc_service();
+}
+
+/// Compiles the words file given in the `PHONE_ENCODER_EMBED_WORDS` environment variable
+/// (relative to this directory) into the program, see `search::EMBEDDED_WORDS`.
+fn embed_words() {
+    println!("cargo:rustc-check-cfg=cfg(embedded_words)");
+    println!("cargo:rerun-if-env-changed=PHONE_ENCODER_EMBED_WORDS");
+    let Some(words_file) = env::var_os("PHONE_ENCODER_EMBED_WORDS") else { return };
+    println!("cargo:rerun-if-changed={}", Path::new(&words_file).display());
+    let out_dir = env::var_os("OUT_DIR").expect("OUT_DIR is set by cargo");
+    fs::copy(&words_file, Path::new(&out_dir).join("embedded_words.txt"))
+        .unwrap_or_else(|e| panic!("cannot embed words file {:?}: {}", words_file, e));
+    println!("cargo:rustc-cfg=embedded_words");
+}
+
+/// Generates the code for the service described in `proto/phone_encoding.proto`.
+///
+/// The messages are defined by hand in `src/grpc.rs`, so `protoc` is not required.
+#[cfg(feature = "grpc")]
+fn generate_grpc_service() {
+    use tonic_build::manual::{Builder, Method, Service};
+
+    let service = Service::builder()
+        .name("PhoneEncoding")
+        .package("phone_encoding")
+        .method(Method::builder()
+            .name("encode")
+            .route_name("Encode")
+            .input_type("crate::grpc::Number")
+            .output_type("crate::grpc::Solution")
+            .codec_path("tonic::codec::ProstCodec")
+            .client_streaming()
+            .server_streaming()
+            .build())
+        .build();
+    Builder::new().compile(&[service]);
+}
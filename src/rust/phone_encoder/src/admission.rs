@@ -0,0 +1,209 @@
+//! Bounding the work of the servers (`--resp`, `grpc-serve`), so that a few expensive requests
+//! (e.g. dense numbers of 50 digits) cannot starve all the others: at most so many numbers
+//! are encoded at the same time, at most so many more wait for their turn, the others being
+//! refused at once, and each request is abandoned after a timeout.
+
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+/// The queue depth when only the number of concurrent requests is limited.
+pub const DEFAULT_QUEUE_DEPTH: usize = 64;
+
+/// The limits on the requests of a server, none by default.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ServerLimits {
+    /// how many numbers are encoded at the same time, at most.
+    pub max_concurrent: Option<usize>,
+    /// how many numbers may wait for one of the `max_concurrent` to be encoded, at most.
+    pub queue_depth: usize,
+    /// the time after which a request, waiting or being encoded, is abandoned.
+    pub request_timeout: Option<Duration>,
+}
+
+/// Why a request was not admitted.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Refused {
+    /// as many requests as allowed were waiting already.
+    Saturated,
+    /// the deadline of the request passed while it was waiting.
+    TimedOut,
+}
+
+/// Admits the requests of a server within its [`ServerLimits`], shared by its clones.
+#[derive(Clone)]
+pub struct Admission {
+    inner: Arc<AdmissionInner>,
+}
+
+struct AdmissionInner {
+    limits: ServerLimits,
+    state: Mutex<State>,
+    /// notified whenever a request is done.
+    done: Condvar,
+}
+
+#[derive(Default)]
+struct State {
+    running: usize,
+    waiting: usize,
+}
+
+impl Admission {
+    pub fn new(limits: ServerLimits) -> Admission {
+        Admission {
+            inner: Arc::new(AdmissionInner { limits, state: Mutex::new(State::default()), done: Condvar::new() }),
+        }
+    }
+
+    pub fn limits(&self) -> &ServerLimits {
+        &self.inner.limits
+    }
+
+    /// The deadline of a request received now, if requests time out.
+    pub fn deadline(&self) -> Option<Instant> {
+        self.inner.limits.request_timeout.map(|timeout| Instant::now() + timeout)
+    }
+
+    /// Waits for the turn of a request, until its `deadline`, returning a permit to hold
+    /// while it is encoded, or refuses it at once if the queue is full.
+    pub fn admit(&self, deadline: Option<Instant>) -> Result<Permit, Refused> {
+        let Some(max_concurrent) = self.inner.limits.max_concurrent else {
+            return Ok(Permit { admission: None });
+        };
+        let mut state = self.lock();
+        if state.running >= max_concurrent {
+            if state.waiting >= self.inner.limits.queue_depth {
+                return Err(Refused::Saturated);
+            }
+            state.waiting += 1;
+            while state.running >= max_concurrent {
+                state = match deadline {
+                    None => self.inner.done.wait(state).unwrap_or_else(|e| e.into_inner()),
+                    Some(deadline) => {
+                        let timeout = deadline.saturating_duration_since(Instant::now());
+                        if timeout.is_zero() {
+                            state.waiting -= 1;
+                            return Err(Refused::TimedOut);
+                        }
+                        self.inner.done.wait_timeout(state, timeout).unwrap_or_else(|e| e.into_inner()).0
+                    }
+                };
+            }
+            state.waiting -= 1;
+        }
+        state.running += 1;
+        Ok(Permit { admission: Some(self.clone()) })
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State> {
+        // the counts are consistent even if a thread panicked while holding the lock
+        self.inner.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// The turn of an admitted request, given back to the next one when dropped.
+pub struct Permit {
+    admission: Option<Admission>,
+}
+
+impl Drop for Permit {
+    fn drop(&mut self) {
+        if let Some(admission) = &self.admission {
+            admission.lock().running -= 1;
+            admission.inner.done.notify_one();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc;
+    use std::thread;
+
+    use super::*;
+
+    fn limited(max_concurrent: usize, queue_depth: usize) -> Admission {
+        Admission::new(ServerLimits { max_concurrent: Some(max_concurrent), queue_depth, request_timeout: None })
+    }
+
+    /// The requests encoded and waiting.
+    fn counts(admission: &Admission) -> (usize, usize) {
+        let state = admission.lock();
+        (state.running, state.waiting)
+    }
+
+    /// Waits until as many requests as expected are waiting.
+    fn until_waiting(admission: &Admission, waiting: usize) {
+        while counts(admission).1 < waiting {
+            thread::yield_now();
+        }
+    }
+
+    #[test]
+    fn admits_everything_without_limits() {
+        let admission = Admission::new(ServerLimits::default());
+        let permits: Vec<_> = (0..100).map(|_| admission.admit(None).unwrap()).collect();
+        assert_eq!(counts(&admission), (0, 0));
+        drop(permits);
+    }
+
+    #[test]
+    fn refuses_requests_once_the_queue_is_full() {
+        let admission = limited(2, 1);
+        let permits = [admission.admit(None).unwrap(), admission.admit(None).unwrap()];
+        assert_eq!(counts(&admission), (2, 0));
+        thread::scope(|scope| {
+            let waiter = scope.spawn(|| admission.admit(None).map(drop));
+            until_waiting(&admission, 1);
+            assert_eq!(admission.admit(None).err(), Some(Refused::Saturated));
+            assert_eq!(counts(&admission), (2, 1));
+            drop(permits);
+            assert_eq!(waiter.join().unwrap(), Ok(()));
+        });
+        assert_eq!(counts(&admission), (0, 0));
+        // without a queue, refused as soon as all are running
+        let admission = limited(1, 0);
+        let _permit = admission.admit(None).unwrap();
+        assert_eq!(admission.admit(None).err(), Some(Refused::Saturated));
+    }
+
+    #[test]
+    fn times_out_waiting_at_the_deadline() {
+        let admission = limited(1, 1);
+        let _permit = admission.admit(None).unwrap();
+        let start = Instant::now();
+        let timeout = Duration::from_millis(50);
+        assert_eq!(admission.admit(Some(start + timeout)).err(), Some(Refused::TimedOut));
+        assert!(start.elapsed() >= timeout);
+        // the request no longer waits, so that another one may
+        assert_eq!(counts(&admission), (1, 0));
+        assert_eq!(admission.admit(Some(Instant::now())).err(), Some(Refused::TimedOut));
+        assert_eq!(counts(&admission), (1, 0));
+    }
+
+    #[test]
+    fn dropping_a_permit_admits_a_waiting_request() {
+        let admission = limited(1, 4);
+        let permit = admission.admit(None).unwrap();
+        let (admitted, on_admitted) = mpsc::channel();
+        thread::scope(|scope| {
+            for _ in 0..2 {
+                let (admission, admitted) = (&admission, admitted.clone());
+                scope.spawn(move || {
+                    let permit = admission.admit(Some(Instant::now() + Duration::from_secs(60))).unwrap();
+                    admitted.send(()).unwrap();
+                    // the next one waits until this one is done
+                    thread::sleep(Duration::from_millis(10));
+                    drop(permit);
+                });
+            }
+            until_waiting(&admission, 2);
+            assert!(on_admitted.try_recv().is_err());
+            drop(permit);
+            on_admitted.recv().unwrap();
+            on_admitted.recv().unwrap();
+        });
+        assert_eq!(counts(&admission), (0, 0));
+    }
+}
@@ -0,0 +1,275 @@
+//! An Aho-Corasick automaton of the dictionary: it finds every word whose encoding appears
+//! in some digits, wherever it starts, in a single pass over them.
+//!
+//! The automaton is the [`Trie`](crate::trie::Trie) of the encodings, with a failure link
+//! from each node to the node of the longest proper suffix of its digits that is also in
+//! the trie. As there are only ten digits, the failure links are followed once and for all
+//! when the automaton is built, so that each node has a transition for every digit and
+//! matching takes one step per digit.
+//!
+//! Besides finding words anywhere in some digits, the automaton finds all the words a
+//! solution may be made of at once, for the search of [`AhoCorasickDp`].
+
+use alloc::collections::VecDeque;
+use alloc::vec::Vec;
+use core::ops::Range;
+
+use num_bigint::BigUint;
+
+use crate::dp::{search_matches, DpCount};
+use crate::filter::SolutionFilter;
+use crate::search::{EncodeStats, Limits, Lookup, OnRejected, OnSolution, SearchStrategy, Word};
+
+/// No node, for the links to the nodes where a word ends.
+const NONE: u32 = u32::MAX;
+
+/// A dictionary as an Aho-Corasick automaton of the digits of the words' encodings.
+#[derive(Clone, Debug)]
+pub struct AhoCorasick {
+    nodes: Vec<Node>,
+}
+
+#[derive(Clone, Debug)]
+struct Node {
+    /// the next node for each digit: a child in the trie, or else the next node of the
+    /// failure link (`0`, the root, when no encoding goes on with the digit).
+    next: [u32; 10],
+    /// how many digits lead to the node from the root.
+    depth: u32,
+    /// the longest proper suffix of the digits of the node where a word ends, if any.
+    output: u32,
+    words: Vec<Word>,
+}
+
+impl Node {
+    fn new(depth: u32) -> Node {
+        Node { next: [NONE; 10], depth, output: NONE, words: Vec::new() }
+    }
+}
+
+/// Words whose encoding appears in some digits, with the positions of those digits.
+#[derive(Clone, Debug, PartialEq)]
+pub struct KeyMatch<'a> {
+    pub span: Range<usize>,
+    pub words: &'a [Word],
+}
+
+impl AhoCorasick {
+    /// Builds the automaton of the words of a dictionary, given as its entries.
+    pub fn new<'d, I: IntoIterator<Item = (&'d BigUint, &'d Vec<Word>)>>(entries: I) -> AhoCorasick {
+        let mut nodes = alloc::vec![Node::new(0)];
+        for (key, words) in entries {
+            // the key is the encoding with a leading `1`, which is not part of the automaton
+            let key = key.to_radix_be(10);
+            if key.len() < 2 {
+                // no digits to find
+                continue;
+            }
+            let mut node = 0;
+            for digit in &key[1..] {
+                node = match nodes[node].next[*digit as usize] {
+                    NONE => {
+                        nodes.push(Node::new(nodes[node].depth + 1));
+                        let child = nodes.len() - 1;
+                        nodes[node].next[*digit as usize] = child as u32;
+                        child
+                    }
+                    child => child as usize,
+                };
+            }
+            nodes[node].words.extend(words.iter().cloned());
+        }
+
+        // the failure links, breadth first so that the nodes of shorter digits are done first
+        let mut fail = alloc::vec![0u32; nodes.len()];
+        let mut queue = VecDeque::new();
+        for digit in 0..10 {
+            match nodes[0].next[digit] {
+                NONE => nodes[0].next[digit] = 0,
+                child => queue.push_back(child as usize),
+            }
+        }
+        while let Some(node) = queue.pop_front() {
+            let link = fail[node] as usize;
+            nodes[node].output = if nodes[link].words.is_empty() { nodes[link].output } else { link as u32 };
+            for digit in 0..10 {
+                match nodes[node].next[digit] {
+                    NONE => nodes[node].next[digit] = nodes[link].next[digit],
+                    child => {
+                        fail[child as usize] = nodes[link].next[digit];
+                        queue.push_back(child as usize);
+                    }
+                }
+            }
+        }
+        AhoCorasick { nodes }
+    }
+
+    /// The words whose encoding appears in `digits`, in the order their digits end, the
+    /// longest first for the same end. Any character other than a digit ends the matches.
+    pub fn find<'a, 'd>(&'a self, digits: &'d [char]) -> KeyMatches<'a, 'd> {
+        KeyMatches { automaton: self, digits, end: 0, node: 0, pending: NONE }
+    }
+
+    /// The bytes taken by the nodes of the automaton and the vectors of their words (not
+    /// counting the text of the words).
+    pub fn memory_size(&self) -> usize {
+        self.nodes.capacity() * size_of::<Node>()
+            + self.nodes.iter().map(|node| node.words.capacity() * size_of::<Word>()).sum::<usize>()
+    }
+}
+
+impl Lookup for AhoCorasick {
+    fn words(&self, key: &BigUint) -> Option<&[Word]> {
+        let key = key.to_radix_be(10);
+        let mut node = 0;
+        for digit in &key[1..] {
+            let next = self.nodes[node].next[*digit as usize] as usize;
+            // the other transitions go back to a suffix of the digits, which is not as deep
+            if self.nodes[next].depth != self.nodes[node].depth + 1 {
+                return None;
+            }
+            node = next;
+        }
+        Some(self.nodes[node].words.as_slice()).filter(|words| !words.is_empty())
+    }
+
+    fn as_aho_corasick(&self) -> Option<&AhoCorasick> {
+        Some(self)
+    }
+}
+
+/// The search of [`DpCount`], with the words encoding the digits from each position found
+/// by running an [`AhoCorasick`] automaton over the digits once, rather than by looking up
+/// each of their prefixes from each position.
+///
+/// If the dictionary is not an [`AhoCorasick`] automaton, this is the same as [`DpCount`].
+#[derive(Clone, Copy, Debug, Default)]
+pub struct AhoCorasickDp;
+
+impl SearchStrategy for AhoCorasickDp {
+    fn search<D: Lookup + ?Sized, E>(
+        &self,
+        digits: &[char],
+        dict: &D,
+        filter: &dyn SolutionFilter,
+        limits: Limits,
+        on_solution: &mut OnSolution<E>,
+        on_rejected: &mut OnRejected<E>,
+    ) -> Result<EncodeStats, E> {
+        let Some(automaton) = dict.as_aho_corasick() else {
+            return DpCount.search(digits, dict, filter, limits, on_solution, on_rejected);
+        };
+        let mut matches = alloc::vec![Vec::new(); digits.len()];
+        // found longest first for each end, and in the order of their ends, so that the
+        // words from each position come shortest first
+        for found in automaton.find(digits) {
+            matches[found.span.start].push((found.span.end, found.words));
+        }
+        search_matches(digits, &matches, digits.len() as u64, filter, limits, on_solution, on_rejected)
+    }
+}
+
+/// The iterator of [`AhoCorasick::find`].
+#[derive(Clone, Debug)]
+pub struct KeyMatches<'a, 'd> {
+    automaton: &'a AhoCorasick,
+    digits: &'d [char],
+    /// how many digits were read.
+    end: usize,
+    node: u32,
+    /// the next node to report words of, ending where the digits read end.
+    pending: u32,
+}
+
+impl<'a> Iterator for KeyMatches<'a, '_> {
+    type Item = KeyMatch<'a>;
+
+    fn next(&mut self) -> Option<KeyMatch<'a>> {
+        let nodes = &self.automaton.nodes;
+        while self.pending == NONE {
+            let digit = self.digits.get(self.end)?.to_digit(10)?;
+            self.node = nodes[self.node as usize].next[digit as usize];
+            self.end += 1;
+            let node = &nodes[self.node as usize];
+            self.pending = if node.words.is_empty() { node.output } else { self.node };
+        }
+        let node = &nodes[self.pending as usize];
+        self.pending = node.output;
+        Some(KeyMatch { span: self.end - node.depth as usize..self.end, words: &node.words })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::collections::BTreeMap;
+    use alloc::string::String;
+
+    use super::*;
+    use crate::mapping::Mapping;
+    use crate::search::words_from_bytes;
+
+    fn dict() -> BTreeMap<BigUint, Vec<Word>> {
+        words_from_bytes(b"mir\nMix\nTor\nTorf\nfort\nort\n", &Mapping::default()).into_iter().collect()
+    }
+
+    fn texts(automaton: &AhoCorasick, digits: &str) -> Vec<(Range<usize>, String)> {
+        let digits: Vec<char> = digits.chars().collect();
+        let mut matches: Vec<_> = automaton.find(&digits)
+            .flat_map(|m| m.words.iter().map(move |w| (m.span.clone(), w.text.clone())))
+            .collect();
+        matches.sort_by(|(a, x), (b, y)| (a.start, a.end, x).cmp(&(b.start, b.end, y)));
+        matches
+    }
+
+    #[test]
+    fn finds_the_words_anywhere_in_the_digits() {
+        let automaton = AhoCorasick::new(&dict());
+        let found = texts(&automaton, "05624824");
+        let expected = [(1..4, "Mix"), (1..4, "mir"), (4..7, "Tor"), (4..8, "Torf"), (4..8, "fort"), (5..8, "ort")];
+        assert_eq!(found, expected.map(|(span, text)| (span, String::from(text))));
+    }
+
+    #[test]
+    fn finds_overlapping_words_and_suffixes() {
+        // the digits of ort, 824, end those of Tor and Torf, and start those of Tor again
+        let automaton = AhoCorasick::new(&dict());
+        let found = texts(&automaton, "482482");
+        let expected = [(0..3, "Tor"), (0..4, "Torf"), (0..4, "fort"), (1..4, "ort"), (3..6, "Tor")];
+        assert_eq!(found, expected.map(|(span, text)| (span, String::from(text))));
+        assert!(texts(&automaton, "").is_empty());
+        assert!(texts(&automaton, "999").is_empty());
+    }
+
+    #[test]
+    fn finds_the_same_words_as_looking_up_every_substring() {
+        let dict = dict();
+        let automaton = AhoCorasick::new(&dict);
+        let digits = "4824056210784824";
+        let mut expected = Vec::new();
+        for start in 0..digits.len() {
+            for end in start + 1..=digits.len() {
+                let key = word_to_key(&digits[start..end]);
+                for word in dict.get(&key).into_iter().flatten() {
+                    expected.push((start..end, word.text.clone()));
+                }
+            }
+        }
+        expected.sort_by(|(a, x), (b, y)| (a.start, a.end, x).cmp(&(b.start, b.end, y)));
+        assert_eq!(texts(&automaton, digits), expected);
+    }
+
+    #[test]
+    fn looks_up_whole_encodings_only() {
+        let dict = dict();
+        let automaton = AhoCorasick::new(&dict);
+        assert_eq!(automaton.words(&word_to_key("4824")), dict.get(&word_to_key("4824")).map(Vec::as_slice));
+        // no word is encoded by 48, and the last 8 of 4828 goes back to the 8 starting ort
+        assert_eq!(automaton.words(&word_to_key("48")), None);
+        assert_eq!(automaton.words(&word_to_key("4828")), None);
+    }
+
+    fn word_to_key(digits: &str) -> BigUint {
+        digits.chars().fold(BigUint::from(1u32), |key, ch| key * 10u32 + ch.to_digit(10).unwrap())
+    }
+}
@@ -0,0 +1,66 @@
+//! The audit log of the servers (`--audit-log`): a JSON object for each number requested, on
+//! its own line, with when and by whom it was requested, and how the request ended:
+//!
+//! ```json
+//! {"cached":false,"client":"127.0.0.1:51234","command":"ENCODE","millis":0.412,"number":"#f032bd1bacf871d4","outcome":"ok","server":"resp","solutions":2,"time":1760529600123}
+//! ```
+//!
+//! The numbers are written as given, unless redacted with a [`Redaction`].
+
+use std::io::{self, Write};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde_json::json;
+
+use crate::redact::{redacted, Redaction};
+use crate::stats::millis;
+
+/// A request, as written to the [`AuditLog`].
+#[derive(Clone, Copy, Debug)]
+pub struct AuditEntry<'a> {
+    /// `resp`, `grpc` or `kafka`.
+    pub server: &'a str,
+    /// the address of the client, or where the number came from.
+    pub client: Option<&'a str>,
+    pub command: &'a str,
+    /// the number, as requested.
+    pub number: &'a str,
+    /// `ok`, or the code of the error the request ended with.
+    pub outcome: &'a str,
+    pub solutions: usize,
+    /// whether the solutions came from the cache.
+    pub cached: bool,
+    pub duration: Duration,
+}
+
+/// Writes the requests of a server, shared by its threads.
+pub struct AuditLog {
+    out: Mutex<Box<dyn Write + Send>>,
+    redaction: Option<Redaction>,
+}
+
+impl AuditLog {
+    pub fn new<W: Write + Send + 'static>(out: W, redaction: Option<Redaction>) -> AuditLog {
+        AuditLog { out: Mutex::new(Box::new(out)), redaction }
+    }
+
+    /// Writes a request, flushed at once, so that the log is complete whenever the server stops.
+    pub fn record(&self, entry: &AuditEntry) -> io::Result<()> {
+        let time = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+        let line = json!({
+            "time": time.as_millis() as u64,
+            "server": entry.server,
+            "client": entry.client,
+            "command": entry.command,
+            "number": redacted(self.redaction, entry.number),
+            "outcome": entry.outcome,
+            "solutions": entry.solutions,
+            "cached": entry.cached,
+            "millis": millis(entry.duration),
+        });
+        let mut out = self.out.lock().unwrap_or_else(|e| e.into_inner());
+        writeln!(out, "{}", line)?;
+        out.flush()
+    }
+}
@@ -0,0 +1,83 @@
+//! The `bench` command: running the whole encoding several times in the same process,
+//! discarding the output, to see how much the time varies between runs.
+
+use std::io;
+use std::time::Instant;
+
+use phone_encoder::input::{input_files, LineReader};
+use phone_encoder::stats::{millis, Summary, Timings};
+use phone_encoder::{Encoder, FileContext, FileRole, WordsFile};
+
+use crate::cli::{NumberPolicy, Options};
+use crate::run::{configure, number, preload, write_manifest};
+
+pub fn bench(options: &Options, words_files: &[WordsFile], input_file: &str) -> io::Result<()> {
+    // the write time is only measured on request
+    let mut encode_options = options.encode.clone();
+    encode_options.timings = true;
+    // the numbers of all files of a directory or glob are encoded together
+    let input_files = input_files(input_file).in_file(FileRole::Numbers, input_file)?;
+    let (words_files, preloaded) = preload(options, words_files, &input_files)?;
+    write_manifest(options, &words_files, &input_files)?;
+    let load = || Ok::<_, io::Error>(configure(options, Encoder::load_all(&words_files, &options.dict)?)
+        .with_options(encode_options.clone()));
+    let read_numbers = || {
+        let mut numbers = Vec::new();
+        for (input_file, preloaded) in input_files.iter().zip(&preloaded) {
+            let mut lines = LineReader::open_or_preloaded(input_file, preloaded.as_deref())
+                .in_file(FileRole::Numbers, input_file)?
+                .select(&options.selection)
+                .detect_format(&options.number_field)
+                .fail_on_invalid(options.number_policy == NumberPolicy::Strict);
+            while let Some(num) = lines.next_line().in_file(FileRole::Numbers, input_file)? {
+                numbers.push(number(num).to_string());
+            }
+        }
+        Ok::<_, io::Error>(numbers)
+    };
+
+    // with --reuse-input, the dictionary and the numbers are loaded once, before the runs
+    let cached = if options.reuse_input {
+        let start_time = Instant::now();
+        let encoder = load()?;
+        let load_time = start_time.elapsed();
+        let start_time = Instant::now();
+        let numbers = read_numbers()?;
+        eprintln!("dictionary load {:.3} ms, read {:.3} ms (reused by all runs)",
+                  millis(load_time), millis(start_time.elapsed()));
+        Some((encoder, numbers))
+    } else {
+        None
+    };
+
+    let mut totals = Vec::with_capacity(options.runs);
+    for run in 1..=options.runs {
+        let mut timings = Timings::default();
+        let loaded;
+        let (encoder, numbers) = match &cached {
+            Some((encoder, numbers)) => (encoder, numbers),
+            None => {
+                let start_time = Instant::now();
+                let encoder = load()?;
+                timings.load = start_time.elapsed();
+                let start_time = Instant::now();
+                let numbers = read_numbers()?;
+                timings.read = start_time.elapsed();
+                loaded = (encoder, numbers);
+                (&loaded.0, &loaded.1)
+            }
+        };
+        let start_time = Instant::now();
+        let mut out = io::sink();
+        for num in numbers {
+            timings.write += encoder.encode_to(num, &mut out)?.write_time;
+        }
+        timings.search = start_time.elapsed() - timings.write;
+        eprintln!("run {}: {:.3} ms (load {:.3}, read {:.3}, search {:.3}, format {:.3})",
+                  run, millis(timings.total()), millis(timings.load), millis(timings.read),
+                  millis(timings.search), millis(timings.write));
+        totals.push(timings.total());
+    }
+    eprintln!("{}", Summary::of(&totals));
+    Ok(())
+}
@@ -0,0 +1,132 @@
+//! The `bench-dict` command: loading the same words into each dictionary backend the search
+//! can use, and comparing how long each takes to build, how much memory it takes, and how
+//! fast it finds the words at each position of the numbers, in the same process.
+//!
+//! The backends are the hash map of [`Dictionary`] with the hasher of the standard library
+//! (SipHash, as used when encoding) and with the much faster FxHash (of rustc, which is not
+//! resistant to collisions made on purpose), and the [`Trie`]. The memory is that of the
+//! structure and the vectors of words, without the text of the words, which all share.
+
+use std::collections::HashMap;
+use std::hash::{BuildHasherDefault, Hasher};
+use std::io;
+use std::time::{Duration, Instant};
+
+use num_bigint::BigUint;
+use phone_encoder::input::LineReader;
+use phone_encoder::search::Lookup;
+use phone_encoder::stats::millis;
+use phone_encoder::trie::Trie;
+use phone_encoder::{load_dicts, normalize_number, Dictionary, DigitString, FileContext, FileRole, Word, WordsFile};
+
+use crate::cli::{NumberPolicy, Options};
+use crate::run::number;
+
+pub fn bench_dict(options: &Options, words_files: &[WordsFile], input_file: &str) -> io::Result<()> {
+    let start_time = Instant::now();
+    let dict = load_dicts(words_files, &options.dict)?;
+    let load_time = start_time.elapsed();
+    let mut numbers = Vec::new();
+    let mut lines = LineReader::open(input_file).in_file(FileRole::Numbers, input_file)?
+        .select(&options.selection)
+        .detect_format(&options.number_field)
+        .fail_on_invalid(options.number_policy == NumberPolicy::Strict);
+    while let Some(num) = lines.next_line().in_file(FileRole::Numbers, input_file)? {
+        // the invalid numbers are skipped, as when encoding
+        numbers.extend(normalize_number(number(num), false).ok());
+    }
+    let positions: usize = numbers.iter().map(|digits| digits.len()).sum();
+    eprintln!("{} words loaded in {:.3} ms, looking up the words at the {} positions of {} numbers, best of {} runs",
+              dict.values().map(Vec::len).sum::<usize>(), millis(load_time), positions, numbers.len(), options.runs);
+
+    println!("{:<10} {:>12} {:>12} {:>14} {:>12}", "backend", "build ms", "memory MiB", "lookups/s", "matches");
+    let (build_time, std_map) = timed(|| dict.iter().map(|(key, words)| (key.clone(), words.clone())).collect::<Dictionary>());
+    report("hash/std", build_time, map_memory_size(&std_map), &std_map, &numbers, options.runs);
+    drop(std_map);
+    let (build_time, fx_map) = timed(|| dict.iter().map(|(key, words)| (key.clone(), words.clone())).collect::<FxDictionary>());
+    report("hash/fx", build_time, map_memory_size(&fx_map), &fx_map, &numbers, options.runs);
+    drop(fx_map);
+    let (build_time, trie) = timed(|| Trie::new(&dict));
+    report("trie", build_time, trie.memory_size(), &trie, &numbers, options.runs);
+    Ok(())
+}
+
+fn timed<T>(build: impl FnOnce() -> T) -> (Duration, T) {
+    let start_time = Instant::now();
+    let built = build();
+    (start_time.elapsed(), built)
+}
+
+/// Prints the line of a backend, looking up the words at each position of the numbers in
+/// each run.
+fn report<D: Lookup>(name: &str, build_time: Duration, memory: usize, dict: &D, numbers: &[DigitString], runs: usize) {
+    let mut best = Duration::MAX;
+    let mut matches = 0;
+    for _ in 0..runs.max(1) {
+        let start_time = Instant::now();
+        matches = lookup_all(dict, numbers);
+        best = best.min(start_time.elapsed());
+    }
+    let positions: usize = numbers.iter().map(|digits| digits.len()).sum();
+    println!("{:<10} {:>12.3} {:>12.1} {:>14.0} {:>12}", name, millis(build_time),
+             memory as f64 / (1024.0 * 1024.0), positions as f64 / best.as_secs_f64().max(f64::MIN_POSITIVE), matches);
+}
+
+/// Looks up the words encoding each prefix of the digits at each position of the numbers,
+/// as the search does, returning how many were found.
+fn lookup_all<D: Lookup>(dict: &D, numbers: &[DigitString]) -> usize {
+    let mut matches = 0;
+    for digits in numbers {
+        for start in 0..digits.len() {
+            matches += dict.matches_at(&digits[start..]).map(|(_, words)| words.len()).sum::<usize>();
+        }
+    }
+    matches
+}
+
+/// The bytes taken by the table of a hash map, its keys and its vectors of words.
+fn map_memory_size<S>(map: &HashMap<BigUint, Vec<Word>, S>) -> usize {
+    // a byte of control data for each bucket
+    map.capacity() * (size_of::<(BigUint, Vec<Word>)>() + 1)
+        + map.iter()
+            .map(|(key, words)| key.bits().div_ceil(64) as usize * size_of::<u64>() + words.capacity() * size_of::<Word>())
+            .sum::<usize>()
+}
+
+type FxDictionary = HashMap<BigUint, Vec<Word>, BuildHasherDefault<FxHasher>>;
+
+/// The hasher of rustc (FxHash): a multiplication and a rotation for each word of the input.
+#[derive(Default)]
+struct FxHasher {
+    hash: u64,
+}
+
+impl FxHasher {
+    fn add(&mut self, word: u64) {
+        self.hash = (self.hash.rotate_left(5) ^ word).wrapping_mul(0x51_7c_c1_b7_27_22_0a_95);
+    }
+}
+
+impl Hasher for FxHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        let mut chunks = bytes.chunks_exact(8);
+        for chunk in &mut chunks {
+            self.add(u64::from_le_bytes(chunk.try_into().unwrap_or_default()));
+        }
+        for &byte in chunks.remainder() {
+            self.add(byte as u64);
+        }
+    }
+
+    fn write_u64(&mut self, word: u64) {
+        self.add(word);
+    }
+
+    fn write_usize(&mut self, word: usize) {
+        self.add(word as u64);
+    }
+
+    fn finish(&self) -> u64 {
+        self.hash
+    }
+}
@@ -0,0 +1,255 @@
+//! Caching the solutions of the numbers requested from the servers (`--resp`, `grpc-serve`),
+//! so that popular numbers are encoded once and then served from memory: beyond its capacity,
+//! the cache evicts the numbers used least recently, and the numbers cached longer than its
+//! time to live are encoded again.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, HashMap};
+use std::hash::{Hash, Hasher};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use crate::filter::Rules;
+use crate::mapping::Mapping;
+use crate::number::DigitString;
+
+/// The solutions of a number, as the words (or digits) of each.
+pub type Solutions = Arc<Vec<Vec<String>>>;
+
+/// The bounds of a [`SolutionCache`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CacheOptions {
+    /// how many numbers are cached, at most.
+    pub capacity: usize,
+    /// how long the solutions of a number are served from the cache, forever if not set.
+    pub ttl: Option<Duration>,
+}
+
+/// What the solutions of a number depend on: its digits (whatever the separators it was
+/// written with), the [`Mapping`] of the dictionary and the [`Rules`] of the encoder.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct CacheKey {
+    digits: DigitString,
+    mapping: u64,
+    rules: Option<Rules>,
+}
+
+impl CacheKey {
+    /// The key of `digits`, with the [`mapping_id`] of the mapping.
+    pub fn new(digits: DigitString, mapping: u64, rules: Option<Rules>) -> CacheKey {
+        CacheKey { digits, mapping, rules }
+    }
+}
+
+/// Identifies a mapping in the [`CacheKey`]s, the same for equal mappings.
+pub fn mapping_id(mapping: &Mapping) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    mapping.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// How a [`SolutionCache`] did so far.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    /// the lookups served from the cache.
+    pub hits: u64,
+    /// the lookups of numbers not cached, or cached too long ago.
+    pub misses: u64,
+    /// how many numbers are cached.
+    pub entries: usize,
+}
+
+/// The solutions of the numbers encoded last, shared by the threads of a server.
+///
+/// The cache does not know about the dictionary: it must be cleared when the dictionary
+/// of the encoder is replaced (e.g. with [`Encoder::with_dictionary`](crate::Encoder::with_dictionary)).
+pub struct SolutionCache {
+    options: CacheOptions,
+    entries: Mutex<Entries>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+#[derive(Default)]
+struct Entries {
+    by_key: HashMap<CacheKey, Entry>,
+    /// the keys by the time they were used last, least recently first.
+    by_use: BTreeMap<u64, CacheKey>,
+    /// incremented at each use, as the time of the use.
+    clock: u64,
+}
+
+struct Entry {
+    solutions: Solutions,
+    cached_at: Instant,
+    used_at: u64,
+}
+
+impl SolutionCache {
+    pub fn new(options: CacheOptions) -> SolutionCache {
+        SolutionCache {
+            options,
+            entries: Mutex::new(Entries::default()),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    pub fn options(&self) -> &CacheOptions {
+        &self.options
+    }
+
+    /// The cached solutions of a number, unless they were cached longer than the time to live.
+    pub fn get(&self, key: &CacheKey) -> Option<Solutions> {
+        let mut entries = self.lock();
+        let entries = &mut *entries;
+        let expired = match entries.by_key.get(key) {
+            None => {
+                self.misses.fetch_add(1, Ordering::Relaxed);
+                return None;
+            }
+            Some(entry) => self.options.ttl.is_some_and(|ttl| entry.cached_at.elapsed() >= ttl),
+        };
+        if expired {
+            if let Some(entry) = entries.by_key.remove(key) {
+                entries.by_use.remove(&entry.used_at);
+            }
+            self.misses.fetch_add(1, Ordering::Relaxed);
+            return None;
+        }
+        entries.clock += 1;
+        let clock = entries.clock;
+        let entry = entries.by_key.get_mut(key)?;
+        entries.by_use.remove(&entry.used_at);
+        entries.by_use.insert(clock, key.clone());
+        entry.used_at = clock;
+        self.hits.fetch_add(1, Ordering::Relaxed);
+        Some(entry.solutions.clone())
+    }
+
+    /// Caches the solutions of a number, evicting the number used least recently if the cache
+    /// is full.
+    pub fn insert(&self, key: CacheKey, solutions: Solutions) {
+        if self.options.capacity == 0 {
+            return;
+        }
+        let mut entries = self.lock();
+        let entries = &mut *entries;
+        entries.clock += 1;
+        let entry = Entry { solutions, cached_at: Instant::now(), used_at: entries.clock };
+        entries.by_use.insert(entry.used_at, key.clone());
+        if let Some(previous) = entries.by_key.insert(key, entry) {
+            // encoded by another thread at the same time
+            entries.by_use.remove(&previous.used_at);
+        }
+        while entries.by_key.len() > self.options.capacity {
+            let Some((_, key)) = entries.by_use.pop_first() else { break };
+            entries.by_key.remove(&key);
+        }
+    }
+
+    /// Forgets all the cached solutions, e.g. once the dictionary changed.
+    pub fn clear(&self) {
+        let mut entries = self.lock();
+        entries.by_key.clear();
+        entries.by_use.clear();
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            entries: self.lock().by_key.len(),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Entries> {
+        // the entries are consistent even if a thread panicked while holding the lock
+        self.entries.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(digits: &str) -> CacheKey {
+        CacheKey::new(DigitString::from_digits(digits).unwrap(), mapping_id(&Mapping::default()), None)
+    }
+
+    fn solutions(words: &[&str]) -> Solutions {
+        Arc::new(vec![words.iter().map(|word| word.to_string()).collect()])
+    }
+
+    fn cache(capacity: usize, ttl: Option<Duration>) -> SolutionCache {
+        SolutionCache::new(CacheOptions { capacity, ttl })
+    }
+
+    #[test]
+    fn serves_what_was_cached() {
+        let cache = cache(2, None);
+        assert_eq!(cache.get(&key("562482")), None);
+        cache.insert(key("562482"), solutions(&["mir", "Tor"]));
+        assert_eq!(cache.get(&key("562482")), Some(solutions(&["mir", "Tor"])));
+        assert_eq!(cache.get(&key("562482")), Some(solutions(&["mir", "Tor"])));
+        assert_eq!(cache.stats(), CacheStats { hits: 2, misses: 1, entries: 1 });
+    }
+
+    #[test]
+    fn evicts_the_number_used_least_recently() {
+        let cache = cache(2, None);
+        cache.insert(key("4824"), solutions(&["Torf"]));
+        cache.insert(key("562482"), solutions(&["mir", "Tor"]));
+        // used after 562482, so that 562482 is evicted rather than 4824
+        assert!(cache.get(&key("4824")).is_some());
+        cache.insert(key("381482"), solutions(&["so", "1", "Tor"]));
+        assert_eq!(cache.stats().entries, 2);
+        assert_eq!(cache.get(&key("562482")), None);
+        assert!(cache.get(&key("4824")).is_some());
+        assert!(cache.get(&key("381482")).is_some());
+        assert_eq!(cache.stats(), CacheStats { hits: 3, misses: 1, entries: 2 });
+    }
+
+    #[test]
+    fn caches_a_number_again_without_growing() {
+        let cache = cache(2, None);
+        cache.insert(key("4824"), solutions(&["Torf"]));
+        cache.insert(key("4824"), solutions(&["fort"]));
+        cache.insert(key("562482"), solutions(&["mir", "Tor"]));
+        assert_eq!(cache.get(&key("4824")), Some(solutions(&["fort"])));
+        assert_eq!(cache.stats().entries, 2);
+    }
+
+    #[test]
+    fn expires_numbers_cached_longer_than_the_time_to_live() {
+        let expired = cache(2, Some(Duration::ZERO));
+        expired.insert(key("4824"), solutions(&["Torf"]));
+        assert_eq!(expired.get(&key("4824")), None);
+        assert_eq!(expired.stats(), CacheStats { hits: 0, misses: 1, entries: 0 });
+
+        let fresh = cache(2, Some(Duration::from_secs(3600)));
+        fresh.insert(key("4824"), solutions(&["Torf"]));
+        assert!(fresh.get(&key("4824")).is_some());
+        assert_eq!(fresh.stats(), CacheStats { hits: 1, misses: 0, entries: 1 });
+    }
+
+    #[test]
+    fn caches_nothing_without_capacity() {
+        let cache = cache(0, None);
+        cache.insert(key("4824"), solutions(&["Torf"]));
+        assert_eq!(cache.get(&key("4824")), None);
+        assert_eq!(cache.stats(), CacheStats { hits: 0, misses: 1, entries: 0 });
+    }
+
+    #[test]
+    fn tells_apart_the_mappings_and_rules() {
+        let cache = cache(4, None);
+        cache.insert(key("4824"), solutions(&["Torf"]));
+        let digits = DigitString::from_digits("4824").unwrap();
+        assert_eq!(cache.get(&CacheKey::new(digits.clone(), mapping_id(&Mapping::default()).wrapping_add(1), None)), None);
+        assert_eq!(cache.get(&CacheKey::new(digits, mapping_id(&Mapping::default()), Some(Rules::default()))), None);
+        cache.clear();
+        assert_eq!(cache.get(&key("4824")), None);
+    }
+}
@@ -0,0 +1,976 @@
+use std::env;
+use std::fs;
+use std::io::{self, IsTerminal};
+use std::path::{Path, PathBuf};
+use std::process::exit;
+use std::time::Duration;
+
+use phone_encoder::admission::{ServerLimits, DEFAULT_QUEUE_DEPTH};
+use phone_encoder::cache::CacheOptions;
+use phone_encoder::dict::{CaseFold, WordCase, EMBEDDED_PATH, EMBEDDED_WORDS};
+use phone_encoder::dict_report;
+use phone_encoder::generate::{DictShape, DEFAULT_AVG_BUCKET};
+use phone_encoder::output::OutputOptions;
+use phone_encoder::parallel::Jobs;
+use phone_encoder::redact::Redaction;
+use phone_encoder::sample::Selection;
+use phone_encoder::search::Algorithm;
+use phone_encoder::sort::DEFAULT_RUN_BYTES;
+use phone_encoder::vanity::Position;
+use phone_encoder::{DictOptions, EmptyNumbers, EncodeOptions, Field, FileContext, FileRole, Format, Mapping, NoOutput, Quoting, Rules, WordsFile};
+
+use crate::fetch::{self, Source};
+
+const USAGE: &str = "Usage:
+    phone_encoder [options] [words_file] [numbers_file]
+    phone_encoder complete [options] <digits> [words_file]
+    phone_encoder lookup [options] <digits> [words_file]   (the words encoding exactly the digits)
+    phone_encoder contains [options] <digits> [words_file]   (the words encoding some of the digits, with their
+                                                             positions, as <start>..<end> <word>)
+    phone_encoder bench [options] [words_file] [numbers_file]
+    phone_encoder bench-dict [options] [words_file] [numbers_file]   (compare the dictionary backends)
+    phone_encoder find [options] <word>[,<word>...] [numbers_file]
+    phone_encoder keymap [--keymap <name>|<file>]...   (print the letters of each digit, as a keymap file)
+    phone_encoder compare --other <command> [options] [--] [words_file] [numbers_file]
+    phone_encoder fetch-inputs [--data-dir <dir>] [--source <name>=<url>[#<sha256>]]...
+    phone_encoder report --stats <file> [--output <file>]   (an HTML page of the statistics of --stats-json)
+    phone_encoder scramble --key <key> [numbers_file]   (the numbers with their digits permuted, to publish them)
+    phone_encoder generate-dict --words <n> [--avg-bucket <x>] [--seed <n>] [--keymap <name>|<file>]
+                                                        (a synthetic words file, its words colliding as asked)
+    phone_encoder --jsonrpc [options] [words_file]         (requires the server feature)
+    phone_encoder --resp[=<host:port>] [options] [words_file]   (requires the server feature)
+    phone_encoder --install-service[=<file>] <server command and options>   (write a systemd unit running it)
+    phone_encoder --trace <number> [options] [words_file]
+    phone_encoder --follow [options] [words_file]          (encode the numbers of stdin as they arrive)
+    phone_encoder grpc-serve [options] [words_file]        (requires the grpc feature)
+    phone_encoder grpc-client [options] [numbers_file]     (requires the grpc feature)
+    phone_encoder --kafka-in <topic> --kafka-out <topic> [options] [words_file]   (requires the kafka feature)
+
+Options:
+    --help                             print this message
+    --expand <char>=<letters>|german   expand a character to several letters before encoding
+    --keymap <name>|<file>             the digits of the letters: those of the original problem (prechelt, the default),
+                                       the standard keypad (itu-t9), old British dials (uk-legacy), Russian keypads (ru),
+                                       or those of a TOML file with a line like 2 = \"abc\" for each digit, in any
+                                       alphabet (see 'keymap'); repeated, encode the numbers with each keymap, tagging
+                                       each line with [<keymap>], or into <dir>/<keymap>/ with --output-dir
+    --case-fold[=first|lower|all]      merge words that differ only in case (default: first)
+    --print-case lower|upper|title     change the case of printed words
+    --expand-case title|upper          also print each word in this case, as another word encoded by the same digits,
+                                       without repeating it in the words file (repeated, in each of the cases)
+    --format <format>                  how solutions are printed: plain (default), json (an object per line), csv, tsv
+                                       or grouped (each number followed by its solutions, and a summary)
+    --fields <field>[,<field>...]      with --format json, the members of each object: number, solution, words (without
+                                       the digits), word_count, digit_count (of the digits left as they are), digits
+                                       (encoded by each part) and segments (each part with the positions of its digits
+                                       and whether it is a digit left as it is), instead of number, solution and digits
+                                       with --annotate
+    --join <column>[,<column>...]      name the tab-separated columns following the number in the numbers file (e.g. a
+                                       customer id), and write them with each solution (requires --format csv, tsv or
+                                       json)
+    --color auto|always|never          color the plain and grouped formats (auto: if stdout is a terminal and
+                                       NO_COLOR is not set)
+    --quote never|needed|always        quote printed words, escaping \" and \\ (needed: words with spaces, \" or \\;
+                                       default: never), in all formats but json, whose solutions are arrays
+    --runs <n>                         with bench, how many times to run the encoding, or with bench-dict, to look up
+                                       the words of the numbers with each backend (default: 5)
+    --reuse-input                      with bench, load the words and numbers once instead of in each run
+    --preload                          read the words and numbers files into memory before timing anything,
+                                       so that --timings and bench do not measure reading from the disk
+    --merge                            encode the files of a directory or glob given as the numbers file as if they
+                                       were a single file, instead of in sections headed by '==> <file> <=='
+    --output-dir <dir>                 write the solutions of each numbers file to <dir>/<file name>.out instead of
+                                       stdout
+    --preallocate <size>               with --output-dir, allocate this much space for each output file before writing
+                                       it (e.g. 200G; a number of bytes with an optional K, M, G or T suffix), on Linux
+    --direct-io                        with --output-dir, write the output files with direct I/O (O_DIRECT), bypassing
+                                       the page cache, on Linux (not with --line-buffered, whose flushes would end it)
+    --one-file-per-number              with --output-dir, write the solutions of each number to their own file instead,
+                                       <dir>/<last two digits>/<number>.out, with each byte of the characters of the
+                                       number other than digits and - written as _ and its hex value (5624/82 in
+                                       82/5624_2f82.out), not with --preallocate or --direct-io
+    --schedule numbers|files           with --jobs, share the numbers of each file between the threads (default), or
+                                       give whole files to the threads (requires --output-dir)
+    --sort[=<MiB>]                     write the solutions in the order of the numbers (compared as text) instead
+                                       of the order of the numbers file, sorting the numbers of each file first
+                                       using at most this much memory (default: 256), and temporary files in
+                                       $TMPDIR beyond that
+    --number-field <name>              the column of the numbers in numbers files that are CSV files with a header, or
+                                       the member of the numbers in JSONL files (default: number)
+    --skip <n>                         skip the first n lines of each numbers file
+    --take <n>                         encode at most n numbers of each numbers file (after --skip and --sample)
+    --sample <fraction>                encode a random sample of the numbers, each kept with this probability (e.g.
+                                       0.01 for about 1%)
+    --seed <n>                         the seed of --sample, to draw another sample (default: 0, so that the same
+                                       numbers are drawn in every run), or of the words of generate-dict
+    --avg-bucket <x>                   with generate-dict, how many words are encoded by the same digits on average
+                                       (at least 1; default: 3), most digits encoding fewer and a few many more
+    --match prefix|suffix|anywhere     with find, where the digits of a word must be in a number (default: anywhere)
+    --limit <n>                        print at most n solutions for each phone number
+    --limit-marker                     with --limit, print how many more solutions a number has after its last one,
+                                       e.g. 5624-82: …and 3 more (or an object with \"truncated\": true and \"more\": 3 with
+                                       --format json)
+    --max-total <n>                    print at most n solutions in total, then stop
+    --timeout-per-number <duration>    give up searching a number after this time (e.g. 500ms, 2s; default unit: s),
+                                       listing it on stderr
+    --max-depth <n>                    give up searching a number at a partial solution of more than n words and digits
+                                       (default: 1000), listing it on stderr like a timed out number, but the same ones
+                                       in every run
+    --max-branching <n>                give up searching a number at a partial solution that more than n words may
+                                       follow (default: 100000), listing it the same way
+    --rejects-file <file>              list the numbers that were skipped (invalid) or not fully encoded (timed out, or
+                                       beyond --max-depth or --max-branching), with the reason, in this file instead of
+                                       on stderr, so that they can be given as the numbers file of another run
+    --words [<source>=]<file>          also load the words of another file (source defaults to the file name)
+    --number-policy <policy>           what to do with phone numbers with characters other than digits, '-', '/'
+                                       and spaces: lenient (default) skips those with letters, ignoring the other
+                                       characters, skip-invalid skips them all, listing them like timed out numbers,
+                                       and strict stops with an error naming the line of the first one
+    --strict                           same as --number-policy skip-invalid
+    --empty-numbers <policy>           what to do with phone numbers without any digit, e.g. -/-: print-empty (default)
+                                       prints them with an empty solution, skip skips them, and error stops with an
+                                       error (they are counted as empty_numbers by --stats-json)
+    --annotate                         print the digits encoded by each word after it, e.g. mir[562]
+    --redact hash|truncate             write the phone numbers, in the solutions, the errors and the --audit-log, as a
+                                       hash of their digits (#f032bd1bacf871d4), or only their first 3 digits (562x-xx)
+    --annotate-source                  print the source of each word after it, e.g. mir[words]
+    --only-source <source>             only use words from the given source (may be repeated)
+    --dict-report[=<file>]             once the words are loaded, list the words given more than once, those differing
+                                       only in case, and the digits encoding the most words, to stderr or a file
+    --dict-report-top <n>              with --dict-report, how many of the digits encoding the most words to list
+                                       (default: 10)
+    --rules <rule>[,<rule>...]         reject solutions breaking these rules: consecutive-digits, mixed-lengths
+    --show-rejected[=<file>]           write rejected solutions, with the reason, to stderr or a file
+    --line-buffered                    write the solutions of each number as soon as they are found (with --jobs, of each
+                                       chunk), instead of in blocks; the default when stdout is a terminal
+    --no-output[=format|skip|checksum] do not print the solutions: format them but discard the output (default),
+                                       or skip formatting them too, to measure how long formatting and writing take,
+                                       or print a checksum of them to stderr at the end instead, the same for the same
+                                       solutions in any order, to compare runs
+    --timings                          print how long each phase of the run took to stderr
+    --histogram                        print how many numbers had 0, 1, 2-10, 11-100 and more than 100 solutions
+                                       to stderr at the end
+    --stats-json[=<file>]              write the counts of the run (numbers, solutions, partial solutions extended,
+                                       dictionary lookups, candidates checked, numbers by count of solutions, the 20
+                                       numbers with the most solutions) and timings as JSON to stderr or a file (see
+                                       report)
+    --manifest <file>                  with encode and bench, write what the results depend on as JSON: the version,
+                                       the arguments, the SHA-256 of the words and numbers files, the mapping, the
+                                       --rules and the --seed, to check and reproduce the run later
+    --cache-dir <dir>                  keep the solutions written in this directory, keyed by the SHA-256 of the numbers
+                                       files, the words and the options changing the solutions, and write them from
+                                       there when encoding again with the same ones, without loading the words
+    --jobs <n>|auto                    encode numbers using n threads (auto: based on a calibration sample)
+    --chunk-lines <n>                  with --jobs, read and encode at most n numbers at a time (default: 100000)
+    --algo <algorithm>                 search algorithm: dfs, iterative, dp-count, trie, split (meet-in-the-middle),
+                                       aho-corasick (dp-count, finding the words in one pass over the digits) or auto
+                                       (split for numbers of 40+ digits, dfs otherwise)
+    --unencodable                      only print the numbers that have no encoding (accepted by the --rules)
+    --trace <number>                   instead of encoding the numbers file, print each step of the depth-first search
+                                       for the encodings of this number: the words matching the digits at each point,
+                                       where a digit is kept, dead ends, and the solutions accepted or rejected by the
+                                       --rules, indented by the depth of the recursion
+    --follow                           instead of a numbers file, encode the numbers read from stdin as they arrive, e.g.
+                                       from tail -f, writing the solutions of each as soon as it is encoded, until stdin
+                                       is closed
+    --latency                          with --follow, print how long each number took to encode and write, in ms, after
+                                       the number and a tab, to stderr
+    --jsonrpc                          serve JSON-RPC requests (encode, count, explain, reload-dict) on stdin/stdout
+    --resp[=<host:port>]               serve the Redis protocol commands ENCODE <number> (the solutions), COUNT <number>
+                                       (how many there are) and EXPLAIN <number> <part>... (the rules the parts break)
+                                       on this address (default: 127.0.0.1:6380), for Redis clients
+    --other <command>                  with compare, the other program to run on the words and numbers files, e.g.
+                                       'java -cp build/java Main', checking that both print the same lines in any order
+    --data-dir <dir>                   with fetch-inputs, where to download (or generate) the inputs of the benchmark
+                                       (default: $XDG_DATA_HOME/phone_encoder or ~/.local/share/phone_encoder)
+    --source <name>=<url>[#<sha256>]   with fetch-inputs, also download this file, e.g. a generated numbers file
+                                       (replacing the default file of that name, e.g. dictionary.txt or phones_1000.txt)
+    --stats <file>                     with report, the file written by --stats-json
+    --output <file>                    with report, where to write the HTML page, instead of stdout
+    --key <key>                        with scramble, the secret the permutation of the digits of each number depends
+                                       on (the numbers keep their length, separators and duplicates)
+    --address <host:port>              address of the gRPC server (default: 127.0.0.1:50051)
+    --max-concurrent <n>               with --resp or grpc-serve, encode at most n numbers at the same time, the others
+                                       waiting for their turn
+    --queue-depth <n>                  with --max-concurrent, how many numbers may wait, at most, the others being refused
+                                       at once (BUSY with --resp, RESOURCE_EXHAUSTED with grpc-serve; default: 64)
+    --request-timeout <duration>       with --resp or grpc-serve, give up a number after this time, waiting or being
+                                       encoded (TIMEOUT with --resp, DEADLINE_EXCEEDED with grpc-serve)
+    --cache-size <n>                   with --resp or grpc-serve, keep the solutions of the n numbers requested last in
+                                       memory, serving them again from there (INFO gives the hits and misses with --resp)
+    --cache-ttl <duration>             with --cache-size, encode the numbers cached longer than this again
+    --audit-log <file>                 with --resp, grpc-serve or --kafka-in, append a JSON line for each number
+                                       requested to this file: when, by whom, how it ended, and how long it took
+    --install-service[=<file>]         with --resp, grpc-serve or --kafka-in, instead of serving, write a systemd unit
+                                       serving with the same arguments from the current directory to this file
+                                       (default: /etc/systemd/system/phone_encoder.service); the servers tell systemd
+                                       when they are ready, and log with the priorities of the journal
+    --kafka-in <topic>                 instead of a numbers file, encode the numbers of the messages of this Kafka topic,
+                                       producing a message for each, keyed by the number, with its solutions to the
+                                       topic of --kafka-out; the offsets are committed once the solutions of a batch are
+                                       delivered, so each number is encoded at least once
+    --kafka-out <topic>                the Kafka topic the solutions are produced to
+    --kafka-brokers <host:port>[,...]  the Kafka brokers to connect to (default: localhost:9092)
+    --kafka-group <id>                 the consumer group of the encoder (default: phone_encoder)
+    --kafka-batch <n>                  how many numbers are consumed before committing, at most (default: 1000)
+
+The words file may contain a second, tab-separated column with the weight of each word:
+words with a higher weight are tried first. A second, tab-separated column in the numbers
+file is ignored, unless the columns are named with --join.
+
+The numbers file may also be a CSV file with a header naming a 'number' column (or the column of
+--number-field), or a JSONL file with an object on each line with a 'number' member, which is
+detected from its first line.
+
+The numbers file may also be a directory or a glob like 'shards/*.txt' (quoted, so that the
+shell does not expand it): its files are encoded in file name order, each in its own section,
+with its own --timings and --stats-json line, unless --merge is given.
+
+The words file 'embedded:' is the one compiled into the program by building it with
+PHONE_ENCODER_EMBED_WORDS=<words file>, and the default words file if there is one.";
+
+pub enum Command {
+    Encode { words_files: Vec<WordsFile>, input_file: String },
+    Complete { prefix: String, words_files: Vec<WordsFile> },
+    /// prints the words whose encoding is `digits`, all of them.
+    Lookup { digits: String, words_files: Vec<WordsFile> },
+    /// prints the words whose encoding appears in `digits`, with where.
+    Contains { digits: String, words_files: Vec<WordsFile> },
+    Find { words: Vec<String>, input_file: String },
+    FetchInputs { sources: Vec<Source>, data_dir: PathBuf },
+    /// prints the keymap of the dictionary options, or each of the keymaps of the options.
+    Keymap,
+    /// `args` are the options this program is run with, besides the files.
+    Compare { other: String, words_file: String, input_file: String, args: Vec<String> },
+    Bench { words_files: Vec<WordsFile>, input_file: String },
+    /// compares the build time, memory and lookups of the dictionary backends.
+    BenchDict { words_files: Vec<WordsFile>, input_file: String },
+    #[cfg(feature = "server")]
+    JsonRpc { words_files: Vec<WordsFile> },
+    /// serves the Redis protocol commands of [`phone_encoder::resp`] on `address`.
+    #[cfg(feature = "server")]
+    Resp { words_files: Vec<WordsFile>, address: String },
+    Unencodable { words_files: Vec<WordsFile>, input_file: String },
+    /// prints each step of the search for the encodings of a number.
+    Trace { number: String, words_files: Vec<WordsFile> },
+    /// encodes the numbers of stdin as they arrive, until it is closed.
+    Follow { words_files: Vec<WordsFile> },
+    /// writes an HTML page of the statistics written by `--stats-json`, to stdout if there is no `output`.
+    Report { stats: String, output: Option<String> },
+    /// writes the numbers of `input_file` with their digits permuted with `key`.
+    Scramble { input_file: String, key: String },
+    /// writes the words of a synthetic dictionary of this shape.
+    GenerateDict { shape: DictShape },
+    #[cfg(feature = "grpc")]
+    GrpcServe { words_files: Vec<WordsFile>, address: String },
+    #[cfg(feature = "grpc")]
+    GrpcClient { input_file: String, address: String },
+    /// encodes the numbers of a Kafka topic into another.
+    #[cfg(feature = "kafka")]
+    Kafka { words_files: Vec<WordsFile>, kafka: phone_encoder::kafka::KafkaOptions },
+}
+
+impl Command {
+    /// Whether the command serves requests until stopped, e.g. as a service.
+    pub fn is_server(&self) -> bool {
+        match self {
+            #[cfg(feature = "kafka")]
+            Command::Kafka { .. } => true,
+            command => command.answers_requests(),
+        }
+    }
+
+    /// Whether the command answers the requests of clients, within the [`ServerLimits`].
+    pub fn answers_requests(&self) -> bool {
+        match self {
+            #[cfg(feature = "server")]
+            Command::Resp { .. } => true,
+            #[cfg(feature = "grpc")]
+            Command::GrpcServe { .. } => true,
+            _ => false,
+        }
+    }
+
+    /// Whether the command loads the words files into an encoder, whose words are reported
+    /// with `--dict-report`.
+    pub fn loads_encoder(&self) -> bool {
+        match self {
+            Command::Encode { .. } | Command::Unencodable { .. } | Command::Trace { .. } | Command::Follow { .. } => true,
+            #[cfg(feature = "server")]
+            Command::JsonRpc { .. } | Command::Resp { .. } => true,
+            #[cfg(feature = "grpc")]
+            Command::GrpcServe { .. } => true,
+            #[cfg(feature = "kafka")]
+            Command::Kafka { .. } => true,
+            _ => false,
+        }
+    }
+}
+
+/// How the work is shared between threads when several numbers files are encoded.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Schedule {
+    /// the numbers of each file are shared between the threads, one file after the other.
+    Numbers,
+    /// whole files are given to the threads, each writing its own output file.
+    Files,
+}
+
+/// What is done with the phone numbers with characters other than digits and separators.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NumberPolicy {
+    /// the numbers with letters are skipped, the other characters being ignored.
+    Lenient,
+    /// the numbers are skipped, and listed with the reason.
+    SkipInvalid,
+    /// the run stops with an error.
+    Strict,
+}
+
+// without the servers, their options are still checked, but not used
+#[cfg_attr(not(feature = "server"), allow(dead_code))]
+pub struct Options {
+    pub command: Command,
+    pub dict: DictOptions,
+    pub encode: EncodeOptions,
+    pub rules: Option<Rules>,
+    /// where to write rejected solutions: `Some(None)` means stderr.
+    pub show_rejected: Option<Option<String>>,
+    /// where to write the statistics of the run as JSON: `Some(None)` means stderr.
+    pub stats_json: Option<Option<String>>,
+    /// where to write the report of the words once loaded: `Some(None)` means stderr.
+    pub dict_report: Option<Option<String>>,
+    /// how many of the digits encoding the most words the report lists.
+    pub dict_report_top: usize,
+    /// where to write the manifest of the run.
+    pub manifest: Option<PathBuf>,
+    /// the arguments of the program, as given, for the manifest.
+    pub args: Vec<String>,
+    /// where the solutions written are kept, to write them from there when encoding again.
+    pub cache_dir: Option<PathBuf>,
+    /// where to list the numbers that were not fully encoded, instead of stderr.
+    pub rejects_file: Option<String>,
+    pub jobs: Option<Jobs>,
+    pub chunk_lines: usize,
+    /// where the digits of the words given to `find` must be in a number.
+    pub position: Position,
+    /// how many times `bench` runs the encoding.
+    pub runs: usize,
+    /// whether `bench` loads the words and numbers once for all runs.
+    pub reuse_input: bool,
+    /// whether the words and numbers files are read into memory before timing anything.
+    pub preload: bool,
+    /// whether the files of a directory or glob are encoded as one, instead of in sections.
+    pub merge: bool,
+    /// whether to print how many numbers had how many solutions at the end of the run.
+    pub histogram: bool,
+    /// where to write the solutions of each numbers file, instead of stdout.
+    pub output_dir: Option<String>,
+    /// how the files of the output directory are written.
+    pub output: OutputOptions,
+    /// whether the solutions of each number are written to their own file in the output directory.
+    pub one_file_per_number: bool,
+    pub schedule: Schedule,
+    pub number_policy: NumberPolicy,
+    /// whether the output is flushed after the solutions of each number.
+    pub line_buffered: bool,
+    /// whether `--follow` prints how long each number took.
+    pub latency: bool,
+    /// where to write a systemd unit running the server with the `service_args`, instead of running it.
+    pub install_service: Option<PathBuf>,
+    pub service_args: Vec<String>,
+    /// the limits on the requests of the servers.
+    pub server_limits: ServerLimits,
+    /// the cache of the solutions requested from the servers, if any.
+    pub cache: Option<CacheOptions>,
+    /// where the servers write the requests, if anywhere.
+    pub audit_log: Option<PathBuf>,
+    /// the column or member of the numbers in CSV or JSONL numbers files.
+    pub number_field: String,
+    /// which lines of each numbers file are encoded.
+    pub selection: Selection,
+    /// with `--sort`, the memory used to sort the numbers of a file before spilling them to
+    /// temporary files, in bytes.
+    pub sort: Option<usize>,
+    /// with several `--keymap`s, the name and mapping of each, to encode the numbers with
+    /// each of them (with a single one, it is the mapping of the dictionary options instead).
+    pub keymaps: Vec<(String, Mapping)>,
+}
+
+impl Options {
+    pub fn parse<I: Iterator<Item=String>>(args: I) -> io::Result<Options> {
+        let given: Vec<String> = args.collect();
+        let mut args = given.iter().cloned();
+        // the options, with their values, that `compare` runs this program with
+        let mut forwarded = Vec::new();
+        let mut other = None;
+        let mut dict = DictOptions::default();
+        let mut encode = EncodeOptions::default();
+        let mut positional = Vec::new();
+        let mut extra_words = Vec::new();
+        let mut rules = None;
+        let mut show_rejected = None;
+        let mut rejects_file = None;
+        let mut stats_json = None;
+        let mut dict_report = None;
+        let mut dict_report_top = None;
+        let mut manifest = None;
+        let mut cache_dir = None;
+        let mut jobs = None;
+        let mut chunk_lines = 100_000;
+        #[cfg(feature = "server")]
+        let (mut jsonrpc, mut resp) = (false, None);
+        let mut install_service = None;
+        let mut server_limits = ServerLimits::default();
+        let mut queue_depth = None;
+        let mut cache_size = None;
+        let mut cache_ttl = None;
+        let mut audit_log = None;
+        let mut unencodable = false;
+        let mut trace = None;
+        let mut follow = false;
+        let mut latency = false;
+        let mut position = Position::default();
+        let mut runs = 5;
+        let mut reuse_input = false;
+        let mut preload = false;
+        let mut merge = false;
+        let mut histogram = false;
+        let mut output_dir = None;
+        let mut output = OutputOptions::default();
+        let mut one_file_per_number = false;
+        let mut schedule = Schedule::Numbers;
+        let mut color = None;
+        let mut line_buffered = false;
+        let mut number_policy = NumberPolicy::Lenient;
+        let mut number_field = "number".to_string();
+        let mut selection = Selection::default();
+        let mut sort = None;
+        let mut expansions = Vec::new();
+        let mut keymaps = Vec::new();
+        let mut sources = Vec::new();
+        let mut data_dir = None;
+        let mut report_stats = None;
+        let mut scramble_key = None;
+        let mut avg_bucket = None;
+        let mut report_output = None;
+        #[cfg(feature = "grpc")]
+        let mut address = phone_encoder::grpc::DEFAULT_ADDRESS.to_string();
+        #[cfg(feature = "kafka")]
+        let (mut kafka_in, mut kafka_out) = (None, None);
+        #[cfg(feature = "kafka")]
+        let mut kafka = phone_encoder::kafka::KafkaOptions::new("", "");
+
+        while let Some(arg) = args.next() {
+            let start = given.len() - args.len() - 1;
+            if arg == "--" {
+                positional.extend(args.by_ref());
+                break;
+            }
+            if !arg.starts_with("--") {
+                positional.push(arg);
+                continue;
+            }
+            let (name, inline_value) = match arg.split_once('=') {
+                Some((name, value)) => (name.to_string(), Some(value.to_string())),
+                None => (arg, None),
+            };
+            let mut value = || inline_value.clone().or_else(|| args.next())
+                .ok_or_else(|| usage_error(format!("missing value for {}", name)));
+            match name.as_str() {
+                "--help" => {
+                    println!("{}", USAGE);
+                    exit(0);
+                }
+                "--expand" => expansions.push(value()?),
+                "--keymap" => keymaps.push(load_keymap(&value()?)?),
+                "--case-fold" => dict.case_fold = Some(match inline_value.as_deref() {
+                    None | Some("first") => CaseFold::First,
+                    Some("lower") => CaseFold::Lower,
+                    Some("all") => CaseFold::All,
+                    Some(other) => return Err(usage_error(format!("invalid --case-fold: {}", other))),
+                }),
+                "--print-case" => dict.word_case = Some(match value()?.as_str() {
+                    "lower" => WordCase::Lower,
+                    "upper" => WordCase::Upper,
+                    "title" => WordCase::Title,
+                    other => return Err(usage_error(format!("invalid --print-case: {}", other))),
+                }),
+                "--expand-case" => dict.expand_case.push(match value()?.as_str() {
+                    "title" => WordCase::Title,
+                    "upper" => WordCase::Upper,
+                    other => return Err(usage_error(format!("invalid --expand-case: {}", other))),
+                }),
+                "--format" => {
+                    let value = value()?;
+                    encode.format = Format::parse(&value)
+                        .ok_or_else(|| usage_error(format!("invalid --format: {}", value)))?;
+                }
+                "--fields" => {
+                    let value = value()?;
+                    encode.fields = value.split(',').map(Field::parse).collect::<Option<_>>()
+                        .ok_or_else(|| usage_error(format!("invalid --fields: {}", value)))?;
+                }
+                "--join" => encode.columns = value()?.split(',').map(str::to_string).collect(),
+                "--color" => color = match value()?.as_str() {
+                    "auto" => None,
+                    "always" => Some(true),
+                    "never" => Some(false),
+                    other => return Err(usage_error(format!("invalid --color: {}", other))),
+                },
+                "--quote" => {
+                    let value = value()?;
+                    encode.quoting = Quoting::parse(&value)
+                        .ok_or_else(|| usage_error(format!("invalid --quote: {}", value)))?;
+                }
+                "--limit" => encode.limit = Some(parse_number(&name, &value()?)?),
+                "--limit-marker" => encode.limit_marker = true,
+                "--line-buffered" => line_buffered = true,
+                "--max-total" => encode.max_total = Some(parse_number(&name, &value()?)?),
+                "--max-depth" => encode.max_depth = Some(parse_number(&name, &value()?)?),
+                "--max-branching" => encode.max_branching = Some(parse_number(&name, &value()?)?),
+                "--timeout-per-number" => encode.timeout_per_number = Some(parse_duration(&name, &value()?)?),
+                "--words" => extra_words.push(WordsFile::parse(&value()?)),
+                "--annotate" => encode.annotate = true,
+                "--redact" => {
+                    let value = value()?;
+                    encode.redact = Some(Redaction::parse(&value)
+                        .ok_or_else(|| usage_error(format!("invalid --redact: {}", value)))?);
+                }
+                "--strict" => number_policy = NumberPolicy::SkipInvalid,
+                "--empty-numbers" => encode.empty_numbers = match value()?.as_str() {
+                    "print-empty" => EmptyNumbers::PrintEmpty,
+                    "skip" => EmptyNumbers::Skip,
+                    "error" => EmptyNumbers::Error,
+                    other => return Err(usage_error(format!("invalid --empty-numbers: {}", other))),
+                },
+                "--number-policy" => number_policy = match value()?.as_str() {
+                    "lenient" => NumberPolicy::Lenient,
+                    "skip-invalid" => NumberPolicy::SkipInvalid,
+                    "strict" => NumberPolicy::Strict,
+                    other => return Err(usage_error(format!("invalid --number-policy: {}", other))),
+                },
+                "--annotate-source" => dict.annotate_source = true,
+                "--only-source" => dict.only_sources.push(value()?),
+                "--dict-report" => dict_report = Some(inline_value.clone()),
+                "--dict-report-top" => dict_report_top = Some(parse_number(&name, &value()?)?),
+                "--rules" => {
+                    let spec = value()?;
+                    rules = Some(Rules::parse(&spec)
+                        .ok_or_else(|| usage_error(format!("invalid --rules: {}", spec)))?);
+                }
+                "--show-rejected" => show_rejected = Some(inline_value.clone()),
+                "--rejects-file" => rejects_file = Some(value()?),
+                "--no-output" => encode.no_output = Some(match inline_value.as_deref() {
+                    None | Some("format") => NoOutput::Format,
+                    Some("skip") => NoOutput::Skip,
+                    Some("checksum") => NoOutput::Checksum,
+                    Some(other) => return Err(usage_error(format!("invalid --no-output: {}", other))),
+                }),
+                "--timings" => encode.timings = true,
+                "--stats-json" => stats_json = Some(inline_value.clone()),
+                "--manifest" => manifest = Some(PathBuf::from(value()?)),
+                "--cache-dir" => cache_dir = Some(PathBuf::from(value()?)),
+                "--jobs" => {
+                    let value = value()?;
+                    jobs = Some(Jobs::parse(&value)
+                        .ok_or_else(|| usage_error(format!("invalid --jobs: {}", value)))?);
+                }
+                "--algo" => {
+                    let value = value()?;
+                    encode.algorithm = Algorithm::parse(&value)
+                        .ok_or_else(|| usage_error(format!("invalid --algo: {}", value)))?;
+                }
+                #[cfg(feature = "server")]
+                "--jsonrpc" => jsonrpc = true,
+                "--max-concurrent" => server_limits.max_concurrent = Some(parse_number(&name, &value()?)?.max(1)),
+                "--queue-depth" => queue_depth = Some(parse_number(&name, &value()?)?),
+                "--request-timeout" => server_limits.request_timeout = Some(parse_duration(&name, &value()?)?),
+                "--cache-size" => cache_size = Some(parse_number(&name, &value()?)?),
+                "--cache-ttl" => cache_ttl = Some(parse_duration(&name, &value()?)?),
+                "--audit-log" => audit_log = Some(PathBuf::from(value()?)),
+                "--install-service" => install_service = Some(PathBuf::from(inline_value.as_deref()
+                    .unwrap_or(crate::service::DEFAULT_UNIT))),
+                #[cfg(feature = "server")]
+                "--resp" => resp = Some(inline_value.clone().unwrap_or_else(|| phone_encoder::resp::DEFAULT_ADDRESS.to_string())),
+                "--unencodable" => unencodable = true,
+                "--trace" => trace = Some(value()?),
+                "--follow" => follow = true,
+                "--latency" => latency = true,
+                "--runs" => runs = parse_number(&name, &value()?)?.max(1),
+                "--reuse-input" => reuse_input = true,
+                "--preload" => preload = true,
+                "--merge" => merge = true,
+                "--data-dir" => data_dir = Some(PathBuf::from(value()?)),
+                "--source" => {
+                    let value = value()?;
+                    sources.push(Source::parse(&value)
+                        .ok_or_else(|| usage_error(format!("invalid --source: {}", value)))?);
+                }
+                "--histogram" => histogram = true,
+                "--stats" => report_stats = Some(value()?),
+                "--key" => scramble_key = Some(value()?),
+                "--avg-bucket" => {
+                    let value = value()?;
+                    avg_bucket = Some(value.parse::<f64>().ok().filter(|avg| *avg >= 1.0)
+                        .ok_or_else(|| usage_error(format!("invalid --avg-bucket: {}", value)))?);
+                }
+                "--output" => report_output = Some(value()?),
+                "--output-dir" => output_dir = Some(value()?),
+                "--preallocate" => output.preallocate = Some(parse_size(&name, &value()?)?),
+                "--direct-io" => output.direct = true,
+                "--one-file-per-number" => one_file_per_number = true,
+                "--schedule" => schedule = match value()?.as_str() {
+                    "numbers" => Schedule::Numbers,
+                    "files" => Schedule::Files,
+                    other => return Err(usage_error(format!("invalid --schedule: {}", other))),
+                },
+                "--match" => {
+                    let value = value()?;
+                    position = Position::parse(&value)
+                        .ok_or_else(|| usage_error(format!("invalid --match: {}", value)))?;
+                }
+                "--sort" => sort = Some(match inline_value.as_deref() {
+                    None => DEFAULT_RUN_BYTES,
+                    Some(mib) => parse_number(&name, mib)?.max(1).saturating_mul(1024 * 1024),
+                }),
+                "--number-field" => number_field = value()?,
+                "--skip" => selection.skip = parse_number(&name, &value()?)?,
+                "--take" => selection.take = Some(parse_number(&name, &value()?)?),
+                "--sample" => {
+                    let value = value()?;
+                    selection.sample = Some(value.parse::<f64>().ok()
+                        .filter(|fraction| (0.0..=1.0).contains(fraction))
+                        .ok_or_else(|| usage_error(format!("invalid --sample: {}", value)))?);
+                }
+                "--seed" => {
+                    let value = value()?;
+                    selection.seed = value.parse()
+                        .map_err(|_| usage_error(format!("invalid value for {}: {}", name, value)))?;
+                }
+                "--chunk-lines" => chunk_lines = parse_number(&name, &value()?)?.max(1),
+                #[cfg(feature = "grpc")]
+                "--address" => address = value()?,
+                #[cfg(feature = "kafka")]
+                "--kafka-in" => kafka_in = Some(value()?),
+                #[cfg(feature = "kafka")]
+                "--kafka-out" => kafka_out = Some(value()?),
+                #[cfg(feature = "kafka")]
+                "--kafka-brokers" => kafka.brokers = value()?,
+                #[cfg(feature = "kafka")]
+                "--kafka-group" => kafka.group = value()?,
+                #[cfg(feature = "kafka")]
+                "--kafka-batch" => kafka.batch = parse_number(&name, &value()?)?.max(1),
+                "--other" => {
+                    other = Some(value()?);
+                    continue;
+                }
+                _ => return Err(usage_error(format!("unknown option: {}", name))),
+            }
+            forwarded.extend_from_slice(&given[start..given.len() - args.len()]);
+        }
+
+        if keymaps.len() > 1 && one_file_per_number {
+            return Err(usage_error("--one-file-per-number cannot be used with several --keymap".into()));
+        }
+        if keymaps.len() > 1 && dict_report.is_some() {
+            return Err(usage_error("--dict-report cannot be used with several --keymap".into()));
+        }
+        if keymaps.len() > 1 && manifest.is_some() {
+            return Err(usage_error("--manifest cannot be used with several --keymap".into()));
+        }
+        if keymaps.len() > 1 && cache_dir.is_some() {
+            return Err(usage_error("--cache-dir cannot be used with several --keymap".into()));
+        }
+        // the expansions apply to the letters of each keymap
+        if let [(_, mapping)] = keymaps.as_slice() {
+            dict.mapping = mapping.clone();
+            keymaps.clear();
+        }
+        for spec in &expansions {
+            dict.mapping.expand_spec(spec)?;
+            for (_, mapping) in &mut keymaps {
+                mapping.expand_spec(spec)?;
+            }
+        }
+
+        let mut positional = positional.into_iter();
+        let command = match positional.next() {
+            #[cfg(feature = "server")]
+            first if jsonrpc => Command::JsonRpc {
+                words_files: words_files(first.unwrap_or_else(default_words), extra_words),
+            },
+            #[cfg(feature = "server")]
+            first if resp.is_some() => Command::Resp {
+                words_files: words_files(first.unwrap_or_else(default_words), extra_words),
+                address: resp.take().unwrap_or_default(),
+            },
+            Some(cmd) if cmd == "complete" => {
+                let prefix = positional.next()
+                    .ok_or_else(|| usage_error("missing <digits> to complete".into()))?;
+                let words_file = positional.next().unwrap_or_else(default_words);
+                Command::Complete { prefix, words_files: words_files(words_file, extra_words) }
+            }
+            Some(cmd) if cmd == "lookup" => {
+                let digits = positional.next()
+                    .ok_or_else(|| usage_error("missing <digits> to look up".into()))?;
+                let words_file = positional.next().unwrap_or_else(default_words);
+                Command::Lookup { digits, words_files: words_files(words_file, extra_words) }
+            }
+            Some(cmd) if cmd == "contains" => {
+                let digits = positional.next()
+                    .ok_or_else(|| usage_error("missing <digits> to search".into()))?;
+                let words_file = positional.next().unwrap_or_else(default_words);
+                Command::Contains { digits, words_files: words_files(words_file, extra_words) }
+            }
+            Some(cmd) if cmd == "bench" => Command::Bench {
+                words_files: words_files(positional.next().unwrap_or_else(default_words), extra_words),
+                input_file: positional.next().unwrap_or_else(default_numbers),
+            },
+            Some(cmd) if cmd == "bench-dict" => Command::BenchDict {
+                words_files: words_files(positional.next().unwrap_or_else(default_words), extra_words),
+                input_file: positional.next().unwrap_or_else(default_numbers),
+            },
+            Some(cmd) if cmd == "find" => {
+                let words = positional.next()
+                    .ok_or_else(|| usage_error("missing <word> to find".into()))?;
+                Command::Find {
+                    words: words.split(',').map(str::to_string).collect(),
+                    input_file: positional.next().unwrap_or_else(default_numbers),
+                }
+            }
+            Some(cmd) if cmd == "keymap" => Command::Keymap,
+            Some(cmd) if cmd == "compare" => Command::Compare {
+                other: other.ok_or_else(|| usage_error("missing --other <command> to compare with".into()))?,
+                words_file: positional.next().unwrap_or_else(default_words),
+                input_file: positional.next().unwrap_or_else(default_numbers),
+                args: forwarded,
+            },
+            Some(cmd) if cmd == "report" => Command::Report {
+                stats: report_stats.take().ok_or_else(|| usage_error("missing --stats <file> to report".into()))?,
+                output: report_output.take(),
+            },
+            Some(cmd) if cmd == "scramble" => Command::Scramble {
+                input_file: positional.next().unwrap_or_else(default_numbers),
+                key: scramble_key.take().ok_or_else(|| usage_error("missing --key <key> to scramble".into()))?,
+            },
+            Some(cmd) if cmd == "generate-dict" => {
+                // --words is the number of words to generate here, rather than a words file
+                let words = match extra_words.as_slice() {
+                    [words] => parse_number("--words", &words.path.to_string_lossy())?,
+                    _ => return Err(usage_error("missing --words <n> to generate".into())),
+                };
+                let avg_bucket = avg_bucket.take().unwrap_or(DEFAULT_AVG_BUCKET);
+                Command::GenerateDict { shape: DictShape { words, avg_bucket, seed: selection.seed } }
+            }
+            Some(cmd) if cmd == "fetch-inputs" => Command::FetchInputs {
+                sources: fetch::sources(&sources),
+                data_dir: data_dir.unwrap_or_else(fetch::default_data_dir),
+            },
+            #[cfg(feature = "grpc")]
+            Some(cmd) if cmd == "grpc-serve" => {
+                let words_file = positional.next().unwrap_or_else(default_words);
+                Command::GrpcServe { words_files: words_files(words_file, extra_words), address }
+            }
+            #[cfg(feature = "grpc")]
+            Some(cmd) if cmd == "grpc-client" => Command::GrpcClient {
+                input_file: positional.next().unwrap_or_else(default_numbers),
+                address,
+            },
+            #[cfg(feature = "kafka")]
+            first if kafka_in.is_some() || kafka_out.is_some() => {
+                kafka.input_topic = kafka_in.ok_or_else(|| usage_error("--kafka-out requires --kafka-in".into()))?;
+                kafka.output_topic = kafka_out.ok_or_else(|| usage_error("--kafka-in requires --kafka-out".into()))?;
+                Command::Kafka { words_files: words_files(first.unwrap_or_else(default_words), extra_words), kafka }
+            }
+            first if trace.is_some() => Command::Trace {
+                number: trace.unwrap_or_default(),
+                words_files: words_files(first.unwrap_or_else(default_words), extra_words),
+            },
+            first if follow => Command::Follow {
+                words_files: words_files(first.unwrap_or_else(default_words), extra_words),
+            },
+            first if unencodable => Command::Unencodable {
+                words_files: words_files(first.unwrap_or_else(default_words), extra_words),
+                input_file: positional.next().unwrap_or_else(default_numbers),
+            },
+            first => Command::Encode {
+                words_files: words_files(first.unwrap_or_else(default_words), extra_words),
+                input_file: positional.next().unwrap_or_else(default_numbers),
+            },
+        };
+        if let Some(extra) = positional.next() {
+            return Err(usage_error(format!("unexpected argument: {}", extra)));
+        }
+
+        if report_stats.is_some() || report_output.is_some() {
+            return Err(usage_error("--stats and --output are only used by report".into()));
+        }
+        if scramble_key.is_some() {
+            return Err(usage_error("--key is only used by scramble".into()));
+        }
+        if avg_bucket.is_some() {
+            return Err(usage_error("--avg-bucket is only used by generate-dict".into()));
+        }
+        if dict_report.is_some() && !command.loads_encoder() {
+            return Err(usage_error("--dict-report is only used by the commands encoding numbers with the words".into()));
+        }
+        if dict_report_top.is_some() && dict_report.is_none() {
+            return Err(usage_error("--dict-report-top requires --dict-report".into()));
+        }
+        let dict_report_top = dict_report_top.unwrap_or(dict_report::DEFAULT_TOP);
+        if manifest.is_some() && !matches!(command, Command::Encode { .. } | Command::Bench { .. }) {
+            return Err(usage_error("--manifest is only used by encode and bench".into()));
+        }
+        if cache_dir.is_some() && !matches!(command, Command::Encode { .. }) {
+            return Err(usage_error("--cache-dir is only used by encode".into()));
+        }
+        // what a cached run would not write again, or may write differently
+        if cache_dir.is_some() && (output_dir.is_some() || show_rejected.is_some() || rejects_file.is_some()
+            || encode.timeout_per_number.is_some()) {
+            return Err(usage_error("--cache-dir cannot be used with --output-dir, --show-rejected, --rejects-file or --timeout-per-number".into()));
+        }
+        if queue_depth.is_some() && server_limits.max_concurrent.is_none() {
+            return Err(usage_error("--queue-depth requires --max-concurrent".into()));
+        }
+        if server_limits != ServerLimits::default() && !command.answers_requests() {
+            return Err(usage_error("--max-concurrent and --request-timeout require --resp or grpc-serve".into()));
+        }
+        server_limits.queue_depth = queue_depth.unwrap_or(DEFAULT_QUEUE_DEPTH);
+        if cache_ttl.is_some() && cache_size.is_none() {
+            return Err(usage_error("--cache-ttl requires --cache-size".into()));
+        }
+        if cache_size.is_some() && !command.answers_requests() {
+            return Err(usage_error("--cache-size requires --resp or grpc-serve".into()));
+        }
+        let cache = cache_size.map(|capacity| CacheOptions { capacity, ttl: cache_ttl });
+        if audit_log.is_some() && !command.is_server() {
+            return Err(usage_error("--audit-log requires --resp, grpc-serve or --kafka-in".into()));
+        }
+        if install_service.is_some() && !command.is_server() {
+            return Err(usage_error("--install-service requires --resp, grpc-serve or --kafka-in".into()));
+        }
+        let service_args = given.iter()
+            .filter(|arg| *arg != "--install-service" && !arg.starts_with("--install-service="))
+            .cloned()
+            .collect();
+        if latency && !follow {
+            return Err(usage_error("--latency requires --follow".into()));
+        }
+        if follow && (jobs.is_some() || sort.is_some() || output_dir.is_some()) {
+            return Err(usage_error("--follow cannot be used with --jobs, --sort or --output-dir".into()));
+        }
+        if schedule == Schedule::Files && output_dir.is_none() {
+            return Err(usage_error("--schedule files requires --output-dir".into()));
+        }
+        if !encode.fields.is_empty() && encode.format != Format::Json {
+            return Err(usage_error("--fields requires --format json".into()));
+        }
+        // the digits of each part are only computed for annotated solutions
+        encode.annotate |= encode.fields.contains(&Field::Digits) || encode.fields.contains(&Field::Segments);
+        if !encode.columns.is_empty() && matches!(encode.format, Format::Plain | Format::Grouped) {
+            return Err(usage_error("--join requires --format csv, tsv or json".into()));
+        }
+        if encode.limit_marker && encode.limit.is_none() {
+            return Err(usage_error("--limit-marker requires --limit".into()));
+        }
+        if output != OutputOptions::default() && output_dir.is_none() {
+            return Err(usage_error("--preallocate and --direct-io require --output-dir".into()));
+        }
+        // a flush writes the unaligned end of the output without direct I/O, for good
+        if output.direct && line_buffered {
+            return Err(usage_error("--direct-io cannot be used with --line-buffered".into()));
+        }
+        if one_file_per_number && output_dir.is_none() {
+            return Err(usage_error("--one-file-per-number requires --output-dir".into()));
+        }
+        // the files of the numbers are small, and written at once
+        if one_file_per_number && output != OutputOptions::default() {
+            return Err(usage_error("--preallocate and --direct-io cannot be used with --one-file-per-number".into()));
+        }
+        encode.strict = number_policy != NumberPolicy::Lenient;
+        // by convention, NO_COLOR turns off the colors that are on by default
+        encode.color = color.unwrap_or_else(|| {
+            output_dir.is_none() && env::var_os("NO_COLOR").is_none() && io::stdout().is_terminal()
+        });
+
+        // solutions are shown as they are found to someone watching, but written in blocks to a pipe
+        line_buffered |= output_dir.is_none() && io::stdout().is_terminal();
+
+        Ok(Options {
+            command, dict, encode, rules, show_rejected, stats_json, dict_report, dict_report_top, manifest, args: given,
+            cache_dir, rejects_file, jobs, chunk_lines, position, runs,
+            reuse_input, preload, merge, histogram, output_dir, output, one_file_per_number, schedule,
+            line_buffered, latency, install_service, service_args, server_limits, cache, audit_log, number_policy, number_field, selection, sort, keymaps,
+        })
+    }
+}
+
+/// The embedded words file, if the program was built with one.
+fn default_words() -> String {
+    match EMBEDDED_WORDS {
+        Some(_) => EMBEDDED_PATH.into(),
+        None => "tests/words.txt".into(),
+    }
+}
+
+fn default_numbers() -> String {
+    "tests/numbers.txt".into()
+}
+
+/// A built-in keymap, or else a keymap file, with its name: the name of the file without
+/// its extension.
+fn load_keymap(spec: &str) -> io::Result<(String, Mapping)> {
+    if let Some(mapping) = Mapping::keymap(spec) {
+        return Ok((spec.to_string(), mapping));
+    }
+    let toml = fs::read_to_string(spec).in_file(FileRole::Keymap, spec)?;
+    let mapping = Mapping::parse_keymap(&toml).in_file(FileRole::Keymap, spec)?;
+    let name = Path::new(spec).file_stem().map_or(spec.into(), |stem| stem.to_string_lossy().into_owned());
+    Ok((name, mapping))
+}
+
+fn words_files(words_file: String, extra_words: Vec<WordsFile>) -> Vec<WordsFile> {
+    let mut files = vec![WordsFile::new(words_file)];
+    files.extend(extra_words);
+    files
+}
+
+fn parse_number(name: &str, value: &str) -> io::Result<usize> {
+    value.parse().map_err(|_| usage_error(format!("invalid value for {}: {}", name, value)))
+}
+
+/// Parses a size in bytes, or with a `K`, `M`, `G` or `T` suffix (powers of 1024).
+fn parse_size(name: &str, value: &str) -> io::Result<u64> {
+    let (number, unit) = match value.find(|ch: char| !ch.is_ascii_digit()) {
+        Some(end) => value.split_at(end),
+        None => (value, ""),
+    };
+    let shift = match unit.to_ascii_uppercase().as_str() {
+        "" => 0,
+        "K" => 10,
+        "M" => 20,
+        "G" => 30,
+        "T" => 40,
+        _ => return Err(usage_error(format!("invalid value for {}: {}", name, value))),
+    };
+    number.parse::<u64>().ok()
+        .and_then(|n| n.checked_mul(1 << shift))
+        .ok_or_else(|| usage_error(format!("invalid value for {}: {}", name, value)))
+}
+
+/// Parses a duration in seconds, or with an `ms` or `s` suffix.
+fn parse_duration(name: &str, value: &str) -> io::Result<Duration> {
+    let (number, millis) = match value.strip_suffix("ms") {
+        Some(number) => (number, true),
+        None => (value.strip_suffix('s').unwrap_or(value), false),
+    };
+    number.parse::<f64>().ok()
+        .filter(|n| n.is_finite() && *n >= 0.0)
+        .and_then(|n| Duration::try_from_secs_f64(if millis { n / 1000.0 } else { n }).ok())
+        .ok_or_else(|| usage_error(format!("invalid value for {}: {}", name, value)))
+}
+
+fn usage_error(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, format!("{}\n\n{}", message, USAGE))
+}
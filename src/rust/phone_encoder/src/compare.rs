@@ -0,0 +1,77 @@
+//! The `compare` command: running another implementation of the encoder on the same inputs
+//! as this one, timing both and checking that they print the same solutions, in any order
+//! (like `diff <(sort ...) <(sort ...)` in `benchmark.sh`).
+
+use std::collections::HashMap;
+use std::env;
+use std::io;
+use std::process::{Command, Output, Stdio};
+use std::time::{Duration, Instant};
+
+use phone_encoder::stats::millis;
+
+/// How many of the lines printed by only one of the programs are shown.
+const MAX_SHOWN_DIFFERENCES: usize = 10;
+
+/// Runs this program with the options in `args`, and `other` (a command line like
+/// `java -cp build/java Main`, split at whitespace), on the words and numbers files.
+pub fn compare(other: &str, words_file: &str, input_file: &str, args: &[String]) -> io::Result<()> {
+    let mut other_args = other.split_whitespace();
+    let other_program = other_args.next()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "empty --other command"))?;
+    let mut other_command = Command::new(other_program);
+    other_command.args(other_args).args([words_file, input_file]);
+
+    let mut this_command = Command::new(env::current_exe()?);
+    this_command.args(args).args([words_file, input_file]);
+
+    let (this_output, this_time) = run(&mut this_command, "phone_encoder")?;
+    let (other_output, other_time) = run(&mut other_command, other)?;
+    let this_lines = lines(&this_output.stdout);
+    let other_lines = lines(&other_output.stdout);
+    eprintln!("phone_encoder: {:>10.3} ms, {} lines", millis(this_time), this_lines.len());
+    eprintln!("{}: {:>10.3} ms, {} lines", other, millis(other_time), other_lines.len());
+
+    // the lines printed more times by one program than by the other
+    let mut counts: HashMap<&str, isize> = HashMap::new();
+    for line in &this_lines {
+        *counts.entry(line).or_default() += 1;
+    }
+    for line in &other_lines {
+        *counts.entry(line).or_default() -= 1;
+    }
+    let mut differences: Vec<(&str, isize)> = counts.into_iter().filter(|(_, count)| *count != 0).collect();
+    if differences.is_empty() {
+        eprintln!("the outputs are the same, ignoring the order of lines");
+        return Ok(());
+    }
+    differences.sort_unstable();
+    let only_this: isize = differences.iter().map(|(_, count)| (*count).max(0)).sum();
+    let only_other: isize = differences.iter().map(|(_, count)| (-*count).max(0)).sum();
+    for (line, count) in differences.iter().take(MAX_SHOWN_DIFFERENCES) {
+        eprintln!("{} {}", if *count > 0 { "<" } else { ">" }, line);
+    }
+    if differences.len() > MAX_SHOWN_DIFFERENCES {
+        eprintln!("...");
+    }
+    Err(io::Error::other(format!(
+        "the outputs differ: {} lines only printed by phone_encoder (<), {} only by {} (>)",
+        only_this, only_other, other)))
+}
+
+fn run(command: &mut Command, name: &str) -> io::Result<(Output, Duration)> {
+    let start_time = Instant::now();
+    let output = command.stdin(Stdio::null()).stderr(Stdio::inherit()).output()
+        .map_err(|e| io::Error::new(e.kind(), format!("cannot run {}: {}", name, e)))?;
+    let time = start_time.elapsed();
+    if !output.status.success() {
+        return Err(io::Error::other(format!("{} failed ({})", name, output.status)));
+    }
+    Ok((output, time))
+}
+
+/// The lines of an output, without their line terminators (`\n` or `\r\n`).
+fn lines(output: &[u8]) -> Vec<&str> {
+    let output = std::str::from_utf8(output).unwrap_or_default();
+    output.lines().collect()
+}
@@ -0,0 +1,589 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{self, BufRead};
+use std::path::{Path, PathBuf};
+use std::slice;
+use std::sync::Arc;
+
+use num_bigint::BigUint;
+
+use crate::error::{EncodeError, FileContext, FileRole};
+use crate::input::decompressed;
+use crate::mapping::Mapping;
+use crate::trie::Trie;
+
+pub use crate::search::{word_to_number, Word, EMBEDDED_WORDS};
+
+/// The path of a [`WordsFile`] standing for the [`EMBEDDED_WORDS`].
+pub const EMBEDDED_PATH: &str = "embedded:";
+
+/// Words keyed by their encoding, which is represented as a number
+/// starting with a `1` (so that leading zeroes are not lost).
+///
+/// An entry may also be a phrase like `so ein`, in which case its key is the
+/// encoding of all its words concatenated, but it is printed with the spaces.
+///
+/// The words in each entry are kept in descending order of weight.
+pub type Dictionary = HashMap<BigUint, Vec<Word>>;
+
+/// Changes to a [`Dictionary`] word by word, so that a long-running service can apply
+/// a delta without loading all its words files again.
+pub trait DictionaryUpdates {
+    /// Adds a word under the key of its encoding with `mapping`, after the words of the
+    /// same key with at least its weight. A word with the same text as one already in
+    /// the dictionary replaces it, which is returned.
+    ///
+    /// A word with a letter `mapping` cannot encode is an [`EncodeError::InvalidWord`] on
+    /// line 1, as if it were the only line of a words file.
+    fn insert_word(&mut self, word: Word, mapping: &Mapping) -> Result<Option<Word>, EncodeError>;
+
+    /// Removes the word with the given text (every copy of it, if it was loaded several
+    /// times, and its variants), returning it. A key left without words is removed, so that
+    /// the search no longer finds a word where it would otherwise fall back to a digit.
+    ///
+    /// Errors like [`DictionaryUpdates::insert_word`].
+    fn remove_word(&mut self, text: &str, mapping: &Mapping) -> Result<Option<Word>, EncodeError>;
+}
+
+/// The key of a word given to [`DictionaryUpdates`], checking that `mapping` can encode it.
+fn update_key(text: &str, mapping: &Mapping) -> Result<BigUint, EncodeError> {
+    match mapping.unmapped_letter(text) {
+        Some(ch) => Err(EncodeError::InvalidWord { line: 1, char: ch }),
+        None => Ok(word_to_number(text, mapping)),
+    }
+}
+
+impl DictionaryUpdates for Dictionary {
+    fn insert_word(&mut self, word: Word, mapping: &Mapping) -> Result<Option<Word>, EncodeError> {
+        let words = self.entry(update_key(&word.text, mapping)?).or_default();
+        let replaced = words.iter()
+            .position(|w| w.text == word.text)
+            .map(|index| words.remove(index));
+        // stable, like the sorting of the words of a weighted file
+        let index = words.partition_point(|w| w.weight >= word.weight);
+        words.insert(index, word);
+        Ok(replaced)
+    }
+
+    fn remove_word(&mut self, text: &str, mapping: &Mapping) -> Result<Option<Word>, EncodeError> {
+        let key = update_key(text, mapping)?;
+        let Some(words) = self.get_mut(&key) else { return Ok(None) };
+        let mut removed = None;
+        words.retain(|w| {
+            if w.text != text && w.match_form.as_deref() != Some(text) {
+                return true;
+            }
+            removed.get_or_insert_with(|| w.clone());
+            false
+        });
+        if words.is_empty() {
+            self.remove(&key);
+        }
+        Ok(removed)
+    }
+}
+
+/// A words file to load into a [`Dictionary`], tagged with the name of its source.
+#[derive(Clone, Debug)]
+pub struct WordsFile {
+    pub source: String,
+    pub path: PathBuf,
+    /// the contents of the file, if it was read into memory before loading it.
+    pub contents: Option<Arc<[u8]>>,
+}
+
+impl WordsFile {
+    /// A words file whose source is named after the file, e.g. `words` for `tests/words.txt`
+    /// (or `embedded` for the [`EMBEDDED_PATH`]).
+    pub fn new<P: Into<PathBuf>>(path: P) -> WordsFile {
+        let path = path.into();
+        if path == Path::new(EMBEDDED_PATH) {
+            return WordsFile { source: "embedded".into(), path, contents: None };
+        }
+        let source = path.file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        WordsFile { source, path, contents: None }
+    }
+
+    /// Parses either `<source>=<path>` or just `<path>`.
+    pub fn parse(spec: &str) -> WordsFile {
+        match spec.split_once('=') {
+            Some((source, path)) => WordsFile { source: source.to_string(), path: path.into(), contents: None },
+            None => WordsFile::new(spec),
+        }
+    }
+
+    /// Reads the file into memory, so that loading it later does not read from the disk.
+    pub fn preload(&mut self) -> Result<(), EncodeError> {
+        if self.contents.is_none() && self.path != Path::new(EMBEDDED_PATH) {
+            self.contents = Some(fs::read(&self.path).in_file(FileRole::Words, &self.path)?.into());
+        }
+        Ok(())
+    }
+}
+
+/// How to merge dictionary words that differ only in case, like `mir` and `Mir`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CaseFold {
+    /// keep the first variant found in the words file.
+    First,
+    /// keep a single, lower-case variant.
+    Lower,
+    /// keep all variants in a single entry, separated by `/`.
+    All,
+}
+
+/// The case in which dictionary words are printed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WordCase {
+    Lower,
+    Upper,
+    Title,
+}
+
+impl WordCase {
+    pub fn apply(self, word: &str) -> String {
+        match self {
+            WordCase::Lower => word.to_lowercase(),
+            WordCase::Upper => word.to_uppercase(),
+            // each word of a phrase is capitalized
+            WordCase::Title => word.split(' ')
+                .map(|w| {
+                    let mut chars = w.chars();
+                    match chars.next() {
+                        Some(first) => first.to_uppercase().chain(chars.flat_map(char::to_lowercase)).collect(),
+                        None => String::new(),
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join(" "),
+        }
+    }
+}
+
+/// Options used when loading a [`Dictionary`].
+#[derive(Clone, Debug, Default)]
+#[non_exhaustive]
+pub struct DictOptions {
+    pub mapping: Mapping,
+    pub case_fold: Option<CaseFold>,
+    pub word_case: Option<WordCase>,
+    /// also add each word in these cases, as variants of it printed differently but matched
+    /// the same (see [`Word::match_form`]), unless it already is in that case.
+    pub expand_case: Vec<WordCase>,
+    /// append the source of each word to it when printing, e.g. `mir[words]`.
+    pub annotate_source: bool,
+    /// only load words from these sources (all sources if empty).
+    pub only_sources: Vec<String>,
+}
+
+pub fn load_dict<P: AsRef<Path>>(words_file: P, options: &DictOptions) -> Result<Dictionary, EncodeError> {
+    load_dicts(&[WordsFile::new(words_file.as_ref())], options)
+}
+
+/// Loads several words files into a single [`Dictionary`], tagging each word with its source.
+pub fn load_dicts(files: &[WordsFile], options: &DictOptions) -> Result<Dictionary, EncodeError> {
+    let mut dicts = load_dicts_for(files, options, slice::from_ref(&options.mapping))?;
+    Ok(dicts.pop().unwrap_or_default())
+}
+
+/// Loads several words files into a [`Dictionary`] for each of the `mappings` (instead of
+/// the mapping of the options), reading and checking each word only once for all of them.
+pub fn load_dicts_for(files: &[WordsFile], options: &DictOptions, mappings: &[Mapping]) -> Result<Vec<Dictionary>, EncodeError> {
+    let mut builder = DictionaryBuilder::for_mappings(options, mappings);
+    for file in files {
+        if !options.only_sources.is_empty() && !options.only_sources.contains(&file.source) {
+            continue;
+        }
+        add_words(&mut builder, file).in_file(FileRole::Words, &file.path)?;
+    }
+    Ok(builder.finish_all())
+}
+
+/// Adds the words of a file to a builder.
+fn add_words(builder: &mut DictionaryBuilder, file: &WordsFile) -> Result<(), EncodeError> {
+    let lines: Box<dyn Iterator<Item = io::Result<String>>> = if let Some(contents) = &file.contents {
+        Box::new(decompressed(&file.path, io::Cursor::new(contents.clone())).lines())
+    } else if file.path == Path::new(EMBEDDED_PATH) {
+        let words = EMBEDDED_WORDS.ok_or_else(|| io::Error::new(io::ErrorKind::NotFound,
+            "no words file was embedded: build with PHONE_ENCODER_EMBED_WORDS=<words file>"))?;
+        Box::new(words.lines())
+    } else {
+        Box::new(read_lines(&file.path)?)
+    };
+    builder.set_source(&file.source);
+    for line in lines {
+        // lines that cannot be read are skipped, but still counted in the line numbers of errors
+        builder.push(&line.unwrap_or_default())?;
+    }
+    Ok(())
+}
+
+/// Builds a [`Dictionary`] from words given one at a time, e.g. by a loader streaming them
+/// from a URL or a database, checking and encoding each word as it comes.
+pub struct DictionaryBuilder<'a> {
+    options: DictOptions,
+    mappings: Vec<Mapping>,
+    /// the dictionary of each mapping.
+    dicts: Vec<Dictionary>,
+    /// whether words must be checked for letters the mappings cannot encode.
+    check_ascii: bool,
+    /// whether any word had a weight, so that the words of each key must be sorted.
+    weighted: bool,
+    /// the source of the words pushed, and how many lines were pushed from it.
+    source: Option<Arc<str>>,
+    line: usize,
+    /// how many words were added.
+    words: usize,
+    /// called with the number of words added after every `progress_every` words.
+    on_progress: Option<Box<dyn FnMut(usize) + 'a>>,
+    progress_every: usize,
+}
+
+impl<'a> DictionaryBuilder<'a> {
+    /// A builder of a dictionary with the mapping of the options.
+    pub fn new(options: &DictOptions) -> DictionaryBuilder<'a> {
+        DictionaryBuilder::for_mappings(options, slice::from_ref(&options.mapping))
+    }
+
+    /// A builder of a dictionary for each of the `mappings`, instead of the mapping of the
+    /// options (see [`DictionaryBuilder::finish_all`]).
+    pub fn for_mappings(options: &DictOptions, mappings: &[Mapping]) -> DictionaryBuilder<'a> {
+        DictionaryBuilder {
+            options: options.clone(),
+            mappings: mappings.to_vec(),
+            dicts: mappings.iter().map(|_| HashMap::with_capacity(100)).collect(),
+            // words that are not ASCII may have letters that cannot be encoded, and so may
+            // others with a keymap that leaves some ASCII letters out
+            check_ascii: mappings.iter().any(|mapping| !mapping.maps_ascii()),
+            weighted: false,
+            source: None,
+            line: 0,
+            words: 0,
+            on_progress: None,
+            progress_every: usize::MAX,
+        }
+    }
+
+    /// Calls `callback` with the number of words added so far after every `every` words.
+    pub fn on_progress<F: FnMut(usize) + 'a>(mut self, every: usize, callback: F) -> DictionaryBuilder<'a> {
+        self.on_progress = Some(Box::new(callback));
+        self.progress_every = every.max(1);
+        self
+    }
+
+    /// Tags the words pushed from now on with `source`, counting their lines from 1 again.
+    pub fn set_source(&mut self, source: &str) {
+        self.source = Some(Arc::from(source));
+        self.line = 0;
+    }
+
+    /// Adds the word or phrase on a line of a words file, with its weight in a second,
+    /// tab-separated column, if any. Empty lines are skipped.
+    ///
+    /// Errors name the line, counting the lines pushed since [`DictionaryBuilder::set_source`].
+    pub fn push(&mut self, line: &str) -> Result<(), EncodeError> {
+        self.line += 1;
+        let (text, weight) = match line.split_once('\t') {
+            Some((text, weight)) => (text, parse_weight(weight, self.line)?),
+            None => (line, 0.0),
+        };
+        self.weighted |= weight != 0.0;
+        let mut text = normalize_phrase(text);
+        if text.is_empty() {
+            return Ok(());
+        }
+        if let Some(ch) = text.chars().find(|ch| ch.is_control()) {
+            return Err(EncodeError::InvalidWord { line: self.line, char: ch });
+        }
+        if self.check_ascii || !text.is_ascii() {
+            if let Some(ch) = self.mappings.iter().find_map(|mapping| mapping.unmapped_letter(&text)) {
+                return Err(EncodeError::InvalidWord { line: self.line, char: ch });
+            }
+        }
+        let keys: Vec<BigUint> = self.mappings.iter().map(|mapping| word_to_number(&text, mapping)).collect();
+        let variants = self.variants(&text, weight);
+        if let Some(case) = self.options.word_case {
+            text = case.apply(&text);
+        }
+        let mut word = Some(Word { text, weight, source: self.source.clone(), match_form: None });
+        let last = keys.len() - 1;
+        for (index, (dict, key)) in self.dicts.iter_mut().zip(keys).enumerate() {
+            // only copied for the mappings but the last
+            let word = if index == last { word.take() } else { word.clone() };
+            let Some(word) = word else { break };
+            let words = dict.entry(key).or_default();
+            match self.options.case_fold {
+                None => words.push(word),
+                Some(fold) => add_folding_case(words, word, fold),
+            }
+            // after their word, and never folded into it
+            for variant in &variants {
+                if !words.iter().any(|w| w.text == variant.text) {
+                    words.push(variant.clone());
+                }
+            }
+        }
+        self.words += 1;
+        if let Some(on_progress) = &mut self.on_progress {
+            if self.words.is_multiple_of(self.progress_every) {
+                on_progress(self.words);
+            }
+        }
+        Ok(())
+    }
+
+    /// The variants of a word of the words file in the cases of [`DictOptions::expand_case`].
+    fn variants(&self, text: &str, weight: f64) -> Vec<Word> {
+        if self.options.expand_case.is_empty() {
+            return Vec::new();
+        }
+        let match_form: Arc<str> = Arc::from(text);
+        self.options.expand_case.iter()
+            .map(|case| case.apply(text))
+            .map(|variant| Word { text: variant, weight, source: self.source.clone(), match_form: Some(match_form.clone()) })
+            .collect()
+    }
+
+    /// How many words were added.
+    pub fn words(&self) -> usize {
+        self.words
+    }
+
+    /// The dictionary, looked up by hashing the encodings of the digits.
+    pub fn finish(self) -> Dictionary {
+        self.finish_all().pop().unwrap_or_default()
+    }
+
+    /// The dictionary as a [`Trie`], looked up digit by digit.
+    pub fn finish_trie(self) -> Trie {
+        Trie::new(&self.finish())
+    }
+
+    /// The dictionary of each mapping given to [`DictionaryBuilder::for_mappings`].
+    pub fn finish_all(self) -> Vec<Dictionary> {
+        let mut dicts = self.dicts;
+        for dict in &mut dicts {
+            if self.weighted {
+                for words in dict.values_mut() {
+                    // stable, so words with the same weight stay in the order of the file
+                    words.sort_by(|a, b| b.weight.partial_cmp(&a.weight).unwrap_or(Ordering::Equal));
+                }
+            }
+            if self.options.annotate_source {
+                for word in dict.values_mut().flatten() {
+                    if let Some(source) = &word.source {
+                        word.text = format!("{}[{}]", word.text, source);
+                    }
+                }
+            }
+        }
+        dicts
+    }
+}
+
+fn parse_weight(weight: &str, line_number: usize) -> Result<f64, EncodeError> {
+    weight.trim().parse()
+        .map_err(|_| EncodeError::InvalidWeight { line: line_number, weight: weight.to_string() })
+}
+
+/// Phrases are printed with single spaces between their words.
+fn normalize_phrase(line: &str) -> String {
+    if !line.contains(char::is_whitespace) {
+        return line.to_string();
+    }
+    line.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn add_folding_case(words: &mut Vec<Word>, mut word: Word, fold: CaseFold) {
+    if fold == CaseFold::Lower {
+        word.text = word.text.to_lowercase();
+    }
+    let lower = word.text.to_lowercase();
+    // with CaseFold::All, the first variant stands for the whole entry
+    let existing = words.iter_mut()
+        .find(|w| w.text.split('/').next().unwrap_or_default().to_lowercase() == lower);
+    match existing {
+        Some(existing) => {
+            // merged entries keep the highest weight of their variants
+            existing.weight = existing.weight.max(word.weight);
+            if fold == CaseFold::All && !existing.text.split('/').any(|w| w == word.text) {
+                existing.text.push('/');
+                existing.text.push_str(&word.text);
+            }
+        }
+        None => words.push(word),
+    }
+}
+
+// The output is wrapped in a Result to allow matching on errors
+// Returns an Iterator to the Reader of the lines of the file.
+fn read_lines<P>(filename: P) -> io::Result<io::Lines<Box<dyn BufRead>>>
+    where P: AsRef<Path>, {
+    let file = File::open(&filename)?;
+    Ok(decompressed(filename.as_ref(), io::BufReader::new(file)).lines())
+}
+
+/// Returns the digits of a dictionary key, i.e. the key without its leading `1`.
+pub fn key_digits(key: &BigUint) -> String {
+    let mut digits = key.to_str_radix(10);
+    digits.remove(0);
+    digits
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::search::Lookup;
+
+    fn word(text: &str, weight: f64) -> Word {
+        Word { text: text.to_string(), weight, source: None, match_form: None }
+    }
+
+    fn texts<'a>(dict: &'a Dictionary, key: &str) -> Vec<&'a str> {
+        let key = word_to_number(key, &Mapping::default());
+        dict.get(&key).map_or_else(Vec::new, |words| words.iter().map(|w| w.text.as_str()).collect())
+    }
+
+    #[test]
+    fn insert_word_adds_under_the_key_of_its_encoding() {
+        let mut dict = Dictionary::new();
+        let mapping = Mapping::default();
+        assert_eq!(dict.insert_word(word("mir", 0.0), &mapping).unwrap(), None);
+        assert_eq!(dict.insert_word(word("Mix", 0.0), &mapping).unwrap(), None);
+        assert_eq!(dict.len(), 1);
+        assert_eq!(texts(&dict, "mir"), ["mir", "Mix"]);
+    }
+
+    #[test]
+    fn insert_word_replaces_a_duplicate() {
+        let mut dict = Dictionary::new();
+        let mapping = Mapping::default();
+        dict.insert_word(word("mir", 1.0), &mapping).unwrap();
+        dict.insert_word(word("Mix", 0.0), &mapping).unwrap();
+        let replaced = dict.insert_word(word("mir", 0.0), &mapping).unwrap();
+        assert_eq!(replaced, Some(word("mir", 1.0)));
+        assert_eq!(texts(&dict, "mir"), ["Mix", "mir"]);
+    }
+
+    #[test]
+    fn insert_word_keeps_words_in_descending_order_of_weight() {
+        let mut dict = Dictionary::new();
+        let mapping = Mapping::default();
+        dict.insert_word(word("mir", 1.0), &mapping).unwrap();
+        dict.insert_word(word("Mix", 3.0), &mapping).unwrap();
+        dict.insert_word(word("Mir", 1.0), &mapping).unwrap();
+        assert_eq!(texts(&dict, "mir"), ["Mix", "mir", "Mir"]);
+    }
+
+    #[test]
+    fn exact_finds_the_words_of_the_whole_digits() {
+        let mut dict = Dictionary::new();
+        let mapping = Mapping::default();
+        for text in ["mir", "Mix", "Tor", "Torf"] {
+            dict.insert_word(word(text, 0.0), &mapping).unwrap();
+        }
+        let texts = |digits| dict.exact(digits).iter().map(|w| w.text.as_str()).collect::<Vec<_>>();
+        assert_eq!(texts("562"), ["mir", "Mix"]);
+        assert_eq!(texts("48-2"), ["Tor"]);
+        // not the words of a prefix, nor of a segmentation
+        assert!(texts("56").is_empty());
+        assert!(texts("562482").is_empty());
+    }
+
+    #[test]
+    fn remove_word_removes_all_copies() {
+        let mut dict = Dictionary::new();
+        let mapping = Mapping::default();
+        let key = word_to_number("mir", &mapping);
+        dict.insert(key, vec![word("mir", 0.0), word("Mix", 0.0), word("mir", 0.0)]);
+        assert_eq!(dict.remove_word("mir", &mapping).unwrap(), Some(word("mir", 0.0)));
+        assert_eq!(texts(&dict, "mir"), ["Mix"]);
+        assert_eq!(dict.remove_word("mir", &mapping).unwrap(), None);
+    }
+
+    #[test]
+    fn remove_word_removes_empty_keys() {
+        let mut dict = Dictionary::new();
+        let mapping = Mapping::default();
+        dict.insert_word(word("mir", 0.0), &mapping).unwrap();
+        dict.insert_word(word("Tor", 0.0), &mapping).unwrap();
+        assert!(dict.remove_word("mir", &mapping).unwrap().is_some());
+        assert!(!dict.contains_key(&word_to_number("mir", &mapping)));
+        assert_eq!(dict.len(), 1);
+    }
+
+    #[test]
+    fn remove_word_ignores_unknown_words() {
+        let mut dict = Dictionary::new();
+        let mapping = Mapping::default();
+        dict.insert_word(word("mir", 0.0), &mapping).unwrap();
+        assert_eq!(dict.remove_word("Tor", &mapping).unwrap(), None);
+        assert_eq!(dict.remove_word("Mix", &mapping).unwrap(), None);
+        assert_eq!(texts(&dict, "mir"), ["mir"]);
+    }
+
+    #[test]
+    fn updates_reject_letters_the_mapping_cannot_encode() {
+        let mut dict = Dictionary::new();
+        let mapping = Mapping::default();
+        dict.insert_word(word("mir", 0.0), &mapping).unwrap();
+        assert!(matches!(dict.insert_word(word("Fuß", 0.0), &mapping), Err(EncodeError::InvalidWord { line: 1, char: 'ß' })));
+        assert!(matches!(dict.remove_word("Fuß", &mapping), Err(EncodeError::InvalidWord { line: 1, char: 'ß' })));
+        assert_eq!(dict.len(), 1);
+        assert_eq!(texts(&dict, "mir"), ["mir"]);
+    }
+
+    #[test]
+    fn words_from_bytes_skips_words_the_mapping_cannot_encode() {
+        let dict = crate::search::words_from_bytes("mir\nFuß\nTor\n".as_bytes(), &Mapping::default());
+        let words: Vec<&str> = dict.values().flatten().map(|w| w.text.as_str()).collect();
+        assert_eq!(words, ["Tor", "mir"]);
+    }
+
+    #[test]
+    fn builder_reports_progress_and_sorts_by_weight() {
+        let mut progress = Vec::new();
+        let mut builder = DictionaryBuilder::new(&DictOptions::default())
+            .on_progress(2, |words| progress.push(words));
+        for line in ["mir", "", "Mix\t2", "Tor"] {
+            builder.push(line).unwrap();
+        }
+        assert_eq!(builder.words(), 3);
+        let dict = builder.finish();
+        assert_eq!(progress, [2]);
+        assert_eq!(texts(&dict, "mir"), ["Mix", "mir"]);
+        assert_eq!(texts(&dict, "Tor"), ["Tor"]);
+    }
+
+    #[test]
+    fn builder_expands_the_case_of_the_words() {
+        let options = DictOptions { expand_case: vec![WordCase::Title, WordCase::Upper], ..DictOptions::default() };
+        let mut builder = DictionaryBuilder::new(&options);
+        for line in ["mir", "Tor", "so ein"] {
+            builder.push(line).unwrap();
+        }
+        assert_eq!(builder.words(), 3);
+        let mut dict = builder.finish();
+        assert_eq!(texts(&dict, "mir"), ["mir", "Mir", "MIR"]);
+        // already in title case
+        assert_eq!(texts(&dict, "Tor"), ["Tor", "TOR"]);
+        assert_eq!(texts(&dict, "soein"), ["so ein", "So Ein", "SO EIN"]);
+        let key = word_to_number("mir", &Mapping::default());
+        assert!(dict[&key].iter().all(|w| w.matched() == "mir"));
+        assert_eq!(dict[&key].iter().filter(|w| w.is_variant()).count(), 2);
+        // the variants go with their word
+        assert!(dict.remove_word("mir", &Mapping::default()).unwrap().is_some());
+        assert_eq!(texts(&dict, "mir"), Vec::<&str>::new());
+    }
+
+    #[test]
+    fn builder_errors_name_the_line_of_their_source() {
+        let mut builder = DictionaryBuilder::new(&DictOptions::default());
+        builder.set_source("words");
+        builder.push("mir").unwrap();
+        assert!(matches!(builder.push("T\u{7}or"), Err(EncodeError::InvalidWord { line: 2, char: '\u{7}' })));
+    }
+}
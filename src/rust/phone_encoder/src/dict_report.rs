@@ -0,0 +1,128 @@
+//! The report of a dictionary as loaded (`--dict-report`), listing what curators of words
+//! files otherwise find by reading the solutions: the words given more than once, the words
+//! differing only in case, and the digits encoding the most words, which multiply the
+//! solutions of every number containing them.
+//!
+//! Words are compared as printed, but without the source of `--annotate-source`, so that a
+//! word given by two words files is still a duplicate.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::dict::{key_digits, Dictionary};
+use crate::search::Word;
+
+/// How many of the digits encoding the most words are reported by default.
+pub const DEFAULT_TOP: usize = 10;
+
+/// What [`DictReport::new`] finds in a dictionary, each list sorted, so that the report of
+/// the same words is the same in every run.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DictReport {
+    pub words: usize,
+    /// how many different digits encode the words.
+    pub keys: usize,
+    pub duplicates: Vec<Duplicate>,
+    /// the groups of words differing only in case, in alphabetical order.
+    pub case_variants: Vec<Vec<String>>,
+    /// the digits encoding the most words, with how many, the most first.
+    pub collisions: Vec<(String, usize)>,
+}
+
+/// A word given more than once.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Duplicate {
+    pub text: String,
+    pub times: usize,
+    /// the different sources of the word, in the order they were loaded.
+    pub sources: Vec<String>,
+}
+
+impl DictReport {
+    /// Reports the words of `dict`, and the `top` digits encoding the most words.
+    ///
+    /// Words are only compared with the others encoded by the same digits, which include all
+    /// their duplicates and case variants.
+    pub fn new(dict: &Dictionary, top: usize) -> DictReport {
+        let mut report = DictReport { keys: dict.len(), ..DictReport::default() };
+        let mut collisions = Vec::with_capacity(dict.len());
+        for (key, words) in dict {
+            // the variants added by --expand-case are not words of the files
+            let words: Vec<&Word> = words.iter().filter(|word| !word.is_variant()).collect();
+            report.words += words.len();
+            collisions.push((words.len(), key));
+            let mut same_text: HashMap<&str, Duplicate> = HashMap::new();
+            let mut same_letters: HashMap<String, Vec<String>> = HashMap::new();
+            for word in words {
+                let text = bare_text(word);
+                let duplicate = same_text.entry(text).or_insert_with(|| Duplicate { text: text.to_string(), ..Duplicate::default() });
+                duplicate.times += 1;
+                if let Some(source) = &word.source {
+                    if !duplicate.sources.iter().any(|known| **known == **source) {
+                        duplicate.sources.push(source.to_string());
+                    }
+                }
+                let variants = same_letters.entry(text.to_lowercase()).or_default();
+                if !variants.iter().any(|variant| variant == text) {
+                    variants.push(text.to_string());
+                }
+            }
+            report.duplicates.extend(same_text.into_values().filter(|duplicate| duplicate.times > 1));
+            report.case_variants.extend(same_letters.into_values().filter(|variants| variants.len() > 1));
+        }
+        report.duplicates.sort_unstable_by(|a, b| a.text.cmp(&b.text));
+        for variants in &mut report.case_variants {
+            variants.sort_unstable();
+        }
+        report.case_variants.sort_unstable();
+        // the largest first, and then the shortest digits
+        collisions.sort_unstable_by(|(a_len, a_key), (b_len, b_key)| b_len.cmp(a_len).then(a_key.cmp(b_key)));
+        report.collisions = collisions.into_iter()
+            .take(top)
+            .map(|(len, key)| (key_digits(key), len))
+            .collect();
+        report
+    }
+}
+
+/// The text of a word without the `[source]` appended by `--annotate-source`.
+fn bare_text(word: &Word) -> &str {
+    let Some(source) = &word.source else { return &word.text };
+    word.text.strip_suffix(']')
+        .and_then(|text| text.strip_suffix(&**source))
+        .and_then(|text| text.strip_suffix('['))
+        .unwrap_or(&word.text)
+}
+
+/// ```text
+/// 10 words encoded by 3 different digits
+/// 1 duplicate words:
+///     mir (3 times: words, extra)
+/// 1 groups of words differing only in case:
+///     TOR, Tor, tor
+/// the 2 digits encoding the most words:
+///     562: 4 words
+///     482: 3 words
+/// ```
+impl fmt::Display for DictReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{} words encoded by {} different digits", self.words, self.keys)?;
+        writeln!(f, "{} duplicate words:", self.duplicates.len())?;
+        for duplicate in &self.duplicates {
+            write!(f, "    {} ({} times", duplicate.text, duplicate.times)?;
+            if !duplicate.sources.is_empty() {
+                write!(f, ": {}", duplicate.sources.join(", "))?;
+            }
+            writeln!(f, ")")?;
+        }
+        writeln!(f, "{} groups of words differing only in case:", self.case_variants.len())?;
+        for variants in &self.case_variants {
+            writeln!(f, "    {}", variants.join(", "))?;
+        }
+        writeln!(f, "the {} digits encoding the most words:", self.collisions.len())?;
+        for (digits, words) in &self.collisions {
+            writeln!(f, "    {}: {} words", digits, words)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,146 @@
+//! Depth-first search guided by the number of encodings of each suffix of the digits.
+
+use alloc::vec::Vec;
+
+use crate::filter::SolutionFilter;
+use crate::search::{
+    Limits, EncodeStats, Lookup, OnRejected, OnSolution, SearchStrategy, Solutions, Word, WordOrDigit,
+};
+
+/// Counts the encodings of the digits from each position to the end first (with dynamic
+/// programming, from the last digit backwards), then only explores the partial solutions
+/// that can be completed, so the search never enters a dead end.
+///
+/// The dictionary is also looked up only once for each position of the digits.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct DpCount;
+
+impl SearchStrategy for DpCount {
+    fn search<D: Lookup + ?Sized, E>(
+        &self,
+        digits: &[char],
+        dict: &D,
+        filter: &dyn SolutionFilter,
+        limits: Limits,
+        on_solution: &mut OnSolution<E>,
+        on_rejected: &mut OnRejected<E>,
+    ) -> Result<EncodeStats, E> {
+        let matches: Vec<_> = (0..digits.len())
+            .map(|start| words_at(digits, start, dict))
+            .collect();
+        // all lookups are done up front
+        let lookups = (digits.len() * (digits.len() + 1) / 2) as u64;
+        search_matches(digits, &matches, lookups, filter, limits, on_solution, on_rejected)
+    }
+}
+
+/// The search of [`DpCount`], given the words encoding the digits from each position with
+/// the position after their last digit, shortest first, found with `lookups` lookups.
+pub(crate) fn search_matches<'a, E>(
+    digits: &'a [char],
+    matches: &'a [Vec<(usize, &'a [Word])>],
+    lookups: u64,
+    filter: &'a dyn SolutionFilter,
+    limits: Limits<'a>,
+    on_solution: &'a mut OnSolution<E>,
+    on_rejected: &'a mut OnRejected<E>,
+) -> Result<EncodeStats, E> {
+    let counts = count_encodings(matches);
+    let mut dp = Dp {
+        digits,
+        matches,
+        counts: &counts,
+        solutions: Solutions::new(digits, filter, limits, on_solution, on_rejected),
+    };
+    dp.solutions.stats.lookups = lookups;
+    if counts[0][0] > 0 {
+        find_translations(&mut dp, 0, &mut Vec::with_capacity(digits.len()))?;
+    }
+    Ok(dp.solutions.stats)
+}
+
+/// Whether the digits have any encoding, found by counting them like [`DpCount`] does.
+///
+/// This takes at most one lookup for each pair of positions of the digits, even for digits
+/// without any encoding, for which the depth-first search explores every dead end.
+pub fn has_encoding<D: Lookup + ?Sized>(digits: &[char], dict: &D) -> bool {
+    encoding_count(digits, dict) > 0
+}
+
+/// The number of encodings of the digits, counted like [`DpCount`] does without finding
+/// them (saturating at `u64::MAX`), regardless of any [`SolutionFilter`].
+pub fn encoding_count<D: Lookup + ?Sized>(digits: &[char], dict: &D) -> u64 {
+    let matches: Vec<_> = (0..digits.len())
+        .map(|start| words_at(digits, start, dict))
+        .collect();
+    count_encodings(&matches)[0][0]
+}
+
+/// The words encoding the digits from `start`, with the position after their last digit.
+fn words_at<'a, D: Lookup + ?Sized>(digits: &'a [char], start: usize, dict: &'a D) -> Vec<(usize, &'a [Word])> {
+    dict.matches_at(&digits[start..]).map(|(len, words)| (start + len, words)).collect()
+}
+
+/// The number of encodings of the digits from each position (and one past the end),
+/// after a word (index `0`) or after a digit (index `1`), saturating at `u64::MAX`.
+fn count_encodings(matches: &[Vec<(usize, &[Word])>]) -> Vec<[u64; 2]> {
+    let mut counts = alloc::vec![[0u64; 2]; matches.len() + 1];
+    counts[matches.len()] = [1, 1];
+    for start in (0..matches.len()).rev() {
+        let after_word = matches[start].iter()
+            .map(|(end, words)| (words.len() as u64).saturating_mul(counts[*end][0]))
+            .fold(0u64, u64::saturating_add);
+        counts[start] = if matches[start].is_empty() {
+            // a digit may only be used where no word fits, and not right after another digit
+            [counts[start + 1][1], 0]
+        } else {
+            [after_word, after_word]
+        };
+    }
+    counts
+}
+
+struct Dp<'a, 'w, E> {
+    digits: &'a [char],
+    matches: &'a [Vec<(usize, &'a [Word])>],
+    counts: &'a [[u64; 2]],
+    solutions: Solutions<'a, 'w, E>,
+}
+
+fn find_translations<'a, E>(dp: &mut Dp<'a, '_, E>, start: usize, words: &mut Vec<WordOrDigit<'a>>) -> Result<(), E> {
+    if dp.solutions.is_full() || dp.solutions.too_deep(words.len()) {
+        return Ok(());
+    }
+    if start >= dp.digits.len() {
+        return dp.solutions.check(words);
+    }
+    dp.solutions.visit(0);
+    let matches = dp.matches;
+    if matches[start].is_empty() {
+        words.push(WordOrDigit::Digit(dp.digits[start]));
+        find_translations(dp, start + 1, words)?;
+        words.pop();
+        return Ok(());
+    }
+    let mut branches = 0;
+    for (end, found_words) in &matches[start] {
+        if dp.counts[*end][0] == 0 {
+            // still ways to go on, as for the other searches
+            branches += found_words.len();
+            if dp.solutions.too_wide(branches) {
+                return Ok(());
+            }
+            continue;
+        }
+        for word in *found_words {
+            branches += 1;
+            if dp.solutions.too_wide(branches) {
+                return Ok(());
+            }
+            words.push(WordOrDigit::Word(&word.text));
+            find_translations(dp, *end, words)?;
+            words.pop();
+        }
+    }
+    Ok(())
+}
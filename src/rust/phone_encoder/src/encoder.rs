@@ -0,0 +1,790 @@
+use std::borrow::Cow;
+use std::convert::Infallible;
+use std::io::{self, Write};
+use std::ops::Range;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use crate::aho_corasick::{AhoCorasick, AhoCorasickDp};
+use crate::cache::{mapping_id, CacheKey};
+use crate::dict::{load_dict, load_dicts, DictOptions, Dictionary, WordsFile};
+use crate::dp::{encoding_count, has_encoding, DpCount};
+use crate::error::EncodeError;
+use crate::filter::{AcceptAll, Rejection, Rules, SolutionFilter};
+use crate::format::{Field, Format, OutputFormat, PlainFormat, Quoting};
+use crate::ids::{SolutionIds, WordIds};
+use crate::iterative::IterativeDfs;
+use crate::mapping::Mapping;
+use crate::number::{check_number, normalize_number, DigitString, InvalidNumber};
+use crate::pool::{BufferPool, Lease, PoolWriter};
+use crate::redact::{redacted, Redaction};
+use crate::search::{
+    search, search_observed, Algorithm, Limits, Lookup, OnRejected, SearchObserver, SearchStrategy, DEFAULT_MAX_BRANCHING,
+    DEFAULT_MAX_DEPTH, SPLIT_THRESHOLD,
+};
+use crate::sink::{Checksum, Discard, Formatter, SolutionRecord, SolutionSink};
+use crate::split::MeetInTheMiddle;
+use crate::stats::TopNumbers;
+use crate::trie::{Trie, TrieWalk};
+use crate::verify::{explain, verify_solution, Explanation, VerifyError};
+#[cfg(feature = "arena")]
+use crate::search::{extract_digits, search_in};
+
+pub use crate::search::{EncodeStats, WordOrDigit};
+
+#[cfg(feature = "arena")]
+thread_local! {
+    /// The arena for the temporaries of the search of a number, reset after each number.
+    static ARENA: std::cell::RefCell<bumpalo::Bump> = std::cell::RefCell::new(bumpalo::Bump::new());
+}
+
+/// Options used when encoding phone numbers.
+#[derive(Clone, Debug, Default)]
+#[non_exhaustive]
+pub struct EncodeOptions {
+    /// maximum number of solutions to print for each phone number.
+    pub limit: Option<usize>,
+    /// after the solutions of a number cut short by `limit`, write a marker with how many
+    /// more there are (see [`OutputFormat::render_truncated`]).
+    pub limit_marker: bool,
+    /// measure the time spent formatting and writing solutions.
+    pub timings: bool,
+    /// the algorithm used to search for solutions.
+    pub algorithm: Algorithm,
+    /// maximum number of solutions to print for all phone numbers together.
+    pub max_total: Option<usize>,
+    /// time after which the search for the encodings of a phone number is abandoned.
+    pub timeout_per_number: Option<Duration>,
+    /// the most parts of a partial solution, beyond which the search for the encodings of a
+    /// phone number is abandoned ([`DEFAULT_MAX_DEPTH`] if not set, see [`Limits::depth`]).
+    pub max_depth: Option<usize>,
+    /// the most ways to go on from a partial solution, beyond which the search is abandoned
+    /// ([`DEFAULT_MAX_BRANCHING`] if not set, see [`Limits::branching`]).
+    pub max_branching: Option<usize>,
+    /// how words are quoted in the printed solutions.
+    pub quoting: Quoting,
+    /// print the digits encoded by each word after it, e.g. `mir[562]`.
+    pub annotate: bool,
+    /// discard the solutions instead of writing them.
+    pub no_output: Option<NoOutput>,
+    /// how solutions are written.
+    pub format: Format,
+    /// color the solutions with ANSI escape codes, in the formats written for people.
+    pub color: bool,
+    /// reject phone numbers with characters other than digits and separators, instead of
+    /// only those with letters (see [`check_number`]).
+    pub strict: bool,
+    /// the names of the columns following the number, separated by tabs, on the lines given to
+    /// [`Encoder::encode_to`] instead of numbers: their values are written with each solution
+    /// by the formats that have columns (see [`Format::renderer`](crate::format::Format::renderer)).
+    pub columns: Vec<String>,
+    /// the members of the objects written by the json format, if not the default ones.
+    pub fields: Vec<Field>,
+    /// what is done with the phone numbers without any digit.
+    pub empty_numbers: EmptyNumbers,
+    /// how the phone numbers are written in the solutions and the errors, if not as given.
+    pub redact: Option<Redaction>,
+}
+
+/// What is done with the phone numbers without any digit, e.g. `-/-` or an empty line,
+/// which are counted in [`EncodeStats::empty_numbers`] either way.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum EmptyNumbers {
+    /// the number is encoded as usual, with a single, empty solution.
+    #[default]
+    PrintEmpty,
+    /// the number is skipped, without any solution.
+    Skip,
+    /// encoding the number fails with [`EncodeError::EmptyNumber`].
+    Error,
+}
+
+/// How solutions are discarded with [`EncodeOptions::no_output`], to measure
+/// how much time goes into formatting and writing them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum NoOutput {
+    /// solutions are formatted as usual, but written to a sink.
+    Format,
+    /// solutions are not even formatted.
+    Skip,
+    /// solutions are not formatted, but checksummed into [`EncodeStats::checksum`], to
+    /// compare the solutions of runs without writing them (see [`Checksum`]).
+    Checksum,
+}
+
+/// Encodes phone numbers using the words of a [`Dictionary`].
+pub struct Encoder {
+    dict: Dictionary,
+    options: EncodeOptions,
+    // shared with the copies made by Encoder::with_dictionary
+    filter: Arc<dyn SolutionFilter + Send + Sync>,
+    format: Arc<dyn OutputFormat + Send + Sync>,
+    rejected_out: Option<Arc<Mutex<Box<dyn Write + Send>>>>,
+    skipped_out: Option<Arc<Mutex<Box<dyn Write + Send>>>>,
+    /// the dictionary as a trie, for [`Algorithm::Trie`].
+    trie: OnceLock<Trie>,
+    /// the ids of the keys of the dictionary, for [`Encoder::solution_ids`].
+    word_ids: OnceLock<WordIds>,
+    /// the dictionary as an Aho-Corasick automaton, for [`Encoder::contained_words`] and
+    /// [`Algorithm::AhoCorasick`].
+    aho_corasick: OnceLock<AhoCorasick>,
+    /// how many more solutions may be printed, if [`EncodeOptions::max_total`] is set.
+    total_remaining: AtomicUsize,
+    /// the numbers with the most solutions, if recorded.
+    top_numbers: Option<Arc<TopNumbers>>,
+    /// what the solutions depend on besides the dictionary, for [`Encoder::cache_key`].
+    mapping_id: u64,
+    rules: Option<Rules>,
+}
+
+impl Encoder {
+    pub fn new(dict: Dictionary) -> Encoder {
+        Encoder {
+            dict,
+            options: EncodeOptions::default(),
+            filter: Arc::new(AcceptAll),
+            format: Arc::new(PlainFormat::default()),
+            rejected_out: None,
+            skipped_out: None,
+            trie: OnceLock::new(),
+            word_ids: OnceLock::new(),
+            aho_corasick: OnceLock::new(),
+            total_remaining: AtomicUsize::new(usize::MAX),
+            top_numbers: None,
+            mapping_id: mapping_id(&Mapping::default()),
+            rules: None,
+        }
+    }
+
+    pub fn load<P: AsRef<Path>>(words_file: P, options: &DictOptions) -> Result<Encoder, EncodeError> {
+        Ok(Encoder::new(load_dict(words_file, options)?).with_mapping(&options.mapping))
+    }
+
+    pub fn load_all(files: &[WordsFile], options: &DictOptions) -> Result<Encoder, EncodeError> {
+        Ok(Encoder::new(load_dicts(files, options)?).with_mapping(&options.mapping))
+    }
+
+    /// Tells the encoder the mapping its dictionary was loaded with (which [`Encoder::load`]
+    /// does), for [`Encoder::cache_key`].
+    pub fn with_mapping(mut self, mapping: &Mapping) -> Encoder {
+        self.mapping_id = mapping_id(mapping);
+        self
+    }
+
+    pub fn with_options(mut self, options: EncodeOptions) -> Encoder {
+        self.total_remaining = AtomicUsize::new(options.max_total.unwrap_or(usize::MAX));
+        self.format = options.format.renderer(&options).into();
+        self.options = options;
+        self
+    }
+
+    /// Uses the given filter to decide which candidate solutions are printed.
+    pub fn with_filter<F: SolutionFilter + Send + Sync + 'static>(mut self, filter: F) -> Encoder {
+        self.filter = Arc::new(filter);
+        self
+    }
+
+    /// Uses the given rules as the filter, like [`Encoder::with_filter`], also telling
+    /// [`Encoder::cache_key`] about them.
+    pub fn with_rules(mut self, rules: Rules) -> Encoder {
+        self.rules = Some(rules);
+        self.with_filter(rules)
+    }
+
+    /// The key of the solutions of a number in a [`SolutionCache`](crate::cache::SolutionCache),
+    /// for the mapping and rules of the encoder.
+    pub fn cache_key(&self, num: &str) -> Result<CacheKey, InvalidNumber> {
+        Ok(CacheKey::new(normalize_number(num, self.options.strict)?, self.mapping_id, self.rules))
+    }
+
+    /// Writes solutions with the given format instead of the one of the [`EncodeOptions`].
+    pub fn with_format<F: OutputFormat + Send + Sync + 'static>(mut self, format: F) -> Encoder {
+        self.format = Arc::new(format);
+        self
+    }
+
+    /// Writes the candidate solutions rejected by the filter, with the reason, to `out`.
+    pub fn with_rejected_output<W: Write + Send + 'static>(mut self, out: W) -> Encoder {
+        self.rejected_out = Some(Arc::new(Mutex::new(Box::new(out))));
+        self
+    }
+
+    /// Writes the phone numbers whose search was abandoned after
+    /// [`EncodeOptions::timeout_per_number`], or beyond [`EncodeOptions::max_depth`] or
+    /// [`EncodeOptions::max_branching`], to `out`, each followed by a tab and the reason.
+    pub fn with_skipped_output<W: Write + Send + 'static>(mut self, out: W) -> Encoder {
+        self.skipped_out = Some(Arc::new(Mutex::new(Box::new(out))));
+        self
+    }
+
+    /// Records the `count` numbers with the most solutions printed, for [`Encoder::top_numbers`].
+    pub fn with_top_numbers(mut self, count: usize) -> Encoder {
+        self.top_numbers = Some(Arc::new(TopNumbers::new(count)));
+        self
+    }
+
+    /// The numbers with the most solutions printed so far, most first, if recorded (see
+    /// [`Encoder::with_top_numbers`]).
+    pub fn top_numbers(&self) -> Vec<(String, usize)> {
+        self.top_numbers.as_ref().map(|top| top.numbers()).unwrap_or_default()
+    }
+
+    pub fn dictionary(&self) -> &Dictionary {
+        &self.dict
+    }
+
+    /// A copy of the encoder using another dictionary, e.g. one updated with
+    /// [`DictionaryUpdates`](crate::dict::DictionaryUpdates), with the same options, filter
+    /// and outputs (and what is left of the [`EncodeOptions::max_total`]).
+    ///
+    /// With a [`Snapshot`](crate::snapshot::Snapshot) of the encoder, the copy can replace
+    /// it while other threads are still encoding with it.
+    pub fn with_dictionary(&self, dict: Dictionary) -> Encoder {
+        Encoder {
+            dict,
+            options: self.options.clone(),
+            filter: self.filter.clone(),
+            format: self.format.clone(),
+            rejected_out: self.rejected_out.clone(),
+            skipped_out: self.skipped_out.clone(),
+            trie: OnceLock::new(),
+            word_ids: OnceLock::new(),
+            aho_corasick: OnceLock::new(),
+            total_remaining: AtomicUsize::new(self.total_remaining.load(Ordering::Relaxed)),
+            top_numbers: self.top_numbers.clone(),
+            mapping_id: self.mapping_id,
+            rules: self.rules,
+        }
+    }
+
+    /// Prints all encodings of the given phone number to stdout.
+    pub fn encode(&self, num: &str) -> Result<EncodeStats, EncodeError> {
+        self.encode_to(num, &mut io::stdout().lock())
+    }
+
+    /// Writes all encodings of the given phone number to `out`.
+    ///
+    /// With [`EncodeOptions::columns`], `num` is the line of the number instead: the number
+    /// followed by the values of the columns, separated by tabs.
+    pub fn encode_to(&self, num: &str, out: &mut dyn Write) -> Result<EncodeStats, EncodeError> {
+        let (num, columns) = self.split_columns(num);
+        match self.options.no_output {
+            None => self.format_to(num, &columns, out),
+            Some(NoOutput::Format) => self.format_to(num, &columns, &mut io::sink()),
+            Some(NoOutput::Skip) => self.encode_into(num, &mut Discard),
+            Some(NoOutput::Checksum) => {
+                let mut checksum = Checksum::new(num);
+                let mut stats = self.encode_into(num, &mut checksum)?;
+                stats.checksum = checksum.sum;
+                Ok(stats)
+            }
+        }
+    }
+
+    /// Gives each encoding of the given phone number to `sink`, as a [`SolutionRecord`].
+    pub fn encode_into(&self, num: &str, sink: &mut dyn SolutionSink) -> Result<EncodeStats, EncodeError> {
+        let mut index = 0;
+        self.for_each_solution(num, &mut |parts| {
+            let record = SolutionRecord { index, parts };
+            index += 1;
+            sink.solution(&record)
+        })
+    }
+
+    /// Writes all encodings of the given phone number like [`Encoder::encode_to`], into
+    /// buffers leased from `pool`, giving each to `on_buffer` once full, and the last one at
+    /// the end (see [`PoolWriter`]).
+    pub fn encode_leased(
+        &self,
+        num: &str,
+        pool: &BufferPool,
+        on_buffer: &mut dyn FnMut(Lease) -> io::Result<()>,
+    ) -> Result<EncodeStats, EncodeError> {
+        let mut out = PoolWriter::new(pool, on_buffer);
+        let stats = self.encode_to(num, &mut out)?;
+        out.finish()?;
+        Ok(stats)
+    }
+
+    /// The number as written in the solutions and the errors, redacted with
+    /// [`EncodeOptions::redact`].
+    fn shown<'a>(&self, num: &'a str) -> Cow<'a, str> {
+        redacted(self.options.redact, num)
+    }
+
+    /// The number on a line and the values of the columns after it, if there are columns.
+    fn split_columns<'a>(&self, line: &'a str) -> (&'a str, Vec<&'a str>) {
+        if self.options.columns.is_empty() {
+            return (line, Vec::new());
+        }
+        let mut fields = line.split('\t');
+        let num = fields.next().unwrap_or_default();
+        (num, fields.collect())
+    }
+
+    fn format_to(&self, num: &str, columns: &[&str], out: &mut dyn Write) -> Result<EncodeStats, EncodeError> {
+        let format = self.format.as_ref();
+        let shown = self.shown(num);
+        let stats = if self.options.annotate {
+            let digits = self.digits(num);
+            self.encode_into(num, &mut Formatter::new(format, out, &shown, columns).annotated(&digits, &self.dict))?
+        } else {
+            self.encode_into(num, &mut Formatter::new(format, out, &shown, columns))?
+        };
+        // the solutions are only counted for the numbers that reached the limit
+        if self.options.limit_marker && self.options.limit == Some(stats.solutions) {
+            let more = self.accepted_count(&self.digits(num)).saturating_sub(stats.solutions as u64);
+            if more > 0 {
+                format.render_truncated(out, &shown, columns, more)?;
+            }
+        }
+        Ok(stats)
+    }
+
+    /// Writes what the format puts before all solutions, unless they are not written.
+    pub fn write_header(&self, out: &mut dyn Write) -> io::Result<()> {
+        match self.options.no_output {
+            None => self.format.render_header(out),
+            Some(_) => Ok(()),
+        }
+    }
+
+    /// Writes what the format puts after all solutions, given the counts for the `numbers`
+    /// encoded, unless solutions are not written.
+    pub fn write_summary(&self, out: &mut dyn Write, numbers: usize, stats: &EncodeStats) -> io::Result<()> {
+        match self.options.no_output {
+            None => self.format.render_summary(out, numbers, stats),
+            Some(_) => Ok(()),
+        }
+    }
+
+    /// Calls `on_solution` with the parts of each encoding of the given phone number.
+    pub fn for_each_solution(
+        &self,
+        num: &str,
+        on_solution: &mut dyn FnMut(&[WordOrDigit]) -> io::Result<()>,
+    ) -> Result<EncodeStats, EncodeError> {
+        self.for_each_solution_until(num, None, on_solution)
+    }
+
+    /// Like [`Encoder::for_each_solution`], abandoning the search at the `deadline` (or after
+    /// [`EncodeOptions::timeout_per_number`], if sooner), e.g. that of a request to a server,
+    /// which is then [`EncodeStats::interrupted`].
+    pub fn for_each_solution_until(
+        &self,
+        num: &str,
+        deadline: Option<Instant>,
+        on_solution: &mut dyn FnMut(&[WordOrDigit]) -> io::Result<()>,
+    ) -> Result<EncodeStats, EncodeError> {
+        if let Err(invalid) = check_number(num, self.options.strict) {
+            let error = EncodeError::InvalidNumber { number: self.shown(num).into_owned(), char: invalid.char };
+            return match &self.skipped_out {
+                Some(out) => write_skipped(out, &self.shown(num), &error).map(|()| EncodeStats::default()),
+                None => Err(error),
+            };
+        }
+        let empty = !num.bytes().any(|byte| byte.is_ascii_digit());
+        match self.options.empty_numbers {
+            EmptyNumbers::Skip if empty => return Ok(EncodeStats { empty_numbers: 1, ..EncodeStats::default() }),
+            EmptyNumbers::Error if empty => return Err(EncodeError::EmptyNumber { number: self.shown(num).into_owned() }),
+            _ => {}
+        }
+        let timings = self.options.timings;
+        let max_total = self.options.max_total.is_some();
+        let mut write_time = Duration::ZERO;
+        // solutions found after another thread used up the max_total budget
+        let mut over_budget = 0;
+        let mut on_solution = |words: &[WordOrDigit]| {
+            if max_total && self.total_remaining
+                .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |n| n.checked_sub(1))
+                .is_err() {
+                over_budget += 1;
+                return Ok(());
+            }
+            if timings {
+                let start_time = Instant::now();
+                on_solution(words)?;
+                write_time += start_time.elapsed();
+                Ok(())
+            } else {
+                on_solution(words)
+            }
+        };
+        let mut on_rejected = |words: &[WordOrDigit], rejection: &Rejection| match &self.rejected_out {
+            Some(out) => write_rejected(out, &self.shown(num), words, rejection),
+            None => Ok(()),
+        };
+        // the total budget bounds the search, but is only taken solution by solution
+        // (above), as other threads may be using it at the same time
+        let limit = self.options.limit.unwrap_or(usize::MAX)
+            .min(self.total_remaining.load(Ordering::Relaxed));
+        let deadline = match (deadline, self.options.timeout_per_number.map(|timeout| Instant::now() + timeout)) {
+            (Some(deadline), Some(timeout)) => Some(deadline.min(timeout)),
+            (deadline, timeout) => deadline.or(timeout),
+        };
+        let past_deadline = || deadline.is_some_and(|deadline| Instant::now() >= deadline);
+        let limits = Limits {
+            solutions: limit,
+            interrupt: deadline.map(|_| &past_deadline as &dyn Fn() -> bool),
+            depth: self.options.max_depth.unwrap_or(DEFAULT_MAX_DEPTH),
+            branching: self.options.max_branching.unwrap_or(DEFAULT_MAX_BRANCHING),
+        };
+        let algorithm = match self.options.algorithm {
+            // a number has at most as many digits as bytes
+            Algorithm::Auto if num.len() < SPLIT_THRESHOLD => Algorithm::Dfs,
+            algorithm => algorithm,
+        };
+        let stats = if algorithm == Algorithm::Dfs {
+            self.search(num, limits, &mut on_solution, &mut on_rejected)
+        } else {
+            let digits = self.digits(num);
+            let filter = self.filter.as_ref();
+            match algorithm.resolve(digits.len()) {
+                Algorithm::Auto | Algorithm::Dfs => search(&digits, &self.dict, filter, limits, &mut on_solution, &mut on_rejected),
+                Algorithm::Iterative => IterativeDfs.search(&digits, &self.dict, filter, limits, &mut on_solution, &mut on_rejected),
+                Algorithm::DpCount => DpCount.search(&digits, &self.dict, filter, limits, &mut on_solution, &mut on_rejected),
+                Algorithm::Trie => TrieWalk.search(&digits, self.trie(), filter, limits, &mut on_solution, &mut on_rejected),
+                Algorithm::Split => MeetInTheMiddle.search(&digits, &self.dict, filter, limits, &mut on_solution, &mut on_rejected),
+                Algorithm::AhoCorasick => {
+                    AhoCorasickDp.search(&digits, self.aho_corasick(), filter, limits, &mut on_solution, &mut on_rejected)
+                }
+            }
+        };
+        let mut stats = stats?;
+        stats.solutions -= over_budget;
+        stats.solution_counts.record(stats.solutions);
+        if let Some(top) = &self.top_numbers {
+            top.record(num, stats.solutions);
+        }
+        stats.write_time = write_time;
+        stats.empty_numbers = empty as u64;
+        stats.truncated = max_total && self.total_remaining.load(Ordering::Relaxed) == 0;
+        if stats.interrupted {
+            if let (Some(out), Some(timeout)) = (&self.skipped_out, self.options.timeout_per_number) {
+                let reason = format!("timed out after {:?} ({} solutions printed)", timeout, stats.solutions);
+                write_skipped(out, &self.shown(num), &EncodeError::LimitExceeded(reason))?;
+            }
+        }
+        if let (Some(out), Some(guard)) = (&self.skipped_out, stats.guard) {
+            let reason = format!("search abandoned with {} ({} solutions printed)", guard, stats.solutions);
+            write_skipped(out, &self.shown(num), &EncodeError::LimitExceeded(reason))?;
+        }
+        Ok(stats)
+    }
+
+    /// The depth-first search, in the arena if enabled.
+    fn search(
+        &self,
+        num: &str,
+        limits: Limits,
+        on_solution: &mut dyn FnMut(&[WordOrDigit]) -> io::Result<()>,
+        on_rejected: &mut OnRejected<io::Error>,
+    ) -> io::Result<EncodeStats> {
+        #[cfg(feature = "arena")]
+        return ARENA.with(|arena| match arena.try_borrow_mut() {
+            Ok(mut arena) => {
+                let stats = {
+                    let mut digits = bumpalo::collections::Vec::with_capacity_in(num.len(), &arena);
+                    extract_digits(num, &mut digits);
+                    search_in(&arena, &digits, &self.dict, self.filter.as_ref(), limits, on_solution, on_rejected)
+                };
+                arena.reset();
+                stats
+            }
+            // a number encoded from within on_solution cannot share the arena
+            Err(_) => search(&self.digits(num), &self.dict, self.filter.as_ref(), limits, on_solution, on_rejected),
+        });
+        #[cfg(not(feature = "arena"))]
+        search(&self.digits(num), &self.dict, self.filter.as_ref(), limits, on_solution, on_rejected)
+    }
+
+    /// Searches for the encodings of a phone number with the depth-first search, within the
+    /// limit of solutions of the [`EncodeOptions`], telling `observer` about every step of it
+    /// (see [`search_observed`]).
+    pub fn observe(&self, num: &str, observer: &mut dyn SearchObserver) -> Result<EncodeStats, EncodeError> {
+        let digits = normalize_number(num, self.options.strict)
+            .map_err(|invalid| EncodeError::InvalidNumber { number: self.shown(num).into_owned(), char: invalid.char })?;
+        let limits = Limits::solutions(self.options.limit.unwrap_or(usize::MAX));
+        let stats = search_observed::<_, Infallible, _>(
+            &digits, &self.dict, self.filter.as_ref(), limits, observer, &mut |_| Ok(()), &mut |_, _| Ok(()));
+        Ok(stats.unwrap_or_else(|never| match never {}))
+    }
+
+    /// The solutions of the given phone number as the ids of their words in the dictionary
+    /// (see [`Encoder::resolve`]), owning nothing, e.g. to send them to another thread or
+    /// deduplicate them.
+    pub fn solution_ids(&self, num: &str) -> Result<(Vec<SolutionIds>, EncodeStats), EncodeError> {
+        let digits = self.digits(num);
+        let word_ids = self.word_ids();
+        let mut solutions = Vec::new();
+        let stats = self.for_each_solution(num, &mut |parts| {
+            // every word the search finds is in the dictionary
+            solutions.extend(word_ids.ids(&digits, parts, &self.dict));
+            Ok(())
+        })?;
+        Ok((solutions, stats))
+    }
+
+    /// The parts of a solution given by [`Encoder::solution_ids`], if its words are in the
+    /// dictionary of this encoder.
+    pub fn resolve(&self, ids: &SolutionIds) -> Option<Vec<WordOrDigit<'_>>> {
+        self.word_ids().resolve(ids, &self.dict)
+    }
+
+    /// Whether the given phone number has any encoding accepted by the filter.
+    ///
+    /// Unlike [`Encoder::for_each_solution`], this ignores the limits of the [`EncodeOptions`].
+    pub fn is_encodable(&self, num: &str) -> bool {
+        let Ok(digits) = normalize_number(num, self.options.strict) else { return false };
+        let filter = self.filter.as_ref();
+        if filter.accepts_all() {
+            return has_encoding(&digits, &self.dict);
+        }
+        let stats = search::<_, Infallible>(&digits, &self.dict, filter, Limits::solutions(1), &mut |_| Ok(()), &mut |_, _| Ok(()));
+        stats.is_ok_and(|stats| stats.solutions > 0)
+    }
+
+    /// The number of encodings of the digits accepted by the filter: counted without finding
+    /// them if it accepts all, or else by searching them all.
+    fn accepted_count(&self, digits: &[char]) -> u64 {
+        let filter = self.filter.as_ref();
+        if filter.accepts_all() {
+            return encoding_count(digits, &self.dict);
+        }
+        let stats = search::<_, Infallible>(digits, &self.dict, filter, Limits::solutions(usize::MAX), &mut |_| Ok(()), &mut |_, _| Ok(()));
+        stats.map_or(0, |stats| stats.solutions as u64)
+    }
+
+    /// Checks that `parts` is a solution for the given phone number, accepted by the filter.
+    ///
+    /// See [`verify_solution`].
+    pub fn verify(&self, num: &str, parts: &[WordOrDigit]) -> Result<(), VerifyError> {
+        let digits = normalize_number(num, self.options.strict).map_err(VerifyError::InvalidNumber)?;
+        verify_solution(&digits, parts, &self.dict, self.filter.as_ref())
+    }
+
+    /// Explains why `parts` is or is not a solution for the given phone number, listing every
+    /// rule it breaks (only that the number is invalid, if it is).
+    ///
+    /// See [`explain`].
+    pub fn explain(&self, num: &str, parts: &[WordOrDigit]) -> Explanation {
+        match normalize_number(num, self.options.strict) {
+            Ok(digits) => explain(&digits, parts, &self.dict, self.filter.as_ref()),
+            Err(invalid) => Explanation { violations: vec![VerifyError::InvalidNumber(invalid)], ..Explanation::default() },
+        }
+    }
+
+    /// The dictionary as a trie, built when first needed.
+    fn trie(&self) -> &Trie {
+        self.trie.get_or_init(|| Trie::new(&self.dict))
+    }
+
+    /// The dictionary as an Aho-Corasick automaton, built when first needed.
+    fn aho_corasick(&self) -> &AhoCorasick {
+        self.aho_corasick.get_or_init(|| AhoCorasick::new(&self.dict))
+    }
+
+    /// The ids of the keys of the dictionary, given when first needed.
+    fn word_ids(&self) -> &WordIds {
+        self.word_ids.get_or_init(|| WordIds::new(&self.dict))
+    }
+
+    /// The digits of a number checked by [`Encoder::for_each_solution`].
+    fn digits(&self, num: &str) -> DigitString {
+        DigitString::from_checked(num)
+    }
+
+    /// Lists the dictionary words whose encoding is the given digits (see [`Lookup::exact`]).
+    pub fn lookup(&self, digits: &str) -> Vec<&str> {
+        self.dict.exact(digits).iter().map(|w| w.text.as_str()).collect()
+    }
+
+    /// Lists the dictionary words whose encoding appears anywhere in the given digits, with the
+    /// positions of those digits (counting only the digits), by where they start and then
+    /// end (vanity search).
+    pub fn contained_words(&self, digits: &str) -> Vec<(Range<usize>, &str)> {
+        let digits: Vec<char> = digits.chars().filter(char::is_ascii_digit).collect();
+        let mut matches: Vec<_> = self.aho_corasick().find(&digits)
+            .flat_map(|found| found.words.iter().map(move |w| (found.span.clone(), w.text.as_str())))
+            .collect();
+        // stable, keeping the words of the same digits in their order
+        matches.sort_by_key(|(span, _)| (span.start, span.end));
+        matches
+    }
+
+    /// Lists all dictionary words whose encoding starts with the given digits,
+    /// shortest encodings first (T9-style completion, see [`Trie::completions`]).
+    pub fn words_with_prefix(&self, digits: &str) -> Vec<&str> {
+        let prefix: Vec<char> = digits.chars().filter(char::is_ascii_digit).collect();
+        self.trie().completions(&prefix).into_iter().map(|w| w.text.as_str()).collect()
+    }
+}
+
+fn write_rejected(
+    out: &Mutex<Box<dyn Write + Send>>,
+    num: &str,
+    words: &[WordOrDigit],
+    rejection: &Rejection,
+) -> io::Result<()> {
+    let mut out = out.lock().unwrap_or_else(|e| e.into_inner());
+    write!(out, "{}:", num)?;
+    for word in words {
+        write!(out, " {}", word)?;
+    }
+    writeln!(out, " ({})", rejection)
+}
+
+fn write_skipped(out: &Mutex<Box<dyn Write + Send>>, num: &str, reason: &EncodeError) -> Result<(), EncodeError> {
+    let mut out = out.lock().unwrap_or_else(|e| e.into_inner());
+    Ok(writeln!(out, "{}\t{}", num, reason)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+
+    use super::*;
+    #[cfg(feature = "alloc-counter")]
+    use crate::alloc_counter::allocations;
+    use crate::search::words_from_bytes;
+
+    /// An encoder whose numbers of fives have many solutions: 385 for `555555`, 24736 for
+    /// `5555555555`.
+    fn encoder() -> Encoder {
+        let encoder = Encoder::new(words_from_bytes(b"a\nM\nam\nma\nmam\n", &Mapping::default()).into_iter().collect());
+        // the first number allocates the arena, if enabled
+        encoder.encode_to("5555555555", &mut io::sink()).unwrap();
+        encoder
+    }
+
+    #[test]
+    #[cfg(feature = "alloc-counter")]
+    fn search_does_not_allocate_for_each_solution() {
+        let encoder = encoder();
+        let (stats, few) = allocations(|| encoder.for_each_solution("555", &mut |_| Ok(())).unwrap());
+        assert_eq!(stats.solutions, 17);
+        let (stats, many) = allocations(|| encoder.for_each_solution("5555555555", &mut |_| Ok(())).unwrap());
+        assert_eq!(stats.solutions, 24736);
+        assert_eq!(many, few);
+    }
+
+    #[test]
+    #[cfg(feature = "alloc-counter")]
+    fn writing_does_not_allocate_for_each_solution() {
+        let encoder = encoder();
+        let (stats, few) = allocations(|| encoder.encode_to("5-55", &mut io::sink()).unwrap());
+        assert_eq!(stats.solutions, 17);
+        let (stats, many) = allocations(|| encoder.encode_to("5555-55/5555", &mut io::sink()).unwrap());
+        assert_eq!(stats.solutions, 24736);
+        assert_eq!(many, few);
+    }
+
+    fn with_max_total(max_total: usize) -> Encoder {
+        encoder().with_options(EncodeOptions { max_total: Some(max_total), ..EncodeOptions::default() })
+    }
+
+    #[test]
+    fn max_total_is_shared_by_the_numbers() {
+        let encoder = with_max_total(40);
+        let mut out = Vec::new();
+        let stats: Vec<_> = (0..4).map(|_| encoder.encode_to("555", &mut out).unwrap()).collect();
+        // 17 solutions each for the first two numbers, what is left of the 40 for the third
+        let solutions: Vec<_> = stats.iter().map(|stats| stats.solutions).collect();
+        assert_eq!(solutions, [17, 17, 6, 0]);
+        let truncated: Vec<_> = stats.iter().map(|stats| stats.truncated).collect();
+        assert_eq!(truncated, [false, false, true, true]);
+        assert_eq!(out.split(|&byte| byte == b'\n').filter(|line| !line.is_empty()).count(), 40);
+
+        // the rest of the budget goes with a copy of the encoder
+        let encoder = with_max_total(20);
+        assert_eq!(encoder.encode_to("555", &mut io::sink()).unwrap().solutions, 17);
+        let copy = encoder.with_dictionary(encoder.dictionary().clone());
+        assert_eq!(copy.encode_to("555", &mut io::sink()).unwrap().solutions, 3);
+    }
+
+    #[test]
+    fn max_total_larger_than_the_solutions_does_not_truncate() {
+        let encoder = with_max_total(1000);
+        let stats = encoder.encode_to("555", &mut io::sink()).unwrap();
+        assert_eq!(stats.solutions, 17);
+        assert!(!stats.truncated);
+    }
+
+    #[test]
+    #[cfg(feature = "parallel")]
+    fn max_total_holds_with_several_threads() {
+        use crate::parallel::{encode_parallel, Jobs};
+
+        // 385 solutions for each number, 24640 in all
+        let numbers = vec![String::from("555555"); 64];
+        for (max_total, solutions) in [(1000, 1000), (24639, 24639), (30000, 24640)] {
+            let encoder = with_max_total(max_total);
+            let mut out = Vec::new();
+            let stats = encode_parallel(&encoder, &numbers, &mut Jobs::Count(8), &mut out).unwrap();
+            assert_eq!(stats.solutions, solutions);
+            assert_eq!(stats.truncated, solutions < 24640);
+            assert_eq!(out.split(|&byte| byte == b'\n').filter(|line| !line.is_empty()).count(), solutions);
+        }
+    }
+
+    /// A writer whose output is kept for the test, for [`Encoder::with_skipped_output`].
+    #[derive(Clone, Default)]
+    struct Shared(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Shared {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn search_is_abandoned_after_the_timeout() {
+        for timeout in [Duration::ZERO, Duration::from_nanos(1)] {
+            for algorithm in [Algorithm::Dfs, Algorithm::Iterative, Algorithm::Trie] {
+                let skipped = Shared::default();
+                let options = EncodeOptions { timeout_per_number: Some(timeout), algorithm, ..EncodeOptions::default() };
+                let encoder = encoder().with_options(options).with_skipped_output(skipped.clone());
+                let stats = encoder.encode_to("5555555555", &mut io::sink()).unwrap();
+                assert!(stats.interrupted, "{:?}", algorithm);
+                // of the 24736 solutions, those found before the first check of the time
+                assert!(stats.solutions < 24736, "{:?}", algorithm);
+                let skipped = String::from_utf8(skipped.0.lock().unwrap().clone()).unwrap();
+                let expected = format!("5555555555\ttimed out after {:?} ({} solutions printed)\n", timeout, stats.solutions);
+                assert_eq!(skipped, expected);
+            }
+        }
+
+        // a number whose search is done before the first check is not reported
+        let skipped = Shared::default();
+        let options = EncodeOptions { timeout_per_number: Some(Duration::ZERO), ..EncodeOptions::default() };
+        let encoder = encoder().with_options(options).with_skipped_output(skipped.clone());
+        let stats = encoder.encode_to("555", &mut io::sink()).unwrap();
+        assert_eq!(stats.solutions, 17);
+        assert!(!stats.interrupted);
+        assert!(skipped.0.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn limit_marker_counts_the_solutions_accepted_by_the_rules() {
+        let options = EncodeOptions { limit: Some(20), limit_marker: true, ..EncodeOptions::default() };
+        let mut out = Vec::new();
+        encoder().with_options(options.clone()).encode_to("55555", &mut out).unwrap();
+        // 136 solutions in all
+        assert!(String::from_utf8(out).unwrap().ends_with("55555: …and 116 more\n"));
+
+        let rules = Rules { same_word_lengths: true, ..Rules::default() };
+        let encoder = encoder().with_options(options).with_rules(rules);
+        let mut out = Vec::new();
+        assert_eq!(encoder.encode_to("55555", &mut out).unwrap().solutions, 20);
+        // of which 104 are accepted
+        assert!(String::from_utf8(out).unwrap().ends_with("55555: …and 84 more\n"));
+    }
+}
@@ -0,0 +1,140 @@
+//! The errors of loading dictionaries and encoding phone numbers.
+
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// An error of the library, with enough detail for precise diagnostics.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum EncodeError {
+    /// reading or writing failed.
+    Io(io::Error),
+    /// a word of a words file contains a character that cannot be part of a word.
+    InvalidWord { line: usize, char: char },
+    /// the weight column of a line of a words file is not a number.
+    InvalidWeight { line: usize, weight: String },
+    /// a phone number contains a character that is neither a digit nor a separator.
+    InvalidNumber { number: String, char: char },
+    /// a phone number has no digits, with [`EmptyNumbers::Error`](crate::encoder::EmptyNumbers::Error).
+    EmptyNumber { number: String },
+    /// an expansion of the [`Mapping`](crate::mapping::Mapping) is invalid.
+    MappingError(String),
+    /// the search for the encodings of a number was abandoned after reaching a limit.
+    LimitExceeded(String),
+    /// an error reading or writing a file, with the file and its role in the run.
+    InFile { role: FileRole, path: PathBuf, error: Box<EncodeError> },
+}
+
+/// What a file is used for, to tell the files apart in error messages.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum FileRole {
+    /// a words file, making up the dictionary.
+    Words,
+    /// the file of phone numbers to encode.
+    Numbers,
+    /// the file the rejected solutions are written to.
+    Rejected,
+    /// the file the numbers that were skipped are written to.
+    Skipped,
+    /// the file the statistics of the run are written to.
+    Stats,
+    /// a file the solutions are written to.
+    Output,
+    /// a file mapping the letters to digits.
+    Keymap,
+    /// the file the report of the dictionary is written to.
+    DictReport,
+    /// the file the manifest of the run is written to.
+    Manifest,
+    /// a file of the solutions of a run kept in the cache directory.
+    CachedResults,
+}
+
+impl fmt::Display for FileRole {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            FileRole::Words => "words file",
+            FileRole::Numbers => "numbers file",
+            FileRole::Rejected => "rejected solutions file",
+            FileRole::Skipped => "rejects file",
+            FileRole::Stats => "stats file",
+            FileRole::Output => "output file",
+            FileRole::Keymap => "keymap file",
+            FileRole::DictReport => "dictionary report file",
+            FileRole::Manifest => "manifest file",
+            FileRole::CachedResults => "cached results file",
+        })
+    }
+}
+
+impl EncodeError {
+    /// The kind of [`io::Error`] this error is converted to.
+    fn kind(&self) -> io::ErrorKind {
+        match self {
+            EncodeError::Io(e) => e.kind(),
+            EncodeError::MappingError(_) => io::ErrorKind::InvalidInput,
+            EncodeError::InFile { error, .. } => error.kind(),
+            _ => io::ErrorKind::InvalidData,
+        }
+    }
+}
+
+impl fmt::Display for EncodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EncodeError::Io(e) => write!(f, "{}", e),
+            EncodeError::InvalidWord { line, char } =>
+                write!(f, "invalid character {:?} in the word on line {}", char, line),
+            EncodeError::InvalidWeight { line, weight } =>
+                write!(f, "invalid weight on line {}: '{}'", line, weight),
+            EncodeError::InvalidNumber { number, char } =>
+                write!(f, "invalid character {:?} in phone number '{}'", char, number),
+            EncodeError::EmptyNumber { number } => write!(f, "no digits in phone number '{}'", number),
+            EncodeError::MappingError(message) => f.write_str(message),
+            EncodeError::LimitExceeded(limit) => f.write_str(limit),
+            EncodeError::InFile { role, path, error } => write!(f, "{} '{}': {}", role, path.display(), error),
+        }
+    }
+}
+
+impl Error for EncodeError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            EncodeError::Io(e) => Some(e),
+            EncodeError::InFile { error, .. } => Some(error.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for EncodeError {
+    fn from(e: io::Error) -> EncodeError {
+        EncodeError::Io(e)
+    }
+}
+
+/// For callers working with `io::Result`: I/O errors are returned as they are,
+/// the others as `InvalidData` (or `InvalidInput` for mapping errors) errors.
+/// Errors in a file keep the kind of the error they wrap.
+impl From<EncodeError> for io::Error {
+    fn from(e: EncodeError) -> io::Error {
+        match e {
+            EncodeError::Io(e) => e,
+            e => io::Error::new(e.kind(), e),
+        }
+    }
+}
+
+/// Adds the file an error happened in to the errors of a result.
+pub trait FileContext<T> {
+    fn in_file<P: AsRef<Path>>(self, role: FileRole, path: P) -> Result<T, EncodeError>;
+}
+
+impl<T, E: Into<EncodeError>> FileContext<T> for Result<T, E> {
+    fn in_file<P: AsRef<Path>>(self, role: FileRole, path: P) -> Result<T, EncodeError> {
+        self.map_err(|e| EncodeError::InFile { role, path: path.as_ref().to_path_buf(), error: Box::new(e.into()) })
+    }
+}
@@ -0,0 +1,159 @@
+//! The `fetch-inputs` command: downloading the inputs of the benchmark into a data directory,
+//! checking their SHA-256 checksums, so that it can be reproduced with a single command.
+//!
+//! The numbers files the benchmark generates anew on each run are generated with a fixed
+//! seed instead, so that they are the same on every machine.
+//!
+//! Files are downloaded with `curl`, like the benchmark script relies on other tools being
+//! installed, rather than building an HTTP client into the program.
+
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use phone_encoder::generate::generate_numbers;
+use phone_encoder::sha256::{hex, sha256};
+
+/// Where the default inputs are downloaded from: the repository of the benchmark.
+const UPSTREAM: &str = "https://raw.githubusercontent.com/renatoathaydes/prechelt-phone-number-encoding/master/";
+
+/// The default inputs: the dictionary of the original problem, with the sample input and
+/// its expected output.
+const DEFAULT_SOURCES: [(&str, &str); 3] = [
+    ("dictionary.txt", "291b4e05c0638d586e4aef387c4bba3618af09214198cf9b61f504bd814fd331"),
+    ("input.txt", "2522a48418b10f6c8ec8aa66dde4c7613c49a4bbaa1205314e88586651375a08"),
+    ("output.txt", "90d794b45a4a6ac9ff2bed84e51cbd5f2a518eda37d18a81c53eb5e3ad02b406"),
+];
+
+/// The numbers files generated by the benchmark, each with how many numbers it has and
+/// whether numbers without digits are allowed.
+const GENERATED_INPUTS: [(&str, usize, bool); 4] = [
+    ("phones_1000.txt", 1000, false),
+    ("phones_10_000.txt", 10_000, false),
+    ("phones_50_000.txt", 50_000, false),
+    ("phones_100_000_with_empty.txt", 100_000, true),
+];
+
+/// The seed of the first of the [`GENERATED_INPUTS`], each of the others having the next one.
+const GENERATED_SEED: u64 = 1999;
+
+/// A file to download into the data directory.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Source {
+    /// the name of the file in the data directory.
+    pub name: String,
+    pub url: String,
+    /// the expected SHA-256 checksum of the file, in hex, if known.
+    pub sha256: Option<String>,
+}
+
+impl Source {
+    /// Parses `<name>=<url>[#<sha256>]`.
+    pub fn parse(spec: &str) -> Option<Source> {
+        let (name, url) = spec.split_once('=')?;
+        let (url, sha256) = match url.rsplit_once('#') {
+            Some((url, sha256)) => (url, Some(sha256.to_ascii_lowercase())),
+            None => (url, None),
+        };
+        // the name must not lead outside of the data directory
+        let is_file_name = !name.is_empty() && name != "." && name != ".." && !name.contains(['/', '\\']);
+        (is_file_name && !url.is_empty()).then(|| Source { name: name.to_string(), url: url.to_string(), sha256 })
+    }
+}
+
+/// The default sources, replaced by the `extra` sources of the same name, followed by the
+/// other `extra` sources (e.g. the generated inputs of a published run of the benchmark).
+pub fn sources(extra: &[Source]) -> Vec<Source> {
+    let mut sources: Vec<Source> = DEFAULT_SOURCES.iter()
+        .map(|(name, sha256)| Source {
+            name: name.to_string(),
+            url: format!("{}{}", UPSTREAM, name),
+            sha256: Some(sha256.to_string()),
+        })
+        .collect();
+    for source in extra {
+        match sources.iter_mut().find(|s| s.name == source.name) {
+            Some(existing) => *existing = source.clone(),
+            None => sources.push(source.clone()),
+        }
+    }
+    sources
+}
+
+/// `$XDG_DATA_HOME/phone_encoder`, or else `~/.local/share/phone_encoder`.
+pub fn default_data_dir() -> PathBuf {
+    let data_home = env::var_os("XDG_DATA_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".local/share")))
+        .unwrap_or_default();
+    data_home.join("phone_encoder")
+}
+
+/// Downloads the sources missing from `data_dir`, or whose checksum does not match, and
+/// generates the numbers files of the benchmark not given as sources, then prints the data
+/// directory.
+pub fn fetch_inputs(sources: &[Source], data_dir: &Path) -> io::Result<()> {
+    fs::create_dir_all(data_dir)?;
+    for source in sources {
+        let path = data_dir.join(&source.name);
+        if let (Ok(contents), Some(expected)) = (fs::read(&path), &source.sha256) {
+            if hex(&sha256(&contents)) == *expected {
+                eprintln!("{}: cached", source.name);
+                continue;
+            }
+        } else if path.exists() && source.sha256.is_none() {
+            eprintln!("{}: cached (no checksum to verify)", source.name);
+            continue;
+        }
+
+        eprintln!("{}: downloading {}", source.name, source.url);
+        // downloaded next to the file, so that a failed download leaves the cache as it was
+        let partial = data_dir.join(format!("{}.part", source.name));
+        let status = Command::new("curl")
+            .args(["--fail", "--silent", "--show-error", "--location", "--output"])
+            .arg(&partial)
+            .arg(&source.url)
+            .status()
+            .map_err(|e| io::Error::new(e.kind(), format!("cannot run curl: {}", e)))?;
+        if !status.success() {
+            let _ = fs::remove_file(&partial);
+            return Err(io::Error::other(format!("downloading {} failed ({})", source.url, status)));
+        }
+        let checksum = hex(&sha256(&fs::read(&partial)?));
+        match &source.sha256 {
+            Some(expected) if *expected != checksum => {
+                let _ = fs::remove_file(&partial);
+                return Err(io::Error::new(io::ErrorKind::InvalidData, format!(
+                    "checksum mismatch for {}: expected {}, got {}", source.name, expected, checksum)));
+            }
+            Some(_) => eprintln!("{}: checksum verified", source.name),
+            // so that the source can be given with its checksum next time
+            None => eprintln!("{}: sha256 {}", source.name, checksum),
+        }
+        fs::rename(&partial, &path)?;
+    }
+    for (seed, (name, count, allow_empty)) in (GENERATED_SEED..).zip(GENERATED_INPUTS) {
+        if sources.iter().any(|source| source.name == name) {
+            continue;
+        }
+        let mut contents = String::new();
+        for number in generate_numbers(count, allow_empty, seed) {
+            contents.push_str(&number);
+            contents.push('\n');
+        }
+        let path = data_dir.join(name);
+        if fs::read(&path).is_ok_and(|cached| cached == contents.as_bytes()) {
+            eprintln!("{}: cached", name);
+            continue;
+        }
+        let partial = data_dir.join(format!("{}.part", name));
+        fs::write(&partial, &contents)?;
+        fs::rename(&partial, &path)?;
+        eprintln!("{}: generated, sha256 {}", name, hex(&sha256(contents.as_bytes())));
+    }
+    println!("{}", data_dir.display());
+    Ok(())
+}
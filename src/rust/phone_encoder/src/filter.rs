@@ -0,0 +1,163 @@
+use alloc::string::String;
+use core::fmt;
+
+use crate::search::WordOrDigit;
+
+/// Decides whether a complete candidate solution for a phone number is accepted (printed).
+///
+/// The search only generates candidates that follow the encoding rules, so the default
+/// filter, [`AcceptAll`], accepts everything. Library users may plug in their own
+/// acceptance logic with `Encoder::with_filter`.
+pub trait SolutionFilter {
+    /// Checks a candidate solution, returning the reason for rejecting it, if any.
+    fn check(&self, digits: &[char], parts: &[WordOrDigit]) -> Result<(), Rejection>;
+
+    /// Whether the filter accepts every candidate solution, so the search may skip it.
+    fn accepts_all(&self) -> bool {
+        false
+    }
+}
+
+/// The reason why a candidate solution was rejected by a [`SolutionFilter`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Rejection {
+    /// the parts at `index - 1` and `index` are both digits.
+    ConsecutiveDigits { index: usize },
+    /// the words between the first and last parts do not all have the same length.
+    MixedWordLengths,
+    /// any other reason.
+    Other(String),
+}
+
+impl fmt::Display for Rejection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Rejection::ConsecutiveDigits { index } =>
+                write!(f, "consecutive digits at parts {} and {}", index - 1, index),
+            Rejection::MixedWordLengths => f.write_str("mixed word lengths"),
+            Rejection::Other(reason) => f.write_str(reason),
+        }
+    }
+}
+
+/// Accepts every candidate solution.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct AcceptAll;
+
+impl SolutionFilter for AcceptAll {
+    fn check(&self, _digits: &[char], _parts: &[WordOrDigit]) -> Result<(), Rejection> {
+        Ok(())
+    }
+
+    fn accepts_all(&self) -> bool {
+        true
+    }
+}
+
+/// Checks candidate solutions against a configurable set of rules.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Rules {
+    /// reject solutions where two digits are next to each other.
+    pub no_consecutive_digits: bool,
+    /// reject solutions whose interior words (all but the first and last parts)
+    /// do not all have the same length, in letters (see [`WordOrDigit::len`]).
+    pub same_word_lengths: bool,
+}
+
+impl Rules {
+    /// Parses a comma-separated list of rule names:
+    /// `consecutive-digits` and `mixed-lengths` (or `all`, `none`).
+    pub fn parse(spec: &str) -> Option<Rules> {
+        let mut rules = Rules::default();
+        for name in spec.split(',') {
+            match name.trim() {
+                "consecutive-digits" => rules.no_consecutive_digits = true,
+                "mixed-lengths" => rules.same_word_lengths = true,
+                "all" => rules = Rules { no_consecutive_digits: true, same_word_lengths: true },
+                "none" => {}
+                _ => return None,
+            }
+        }
+        Some(rules)
+    }
+}
+
+/// The names of the rules, as [`Rules::parse`] reads them: `consecutive-digits,mixed-lengths`,
+/// one of them, or `none`.
+impl fmt::Display for Rules {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.no_consecutive_digits, self.same_word_lengths) {
+            (true, true) => f.write_str("consecutive-digits,mixed-lengths"),
+            (true, false) => f.write_str("consecutive-digits"),
+            (false, true) => f.write_str("mixed-lengths"),
+            (false, false) => f.write_str("none"),
+        }
+    }
+}
+
+impl SolutionFilter for Rules {
+    fn check(&self, _digits: &[char], parts: &[WordOrDigit]) -> Result<(), Rejection> {
+        if self.no_consecutive_digits {
+            if let Some(i) = parts.windows(2).position(|w| w[0].is_digit() && w[1].is_digit()) {
+                return Err(Rejection::ConsecutiveDigits { index: i + 1 });
+            }
+        }
+        if self.same_word_lengths && parts.len() > 2 {
+            let mut lengths = parts[1..parts.len() - 1].iter()
+                .filter(|part| !part.is_digit())
+                .map(WordOrDigit::len);
+            if let Some(first) = lengths.next() {
+                if lengths.any(|len| len != first) {
+                    return Err(Rejection::MixedWordLengths);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn accepts_all(&self) -> bool {
+        !self.no_consecutive_digits && !self.same_word_lengths
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use WordOrDigit::{Digit, Word};
+
+    fn same_lengths(parts: &[WordOrDigit]) -> Result<(), Rejection> {
+        Rules { same_word_lengths: true, ..Rules::default() }.check(&[], parts)
+    }
+
+    #[test]
+    fn counts_letters_not_bytes() {
+        // "für" has 4 bytes, "Tor" 3
+        assert_eq!(Word("für").len(), 3);
+        assert_eq!(same_lengths(&[Digit('5'), Word("für"), Word("Tor"), Digit('2')]), Ok(()));
+        assert_eq!(same_lengths(&[Digit('5'), Word("für"), Word("Ort"), Word("an"), Digit('2')]),
+                   Err(Rejection::MixedWordLengths));
+    }
+
+    #[test]
+    fn counts_decomposed_umlauts_as_one_letter() {
+        // a, combining diaeresis
+        let decomposed = "Ba\u{308}r";
+        assert_eq!(Word(decomposed).len(), 3);
+        assert_eq!(same_lengths(&[Word("a"), Word(decomposed), Word("Bär"), Word("Tor"), Word("b")]), Ok(()));
+    }
+
+    #[test]
+    fn counts_cyrillic_letters() {
+        // two bytes for each letter
+        assert_eq!(Word("мир").len(), 3);
+        assert_eq!(same_lengths(&[Word("да"), Word("мир"), Word("кот"), Word("дом"), Word("нет")]), Ok(()));
+        assert_eq!(same_lengths(&[Word("да"), Word("мир"), Word("дома"), Word("нет")]), Err(Rejection::MixedWordLengths));
+    }
+
+    #[test]
+    fn ignores_the_first_and_last_parts_and_digits() {
+        assert_eq!(same_lengths(&[Word("Straße"), Word("мир"), Digit('4'), Word("Tor"), Word("ab")]), Ok(()));
+    }
+}
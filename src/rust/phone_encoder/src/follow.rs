@@ -0,0 +1,59 @@
+//! Encoding the numbers read from stdin as they arrive (`--follow`), e.g. from `tail -f` or
+//! another program writing numbers to a pipe, so that this program can serve them like a
+//! simple streaming service: the solutions of each number are written as soon as it is
+//! encoded, until stdin is closed.
+
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::time::Instant;
+
+use phone_encoder::input::LineReader;
+use phone_encoder::stats::millis;
+use phone_encoder::{FileContext, FileRole, WordsFile};
+
+use crate::cli::{NumberPolicy, Options};
+use crate::run::{finish, load, flush, number, row, Totals};
+
+/// Encodes the numbers of stdin, one after the other, flushing stdout after each, and
+/// printing how long each took to stderr with `--latency`.
+pub fn follow(options: &Options, words_files: &[WordsFile]) -> io::Result<()> {
+    let mut total = Totals::default();
+    let start_time = Instant::now();
+    let encoder = load(options, words_files)?;
+    total.timings.load = start_time.elapsed();
+    // invalid numbers are skipped instead of stopping the stream
+    let encoder = match &options.rejects_file {
+        Some(file) => encoder.with_skipped_output(BufWriter::new(File::create(file).in_file(FileRole::Skipped, file)?)),
+        None => encoder.with_skipped_output(io::stderr()),
+    };
+
+    let mut lines = LineReader::new(io::stdin().lock())
+        .select(&options.selection)
+        .detect_format(&options.number_field)
+        .fail_on_invalid(options.number_policy == NumberPolicy::Strict);
+    let mut out = BufWriter::new(io::stdout().lock());
+    encoder.write_header(&mut out)?;
+    out.flush()?;
+    loop {
+        let start_time = Instant::now();
+        let Some(num) = lines.next_line().in_file(FileRole::Numbers, "<stdin>")? else { break };
+        let read_time = Instant::now();
+        // mostly waiting for the next number
+        total.timings.read += read_time - start_time;
+        let stats = encoder.encode_to(row(options, num), &mut out)?;
+        total.timings.search += read_time.elapsed() - stats.write_time;
+        total.timings.write += stats.write_time + flush(&mut out)?;
+        let latency = read_time.elapsed();
+        if options.latency {
+            eprintln!("{}\t{:.3} ms", number(num), millis(latency));
+        }
+        total.numbers += 1;
+        total.stats += stats;
+        if total.stats.truncated {
+            break;
+        }
+    }
+    encoder.write_summary(&mut out, total.numbers, &total.stats)?;
+    out.flush()?;
+    finish(options, &total, &[], &encoder.top_numbers())
+}
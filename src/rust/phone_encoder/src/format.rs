@@ -0,0 +1,748 @@
+//! How solutions are written: the [`OutputFormat`] trait, and the formats provided by the
+//! crate, selected at runtime with [`Format`].
+
+use std::fmt::{self, Write as _};
+use std::io::{self, Write};
+use std::ops::Range;
+
+#[cfg(feature = "formats-json")]
+use serde_json::{json, Value};
+
+use crate::encoder::EncodeOptions;
+use crate::search::{EncodeStats, Segment, WordOrDigit};
+
+/// ANSI escape codes of the parts of colored solutions.
+const NUMBER_COLOR: &str = "\x1b[1m";
+const WORD_COLOR: &str = "\x1b[32m";
+const DIGIT_COLOR: &str = "\x1b[1;33m";
+const ANNOTATION_COLOR: &str = "\x1b[2m";
+const SUMMARY_COLOR: &str = "\x1b[1;36m";
+const RESET: &str = "\x1b[0m";
+
+/// A solution of a phone number, as given to an [`OutputFormat`].
+#[derive(Clone, Copy, Debug)]
+pub struct Solution<'a> {
+    /// the phone number, as given.
+    pub number: &'a str,
+    pub parts: &'a [WordOrDigit<'a>],
+    /// the index of the solution among those of the number, from 0.
+    pub index: usize,
+    /// with [`EncodeOptions::annotate`], the digits of the number and the span of them
+    /// encoded by each part (otherwise, both are empty).
+    pub digits: &'a [char],
+    pub spans: &'a [Range<usize>],
+    /// the values of the [`EncodeOptions::columns`] on the line of the number (otherwise, empty).
+    pub columns: &'a [&'a str],
+}
+
+impl<'a> Solution<'a> {
+    /// The value of the column at `index`, empty if the line of the number has no such column.
+    pub fn column(&self, index: usize) -> &str {
+        self.columns.get(index).copied().unwrap_or_default()
+    }
+
+    /// The digits encoded by the part at `index`, if the solution is annotated.
+    pub fn digits_of(&self, index: usize) -> &[char] {
+        self.spans.get(index).map_or(&[], |span| &self.digits[span.clone()])
+    }
+
+    /// The parts with the positions of the digits each encodes, if the solution is annotated
+    /// (otherwise, none).
+    pub fn segments(&self) -> impl Iterator<Item = Segment<'a>> + '_ {
+        self.parts.iter().zip(self.spans).map(|(part, span)| Segment { part: *part, span: span.clone() })
+    }
+
+    /// The parts separated by spaces, each word followed by its digits if annotated.
+    pub fn text(&self) -> String {
+        self.quoted_text(Quoting::Never)
+    }
+
+    /// Like [`Solution::text`], with the words quoted as `quoting` says, so that a phrase
+    /// like `so ein` is not mistaken for two words.
+    pub fn quoted_text(&self, quoting: Quoting) -> String {
+        let mut text = String::new();
+        for (i, part) in self.parts.iter().enumerate() {
+            if i > 0 {
+                text.push(' ');
+            }
+            let _ = match part {
+                WordOrDigit::Word(word) if quoting.applies_to(word) => write!(text, "{}", Quoted(word)),
+                _ => write!(text, "{}", part),
+            };
+            let digits = self.digits_of(i);
+            if !part.is_digit() && !digits.is_empty() {
+                text.push('[');
+                text.extend(digits);
+                text.push(']');
+            }
+        }
+        text
+    }
+}
+
+/// Writes solutions in some format.
+///
+/// The header and summary are written once for each output: stdout, or each file
+/// written with `--output-dir`.
+pub trait OutputFormat {
+    /// Writes what comes before all solutions, like the names of the columns of a table.
+    fn render_header(&self, _out: &mut dyn Write) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn render_solution(&self, out: &mut dyn Write, solution: &Solution) -> io::Result<()>;
+
+    /// Writes, after the solutions of a number, that there are `more` solutions that were not
+    /// written because of the limit of solutions per number (with the values of its columns).
+    ///
+    /// `more` counts the encodings of the number regardless of the
+    /// [`SolutionFilter`](crate::filter::SolutionFilter), so it is an upper bound with one.
+    fn render_truncated(&self, _out: &mut dyn Write, _number: &str, _columns: &[&str], _more: u64) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// Writes what comes after all solutions, given the counts for the `numbers` encoded.
+    fn render_summary(&self, _out: &mut dyn Write, _numbers: usize, _stats: &EncodeStats) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// The formats provided by the crate.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Format {
+    /// `5624-82: mir Tor`, as required by the original problem.
+    #[default]
+    Plain,
+    /// a JSON object for each solution, on its own line: `{"number":"5624-82","solution":["mir","Tor"]}`.
+    #[cfg(feature = "formats-json")]
+    Json,
+    /// a `number,solution` table, with the parts of a solution separated by spaces.
+    Csv,
+    /// like CSV, but separated by tabs, which neither numbers nor words contain, so never quoted.
+    Tsv,
+    /// each number on a line of its own, followed by its solutions, indented, and a summary.
+    Grouped,
+}
+
+impl Format {
+    pub fn parse(name: &str) -> Option<Format> {
+        match name {
+            "plain" => Some(Format::Plain),
+            #[cfg(feature = "formats-json")]
+            "json" => Some(Format::Json),
+            "csv" => Some(Format::Csv),
+            "tsv" => Some(Format::Tsv),
+            "grouped" => Some(Format::Grouped),
+            _ => None,
+        }
+    }
+
+    /// The implementation of the format, configured by the options (only the plain and
+    /// grouped formats, written for people, use colors, and only the others, written for
+    /// programs, have the columns of the numbers; all but json, which has arrays of parts,
+    /// quote words).
+    pub fn renderer(self, options: &EncodeOptions) -> Box<dyn OutputFormat + Send + Sync> {
+        let (quoting, color) = (options.quoting, options.color);
+        let columns = options.columns.clone();
+        match self {
+            Format::Plain => Box::new(PlainFormat { quoting, color }),
+            #[cfg(feature = "formats-json")]
+            Format::Json => Box::new(JsonFormat { columns, fields: options.fields.clone() }),
+            Format::Csv => Box::new(CsvFormat { columns, quoting }),
+            Format::Tsv => Box::new(TsvFormat { columns, quoting }),
+            Format::Grouped => Box::new(GroupedFormat { quoting, color }),
+        }
+    }
+}
+
+/// When words are quoted in printed solutions, which are otherwise ambiguous if a word
+/// (a phrase) contains a space.
+///
+/// Quoted words are enclosed in `"`, with `"` and `\` escaped by a `\`. Digits are never quoted.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Quoting {
+    /// words are printed as they are.
+    #[default]
+    Never,
+    /// only words that are empty or contain whitespace, `"` or `\` are quoted.
+    Needed,
+    /// all words are quoted.
+    Always,
+}
+
+impl Quoting {
+    pub fn parse(name: &str) -> Option<Quoting> {
+        match name {
+            "never" => Some(Quoting::Never),
+            "needed" => Some(Quoting::Needed),
+            "always" => Some(Quoting::Always),
+            _ => None,
+        }
+    }
+
+    fn applies_to(self, word: &str) -> bool {
+        match self {
+            Quoting::Never => false,
+            Quoting::Needed => word.is_empty() || word.contains(|ch: char| ch.is_whitespace() || ch == '"' || ch == '\\'),
+            Quoting::Always => true,
+        }
+    }
+}
+
+/// A word enclosed in `"`, with `"` and `\` escaped by a `\`.
+struct Quoted<'a>(&'a str);
+
+impl fmt::Display for Quoted<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_char('"')?;
+        for ch in self.0.chars() {
+            if ch == '"' || ch == '\\' {
+                f.write_char('\\')?;
+            }
+            f.write_char(ch)?;
+        }
+        f.write_char('"')
+    }
+}
+
+/// `5624-82: mir Tor`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PlainFormat {
+    pub quoting: Quoting,
+    /// color the number, the words, the digits and the annotations differently, for terminals.
+    pub color: bool,
+}
+
+impl OutputFormat for PlainFormat {
+    fn render_solution(&self, out: &mut dyn Write, solution: &Solution) -> io::Result<()> {
+        if self.quoting == Quoting::Never && !self.color && solution.spans.is_empty() {
+            return print_solution(out, solution.number, solution.parts);
+        }
+        print_number(out, solution.number, self.color)?;
+        print_parts(out, solution, self.quoting, self.color)?;
+        writeln!(out)
+    }
+
+    /// `5624-82: …and 3 more`
+    fn render_truncated(&self, out: &mut dyn Write, number: &str, _columns: &[&str], more: u64) -> io::Result<()> {
+        print_number(out, number, self.color)?;
+        writeln!(out, " …and {} more", more)
+    }
+}
+
+/// `{"number":"5624-82","solution":["mir","Tor"]}`, with the digits of each part as
+/// `"digits":["562","482"]` if annotated, and a string member for each column.
+#[cfg(feature = "formats-json")]
+#[derive(Clone, Debug, Default)]
+pub struct JsonFormat {
+    /// the names of the columns of the numbers.
+    pub columns: Vec<String>,
+    /// the members of each object, in this order, followed by the columns, instead of the
+    /// number, the solution and the digits if annotated (if empty).
+    pub fields: Vec<Field>,
+}
+
+/// A member of the objects written by the json format, selected with [`EncodeOptions::fields`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Field {
+    /// the phone number, as given.
+    Number,
+    /// the parts of the solution: its words and the digits left as they are.
+    Solution,
+    /// the words of the solution, without the digits.
+    Words,
+    /// how many words the solution has.
+    WordCount,
+    /// how many digits of the number the solution leaves as they are.
+    DigitCount,
+    /// the digits encoded by each part, if annotated.
+    Digits,
+    /// each part with the positions of the digits it encodes and whether it is a digit kept
+    /// as it is, if annotated (see [`Segment`]).
+    Segments,
+}
+
+impl Field {
+    pub fn parse(name: &str) -> Option<Field> {
+        match name {
+            "number" => Some(Field::Number),
+            "solution" => Some(Field::Solution),
+            "words" => Some(Field::Words),
+            "word_count" => Some(Field::WordCount),
+            "digit_count" => Some(Field::DigitCount),
+            "digits" => Some(Field::Digits),
+            "segments" => Some(Field::Segments),
+            _ => None,
+        }
+    }
+
+    /// The name of the member.
+    pub fn name(self) -> &'static str {
+        match self {
+            Field::Number => "number",
+            Field::Solution => "solution",
+            Field::Words => "words",
+            Field::WordCount => "word_count",
+            Field::DigitCount => "digit_count",
+            Field::Digits => "digits",
+            Field::Segments => "segments",
+        }
+    }
+}
+
+#[cfg(feature = "formats-json")]
+impl JsonFormat {
+    /// Writes the selected members one after the other, computing only those, without
+    /// building a [`Value`] of the object first.
+    fn render_fields(&self, out: &mut dyn Write, solution: &Solution) -> io::Result<()> {
+        let parts = solution.parts;
+        write!(out, "{{")?;
+        for (i, field) in self.fields.iter().enumerate() {
+            if i > 0 {
+                write!(out, ",")?;
+            }
+            write!(out, "\"{}\":", field.name())?;
+            match field {
+                Field::Number => write_json_string(out, solution.number)?,
+                Field::Solution => write_json_list(out, parts.iter().map(|part| part.to_string()))?,
+                Field::Words => write_json_list(out, parts.iter().filter_map(|part| match part {
+                    WordOrDigit::Word(word) => Some(*word),
+                    WordOrDigit::Digit(_) => None,
+                }))?,
+                Field::WordCount => write!(out, "{}", parts.iter().filter(|part| !part.is_digit()).count())?,
+                Field::DigitCount => write!(out, "{}", parts.iter().filter(|part| part.is_digit()).count())?,
+                Field::Digits => write_json_list(out, (0..parts.len())
+                    .map(|i| solution.digits_of(i).iter().collect::<String>()))?,
+                Field::Segments => write_json_segments(out, solution.segments())?,
+            }
+        }
+        for (index, name) in self.columns.iter().enumerate() {
+            if index > 0 || !self.fields.is_empty() {
+                write!(out, ",")?;
+            }
+            write_json_string(out, name)?;
+            write!(out, ":")?;
+            write_json_string(out, solution.column(index))?;
+        }
+        writeln!(out, "}}")
+    }
+}
+
+#[cfg(feature = "formats-json")]
+fn write_json_string(out: &mut dyn Write, text: &str) -> io::Result<()> {
+    serde_json::to_writer(out, text).map_err(io::Error::from)
+}
+
+#[cfg(feature = "formats-json")]
+fn write_json_list<T: AsRef<str>>(out: &mut dyn Write, items: impl Iterator<Item=T>) -> io::Result<()> {
+    write!(out, "[")?;
+    for (i, item) in items.enumerate() {
+        if i > 0 {
+            write!(out, ",")?;
+        }
+        write_json_string(out, item.as_ref())?;
+    }
+    write!(out, "]")
+}
+
+/// The segments as `{"part":"mir","span":{"start":0,"end":3},"fallback_digit":false}`, like
+/// their serde serialization.
+#[cfg(feature = "formats-json")]
+fn write_json_segments<'a>(out: &mut dyn Write, segments: impl Iterator<Item = Segment<'a>>) -> io::Result<()> {
+    write!(out, "[")?;
+    for (i, segment) in segments.enumerate() {
+        if i > 0 {
+            write!(out, ",")?;
+        }
+        write!(out, "{{\"part\":")?;
+        write_json_string(out, &segment.part.to_string())?;
+        write!(out, ",\"span\":{{\"start\":{},\"end\":{}}},\"fallback_digit\":{}}}",
+               segment.span.start, segment.span.end, segment.is_fallback_digit())?;
+    }
+    write!(out, "]")
+}
+
+#[cfg(feature = "formats-json")]
+impl OutputFormat for JsonFormat {
+    fn render_solution(&self, out: &mut dyn Write, solution: &Solution) -> io::Result<()> {
+        if !self.fields.is_empty() {
+            return self.render_fields(out, solution);
+        }
+        let parts: Vec<Value> = solution.parts.iter().map(|part| part.to_string().into()).collect();
+        let mut json = json!({ "number": solution.number, "solution": parts });
+        for (index, name) in self.columns.iter().enumerate() {
+            json[name] = solution.column(index).into();
+        }
+        if !solution.spans.is_empty() {
+            json["digits"] = (0..solution.parts.len())
+                .map(|i| Value::from(solution.digits_of(i).iter().collect::<String>()))
+                .collect();
+        }
+        writeln!(out, "{}", json)
+    }
+
+    /// `{"number":"5624-82","truncated":true,"more":3}`, followed by the columns.
+    fn render_truncated(&self, out: &mut dyn Write, number: &str, columns: &[&str], more: u64) -> io::Result<()> {
+        write!(out, "{{\"number\":")?;
+        write_json_string(out, number)?;
+        write!(out, ",\"truncated\":true,\"more\":{}", more)?;
+        for (index, name) in self.columns.iter().enumerate() {
+            write!(out, ",")?;
+            write_json_string(out, name)?;
+            write!(out, ":")?;
+            write_json_string(out, columns.get(index).copied().unwrap_or_default())?;
+        }
+        writeln!(out, "}}")
+    }
+}
+
+/// `number,solution`, with the columns between the two, and fields quoted as needed by RFC 4180.
+#[derive(Clone, Debug, Default)]
+pub struct CsvFormat {
+    /// the names of the columns of the numbers.
+    pub columns: Vec<String>,
+    /// how the words are quoted within the solution, before it is quoted as a field.
+    pub quoting: Quoting,
+}
+
+impl OutputFormat for CsvFormat {
+    fn render_header(&self, out: &mut dyn Write) -> io::Result<()> {
+        write!(out, "number,")?;
+        for name in &self.columns {
+            write_csv_field(out, name)?;
+            write!(out, ",")?;
+        }
+        writeln!(out, "solution")
+    }
+
+    fn render_solution(&self, out: &mut dyn Write, solution: &Solution) -> io::Result<()> {
+        write_csv_field(out, solution.number)?;
+        write!(out, ",")?;
+        for index in 0..self.columns.len() {
+            write_csv_field(out, solution.column(index))?;
+            write!(out, ",")?;
+        }
+        write_csv_field(out, &solution.quoted_text(self.quoting))?;
+        writeln!(out)
+    }
+
+    /// A row with `…and 3 more` as the solution.
+    fn render_truncated(&self, out: &mut dyn Write, number: &str, columns: &[&str], more: u64) -> io::Result<()> {
+        write_csv_field(out, number)?;
+        write!(out, ",")?;
+        for index in 0..self.columns.len() {
+            write_csv_field(out, columns.get(index).copied().unwrap_or_default())?;
+            write!(out, ",")?;
+        }
+        writeln!(out, "…and {} more", more)
+    }
+}
+
+fn write_csv_field(out: &mut dyn Write, field: &str) -> io::Result<()> {
+    if !field.contains([',', '"', '\n', '\r']) {
+        return write!(out, "{}", field);
+    }
+    write!(out, "\"{}\"", field.replace('"', "\"\""))
+}
+
+/// `number<tab>solution`, with the columns between the two.
+#[derive(Clone, Debug, Default)]
+pub struct TsvFormat {
+    /// the names of the columns of the numbers.
+    pub columns: Vec<String>,
+    /// how the words are quoted within the solution.
+    pub quoting: Quoting,
+}
+
+impl OutputFormat for TsvFormat {
+    fn render_header(&self, out: &mut dyn Write) -> io::Result<()> {
+        write!(out, "number\t")?;
+        for name in &self.columns {
+            write!(out, "{}\t", name)?;
+        }
+        writeln!(out, "solution")
+    }
+
+    fn render_solution(&self, out: &mut dyn Write, solution: &Solution) -> io::Result<()> {
+        write!(out, "{}\t", solution.number)?;
+        for index in 0..self.columns.len() {
+            write!(out, "{}\t", solution.column(index))?;
+        }
+        writeln!(out, "{}", solution.quoted_text(self.quoting))
+    }
+
+    /// A row with `…and 3 more` as the solution.
+    fn render_truncated(&self, out: &mut dyn Write, number: &str, columns: &[&str], more: u64) -> io::Result<()> {
+        write!(out, "{}\t", number)?;
+        for index in 0..self.columns.len() {
+            write!(out, "{}\t", columns.get(index).copied().unwrap_or_default())?;
+        }
+        writeln!(out, "…and {} more", more)
+    }
+}
+
+/// Each number followed by its solutions, indented, then the totals of the run.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct GroupedFormat {
+    pub quoting: Quoting,
+    /// like [`PlainFormat::color`], also coloring the summary.
+    pub color: bool,
+}
+
+impl OutputFormat for GroupedFormat {
+    fn render_solution(&self, out: &mut dyn Write, solution: &Solution) -> io::Result<()> {
+        if solution.index == 0 {
+            print_number(out, solution.number, self.color)?;
+            writeln!(out)?;
+        }
+        write!(out, "   ")?;
+        print_parts(out, solution, self.quoting, self.color)?;
+        writeln!(out)
+    }
+
+    fn render_truncated(&self, out: &mut dyn Write, _number: &str, _columns: &[&str], more: u64) -> io::Result<()> {
+        writeln!(out, "    …and {} more", more)
+    }
+
+    fn render_summary(&self, out: &mut dyn Write, numbers: usize, stats: &EncodeStats) -> io::Result<()> {
+        let summary = format!("{} solutions for {} numbers", stats.solutions, numbers);
+        if self.color {
+            writeln!(out, "{}{}{}", SUMMARY_COLOR, summary, RESET)
+        } else {
+            writeln!(out, "{}", summary)
+        }
+    }
+}
+
+fn print_solution(out: &mut dyn Write, num: &str, words: &[WordOrDigit]) -> io::Result<()> {
+    // do a little gymnastics here to avoid allocating a big string just for printing it
+    write!(out, "{}", num)?;
+    if words.is_empty() {
+        return writeln!(out, ":");
+    }
+    write!(out, ": ")?;
+    let (head, tail) = words.split_at(words.len() - 1);
+    for word in head {
+        write!(out, "{} ", word)?;
+    }
+    for word in tail { // only last word in tail
+        writeln!(out, "{}", word)?;
+    }
+    Ok(())
+}
+
+/// Prints the number and a `:`.
+fn print_number(out: &mut dyn Write, num: &str, color: bool) -> io::Result<()> {
+    if color {
+        write!(out, "{}{}{}:", NUMBER_COLOR, num, RESET)
+    } else {
+        write!(out, "{}:", num)
+    }
+}
+
+/// Prints the parts of a solution, each preceded by a space, with its words quoted
+/// and each followed by the digits it encodes in brackets, if annotated.
+fn print_parts(out: &mut dyn Write, solution: &Solution, quoting: Quoting, color: bool) -> io::Result<()> {
+    for (i, word) in solution.parts.iter().enumerate() {
+        write!(out, " ")?;
+        if color {
+            write!(out, "{}", if word.is_digit() { DIGIT_COLOR } else { WORD_COLOR })?;
+        }
+        match word {
+            WordOrDigit::Word(text) if quoting.applies_to(text) => write!(out, "{}", Quoted(text))?,
+            _ => write!(out, "{}", word)?,
+        }
+        if color {
+            write!(out, "{}", RESET)?;
+        }
+        let digits = solution.digits_of(i);
+        if !word.is_digit() && !digits.is_empty() {
+            if color {
+                write!(out, "{}", ANNOTATION_COLOR)?;
+            }
+            write!(out, "[")?;
+            for digit in digits {
+                write!(out, "{}", digit)?;
+            }
+            write!(out, "]")?;
+            if color {
+                write!(out, "{}", RESET)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use WordOrDigit::{Digit, Word};
+
+    /// A number of the fixture, with the values of its columns and its solutions, each part
+    /// with the digits it encodes.
+    struct Number {
+        number: &'static str,
+        columns: &'static [&'static str],
+        solutions: &'static [&'static [(WordOrDigit<'static>, &'static str)]],
+        /// how many solutions were not written.
+        more: u64,
+    }
+
+    /// Numbers with several solutions and with none, words to quote and to escape, and
+    /// numbers whose solutions were truncated.
+    const FIXTURE: &[Number] = &[
+        Number {
+            number: "5624-82",
+            columns: &["Ann", "home"],
+            solutions: &[&[(Word("mir"), "562"), (Word("Tor"), "482")], &[(Word("Mix"), "562"), (Word("Tor"), "482")]],
+            more: 0,
+        },
+        Number { number: "1078-913-5", columns: &["Bob", "work"], solutions: &[], more: 0 },
+        Number {
+            number: "04824",
+            columns: &["Lee, Jo", "mobile"],
+            solutions: &[
+                &[(Digit('0'), "0"), (Word("Torf"), "4824")],
+                &[(Digit('0'), "0"), (Word("fort"), "4824")],
+                &[(Digit('0'), "0"), (Word("Tor"), "482"), (Digit('4'), "4")],
+            ],
+            more: 0,
+        },
+        Number {
+            number: "10/783--5",
+            columns: &["\"O'Hara\"", ""],
+            solutions: &[&[(Word("neu"), "107"), (Word("o\"d"), "83"), (Digit('5'), "5")]],
+            more: 2,
+        },
+        Number {
+            number: "+49 30",
+            columns: &[],
+            solutions: &[&[(Word("New York"), "4930")], &[(Word("back\\slash"), "4930")]],
+            more: 0,
+        },
+    ];
+
+    /// The fixture written in `format`, as a whole output: the header, the solutions of each
+    /// number (annotated with the digits of each part with [`EncodeOptions::annotate`]), and
+    /// the summary.
+    fn render(format: Format, options: &EncodeOptions) -> String {
+        let renderer = format.renderer(options);
+        let mut out = Vec::new();
+        renderer.render_header(&mut out).unwrap();
+        let mut stats = EncodeStats::default();
+        for number in FIXTURE {
+            for (index, solution) in number.solutions.iter().enumerate() {
+                let parts: Vec<WordOrDigit> = solution.iter().map(|(part, _)| *part).collect();
+                let mut digits = Vec::new();
+                let mut spans = Vec::new();
+                if options.annotate {
+                    for (_, encoded) in solution.iter() {
+                        spans.push(digits.len()..digits.len() + encoded.len());
+                        digits.extend(encoded.chars());
+                    }
+                }
+                let solution = Solution {
+                    number: number.number,
+                    parts: &parts,
+                    index,
+                    digits: &digits,
+                    spans: &spans,
+                    columns: if options.columns.is_empty() { &[] } else { number.columns },
+                };
+                renderer.render_solution(&mut out, &solution).unwrap();
+                stats.solutions += 1;
+            }
+            if number.more > 0 {
+                renderer.render_truncated(&mut out, number.number, number.columns, number.more).unwrap();
+            }
+        }
+        renderer.render_summary(&mut out, FIXTURE.len(), &stats).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    fn with_columns() -> EncodeOptions {
+        EncodeOptions { columns: vec!["name".into(), "kind".into()], ..EncodeOptions::default() }
+    }
+
+    #[test]
+    fn plain() {
+        insta::assert_snapshot!(render(Format::Plain, &EncodeOptions::default()));
+    }
+
+    #[test]
+    fn plain_quoted_and_annotated() {
+        let options = EncodeOptions { quoting: Quoting::Needed, annotate: true, ..EncodeOptions::default() };
+        insta::assert_snapshot!(render(Format::Plain, &options));
+    }
+
+    #[test]
+    fn plain_colored() {
+        let options = EncodeOptions { color: true, annotate: true, ..EncodeOptions::default() };
+        insta::assert_snapshot!(render(Format::Plain, &options));
+    }
+
+    #[test]
+    fn grouped() {
+        let options = EncodeOptions { quoting: Quoting::Always, ..EncodeOptions::default() };
+        insta::assert_snapshot!(render(Format::Grouped, &options));
+    }
+
+    #[test]
+    #[cfg(feature = "formats-json")]
+    fn json() {
+        insta::assert_snapshot!(render(Format::Json, &EncodeOptions::default()));
+    }
+
+    #[test]
+    #[cfg(feature = "formats-json")]
+    fn json_annotated_with_columns() {
+        let options = EncodeOptions { annotate: true, ..with_columns() };
+        insta::assert_snapshot!(render(Format::Json, &options));
+    }
+
+    #[test]
+    #[cfg(feature = "formats-json")]
+    fn json_fields() {
+        let fields = vec![Field::Number, Field::Words, Field::WordCount, Field::DigitCount, Field::Digits];
+        let options = EncodeOptions { annotate: true, fields, ..with_columns() };
+        insta::assert_snapshot!(render(Format::Json, &options));
+    }
+
+    #[test]
+    #[cfg(feature = "formats-json")]
+    fn json_segments() {
+        let options = EncodeOptions { annotate: true, fields: vec![Field::Number, Field::Segments], ..EncodeOptions::default() };
+        insta::assert_snapshot!(render(Format::Json, &options));
+    }
+
+    #[test]
+    fn csv() {
+        insta::assert_snapshot!(render(Format::Csv, &with_columns()));
+    }
+
+    #[test]
+    fn csv_annotated() {
+        let options = EncodeOptions { annotate: true, ..EncodeOptions::default() };
+        insta::assert_snapshot!(render(Format::Csv, &options));
+    }
+
+    #[test]
+    fn csv_quoted() {
+        let options = EncodeOptions { quoting: Quoting::Needed, annotate: true, ..EncodeOptions::default() };
+        insta::assert_snapshot!(render(Format::Csv, &options));
+    }
+
+    #[test]
+    fn tsv() {
+        insta::assert_snapshot!(render(Format::Tsv, &with_columns()));
+    }
+
+    #[test]
+    fn tsv_quoted() {
+        let options = EncodeOptions { quoting: Quoting::Always, ..EncodeOptions::default() };
+        insta::assert_snapshot!(render(Format::Tsv, &options));
+    }
+}
@@ -0,0 +1,109 @@
+//! Generating synthetic dictionaries (`generate-dict`), to study how the search and the
+//! dictionary backends (the hash map of [`Dictionary`](crate::Dictionary) and the
+//! [`Trie`](crate::trie::Trie)) behave as more words collide on the same digits.
+//!
+//! The words are drawn in buckets of words encoded by the same digits, whose sizes follow a
+//! geometric distribution of the given mean, like the buckets of natural languages, where
+//! most digits encode a single word and a few encode many. The same seed gives the same
+//! words on every platform.
+//!
+//! Phone numbers are generated like the numbers files of the benchmark, for `fetch-inputs`.
+
+use std::collections::HashSet;
+
+use crate::mapping::Mapping;
+use crate::sample::Rng;
+
+/// The mean size of the buckets by default.
+pub const DEFAULT_AVG_BUCKET: f64 = 3.0;
+
+/// The digits of each bucket are as long as the words of a dictionary, between these lengths.
+const MIN_LEN: u64 = 2;
+const MAX_LEN: u64 = 12;
+
+/// The characters of the numbers of [`generate_numbers`], as in the benchmark.
+const NUMBER_CHARS: [char; 12] = ['0', '1', '2', '3', '4', '5', '6', '7', '8', '9', '/', '-'];
+/// The numbers of [`generate_numbers`] have up to this many characters.
+const MAX_NUMBER_LEN: u64 = 50;
+
+/// What [`generate_dict`] generates.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DictShape {
+    pub words: usize,
+    /// the mean number of words encoded by the same digits, at least 1.
+    pub avg_bucket: f64,
+    pub seed: u64,
+}
+
+/// The words generated by [`generate_dict`], with how they collide.
+#[derive(Clone, Debug, Default)]
+pub struct GeneratedDict {
+    /// the words, in lower case and in alphabetical order.
+    pub words: Vec<String>,
+    /// how many different digits encode them.
+    pub buckets: usize,
+    /// the most words encoded by the same digits.
+    pub largest_bucket: usize,
+}
+
+/// Generates the words of a dictionary whose letters are mapped to digits by `mapping`, or
+/// none if it maps no letters.
+pub fn generate_dict(shape: &DictShape, mapping: &Mapping) -> GeneratedDict {
+    let letters = mapping.digit_letters();
+    let digits: Vec<&[char]> = letters.iter().filter(|letters| !letters.is_empty()).map(Vec::as_slice).collect();
+    let mut generated = GeneratedDict::default();
+    if digits.is_empty() {
+        return generated;
+    }
+    let mut rng = Rng::new(shape.seed);
+    let mut keys = HashSet::new();
+    while generated.words.len() < shape.words {
+        let size = bucket_size(&mut rng, shape.avg_bucket).min(shape.words - generated.words.len());
+        let len = MIN_LEN + rng.next_u64() % (MAX_LEN - MIN_LEN + 1);
+        let key: Vec<usize> = (0..len).map(|_| pick(&mut rng, digits.len())).collect();
+        if !keys.insert(key.clone()) {
+            continue;
+        }
+        // short digits with few letters may not encode as many words as drawn
+        let mut bucket = HashSet::new();
+        for _ in 0..size * 10 {
+            if bucket.len() == size {
+                break;
+            }
+            bucket.insert(key.iter().map(|&digit| digits[digit][pick(&mut rng, digits[digit].len())]).collect::<String>());
+        }
+        generated.buckets += 1;
+        generated.largest_bucket = generated.largest_bucket.max(bucket.len());
+        generated.words.extend(bucket);
+    }
+    generated.words.sort_unstable();
+    generated
+}
+
+/// Generates phone numbers like the generator of the benchmark (`util.GeneratePhoneNumbers`):
+/// up to 50 random digits, `/` and `-`, with at least one digit unless `allow_empty`.
+pub fn generate_numbers(count: usize, allow_empty: bool, seed: u64) -> Vec<String> {
+    let mut rng = Rng::new(seed);
+    let mut numbers = Vec::with_capacity(count);
+    while numbers.len() < count {
+        let len = 1 + rng.next_u64() % MAX_NUMBER_LEN;
+        let number: String = (0..len).map(|_| NUMBER_CHARS[pick(&mut rng, NUMBER_CHARS.len())]).collect();
+        if allow_empty || number.contains(|ch: char| ch.is_ascii_digit()) {
+            numbers.push(number);
+        }
+    }
+    numbers
+}
+
+/// The size of a bucket, from a geometric distribution of mean `avg_bucket`.
+fn bucket_size(rng: &mut Rng, avg_bucket: f64) -> usize {
+    if avg_bucket <= 1.0 {
+        return 1;
+    }
+    let p = 1.0 / avg_bucket;
+    1 + ((1.0 - rng.next_f64()).ln() / (1.0 - p).ln()) as usize
+}
+
+fn pick(rng: &mut Rng, len: usize) -> usize {
+    (rng.next_u64() % len as u64) as usize
+}
@@ -0,0 +1,240 @@
+//! A gRPC server and client for the `PhoneEncoding` service described in
+//! `proto/phone_encoding.proto`, enabled with the `grpc` feature.
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Instant;
+
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use tokio_stream::StreamExt;
+use tonic::transport::{Channel, Server};
+use tonic::{Request, Response, Status, Streaming};
+
+use crate::admission::{Admission, Refused, ServerLimits};
+use crate::audit::{AuditEntry, AuditLog};
+use crate::cache::{CacheStats, SolutionCache};
+use crate::encoder::Encoder;
+use crate::error::EncodeError;
+use crate::snapshot::Snapshot;
+
+mod generated {
+    include!(concat!(env!("OUT_DIR"), "/phone_encoding.PhoneEncoding.rs"));
+}
+
+pub use generated::phone_encoding_client::PhoneEncodingClient;
+pub use generated::phone_encoding_server::{PhoneEncoding, PhoneEncodingServer};
+
+/// The default address of the server.
+pub const DEFAULT_ADDRESS: &str = "127.0.0.1:50051";
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Number {
+    #[prost(string, tag = "1")]
+    pub number: String,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Solution {
+    #[prost(string, tag = "1")]
+    pub number: String,
+    #[prost(string, repeated, tag = "2")]
+    pub words: Vec<String>,
+}
+
+/// Implementation of the `PhoneEncoding` service backed by an [`Encoder`].
+///
+/// Each call encodes all its numbers with the snapshot of the encoder current when it
+/// started, so the encoder can be replaced (e.g. with an updated dictionary) at any time.
+///
+/// Each number is a request of its own for the [`ServerLimits`]: the numbers beyond them
+/// end the call with `RESOURCE_EXHAUSTED`, or `DEADLINE_EXCEEDED` if they time out.
+pub struct PhoneEncodingService {
+    encoder: Arc<Snapshot<Encoder>>,
+    admission: Admission,
+    cache: Option<Arc<SolutionCache>>,
+    audit: Option<Arc<AuditLog>>,
+}
+
+impl PhoneEncodingService {
+    pub fn new(encoder: Arc<Snapshot<Encoder>>) -> PhoneEncodingService {
+        PhoneEncodingService { encoder, admission: Admission::new(ServerLimits::default()), cache: None, audit: None }
+    }
+
+    pub fn with_limits(mut self, limits: ServerLimits) -> PhoneEncodingService {
+        self.admission = Admission::new(limits);
+        self
+    }
+
+    /// Serves the numbers requested again from `cache`, which must be cleared whenever the
+    /// encoder is replaced with one using another dictionary.
+    pub fn with_cache(mut self, cache: Arc<SolutionCache>) -> PhoneEncodingService {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// Writes each number requested to `audit`, ending the call if that fails.
+    pub fn with_audit(mut self, audit: Arc<AuditLog>) -> PhoneEncodingService {
+        self.audit = Some(audit);
+        self
+    }
+
+    /// The hits and misses of the cache, if any.
+    pub fn cache_stats(&self) -> Option<CacheStats> {
+        self.cache.as_ref().map(|cache| cache.stats())
+    }
+}
+
+#[tonic::async_trait]
+impl PhoneEncoding for PhoneEncodingService {
+    type EncodeStream = ReceiverStream<Result<Solution, Status>>;
+
+    async fn encode(&self, request: Request<Streaming<Number>>) -> Result<Response<Self::EncodeStream>, Status> {
+        let client = request.remote_addr().map(|address| address.to_string());
+        let mut numbers = request.into_inner();
+        let (sender, receiver) = mpsc::channel(64);
+        let encoder = self.encoder.load();
+        let admission = self.admission.clone();
+        let cache = self.cache.clone();
+        let audit = self.audit.clone();
+        tokio::spawn(async move {
+            while let Some(number) = numbers.next().await {
+                let number = match number {
+                    Ok(number) => number.number,
+                    Err(status) => {
+                        let _ = sender.send(Err(status)).await;
+                        return;
+                    }
+                };
+                // encoding is CPU-bound, so it's kept off the async worker threads
+                let (task_encoder, task_admission, task_cache) = (encoder.clone(), admission.clone(), cache.clone());
+                let (task_audit, task_client) = (audit.clone(), client.clone());
+                let solutions = tokio::task::spawn_blocking(move || {
+                    let start_time = Instant::now();
+                    let result = solutions_of(&task_encoder, &task_admission, task_cache.as_deref(), &number);
+                    match task_audit {
+                        Some(audit) => record(&audit, task_client.as_deref(), &number, result, start_time),
+                        None => result,
+                    }
+                }).await;
+                let solutions = match solutions {
+                    Ok(Ok((solutions, _))) => solutions,
+                    Ok(Err(status)) => {
+                        let _ = sender.send(Err(*status)).await;
+                        return;
+                    }
+                    Err(e) => {
+                        let _ = sender.send(Err(Status::internal(e.to_string()))).await;
+                        return;
+                    }
+                };
+                for solution in solutions {
+                    if sender.send(Ok(solution)).await.is_err() {
+                        return; // client went away
+                    }
+                }
+            }
+        });
+        Ok(Response::new(ReceiverStream::new(receiver)))
+    }
+}
+
+/// The solutions of a number, from the cache if there, or else once admitted, blocking until
+/// then, with whether they came from the cache (the status is boxed, as it is much larger
+/// than the solutions).
+fn solutions_of(
+    encoder: &Encoder,
+    admission: &Admission,
+    cache: Option<&SolutionCache>,
+    number: &str,
+) -> Result<(Vec<Solution>, bool), Box<Status>> {
+    // invalid numbers are not cached, but get their status from the encoder
+    let key = cache.and_then(|cache| Some((cache, encoder.cache_key(number).ok()?)));
+    if let Some(words) = key.as_ref().and_then(|(cache, key)| cache.get(key)) {
+        let solutions = words.iter().map(|words| Solution { number: number.to_string(), words: words.clone() }).collect();
+        return Ok((solutions, true));
+    }
+    let deadline = admission.deadline();
+    let _permit = admission.admit(deadline).map_err(|refused| match refused {
+        Refused::Saturated => Status::resource_exhausted("too many requests, try again later"),
+        Refused::TimedOut => Status::deadline_exceeded("timed out waiting for other requests"),
+    }).map_err(Box::new)?;
+    let mut solutions = Vec::new();
+    let stats = encoder.for_each_solution_until(number, deadline, &mut |parts| {
+        solutions.push(Solution {
+            number: number.to_string(),
+            words: parts.iter().map(|part| part.to_string()).collect(),
+        });
+        Ok(())
+    });
+    let status = match stats {
+        Ok(stats) if stats.interrupted => Status::deadline_exceeded(format!("timed out after {} solutions", stats.solutions)),
+        Ok(_) => {
+            if let Some((cache, key)) = key {
+                cache.insert(key, Arc::new(solutions.iter().map(|solution| solution.words.clone()).collect()));
+            }
+            return Ok((solutions, false));
+        }
+        Err(e @ (EncodeError::InvalidNumber { .. } | EncodeError::EmptyNumber { .. })) => Status::invalid_argument(e.to_string()),
+        Err(e) => Status::internal(e.to_string()),
+    };
+    Err(Box::new(status))
+}
+
+/// Writes a request to the audit log, with the code of its status if it failed, and returns
+/// its result, or an internal error if the log cannot be written.
+fn record(
+    audit: &AuditLog,
+    client: Option<&str>,
+    number: &str,
+    result: Result<(Vec<Solution>, bool), Box<Status>>,
+    start_time: Instant,
+) -> Result<(Vec<Solution>, bool), Box<Status>> {
+    let outcome = match &result {
+        Ok(_) => "ok".to_string(),
+        Err(status) => format!("{:?}", status.code()),
+    };
+    let (solutions, cached) = result.as_ref().map_or((0, false), |(solutions, cached)| (solutions.len(), *cached));
+    let entry = AuditEntry { server: "grpc", client, command: "Encode", number, outcome: &outcome, solutions, cached, duration: start_time.elapsed() };
+    match audit.record(&entry) {
+        Ok(()) => result,
+        Err(e) => Err(Box::new(Status::internal(format!("cannot write the audit log: {}", e)))),
+    }
+}
+
+/// Serves the `PhoneEncoding` service on the given address until the process is stopped.
+pub async fn serve(
+    encoder: Encoder,
+    address: SocketAddr,
+    limits: ServerLimits,
+    cache: Option<Arc<SolutionCache>>,
+    audit: Option<Arc<AuditLog>>,
+) -> Result<(), tonic::transport::Error> {
+    let mut service = PhoneEncodingService::new(Arc::new(Snapshot::new(encoder))).with_limits(limits);
+    if let Some(cache) = cache {
+        service = service.with_cache(cache);
+    }
+    if let Some(audit) = audit {
+        service = service.with_audit(audit);
+    }
+    Server::builder()
+        .add_service(PhoneEncodingServer::new(service))
+        .serve(address)
+        .await
+}
+
+/// Sends the given numbers to the server at `address`, calling `on_solution` with each solution received.
+pub async fn encode_remote<F: FnMut(Solution)>(
+    address: String,
+    numbers: Vec<String>,
+    mut on_solution: F,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let channel = Channel::from_shared(address)?.connect().await?;
+    let mut client = PhoneEncodingClient::new(channel);
+    let requests = tokio_stream::iter(numbers.into_iter().map(|number| Number { number }));
+    let mut solutions = client.encode(requests).await?.into_inner();
+    while let Some(solution) = solutions.next().await {
+        on_solution(solution?);
+    }
+    Ok(())
+}
@@ -0,0 +1,136 @@
+//! Solutions as indices into the dictionary: each word of a solution is the id of the key
+//! of its entry and its index among the words of the entry, resolved to its text when the
+//! solution is written.
+//!
+//! The search hands the parts of a solution over as text borrowed from the dictionary (see
+//! [`WordOrDigit`]), which the filters need. A [`SolutionIds`] is a copy of them a few bytes
+//! a part long, which owns nothing: solutions can be kept, sent to other threads, hashed
+//! and deduplicated cheaply, and resolved with the dictionary they were found in.
+
+use std::collections::HashMap;
+
+use num_bigint::BigUint;
+
+use crate::dict::Dictionary;
+use crate::search::{digit_spans, nth_digit, WordOrDigit};
+
+/// A word of the dictionary: the id of its key and its index among the words of the key.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct WordId {
+    pub key: u32,
+    pub word: u32,
+}
+
+/// A part of a [`SolutionIds`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum PartId {
+    Word(WordId),
+    /// a digit of the number, from 0 to 9.
+    Digit(u8),
+}
+
+/// A solution as the ids of its parts.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct SolutionIds(pub Box<[PartId]>);
+
+/// The ids of the keys of a dictionary, in the order of their digits, so that the ids of the
+/// same words are the same in every run.
+#[derive(Clone, Debug, Default)]
+pub struct WordIds {
+    keys: Vec<BigUint>,
+    ids: HashMap<BigUint, u32>,
+}
+
+impl WordIds {
+    pub fn new(dict: &Dictionary) -> WordIds {
+        let mut keys: Vec<BigUint> = dict.keys().cloned().collect();
+        // as the keys start with a 1, shorter digits come first
+        keys.sort_unstable();
+        let ids = keys.iter().enumerate().map(|(id, key)| (key.clone(), id as u32)).collect();
+        WordIds { keys, ids }
+    }
+
+    /// The ids of the parts of a solution for the given digits, found in `dict`, if they are
+    /// all there.
+    pub fn ids(&self, digits: &[char], parts: &[WordOrDigit], dict: &Dictionary) -> Option<SolutionIds> {
+        let spans = digit_spans(digits, parts, dict);
+        let mut ids = Vec::with_capacity(parts.len());
+        for (part, span) in parts.iter().zip(spans) {
+            ids.push(match part {
+                WordOrDigit::Digit(digit) => PartId::Digit(digit.to_digit(10)? as u8),
+                WordOrDigit::Word(text) => {
+                    let key = span.map(|i| nth_digit(digits, i))
+                        .try_fold(BigUint::from(1u32), |key, digit| Some(key * 10u32 + digit?))?;
+                    let word = dict.get(&key)?.iter().position(|word| word.text == *text)?;
+                    PartId::Word(WordId { key: *self.ids.get(&key)?, word: word as u32 })
+                }
+            });
+        }
+        Some(SolutionIds(ids.into_boxed_slice()))
+    }
+
+    /// The parts of a solution, with the text of its words in `dict`, if they are all there.
+    pub fn resolve<'d>(&self, ids: &SolutionIds, dict: &'d Dictionary) -> Option<Vec<WordOrDigit<'d>>> {
+        ids.0.iter()
+            .map(|id| match *id {
+                PartId::Digit(digit) => Some(WordOrDigit::Digit(char::from(b'0' + digit))),
+                PartId::Word(WordId { key, word }) => {
+                    let words = dict.get(self.keys.get(key as usize)?)?;
+                    Some(WordOrDigit::Word(&words.get(word as usize)?.text))
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+    use crate::mapping::Mapping;
+    use crate::search::words_from_bytes;
+
+    use WordOrDigit::{Digit, Word};
+
+    fn dict() -> Dictionary {
+        words_from_bytes(b"mir\nMix\nTor\nTorf\nfort\n", &Mapping::default()).into_iter().collect()
+    }
+
+    fn digits(num: &str) -> Vec<char> {
+        num.chars().filter(char::is_ascii_digit).collect()
+    }
+
+    #[test]
+    fn resolves_the_ids_of_a_solution() {
+        let dict = dict();
+        let ids = WordIds::new(&dict);
+        for (num, parts) in [("5624-82", [Word("Mix"), Word("Tor")].as_slice()), ("4824", &[Word("Tor"), Digit('4')])] {
+            let solution = ids.ids(&digits(num), parts, &dict).unwrap();
+            assert_eq!(ids.resolve(&solution, &dict).unwrap(), parts);
+        }
+    }
+
+    #[test]
+    fn the_same_solutions_have_the_same_ids() {
+        let dict = dict();
+        let ids = WordIds::new(&dict);
+        let digits = digits("562482");
+        let solutions: HashSet<SolutionIds> = [[Word("mir"), Word("Tor")], [Word("Mix"), Word("Tor")], [Word("mir"), Word("Tor")]]
+            .iter()
+            .map(|parts| ids.ids(&digits, parts, &dict).unwrap())
+            .collect();
+        assert_eq!(solutions.len(), 2);
+        // words of the same key differ only by their index
+        let [PartId::Word(mir), _] = *ids.ids(&digits, &[Word("mir"), Word("Tor")], &dict).unwrap().0 else { panic!() };
+        let [PartId::Word(mix), _] = *ids.ids(&digits, &[Word("Mix"), Word("Tor")], &dict).unwrap().0 else { panic!() };
+        assert_eq!(mir.key, mix.key);
+        assert_ne!(mir.word, mix.word);
+    }
+
+    #[test]
+    fn a_word_not_in_the_dictionary_has_no_id() {
+        let dict = dict();
+        assert_eq!(WordIds::new(&dict).ids(&digits("562482"), &[Word("mir"), Word("Ort")], &dict), None);
+    }
+}
@@ -0,0 +1,422 @@
+//! Reading the phone numbers to encode.
+//!
+//! With the `compress` feature, the numbers and words files whose name ends with `.gz` are
+//! decompressed as they are read (see [`decompressed`]).
+
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader};
+use std::mem;
+use std::path::{Path, PathBuf};
+
+#[cfg(feature = "compress")]
+use flate2::bufread::MultiGzDecoder;
+use memchr::memchr;
+
+use crate::error::EncodeError;
+use crate::number::check_number;
+use crate::sample::{Select, Selection, Selector};
+
+/// The size of the buffer used to read input files.
+const BUFFER_SIZE: usize = 64 * 1024;
+
+/// Reads the lines of the input, reusing a single buffer for all of them.
+///
+/// Line ends are located with `memchr`, which scans many bytes at a time using SIMD
+/// instructions where the CPU supports them (with a scalar fallback elsewhere).
+pub struct LineReader<R> {
+    reader: R,
+    line: String,
+    /// which lines are returned, if not all of them.
+    selector: Option<Selector>,
+    /// how the numbers are laid out on the lines.
+    records: Records,
+    /// the number of the last line read, from 1.
+    line_number: usize,
+    /// whether a number with characters other than digits and separators is an error.
+    fail_on_invalid: bool,
+}
+
+/// How the numbers are laid out on the lines of a numbers file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Records {
+    /// a number on each line, which is returned as it is.
+    Lines,
+    /// found out from the first line, which is either the header of a CSV file with a column
+    /// of this name, or else a JSON object, or else a number.
+    Detect(String),
+    /// CSV, the numbers being in the column at this index (the header is skipped).
+    Csv(usize),
+    /// an object on each line, with the number as a member of this name.
+    Jsonl(String),
+}
+
+impl<'a> LineReader<Box<dyn BufRead + 'a>> {
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<LineReader<Box<dyn BufRead + 'a>>> {
+        LineReader::open_or_preloaded(path, None)
+    }
+
+    /// Reads the lines of `preloaded`, the contents of the file read into memory beforehand,
+    /// or else of the file at `path`.
+    pub fn open_or_preloaded<P: AsRef<Path>>(path: P, preloaded: Option<&'a [u8]>) -> io::Result<LineReader<Box<dyn BufRead + 'a>>> {
+        let path = path.as_ref();
+        Ok(LineReader::new(match preloaded {
+            Some(contents) => decompressed(path, contents),
+            None => decompressed(path, BufReader::with_capacity(BUFFER_SIZE, File::open(path)?)),
+        }))
+    }
+}
+
+impl<R: BufRead> LineReader<R> {
+    pub fn new(reader: R) -> LineReader<R> {
+        LineReader { reader, line: String::new(), selector: None, records: Records::Lines, line_number: 0, fail_on_invalid: false }
+    }
+
+    /// Only returns the lines picked by `selection`, ending the input once it is done.
+    pub fn select(mut self, selection: &Selection) -> LineReader<R> {
+        self.selector = (!selection.is_all()).then(|| selection.selector());
+        self
+    }
+
+    /// Returns the number of each line instead of the whole line, for numbers files that are
+    /// CSV files with a header or JSONL files, detected from their first line, with the
+    /// numbers in the column or member named `field`.
+    pub fn detect_format(mut self, field: &str) -> LineReader<R> {
+        self.records = Records::Detect(field.to_string());
+        self
+    }
+
+    /// With `fail` set, fails on a number with characters other than digits and separators
+    /// (the columns after a tab are not checked), naming its line.
+    pub fn fail_on_invalid(mut self, fail: bool) -> LineReader<R> {
+        self.fail_on_invalid = fail;
+        self
+    }
+
+    /// Returns the next line without its line terminator, or `None` at the end of the input.
+    ///
+    /// Like with [`BufRead::lines`], lines may end with `\n` or `\r\n`, but lines that
+    /// are not valid UTF-8 are skipped instead of returning an error.
+    ///
+    /// With [`LineReader::detect_format`], returns the number of the next record instead,
+    /// failing if a line of a JSONL file is not an object with the number.
+    /// With [`LineReader::fail_on_invalid`], also fails on invalid numbers.
+    pub fn next_line(&mut self) -> io::Result<Option<&str>> {
+        let mut bytes = mem::take(&mut self.line).into_bytes();
+        loop {
+            bytes.clear();
+            if !read_line(&mut self.reader, &mut bytes)? {
+                return Ok(None);
+            }
+            self.line_number += 1;
+            match String::from_utf8(bytes) {
+                Ok(mut line) => {
+                    if let Records::Detect(field) = &self.records {
+                        self.records = detect_records(&line, field);
+                        if let Records::Csv(_) = self.records {
+                            bytes = line.into_bytes();
+                            continue;
+                        }
+                    }
+                    match &self.records {
+                        Records::Lines | Records::Detect(_) => {}
+                        Records::Csv(column) => line = csv_fields(&line).into_iter().nth(*column).unwrap_or_default(),
+                        Records::Jsonl(field) => line = json_member(&line, field).map_err(|e| io::Error::new(
+                            io::ErrorKind::InvalidData, format!("line {}: {}", self.line_number, e)))?,
+                    }
+                    match self.selector.as_mut().map_or(Select::Keep, Selector::select) {
+                        Select::Keep => {}
+                        Select::Drop => {
+                            bytes = line.into_bytes();
+                            continue;
+                        }
+                        Select::Done => return Ok(None),
+                    }
+                    if self.fail_on_invalid {
+                        let number = line.split('\t').next().unwrap_or_default();
+                        if let Err(invalid) = check_number(number, true) {
+                            let error = EncodeError::InvalidNumber { number: number.to_string(), char: invalid.char };
+                            return Err(io::Error::new(io::ErrorKind::InvalidData, format!("line {}: {}", self.line_number, error)));
+                        }
+                    }
+                    self.line = line;
+                    return Ok(Some(&self.line));
+                }
+                Err(e) => bytes = e.into_bytes(),
+            }
+        }
+    }
+}
+
+/// How the numbers are laid out, from the first line of a numbers file.
+fn detect_records(first_line: &str, field: &str) -> Records {
+    if first_line.trim_start().starts_with('{') {
+        return Records::Jsonl(field.to_string());
+    }
+    // a header of the field alone is a CSV file of a single column, as no number is named so
+    match csv_fields(first_line).iter().position(|name| name.trim() == field) {
+        Some(column) => Records::Csv(column),
+        None => Records::Lines,
+    }
+}
+
+/// The fields of a line of a CSV file, unquoted as needed by RFC 4180 (quoted fields may
+/// not span several lines).
+fn csv_fields(line: &str) -> Vec<String> {
+    let mut fields = vec![String::new()];
+    let mut chars = line.chars().peekable();
+    let mut quoted = false;
+    while let Some(ch) = chars.next() {
+        let field = fields.last_mut().expect("there is a field");
+        match ch {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(String::new()),
+            ch => field.push(ch),
+        }
+    }
+    fields
+}
+
+/// The member `field` of the JSON object on a line, which is a string or a number.
+#[cfg(feature = "formats-json")]
+fn json_member(line: &str, field: &str) -> Result<String, String> {
+    let object: serde_json::Value = serde_json::from_str(line).map_err(|e| e.to_string())?;
+    match object.get(field) {
+        Some(serde_json::Value::String(number)) => Ok(number.clone()),
+        Some(number @ serde_json::Value::Number(_)) => Ok(number.to_string()),
+        Some(_) => Err(format!("'{}' is not a string", field)),
+        None => Err(format!("no '{}' member", field)),
+    }
+}
+
+#[cfg(not(feature = "formats-json"))]
+fn json_member(_line: &str, _field: &str) -> Result<String, String> {
+    Err("JSONL numbers files require the formats-json feature".into())
+}
+
+/// The contents of the file at `path`, decompressed if its name ends with `.gz`.
+#[cfg(feature = "compress")]
+pub fn decompressed<'a, R: BufRead + 'a>(path: &Path, contents: R) -> Box<dyn BufRead + 'a> {
+    if path.extension().is_some_and(|extension| extension == "gz") {
+        // the files made by concatenating gzip files, e.g. shards, are read whole
+        Box::new(BufReader::with_capacity(BUFFER_SIZE, MultiGzDecoder::new(contents)))
+    } else {
+        Box::new(contents)
+    }
+}
+
+/// The contents of the file at `path`, as they are without the `compress` feature.
+#[cfg(not(feature = "compress"))]
+pub fn decompressed<'a, R: BufRead + 'a>(_path: &Path, contents: R) -> Box<dyn BufRead + 'a> {
+    Box::new(contents)
+}
+
+/// Appends the next line, without its line terminator, to `line`,
+/// returning `false` if the end of the input was reached before.
+pub(crate) fn read_line<R: BufRead>(reader: &mut R, line: &mut Vec<u8>) -> io::Result<bool> {
+    let mut read_any = false;
+    loop {
+        let buf = match reader.fill_buf() {
+            Ok(buf) => buf,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        if buf.is_empty() {
+            return Ok(read_any);
+        }
+        read_any = true;
+        match memchr(b'\n', buf) {
+            Some(end) => {
+                line.extend_from_slice(&buf[..end]);
+                reader.consume(end + 1);
+                if line.last() == Some(&b'\r') {
+                    line.pop();
+                }
+                return Ok(true);
+            }
+            None => {
+                let len = buf.len();
+                line.extend_from_slice(buf);
+                reader.consume(len);
+            }
+        }
+    }
+}
+
+/// The numbers files named by `spec`: every file of a directory, the files matching a glob
+/// (with `*` and `?` in the file name only, e.g. `shards/part-*.txt`), or else the file itself.
+/// The files of a directory or glob are sorted by name, so that runs are deterministic.
+pub fn input_files(spec: &str) -> io::Result<Vec<PathBuf>> {
+    let path = Path::new(spec);
+    let (dir, pattern) = if path.is_dir() {
+        (path, "*")
+    } else {
+        match path.file_name().and_then(|name| name.to_str()) {
+            Some(name) if name.contains(['*', '?']) => (path.parent().unwrap_or(Path::new("")), name),
+            _ => return Ok(vec![path.to_path_buf()]),
+        }
+    };
+    let dir = if dir.as_os_str().is_empty() { Path::new(".") } else { dir };
+    let pattern: Vec<char> = pattern.chars().collect();
+    let mut files = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let matches = entry.file_name().to_str()
+            .is_some_and(|name| glob_matches(&pattern, &name.chars().collect::<Vec<_>>()));
+        if matches && entry.file_type()?.is_file() {
+            files.push(entry.path());
+        }
+    }
+    if files.is_empty() && !path.is_dir() {
+        return Err(io::Error::new(io::ErrorKind::NotFound, "no files match"));
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Whether `name` matches `pattern`, where `*` matches any characters and `?` any one character.
+fn glob_matches(pattern: &[char], name: &[char]) -> bool {
+    match pattern.split_first() {
+        None => name.is_empty(),
+        Some(('*', rest)) => (0..=name.len()).any(|skip| glob_matches(rest, &name[skip..])),
+        Some((ch, rest)) => name.split_first()
+            .is_some_and(|(first, name)| (*ch == '?' || ch == first) && glob_matches(rest, name)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The numbers of the records of `contents`, detected with the field `number`.
+    fn records(contents: &str) -> io::Result<Vec<String>> {
+        let mut reader = LineReader::new(contents.as_bytes()).detect_format("number");
+        let mut numbers = Vec::new();
+        while let Some(number) = reader.next_line()? {
+            numbers.push(number.to_string());
+        }
+        Ok(numbers)
+    }
+
+    #[test]
+    fn splits_csv_fields() {
+        assert_eq!(csv_fields("a,\"b,c\",\"d\"\"e\","), ["a", "b,c", "d\"e", ""]);
+        assert_eq!(csv_fields(""), [""]);
+        assert_eq!(csv_fields("\"5624-82\""), ["5624-82"]);
+    }
+
+    #[test]
+    fn reads_the_numbers_of_csv_files() {
+        let csv = "id,number,name\r\n1,\"5624-82\",\"Mix, Tor\"\r\n2,4824,\"\"\"Torf\"\"\"\r\n";
+        assert_eq!(records(csv).unwrap(), ["5624-82", "4824"]);
+        // a header of the number alone, and a row without it
+        assert_eq!(records("number\n5624-82\n4824\n").unwrap(), ["5624-82", "4824"]);
+        assert_eq!(records("id, number \n1,5624-82\n2,\n3\n").unwrap(), ["5624-82", "", ""]);
+        let mut reader = LineReader::new("id,phone\n1,4824\n".as_bytes()).detect_format("phone");
+        assert_eq!(reader.next_line().unwrap(), Some("4824"));
+        // without a header naming the number, the lines are numbers
+        assert_eq!(records("5624-82\nid,phone\n").unwrap(), ["5624-82", "id,phone"]);
+        assert_eq!(records("").unwrap(), [] as [String; 0]);
+    }
+
+    #[cfg(feature = "formats-json")]
+    #[test]
+    fn reads_the_numbers_of_jsonl_files() {
+        let jsonl = "{\"number\": \"5624-82\", \"id\": 1}\r\n {\"number\": 4824}\n";
+        assert_eq!(records(jsonl).unwrap(), ["5624-82", "4824"]);
+        let error = records("{\"number\": \"4824\"}\n{\"phone\": \"4824\"}\n").unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(error.to_string(), "line 2: no 'number' member");
+        assert_eq!(records("{\"number\": [4824]}\n").unwrap_err().to_string(), "line 1: 'number' is not a string");
+        assert!(records("{\"number\": \"4824\"}\nnot json\n").unwrap_err().to_string().starts_with("line 2: "));
+    }
+
+    fn lines(reader: &mut LineReader<impl BufRead>) -> Vec<String> {
+        let mut lines = Vec::new();
+        while let Some(line) = reader.next_line().unwrap() {
+            lines.push(line.to_string());
+        }
+        lines
+    }
+
+    #[test]
+    fn reads_lines_ending_in_any_way() {
+        // lines longer than the buffer, too
+        let contents = "5624-82\r\n\n4824\r\r\n0721/608-4067\n10/783--5";
+        for capacity in [1, 3, BUFFER_SIZE] {
+            let mut reader = LineReader::new(BufReader::with_capacity(capacity, contents.as_bytes()));
+            assert_eq!(lines(&mut reader), ["5624-82", "", "4824\r", "0721/608-4067", "10/783--5"]);
+        }
+        assert!(lines(&mut LineReader::new("".as_bytes())).is_empty());
+        assert_eq!(lines(&mut LineReader::new("\n".as_bytes())), [""]);
+    }
+
+    #[test]
+    fn skips_lines_that_are_not_utf8_keeping_the_line_numbers() {
+        let contents: &[u8] = b"5624-82\n48\xff24\n4824\n56a2\n";
+        assert_eq!(lines(&mut LineReader::new(contents)), ["5624-82", "4824", "56a2"]);
+        let mut reader = LineReader::new(contents).fail_on_invalid(true);
+        assert_eq!(reader.next_line().unwrap(), Some("5624-82"));
+        assert_eq!(reader.next_line().unwrap(), Some("4824"));
+        let error = reader.next_line().unwrap_err();
+        assert_eq!(error.to_string(), "line 4: invalid character 'a' in phone number '56a2'");
+    }
+
+    /// A directory of its own for the files of a test, deleted when dropped.
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> TempDir {
+            let dir = std::env::temp_dir().join(format!("phone_encoder-input-{}-{}", name, std::process::id()));
+            let _ = fs::remove_dir_all(&dir);
+            fs::create_dir_all(&dir).unwrap();
+            TempDir(dir)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn matches_globs() {
+        let matches = |pattern: &str, name: &str| {
+            glob_matches(&pattern.chars().collect::<Vec<_>>(), &name.chars().collect::<Vec<_>>())
+        };
+        assert!(matches("*", "") && matches("*", "part-1.txt"));
+        assert!(matches("part-*.txt", "part-.txt") && matches("part-*.txt", "part-10.txt"));
+        assert!(!matches("part-*.txt", "part-1.txt.gz") && !matches("part-*.txt", "xpart-1.txt"));
+        assert!(matches("part-?.txt", "part-1.txt") && !matches("part-?.txt", "part-10.txt"));
+        assert!(matches("*-*-?", "a-b-c") && !matches("*-*-?", "a-b"));
+        assert!(matches("?ü*", "xü") && !matches("", "a"));
+    }
+
+    #[test]
+    fn lists_the_files_of_a_directory_or_glob_sorted() {
+        let dir = TempDir::new("files");
+        for name in ["part-2.txt", "part-10.txt", "part-1.txt", "other.txt"] {
+            fs::write(dir.0.join(name), "").unwrap();
+        }
+        fs::create_dir(dir.0.join("part-3.txt")).unwrap();
+        let names = |spec: &Path| -> Vec<String> {
+            input_files(spec.to_str().unwrap()).unwrap().iter()
+                .map(|file| file.file_name().unwrap().to_str().unwrap().to_string())
+                .collect()
+        };
+        assert_eq!(names(&dir.0), ["other.txt", "part-1.txt", "part-10.txt", "part-2.txt"]);
+        assert_eq!(names(&dir.0.join("part-*.txt")), ["part-1.txt", "part-10.txt", "part-2.txt"]);
+        assert_eq!(names(&dir.0.join("part-?.txt")), ["part-1.txt", "part-2.txt"]);
+        // a file is itself, even if it does not exist
+        assert_eq!(names(&dir.0.join("missing.txt")), ["missing.txt"]);
+        let error = input_files(dir.0.join("*.gz").to_str().unwrap()).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::NotFound);
+        // an empty directory is no error
+        let empty = TempDir::new("empty");
+        assert!(input_files(empty.0.to_str().unwrap()).unwrap().is_empty());
+    }
+}
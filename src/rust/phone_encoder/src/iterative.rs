@@ -0,0 +1,61 @@
+//! Depth-first search with an explicit stack instead of recursion.
+
+use alloc::vec::Vec;
+
+use crate::filter::SolutionFilter;
+use crate::search::{Limits, EncodeStats, Lookup, OnRejected, OnSolution, SearchStrategy, Solutions, WordOrDigit};
+
+/// The depth-first search of [`RecursiveDfs`](crate::search::RecursiveDfs), keeping the
+/// partial solutions still to be explored on a stack, so that the depth of the search is
+/// not limited by the size of the call stack.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct IterativeDfs;
+
+impl SearchStrategy for IterativeDfs {
+    fn search<D: Lookup + ?Sized, E>(
+        &self,
+        digits: &[char],
+        dict: &D,
+        filter: &dyn SolutionFilter,
+        limits: Limits,
+        on_solution: &mut OnSolution<E>,
+        on_rejected: &mut OnRejected<E>,
+    ) -> Result<EncodeStats, E> {
+        let mut solutions = Solutions::new(digits, filter, limits, on_solution, on_rejected);
+        // the position of the next digit to encode and the parts before it
+        let mut stack = Vec::with_capacity(digits.len());
+        stack.push((0, Vec::new()));
+        let mut branches = Vec::new();
+        while let Some((start, words)) = stack.pop() {
+            if solutions.is_full() || solutions.too_deep(words.len()) {
+                break;
+            }
+            if start >= digits.len() {
+                solutions.check(&words)?;
+                continue;
+            }
+            solutions.visit(digits.len() - start);
+            for (len, found_words) in dict.matches_at(&digits[start..]) {
+                for word in found_words {
+                    if solutions.too_wide(branches.len() + 1) {
+                        return Ok(solutions.stats);
+                    }
+                    let mut partial_solution: Vec<WordOrDigit> = words.clone();
+                    partial_solution.push(WordOrDigit::Word(&word.text));
+                    branches.push((start + len, partial_solution));
+                }
+            }
+            if branches.is_empty() {
+                if !words.last().map(|w| w.is_digit()).unwrap_or(false) {
+                    let mut partial_solution = words;
+                    partial_solution.push(WordOrDigit::Digit(digits[start]));
+                    stack.push((start + 1, partial_solution));
+                }
+            } else {
+                // the branches are explored in the order they were found
+                stack.extend(branches.drain(..).rev());
+            }
+        }
+        Ok(solutions.stats)
+    }
+}
@@ -0,0 +1,285 @@
+//! Newline-delimited [JSON-RPC 2.0](https://www.jsonrpc.org/specification) over any
+//! reader/writer pair (normally stdin/stdout), so that tools can keep a warm encoder process.
+//!
+//! Methods:
+//!
+//! * `encode` - params: `{"number": "5624-82"}` or `["5624-82"]`, result: `["mir Tor", "Mix Tor"]`.
+//!   With `{"number": "5624-82", "parts": true}`, each solution is an array of its parts instead,
+//!   `[["mir", "Tor"], ["Mix", "Tor"]]`, which stays unambiguous for words containing spaces.
+//! * `count` - same params as `encode`, result: the number of solutions. With
+//!   `{"number": "5624-82", "examples": 2}`, the result is `{"solutions": 2, "examples": ["mir Tor", ...]}`,
+//!   with a random sample of at most that many of the solutions (given a `"seed"`, the same one
+//!   every time), to see what they look like without returning them all.
+//! * `explain` - params: `{"number": "4824", "solution": ["4", "Tor"]}`, with the parts of a
+//!   candidate solution (a single digit is a digit, anything else a word), result: whether it is
+//!   a solution, the digits encoded by each part (as far as they can be followed), and every rule
+//!   it breaks, with the part breaking it: `{"solution": false, "spans": [[0, 1]], "violations":
+//!   [{"rule": "digit_where_word_fits", "part": 0, "message": "part 0 is a digit where a word
+//!   fits"}, {"rule": "wrong_word", "part": 1, ...}]}`.
+//! * `reload-dict` - params (optional): `{"words": ["words.txt", ...]}`, reloads the dictionary
+//!   (from the same files as before if none are given), result: `{"entries": <number of keys>}`.
+
+use std::io::{self, BufRead, Write};
+
+use serde_json::{json, Value};
+
+use crate::dict::WordsFile;
+use crate::encoder::Encoder;
+use crate::error::EncodeError;
+use crate::sample::Reservoir;
+use crate::search::WordOrDigit;
+use crate::verify::Explanation;
+
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const SERVER_ERROR: i64 = -32000;
+
+/// Serves JSON-RPC requests read from `input` until it is closed.
+///
+/// `load` creates an [`Encoder`] from words files, and is used initially and on `reload-dict`.
+pub fn serve<L, R, W>(mut words_files: Vec<WordsFile>, load: L, input: R, mut output: W) -> io::Result<()>
+    where L: Fn(&[WordsFile]) -> Result<Encoder, EncodeError>, R: BufRead, W: Write {
+    let mut encoder = load(&words_files)?;
+    for line in input.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let request: Value = match serde_json::from_str(&line) {
+            Ok(request) => request,
+            Err(e) => {
+                write_response(&mut output, &Value::Null, Err((PARSE_ERROR, e.to_string())))?;
+                continue;
+            }
+        };
+        // requests without an id are notifications, which get no response
+        let id = request.get("id").cloned();
+        let result = match request.get("method").and_then(Value::as_str) {
+            None => Err((INVALID_REQUEST, "missing method".to_string())),
+            Some("encode") => number_param(&request).and_then(|number| {
+                let as_parts = request.get("params")
+                    .and_then(|p| p.get("parts"))
+                    .and_then(Value::as_bool)
+                    .unwrap_or(false);
+                let mut solutions = Vec::new();
+                encoder.for_each_solution(&number, &mut |parts| {
+                    let parts = parts.iter().map(ToString::to_string);
+                    solutions.push(if as_parts {
+                        Value::Array(parts.map(Value::String).collect())
+                    } else {
+                        Value::String(parts.collect::<Vec<_>>().join(" "))
+                    });
+                    Ok(())
+                }).map_err(server_error)?;
+                Ok(Value::Array(solutions))
+            }),
+            Some("count") => number_param(&request).and_then(|number| {
+                let params = request.get("params");
+                let Some(examples) = params.and_then(|p| p.get("examples")) else {
+                    let stats = encoder.for_each_solution(&number, &mut |_| Ok(())).map_err(server_error)?;
+                    return Ok(json!(stats.solutions));
+                };
+                let size = examples.as_u64()
+                    .ok_or_else(|| (INVALID_PARAMS, "expected examples to be a number".to_string()))?;
+                let seed = params.and_then(|p| p.get("seed")).and_then(Value::as_u64).unwrap_or(0);
+                let mut reservoir = Reservoir::new(size as usize, seed);
+                let stats = encoder.for_each_solution(&number, &mut |parts| {
+                    reservoir.offer(|| parts.iter().map(ToString::to_string).collect::<Vec<_>>().join(" "));
+                    Ok(())
+                }).map_err(server_error)?;
+                Ok(json!({ "solutions": stats.solutions, "examples": reservoir.into_items() }))
+            }),
+            Some("explain") => number_param(&request).and_then(|number| {
+                let solution = solution_param(&request)?;
+                let parts: Vec<WordOrDigit> = solution.iter().map(|part| WordOrDigit::parse(part)).collect();
+                Ok(explanation_json(&encoder.explain(&number, &parts)))
+            }),
+            Some("reload-dict") => reload_params(&request).and_then(|files| {
+                if let Some(files) = files {
+                    words_files = files;
+                }
+                encoder = load(&words_files).map_err(server_error)?;
+                Ok(json!({ "entries": encoder.dictionary().len() }))
+            }),
+            Some(method) => Err((METHOD_NOT_FOUND, format!("unknown method: {}", method))),
+        };
+        if let Some(id) = id {
+            write_response(&mut output, &id, result)?;
+        }
+    }
+    Ok(())
+}
+
+fn number_param(request: &Value) -> Result<String, (i64, String)> {
+    let params = request.get("params");
+    params.and_then(|p| p.get("number"))
+        .or_else(|| params.and_then(|p| p.get(0)))
+        .and_then(Value::as_str)
+        .map(str::to_string)
+        .ok_or_else(|| (INVALID_PARAMS, "expected params {\"number\": <string>}".to_string()))
+}
+
+fn solution_param(request: &Value) -> Result<Vec<String>, (i64, String)> {
+    request.get("params")
+        .and_then(|p| p.get("solution"))
+        .and_then(Value::as_array)
+        .and_then(|parts| parts.iter().map(|part| part.as_str().map(str::to_string)).collect())
+        .ok_or_else(|| (INVALID_PARAMS, "expected solution to be an array of strings".to_string()))
+}
+
+fn explanation_json(explanation: &Explanation) -> Value {
+    let violations: Vec<Value> = explanation.violations.iter()
+        .map(|violation| json!({ "rule": violation.name(), "part": violation.part(), "message": violation.to_string() }))
+        .collect();
+    json!({
+        "solution": explanation.is_solution(),
+        "spans": explanation.spans.iter().map(|span| [span.start, span.end]).collect::<Vec<_>>(),
+        "violations": violations,
+    })
+}
+
+fn reload_params(request: &Value) -> Result<Option<Vec<WordsFile>>, (i64, String)> {
+    match request.get("params").and_then(|p| p.get("words")) {
+        None => Ok(None),
+        Some(Value::Array(files)) => files.iter()
+            .map(|file| file.as_str().map(WordsFile::parse))
+            .collect::<Option<Vec<_>>>()
+            .map(Some)
+            .ok_or_else(|| (INVALID_PARAMS, "expected words to be an array of strings".to_string())),
+        Some(_) => Err((INVALID_PARAMS, "expected words to be an array of strings".to_string())),
+    }
+}
+
+fn server_error(error: EncodeError) -> (i64, String) {
+    match error {
+        EncodeError::InvalidNumber { .. } | EncodeError::EmptyNumber { .. } => (INVALID_PARAMS, error.to_string()),
+        _ => (SERVER_ERROR, error.to_string()),
+    }
+}
+
+fn write_response<W: Write>(output: &mut W, id: &Value, result: Result<Value, (i64, String)>) -> io::Result<()> {
+    let response = match result {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err((code, message)) => json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": message } }),
+    };
+    writeln!(output, "{}", response)?;
+    output.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::dict::{DictOptions, DictionaryBuilder};
+
+    use super::*;
+
+    /// Serves the requests, one per line, with the words files `small` (the words of
+    /// `5624-82`) and `problem` (those of the problem statement), returning the responses.
+    fn serve_requests(requests: &[&str]) -> Vec<Value> {
+        let load = |files: &[WordsFile]| {
+            let mut builder = DictionaryBuilder::new(&DictOptions::default());
+            for file in files {
+                let words: &[&str] = match file.path.to_str() {
+                    Some("small") => &["mir", "Mix", "Tor"],
+                    _ => &["an", "blau", "Bo\"", "Boot", "bo\"s", "da", "Fee", "fern", "Fest", "fort", "je", "jemand",
+                           "mir", "Mix", "Mixer", "Name", "neu", "o\"d", "Ort", "so", "Tor", "Torf", "Wasser"],
+                };
+                for word in words {
+                    builder.push(word)?;
+                }
+            }
+            Ok(Encoder::new(builder.finish()))
+        };
+        let mut output = Vec::new();
+        serve(vec![WordsFile::new("small")], load, requests.join("\n").as_bytes(), &mut output).unwrap();
+        String::from_utf8(output).unwrap().lines().map(|line| serde_json::from_str(line).unwrap()).collect()
+    }
+
+    fn result(response: &Value) -> &Value {
+        assert_eq!(response["jsonrpc"], "2.0");
+        response.get("result").unwrap_or_else(|| panic!("not a result: {}", response))
+    }
+
+    fn error_code(response: &Value) -> i64 {
+        response["error"]["code"].as_i64().unwrap_or_else(|| panic!("not an error: {}", response))
+    }
+
+    #[test]
+    fn encodes_numbers() {
+        let responses = serve_requests(&[
+            r#"{"jsonrpc": "2.0", "id": 1, "method": "encode", "params": {"number": "5624-82"}}"#,
+            r#"{"jsonrpc": "2.0", "id": 2, "method": "encode", "params": ["5624-82"]}"#,
+            r#"{"jsonrpc": "2.0", "id": "3", "method": "encode", "params": {"number": "5624-82", "parts": true}}"#,
+        ]);
+        assert_eq!(responses.len(), 3);
+        assert_eq!(responses[0]["id"], 1);
+        assert_eq!(*result(&responses[0]), json!(["mir Tor", "Mix Tor"]));
+        assert_eq!(*result(&responses[1]), json!(["mir Tor", "Mix Tor"]));
+        assert_eq!(responses[2]["id"], "3");
+        assert_eq!(*result(&responses[2]), json!([["mir", "Tor"], ["Mix", "Tor"]]));
+    }
+
+    #[test]
+    fn counts_solutions_with_examples() {
+        let responses = serve_requests(&[
+            r#"{"jsonrpc": "2.0", "id": 1, "method": "count", "params": {"number": "5624-82"}}"#,
+            r#"{"jsonrpc": "2.0", "id": 2, "method": "count", "params": {"number": "5624-82", "examples": 1, "seed": 7}}"#,
+            r#"{"jsonrpc": "2.0", "id": 3, "method": "count", "params": {"number": "5624-82", "examples": 1, "seed": 7}}"#,
+        ]);
+        assert_eq!(*result(&responses[0]), json!(2));
+        let counted = result(&responses[1]);
+        assert_eq!(counted["solutions"], 2);
+        assert_eq!(counted["examples"].as_array().map(Vec::len), Some(1));
+        // the same sample for the same seed
+        assert_eq!(result(&responses[2]), counted);
+    }
+
+    #[test]
+    fn reloads_the_dictionary() {
+        let responses = serve_requests(&[
+            r#"{"jsonrpc": "2.0", "id": 1, "method": "encode", "params": ["4824"]}"#,
+            r#"{"jsonrpc": "2.0", "id": 2, "method": "reload-dict", "params": {"words": ["problem"]}}"#,
+            r#"{"jsonrpc": "2.0", "id": 3, "method": "encode", "params": ["4824"]}"#,
+            r#"{"jsonrpc": "2.0", "id": 4, "method": "reload-dict"}"#,
+        ]);
+        assert_eq!(*result(&responses[0]), json!(["Tor 4"]));
+        assert_eq!(result(&responses[1])["entries"], 21);
+        assert_eq!(*result(&responses[2]), json!(["Tor 4", "fort", "Torf"]));
+        // from the same files again
+        assert_eq!(result(&responses[3])["entries"], 21);
+    }
+
+    #[test]
+    fn does_not_answer_notifications() {
+        let responses = serve_requests(&[
+            r#"{"jsonrpc": "2.0", "method": "encode", "params": ["5624-82"]}"#,
+            r#"{"jsonrpc": "2.0", "method": "no-such-method"}"#,
+            "",
+            r#"{"jsonrpc": "2.0", "id": 1, "method": "count", "params": ["5624-82"]}"#,
+        ]);
+        assert_eq!(responses.len(), 1);
+        assert_eq!(responses[0]["id"], 1);
+    }
+
+    #[test]
+    fn answers_errors_with_their_codes() {
+        let responses = serve_requests(&[
+            r#"{"jsonrpc": "2.0", "id": 1, "method": "decode", "params": ["5624-82"]}"#,
+            r#"{"jsonrpc": "2.0", "id": 2, "method": "encode", "params": {"digits": "5624-82"}}"#,
+            r#"{"jsonrpc": "2.0", "id": 3, "method": "encode", "params": ["56a4"]}"#,
+            r#"{"jsonrpc": "2.0", "id": 4, "method": "count", "params": {"number": "4824", "examples": "two"}}"#,
+            r#"{"jsonrpc": "2.0", "id": 5, "method": "explain", "params": {"number": "4824", "solution": "Tor 4"}}"#,
+            r#"{"jsonrpc": "2.0", "id": 6, "method": "reload-dict", "params": {"words": "problem"}}"#,
+            r#"{"jsonrpc": "2.0", "id": 7}"#,
+            r#"{"jsonrpc": "2.0", "id": 8, "method": "#,
+        ]);
+        let codes: Vec<i64> = responses.iter().map(error_code).collect();
+        assert_eq!(codes, [METHOD_NOT_FOUND, INVALID_PARAMS, INVALID_PARAMS, INVALID_PARAMS, INVALID_PARAMS, INVALID_PARAMS,
+                           INVALID_REQUEST, PARSE_ERROR]);
+        assert_eq!(responses[0]["id"], 1);
+        assert_eq!(responses[0]["error"]["message"], "unknown method: decode");
+        // the id of a request that cannot be parsed is unknown
+        assert_eq!(responses[7]["id"], Value::Null);
+    }
+}
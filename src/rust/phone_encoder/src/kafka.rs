@@ -0,0 +1,176 @@
+//! Encoding the phone numbers of a Kafka topic into another, enabled with the `kafka` feature,
+//! so that the encoder can be a stage of a streaming pipeline.
+//!
+//! Each message of the input topic is a phone number, and gets a message in the output topic
+//! keyed by the number, with its solutions as the lines of the value (written like to a file,
+//! in the format of the [`EncodeOptions`](crate::encoder::EncodeOptions); empty if it has none).
+//!
+//! The numbers are consumed in batches, and the offsets of a batch are only committed once
+//! the solutions of all its numbers were delivered: each number is encoded at least once, and
+//! the numbers of a batch that was not committed are encoded again after a restart.
+
+use std::io;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use rdkafka::config::ClientConfig;
+use rdkafka::consumer::{BaseConsumer, CommitMode, Consumer};
+use rdkafka::error::{KafkaError, RDKafkaErrorCode};
+use rdkafka::message::Message;
+use rdkafka::producer::{BaseProducer, BaseRecord, DeliveryResult, Producer, ProducerContext};
+use rdkafka::ClientContext;
+
+use crate::audit::{AuditEntry, AuditLog};
+use crate::encoder::{EncodeStats, Encoder};
+
+/// The brokers connected to by default.
+pub const DEFAULT_BROKERS: &str = "localhost:9092";
+
+/// The consumer group of the encoder by default.
+pub const DEFAULT_GROUP: &str = "phone_encoder";
+
+/// How many numbers are consumed before committing by default.
+pub const DEFAULT_BATCH: usize = 1000;
+
+/// How long the messages of a batch are waited for, at most, before committing the ones
+/// consumed so far.
+const LINGER: Duration = Duration::from_millis(100);
+
+/// How long the solutions of a batch may take to be delivered.
+const DELIVERY_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Where the numbers are consumed from and the solutions produced to.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KafkaOptions {
+    /// the bootstrap brokers, `host:port` separated by commas.
+    pub brokers: String,
+    /// the consumer group, whose committed offsets are where the encoder resumes.
+    pub group: String,
+    pub input_topic: String,
+    pub output_topic: String,
+    /// how many numbers are consumed before their solutions are delivered and their offsets
+    /// committed, at most.
+    pub batch: usize,
+}
+
+impl KafkaOptions {
+    pub fn new(input_topic: &str, output_topic: &str) -> KafkaOptions {
+        KafkaOptions {
+            brokers: DEFAULT_BROKERS.to_string(),
+            group: DEFAULT_GROUP.to_string(),
+            input_topic: input_topic.to_string(),
+            output_topic: output_topic.to_string(),
+            batch: DEFAULT_BATCH,
+        }
+    }
+}
+
+/// Encodes the numbers of the input topic into the output topic, until consuming, producing
+/// or encoding fails, writing each number to the `audit` log, and calling `on_batch` with the
+/// number of numbers and the statistics of each batch once committed.
+pub fn run(
+    encoder: &Encoder,
+    options: &KafkaOptions,
+    audit: Option<&AuditLog>,
+    mut on_batch: impl FnMut(usize, &EncodeStats),
+) -> io::Result<()> {
+    let consumer: BaseConsumer = ClientConfig::new()
+        .set("bootstrap.servers", &options.brokers)
+        .set("group.id", &options.group)
+        // committed once the solutions are delivered
+        .set("enable.auto.commit", "false")
+        .set("auto.offset.reset", "earliest")
+        .create()
+        .map_err(kafka_error)?;
+    consumer.subscribe(&[&options.input_topic]).map_err(kafka_error)?;
+    let producer: BaseProducer<Deliveries> = ClientConfig::new()
+        .set("bootstrap.servers", &options.brokers)
+        .set("acks", "all")
+        .create_with_context(Deliveries::default())
+        .map_err(kafka_error)?;
+
+    let mut solutions = Vec::new();
+    loop {
+        let mut batch = 0;
+        let mut stats = EncodeStats::default();
+        let mut deadline = None;
+        while batch < options.batch {
+            // the first message of a batch is waited for as long as it takes
+            let timeout = deadline.map_or(LINGER, |deadline: Instant| deadline.saturating_duration_since(Instant::now()));
+            let message = match consumer.poll(timeout) {
+                Some(Ok(message)) => message,
+                // the client reconnects by itself, e.g. once the brokers are back
+                Some(Err(KafkaError::MessageConsumption(
+                    RDKafkaErrorCode::BrokerTransportFailure | RDKafkaErrorCode::AllBrokersDown,
+                ))) => continue,
+                Some(Err(e)) => return Err(kafka_error(e)),
+                None if batch == 0 => continue,
+                None => break,
+            };
+            deadline.get_or_insert_with(|| Instant::now() + LINGER);
+            let number = String::from_utf8_lossy(message.payload().unwrap_or_default());
+            let number = number.trim();
+            solutions.clear();
+            let start_time = Instant::now();
+            let number_stats = encoder.encode_to(number, &mut solutions)?;
+            if let Some(audit) = audit {
+                audit.record(&AuditEntry {
+                    server: "kafka",
+                    client: Some(&format!("{}/{}", message.topic(), message.partition())),
+                    command: "encode",
+                    number,
+                    outcome: "ok",
+                    solutions: number_stats.solutions,
+                    cached: false,
+                    duration: start_time.elapsed(),
+                })?;
+            }
+            stats += number_stats;
+            send(&producer, BaseRecord::to(&options.output_topic).key(number).payload(&solutions))?;
+            batch += 1;
+        }
+        producer.flush(DELIVERY_TIMEOUT).map_err(kafka_error)?;
+        if let Some(e) = producer.context().failed.lock().unwrap_or_else(|e| e.into_inner()).take() {
+            // not committed, so the batch is consumed again by the next run
+            return Err(kafka_error(e));
+        }
+        consumer.commit_consumer_state(CommitMode::Sync).map_err(kafka_error)?;
+        on_batch(batch, &stats);
+    }
+}
+
+/// Sends a message, waiting for room in the queue of the producer if it is full.
+fn send(producer: &BaseProducer<Deliveries>, mut record: BaseRecord<str, [u8]>) -> io::Result<()> {
+    loop {
+        match producer.send(record) {
+            Ok(()) => return Ok(()),
+            Err((KafkaError::MessageProduction(RDKafkaErrorCode::QueueFull), rejected)) => {
+                record = rejected;
+                producer.poll(LINGER);
+            }
+            Err((e, _)) => return Err(kafka_error(e)),
+        }
+    }
+}
+
+/// Keeps the first failed delivery of the solutions of a batch.
+#[derive(Default)]
+struct Deliveries {
+    failed: Mutex<Option<KafkaError>>,
+}
+
+impl ClientContext for Deliveries {}
+
+impl ProducerContext for Deliveries {
+    type DeliveryOpaque = ();
+
+    fn delivery(&self, result: &DeliveryResult<'_>, _: ()) {
+        if let Err((e, _)) = result {
+            self.failed.lock().unwrap_or_else(|e| e.into_inner()).get_or_insert_with(|| e.clone());
+        }
+    }
+}
+
+fn kafka_error(e: KafkaError) -> io::Error {
+    io::Error::other(format!("kafka: {}", e))
+}
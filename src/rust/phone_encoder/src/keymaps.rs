@@ -0,0 +1,93 @@
+//! Encoding the numbers with several keymaps in one run (`--keymap a.toml --keymap t9`),
+//! to compare the encodings they give on the same words and numbers.
+
+use std::fs;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use phone_encoder::input::input_files;
+use phone_encoder::{load_dicts_for, Encoder, FileContext, FileRole, Mapping, WordsFile};
+
+use crate::cli::Options;
+use crate::run::{configure, encode_file, finish, output_file, preload, Totals};
+
+/// Encodes the numbers with each of the keymaps of the options, writing the solutions
+/// tagged with the name of the keymap, or else into a subdirectory of the output directory
+/// for each keymap.
+///
+/// The words files are read once for all keymaps, building a dictionary for each.
+pub fn encode_keymaps(options: &Options, words_files: &[WordsFile], input: &str) -> io::Result<()> {
+    let input_files = input_files(input).in_file(FileRole::Numbers, input)?;
+    let (words_files, preloaded) = preload(options, words_files, &input_files)?;
+    let start_time = Instant::now();
+    let mappings: Vec<Mapping> = options.keymaps.iter().map(|(_, mapping)| mapping.clone()).collect();
+    let dicts = load_dicts_for(&words_files, &options.dict, &mappings)?;
+    let mut total = Totals::default();
+    total.timings.load = start_time.elapsed();
+
+    let mut keymaps = Vec::new();
+    for ((name, _), dict) in options.keymaps.iter().zip(dicts) {
+        // invalid numbers are only listed once, not for each keymap
+        let skipped: Box<dyn Write + Send> = if keymaps.is_empty() { Box::new(io::stderr()) } else { Box::new(io::sink()) };
+        let encoder = configure(options, Encoder::new(dict)).with_skipped_output(skipped);
+        let mut keymap = Totals::default();
+        let mut jobs = options.jobs;
+        for (input_file, preloaded) in input_files.iter().zip(&preloaded) {
+            let file = match &options.output_dir {
+                Some(output_dir) => {
+                    let output_dir = Path::new(output_dir).join(name);
+                    fs::create_dir_all(&output_dir).in_file(FileRole::Output, &output_dir)?;
+                    let mut out = output_file(options, &output_dir, input_file)?;
+                    encoder.write_header(&mut out)?;
+                    let file = encode_file(options, &encoder, &mut jobs, input_file, preloaded.as_deref(), &mut out)?;
+                    encoder.write_summary(&mut out, file.numbers, &file.stats)?;
+                    out.flush()?;
+                    file
+                }
+                None => {
+                    let mut out = Tagged::new(BufWriter::new(io::stdout().lock()), name);
+                    let file = encode_file(options, &encoder, &mut jobs, input_file, preloaded.as_deref(), &mut out)?;
+                    out.flush()?;
+                    file
+                }
+            };
+            keymap += file;
+        }
+        total += keymap;
+        keymaps.push((PathBuf::from(name), keymap));
+    }
+    // reported like the files of a directory, one section for each keymap
+    let keymaps: Vec<_> = keymaps.iter().map(|(name, keymap)| (name, *keymap)).collect();
+    finish(options, &total, &keymaps, &[])
+}
+
+/// Writes each line prefixed with `[<tag>] `.
+struct Tagged<W> {
+    out: W,
+    prefix: String,
+    at_line_start: bool,
+}
+
+impl<W: Write> Tagged<W> {
+    fn new(out: W, tag: &str) -> Tagged<W> {
+        Tagged { out, prefix: format!("[{}] ", tag), at_line_start: true }
+    }
+}
+
+impl<W: Write> Write for Tagged<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        for line in buf.split_inclusive(|byte| *byte == b'\n') {
+            if self.at_line_start {
+                self.out.write_all(self.prefix.as_bytes())?;
+            }
+            self.out.write_all(line)?;
+            self.at_line_start = line.ends_with(b"\n");
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+}
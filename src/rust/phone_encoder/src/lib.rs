@@ -0,0 +1,121 @@
+//! Port of Peter Norvig's Lisp solution to the Prechelt phone-encoding problem.
+//!
+//! Even though this is intended as a port, it deviates quite a bit from it
+//! due to the very different natures of Lisp and Rust.
+//!
+//! Without the default `std` feature, the crate is `no_std` and only provides the
+//! search modules ([`search`] and the other [`SearchStrategy`] implementations),
+//! [`mapping`] and [`filter`], which only need `alloc`.
+//!
+//! Only the modules doing I/O the standard library cannot do (`uring`, behind the writers
+//! of `output`) may use `unsafe`, so that the core can be checked with Miri (see the tests of
+//! the `miri-compat` feature), where they are left out.
+//!
+//! What differs between platforms is kept in [`platform`], so that the crate builds for
+//! Linux, the other Unix systems, Windows and WASI (`wasm32-wasip1`, see `tests/wasi.rs`).
+//!
+//! The items of [`prelude`] follow semantic versioning: a minor release only adds to them.
+//! The options, statistics and errors are `#[non_exhaustive]`, so that they can gain fields
+//! and variants too: options are made from their `Default` and then changed, and matches on
+//! the errors need a wildcard arm. The other modules are public for the tools of the crate
+//! (the benchmarks, the servers and the bindings) and may change more freely.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+#![deny(unsafe_code)]
+
+extern crate alloc;
+
+#[cfg(feature = "std")]
+pub mod admission;
+pub mod aho_corasick;
+#[cfg(all(test, feature = "alloc-counter"))]
+#[allow(unsafe_code)]
+mod alloc_counter;
+#[cfg(feature = "server")]
+pub mod audit;
+#[cfg(feature = "std")]
+pub mod cache;
+#[cfg(feature = "std")]
+pub mod dict;
+#[cfg(feature = "std")]
+pub mod dict_report;
+pub mod dp;
+#[cfg(feature = "std")]
+pub mod encoder;
+#[cfg(feature = "std")]
+pub mod error;
+pub mod filter;
+#[cfg(feature = "std")]
+pub mod format;
+#[cfg(feature = "std")]
+pub mod generate;
+#[cfg(feature = "grpc")]
+pub mod grpc;
+#[cfg(feature = "std")]
+pub mod ids;
+#[cfg(feature = "std")]
+pub mod input;
+pub mod iterative;
+#[cfg(feature = "kafka")]
+pub mod kafka;
+#[cfg(feature = "server")]
+pub mod jsonrpc;
+#[cfg(feature = "formats-json")]
+pub mod manifest;
+pub mod mapping;
+#[cfg(all(test, feature = "miri-compat"))]
+mod miri;
+pub mod number;
+#[cfg(feature = "std")]
+pub mod output;
+#[cfg(feature = "parallel")]
+pub mod parallel;
+#[cfg(feature = "std")]
+pub mod platform;
+#[cfg(feature = "std")]
+pub mod pool;
+pub mod prelude;
+pub mod sample;
+#[cfg(feature = "std")]
+pub mod registry;
+#[cfg(feature = "std")]
+pub mod redact;
+#[cfg(feature = "server")]
+pub mod resp;
+#[cfg(feature = "std")]
+pub mod scramble;
+pub mod search;
+#[cfg(feature = "std")]
+pub mod sha256;
+#[cfg(feature = "std")]
+pub mod sink;
+#[cfg(feature = "std")]
+pub mod snapshot;
+pub mod split;
+#[cfg(feature = "std")]
+pub mod sort;
+#[cfg(feature = "std")]
+pub mod stats;
+pub mod trie;
+#[cfg(all(feature = "uring", target_os = "linux", not(miri)))]
+#[allow(unsafe_code)]
+pub mod uring;
+#[cfg(feature = "std")]
+pub mod vanity;
+pub mod verify;
+
+#[cfg(feature = "std")]
+pub use dict::{load_dict, load_dicts, load_dicts_for, DictOptions, Dictionary, DictionaryBuilder, DictionaryUpdates, WordsFile};
+#[cfg(feature = "std")]
+pub use encoder::{EmptyNumbers, EncodeOptions, Encoder, NoOutput};
+#[cfg(feature = "std")]
+pub use error::{EncodeError, FileContext, FileRole};
+pub use filter::{Rejection, Rules, SolutionFilter};
+#[cfg(feature = "std")]
+pub use format::{Field, Format, OutputFormat, Quoting};
+pub use verify::{explain, verify_solution, Explanation, VerifyError};
+pub use mapping::Mapping;
+pub use number::{normalize_number, DigitString, InvalidNumber};
+pub use search::{
+    Algorithm, EncodeStats, NoObserver, SearchGuard, SearchObserver, SearchStrategy, Segment, Word, WordOrDigit,
+};
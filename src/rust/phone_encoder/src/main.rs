@@ -1,145 +1,97 @@
-use std::collections::HashMap;
 use std::env::args;
-use std::fs::File;
-use std::io::{self, BufRead};
-use std::path::Path;
+#[cfg(not(feature = "cli"))]
+use std::io::{self, BufWriter, Write};
+use std::process::exit;
 
-use lazy_static::lazy_static;
-use num_bigint::{BigUint, ToBigUint};
+#[cfg(not(feature = "cli"))]
+use phone_encoder::dict::{EMBEDDED_PATH, EMBEDDED_WORDS};
+#[cfg(not(feature = "cli"))]
+use phone_encoder::input::LineReader;
+#[cfg(not(feature = "cli"))]
+use phone_encoder::{DictOptions, Encoder, FileContext, FileRole, WordsFile};
 
-type Dictionary = HashMap<BigUint, Vec<String>>;
+#[cfg(feature = "cli")]
+use crate::cli::Options;
+#[cfg(feature = "cli")]
+use crate::service::Priority;
 
-lazy_static! {
-    static ref ONE: BigUint = 1.to_biguint().unwrap();
-    static ref TEN: BigUint =10.to_biguint().unwrap();
-}
+#[cfg(feature = "cli")]
+mod bench;
+#[cfg(feature = "cli")]
+mod bench_dict;
+#[cfg(feature = "cli")]
+mod cli;
+#[cfg(feature = "cli")]
+mod compare;
+#[cfg(feature = "cli")]
+mod fetch;
+#[cfg(feature = "cli")]
+mod follow;
+#[cfg(feature = "cli")]
+mod keymaps;
+#[cfg(feature = "cli")]
+mod per_number;
+#[cfg(feature = "cli")]
+mod report;
+#[cfg(feature = "cli")]
+mod result_cache;
+#[cfg(feature = "cli")]
+mod run;
+#[cfg(feature = "cli")]
+#[cfg_attr(not(feature = "server"), allow(dead_code))]
+mod service;
+#[cfg(feature = "cli")]
+mod trace;
 
 /// Port of Peter Norvig's Lisp solution to the Prechelt phone-encoding problem.
 ///
 /// Even though this is intended as a port, it deviates quite a bit from it
 /// due to the very different natures of Lisp and Rust.
-fn main() -> io::Result<()> {
+#[cfg(feature = "cli")]
+fn main() {
     // drop itself from args
-    let mut args: Vec<_> = args().skip(1).collect();
-    let words_file: String = if !args.is_empty() { args.remove(0) } else { "tests/words.txt".into() };
-    let input_file: String = if !args.is_empty() { args.remove(0) } else { "tests/numbers.txt".into() };
-
-    let dict = load_dict(words_file)?;
+    let options = Options::parse(args().skip(1)).unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        exit(1);
+    });
 
-    for line in read_lines(input_file)? {
-        if let Ok(num) = line {
-            let digits: Vec<_> = num.chars()
-                .filter(|ch| ch.is_alphanumeric())
-                .collect();
-            print_translations(&num, &digits, 0, Vec::new(), &dict)?;
-        }
+    if let Err(e) = run::run(&options) {
+        service::log(Priority::Error, format_args!("Error: {}", e));
+        exit(1);
     }
-    Ok(())
 }
 
-fn print_translations(
-    num: &str,
-    digits: &Vec<char>,
-    start: usize,
-    words: Vec<&String>,
-    dict: &Dictionary,
-) -> io::Result<()> {
-    if start >= digits.len() {
-        print_solution(num, &words);
-        return Ok(());
-    }
-    let mut n = ONE.clone();
-    let mut found_word = false;
-    for i in start..digits.len() {
-        n = &n * (&*TEN) + &nth_digit(digits, i);
-        if let Some(found_words) = dict.get(&n) {
-            for word in found_words {
-                found_word = true;
-                let mut partial_solution = words.clone();
-                partial_solution.push(word);
-                print_translations(num, digits, i + 1, partial_solution, dict)?;
-            }
-        }
-    }
-    if !found_word && !words.last().map(|w| is_digit(w)).unwrap_or(false) {
-        let mut partial_solution = words.clone();
-        let digit = nth_digit(digits, start).to_string();
-        partial_solution.push(&digit);
-        print_translations(num, digits, start + 1, partial_solution, dict)
-    } else {
-        Ok(())
+/// Without the `cli` feature, the program only encodes the numbers of a numbers file with
+/// the words of a words file, as the original problem asks (and the benchmark runs it).
+#[cfg(not(feature = "cli"))]
+fn main() {
+    // drop itself from args
+    let args: Vec<String> = args().skip(1).collect();
+    if args.len() > 2 || args.iter().any(|arg| arg.starts_with("--")) {
+        eprintln!("Usage: phone_encoder [words_file] [numbers_file]\n\n(the other options and commands require the cli feature)");
+        exit(1);
     }
-}
+    let mut args = args.into_iter();
+    let words_file = args.next().unwrap_or_else(|| EMBEDDED_WORDS.map_or("tests/words.txt", |_| EMBEDDED_PATH).into());
+    let numbers_file = args.next().unwrap_or_else(|| "tests/numbers.txt".into());
 
-fn print_solution(num: &str, words: &Vec<&String>) {
-    // do a little gymnastics here to avoid allocating a big string just for printing it
-    print!("{}", num);
-    if words.is_empty() {
-        println!(":");
-        return;
-    }
-    print!(": ");
-    let (head, tail) = words.split_at(words.len() - 1);
-    for word in head {
-        print!("{} ", word);
-    }
-    for word in tail { // only last word in tail
-        println!("{}", word);
+    if let Err(e) = encode(&words_file, &numbers_file) {
+        eprintln!("Error: {}", e);
+        exit(1);
     }
 }
 
-fn load_dict(words_file: String) -> io::Result<Dictionary> {
-    let mut dict = HashMap::with_capacity(100);
-    let words = read_lines(words_file)?;
-    for line in words {
-        if let Ok(word) = line {
-            let key = word_to_number(&word);
-            let words = dict.entry(key).or_insert_with(|| Vec::new());
-            words.push(word);
-        }
+#[cfg(not(feature = "cli"))]
+fn encode(words_file: &str, numbers_file: &str) -> io::Result<()> {
+    // invalid numbers are skipped instead of stopping the run
+    let encoder = Encoder::load_all(&[WordsFile::new(words_file)], &DictOptions::default())?
+        .with_skipped_output(io::stderr());
+    let mut out = BufWriter::new(io::stdout().lock());
+    let mut lines = LineReader::open(numbers_file).in_file(FileRole::Numbers, numbers_file)?;
+    while let Some(line) = lines.next_line().in_file(FileRole::Numbers, numbers_file)? {
+        // a second, tab-separated column is ignored
+        let num = line.split_once('\t').map_or(line, |(num, _)| num);
+        encoder.encode_to(num, &mut out)?;
     }
-    Ok(dict)
-}
-
-// The output is wrapped in a Result to allow matching on errors
-// Returns an Iterator to the Reader of the lines of the file.
-fn read_lines<P>(filename: P) -> io::Result<io::Lines<io::BufReader<File>>>
-    where P: AsRef<Path>, {
-    let file = File::open(filename)?;
-    Ok(io::BufReader::new(file).lines())
+    out.flush()
 }
-
-fn word_to_number(word: &str) -> BigUint {
-    let mut n = ONE.clone();
-    for ch in word.chars() {
-        if ch.is_alphabetic() {
-            n = &n * (&*TEN) + &char_to_digit(ch);
-        }
-    }
-    n
-}
-
-fn nth_digit(digits: &Vec<char>, i: usize) -> BigUint {
-    let ch = digits.get(i).expect("index out of bounds");
-    ((*ch as usize) - ('0' as usize)).to_biguint().unwrap()
-}
-
-fn is_digit(string: &str) -> bool {
-    string.len() == 1 && string.chars().next().unwrap().is_digit(10)
-}
-
-fn char_to_digit(ch: char) -> u32 {
-    match ch.to_ascii_lowercase() {
-        'e' => 0,
-        'j' | 'n' | 'q' => 1,
-        'r' | 'w' | 'x' => 2,
-        'd' | 's' | 'y' => 3,
-        'f' | 't' => 4,
-        'a' | 'm' => 5,
-        'c' | 'i' | 'v' => 6,
-        'b' | 'k' | 'u' => 7,
-        'l' | 'o' | 'p' => 8,
-        'g' | 'h' | 'z' => 9,
-        _ => panic!("invalid input: not a digit: {}", ch)
-    }
-}
\ No newline at end of file
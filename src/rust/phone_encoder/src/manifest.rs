@@ -0,0 +1,158 @@
+//! The manifest of a run (`--manifest`), recording what its results depend on, so that a
+//! benchmark can be checked and run again the same way long after:
+//!
+//! ```text
+//! {
+//!   "args": ["bench", "dictionary.txt", "input.txt", "--manifest", "run.json"],
+//!   "dictionary": "29db2a56cedd96a504eff370d0b3c64dae2289f37da4fda9f2818f2de60abb45",
+//!   "features": ["std", "cli", "formats-json", "parallel"],
+//!   "mapping_id": "423558411a36adcb",
+//!   "numbers_files": [{"path": "input.txt", "sha256": "2522a484…"}],
+//!   "rules": "none",
+//!   "seed": 0,
+//!   "version": "0.1.0",
+//!   "words_files": [{"path": "dictionary.txt", "sha256": "291b4e05…", "source": "dictionary"}]
+//! }
+//! ```
+//!
+//! (pretty-printed, with each item of the lists on its own line).
+//!
+//! Files are hashed as stored, compressed or not, and the embedded words as built into the
+//! program. The arguments are recorded as given, with every flag and its value.
+
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use serde_json::{json, Value};
+
+use crate::cache::mapping_id;
+use crate::dict::{WordsFile, EMBEDDED_PATH, EMBEDDED_WORDS};
+use crate::error::{EncodeError, FileContext, FileRole};
+use crate::filter::Rules;
+use crate::mapping::Mapping;
+use crate::sha256::{hex, Sha256};
+
+/// The features the crate was built with, which change what a run does or how fast.
+const FEATURES: &[(&str, bool)] = &[
+    ("std", cfg!(feature = "std")),
+    ("cli", cfg!(feature = "cli")),
+    ("formats-json", cfg!(feature = "formats-json")),
+    ("parallel", cfg!(feature = "parallel")),
+    ("server", cfg!(feature = "server")),
+    ("compress", cfg!(feature = "compress")),
+    ("arena", cfg!(feature = "arena")),
+    ("uring", cfg!(feature = "uring")),
+    ("grpc", cfg!(feature = "grpc")),
+    ("kafka", cfg!(feature = "kafka")),
+];
+
+/// A file a run read, with its SHA-256 digest.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FileDigest {
+    pub path: PathBuf,
+    /// the source of the words of a words file.
+    pub source: Option<String>,
+    pub sha256: [u8; 32],
+}
+
+impl FileDigest {
+    /// Hashes a words file, from its contents if it was preloaded.
+    pub fn words(file: &WordsFile) -> Result<FileDigest, EncodeError> {
+        let mut hasher = Sha256::new();
+        match &file.contents {
+            Some(contents) => hasher.update(contents),
+            None if file.path == Path::new(EMBEDDED_PATH) => hasher.update(EMBEDDED_WORDS.unwrap_or_default()),
+            None => hash_file(&file.path, &mut hasher).in_file(FileRole::Words, &file.path)?,
+        }
+        Ok(FileDigest { path: file.path.clone(), source: Some(file.source.clone()), sha256: hasher.finish() })
+    }
+
+    /// Hashes a numbers file.
+    pub fn numbers(path: &Path) -> Result<FileDigest, EncodeError> {
+        let mut hasher = Sha256::new();
+        hash_file(path, &mut hasher).in_file(FileRole::Numbers, path)?;
+        Ok(FileDigest { path: path.to_path_buf(), source: None, sha256: hasher.finish() })
+    }
+
+    fn to_json(&self) -> Value {
+        let mut json = json!({"path": self.path.display().to_string(), "sha256": hex(&self.sha256)});
+        if let Some(source) = &self.source {
+            json["source"] = source.as_str().into();
+        }
+        json
+    }
+}
+
+fn hash_file(path: &Path, hasher: &mut Sha256) -> io::Result<()> {
+    io::copy(&mut File::open(path)?, hasher)?;
+    Ok(())
+}
+
+/// What the results of a run depend on.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Manifest {
+    /// the arguments of the program, as given.
+    pub args: Vec<String>,
+    pub words_files: Vec<FileDigest>,
+    pub numbers_files: Vec<FileDigest>,
+    /// the id of the mapping of letters to digits, as [`mapping_id`] computes it.
+    pub mapping_id: u64,
+    pub rules: Rules,
+    /// the seed of the sample of the numbers.
+    pub seed: u64,
+}
+
+impl Manifest {
+    /// Hashes the words and numbers files of a run given `args`.
+    pub fn new(
+        args: Vec<String>,
+        words_files: &[WordsFile],
+        numbers_files: &[PathBuf],
+        mapping: &Mapping,
+        rules: Rules,
+        seed: u64,
+    ) -> Result<Manifest, EncodeError> {
+        Ok(Manifest {
+            args,
+            words_files: words_files.iter().map(FileDigest::words).collect::<Result<_, _>>()?,
+            numbers_files: numbers_files.iter().map(|path| FileDigest::numbers(path)).collect::<Result<_, _>>()?,
+            mapping_id: mapping_id(mapping),
+            rules,
+            seed,
+        })
+    }
+
+    /// The digest of the dictionary: the SHA-256 of the lines `<source> <sha256>` of the
+    /// words files, in the order they are loaded, so that it changes with any of their words
+    /// or sources.
+    pub fn dictionary(&self) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        for file in &self.words_files {
+            let source = file.source.as_deref().unwrap_or_default();
+            hasher.update(format!("{} {}\n", source, hex(&file.sha256)).as_bytes());
+        }
+        hasher.finish()
+    }
+
+    pub fn to_json(&self) -> Value {
+        json!({
+            "version": env!("CARGO_PKG_VERSION"),
+            "features": FEATURES.iter().filter(|(_, on)| *on).map(|(name, _)| *name).collect::<Vec<_>>(),
+            "args": self.args,
+            "words_files": self.words_files.iter().map(FileDigest::to_json).collect::<Vec<_>>(),
+            "dictionary": hex(&self.dictionary()),
+            "numbers_files": self.numbers_files.iter().map(FileDigest::to_json).collect::<Vec<_>>(),
+            "mapping_id": format!("{:016x}", self.mapping_id),
+            "rules": self.rules.to_string(),
+            "seed": self.seed,
+        })
+    }
+
+    /// Writes the manifest as pretty-printed JSON.
+    pub fn write<W: Write>(&self, mut out: W) -> io::Result<()> {
+        serde_json::to_writer_pretty(&mut out, &self.to_json())?;
+        writeln!(out)
+    }
+}
@@ -0,0 +1,379 @@
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+
+#[cfg(feature = "std")]
+use crate::error::EncodeError;
+
+/// The mapping from letters to digits used to encode dictionary words.
+///
+/// ```text
+/// E | J N Q | R W X | D S Y | F T | A M | C I V | B K U | L O P | G H Z
+/// e | j n q | r w x | d s y | f t | a m | c i v | b k u | l o p | g h z
+/// 0 |   1   |   2   |   3   |  4  |  5  |   6   |   7   |   8   |   9
+/// ```
+///
+/// Another keymap may map the letters to other digits instead, like the standard keypad of
+/// phones, see [`KEYMAPS`] and [`Mapping::parse_keymap`].
+///
+/// On top of that, single characters may be expanded to several letters before being
+/// encoded, e.g. `ß` to `ss` or `ä` to `ae`, as is conventional for German words.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Mapping {
+    expansions: BTreeMap<char, Vec<u32>>,
+    /// the digit of each ASCII letter, [`NOT_A_LETTER`] for other ASCII characters.
+    letters: [u8; 128],
+    /// the digit of each other letter, like those of the Cyrillic or Greek alphabets,
+    /// keyed by the lower case letter.
+    other_letters: BTreeMap<char, u32>,
+    /// whether some of the letters `a` to `z` are not mapped, so that ASCII words must be
+    /// checked with [`Mapping::unmapped_letter`] too.
+    ascii_gaps: bool,
+}
+
+impl Default for Mapping {
+    fn default() -> Mapping {
+        Mapping { expansions: BTreeMap::new(), letters: ASCII_DIGITS, other_letters: BTreeMap::new(), ascii_gaps: false }
+    }
+}
+
+/// The built-in keymaps: the name of each, with the letters of each digit from `0` to `9`.
+pub const KEYMAPS: [(&str, [&str; 10]); 4] = [
+    // the mapping of the original problem, the default
+    ("prechelt", ["e", "jnq", "rwx", "dsy", "ft", "am", "civ", "bku", "lop", "ghz"]),
+    // the standard keypad of phones (ITU E.161), as used for T9
+    ("itu-t9", ["", "", "abc", "def", "ghi", "jkl", "mno", "pqrs", "tuv", "wxyz"]),
+    // the dials of British phones before 1995, with o on 0 and without q and z, which
+    // are put on 0 too so that every word can be encoded
+    ("uk-legacy", ["oqz", "", "abc", "def", "ghi", "jkl", "mn", "prs", "tuv", "wxy"]),
+    // the keypads of Russian phones, with the Cyrillic letters next to the Latin ones
+    ("ru", ["", "", "abcабвг", "defдеёжз", "ghiийкл", "jklмноп", "mnoрсту", "pqrsфхцч", "tuvшщъы", "wxyzьэюя"]),
+];
+
+impl Mapping {
+    pub fn new() -> Mapping {
+        Mapping::default()
+    }
+
+    /// The conventional German expansions of `ß` and the umlauts.
+    #[cfg(feature = "std")]
+    pub fn german() -> Mapping {
+        let mut mapping = Mapping::new();
+        mapping.add_german().expect("the default mapping has the letters of the German expansions");
+        mapping
+    }
+
+    /// Adds the expansions of [`Mapping::german`], whose letters must all be mapped to a digit.
+    #[cfg(feature = "std")]
+    fn add_german(&mut self) -> Result<(), EncodeError> {
+        for (ch, letters) in [('ß', "ss"), ('ä', "ae"), ('ö', "oe"), ('ü', "ue")] {
+            self.expand(ch, letters)?;
+        }
+        Ok(())
+    }
+
+    /// The letters of the standard keypad of phones (ITU E.161), as used for T9:
+    /// `abc` on 2, `def` on 3, ..., `pqrs` on 7, `tuv` on 8 and `wxyz` on 9.
+    pub fn t9() -> Mapping {
+        Mapping::keymap("itu-t9").unwrap_or_default()
+    }
+
+    /// The built-in keymap of the given name, see [`KEYMAPS`] (`t9` standing for `itu-t9`).
+    pub fn keymap(name: &str) -> Option<Mapping> {
+        let name = if name == "t9" { "itu-t9" } else { name };
+        let (_, digits) = KEYMAPS.iter().find(|(keymap, _)| *keymap == name)?;
+        let mut mapping = Mapping::unmapped();
+        for (digit, letters) in digits.iter().enumerate() {
+            for letter in letters.chars() {
+                mapping.set_letter(letter, digit as u32);
+            }
+        }
+        mapping.ascii_gaps = mapping.has_ascii_gaps();
+        Some(mapping)
+    }
+
+    /// A mapping without any letters.
+    fn unmapped() -> Mapping {
+        Mapping { letters: [NOT_A_LETTER; 128], ..Mapping::default() }
+    }
+
+    /// The letters mapped to each digit from `0` to `9`, in lower case and in the order
+    /// of their code points (not including the expansions).
+    pub fn digit_letters(&self) -> [Vec<char>; 10] {
+        let mut digits: [Vec<char>; 10] = Default::default();
+        for letter in 'a'..='z' {
+            if let Some(digit) = self.letter_to_digit(letter) {
+                digits[digit as usize].push(letter);
+            }
+        }
+        for (letter, digit) in &self.other_letters {
+            digits[*digit as usize].push(*letter);
+        }
+        digits
+    }
+
+    /// The keymap of the mapping in the format read by [`Mapping::parse_keymap`].
+    #[cfg(feature = "std")]
+    pub fn keymap_toml(&self) -> String {
+        let mut toml = String::from("[digits]\n");
+        for (digit, letters) in self.digit_letters().iter().enumerate() {
+            if !letters.is_empty() {
+                toml += &format!("{} = \"{}\"\n", digit, letters.iter().collect::<String>());
+            }
+        }
+        toml
+    }
+
+    /// Parses a keymap in TOML, giving the letters of each digit (in either case, of any
+    /// alphabet), which must each be mapped once:
+    ///
+    /// ```toml
+    /// # the standard keypad
+    /// [digits]
+    /// 2 = "abc"
+    /// 3 = "def"
+    /// # ...
+    /// ```
+    ///
+    /// Only this subset of TOML is understood: comments, the `[digits]` table (which may
+    /// be left out) and keys from `0` to `9` (which may be quoted) with basic strings.
+    ///
+    /// Words with letters that are not mapped, like Latin letters with a keymap of the
+    /// Cyrillic alphabet only, are rejected when loading them.
+    #[cfg(feature = "std")]
+    pub fn parse_keymap(toml: &str) -> Result<Mapping, EncodeError> {
+        let error = |line: usize, message: &str| EncodeError::MappingError(format!("line {}: {}", line, message));
+        let mut mapping = Mapping::unmapped();
+        for (index, line) in toml.lines().enumerate() {
+            let line = line.split_once('#').map_or(line, |(line, _)| line).trim();
+            if line.is_empty() || line == "[digits]" {
+                continue;
+            }
+            let (key, value) = line.split_once('=').ok_or_else(|| error(index + 1, "expected <digit> = \"<letters>\""))?;
+            let key = key.trim().trim_matches('"');
+            let digit = match key.as_bytes() {
+                [digit @ b'0'..=b'9'] => digit - b'0',
+                _ => return Err(error(index + 1, &format!("invalid digit '{}'", key))),
+            };
+            let letters = value.trim().strip_prefix('"').and_then(|value| value.strip_suffix('"'))
+                .ok_or_else(|| error(index + 1, "expected the letters in double quotes"))?;
+            for letter in letters.chars() {
+                if !letter.is_alphabetic() {
+                    return Err(error(index + 1, &format!("'{}' is not a letter", letter)));
+                }
+                if mapping.letter_to_digit(letter).is_some() {
+                    return Err(error(index + 1, &format!("'{}' is mapped twice", letter)));
+                }
+                mapping.set_letter(letter, u32::from(digit));
+            }
+        }
+        if mapping.digit_letters().iter().all(Vec::is_empty) {
+            return Err(EncodeError::MappingError("no letters are mapped to a digit".into()));
+        }
+        mapping.ascii_gaps = mapping.has_ascii_gaps();
+        Ok(mapping)
+    }
+
+    fn set_letter(&mut self, letter: char, digit: u32) {
+        if letter.is_ascii() {
+            self.letters[letter.to_ascii_lowercase() as usize] = digit as u8;
+            self.letters[letter.to_ascii_uppercase() as usize] = digit as u8;
+        } else {
+            self.other_letters.insert(lower_case(letter), digit);
+        }
+    }
+
+    fn has_ascii_gaps(&self) -> bool {
+        ('a'..='z').any(|letter| self.letter_to_digit(letter).is_none())
+    }
+
+    /// Whether all the letters `a` to `z` are mapped to a digit.
+    pub fn maps_ascii(&self) -> bool {
+        !self.ascii_gaps
+    }
+
+    /// The digit of a letter, if it is mapped to one.
+    fn letter_to_digit(&self, ch: char) -> Option<u32> {
+        if ch.is_ascii() {
+            return match self.letters[ch as usize] {
+                NOT_A_LETTER => None,
+                digit => Some(digit as u32),
+            };
+        }
+        if self.other_letters.is_empty() {
+            return None;
+        }
+        self.other_letters.get(&ch).or_else(|| self.other_letters.get(&lower_case(ch))).copied()
+    }
+
+    fn digit_of(&self, ch: char) -> u32 {
+        self.letter_to_digit(ch).unwrap_or_else(|| panic!("invalid input: not a digit: {}", ch))
+    }
+
+    /// Makes `ch` expand to the given letters, which must all be mapped to a digit.
+    ///
+    /// The letters are encoded with the keymap of the mapping at the time.
+    #[cfg(feature = "std")]
+    pub fn expand(&mut self, ch: char, letters: &str) -> Result<(), EncodeError> {
+        let digits = letters.chars()
+            .map(|letter| self.letter_to_digit(letter).ok_or_else(|| EncodeError::MappingError(
+                format!("cannot expand '{}' to '{}': '{}' is not mapped to a digit", ch, letters, letter))))
+            .collect::<Result<Vec<_>, _>>()?;
+        self.expansions.insert(ch, digits);
+        Ok(())
+    }
+
+    /// Adds expansions from a specification, which is either the name of a
+    /// built-in set of expansions (`german`) or of the form `<char>=<letters>`.
+    #[cfg(feature = "std")]
+    pub fn expand_spec(&mut self, spec: &str) -> Result<(), EncodeError> {
+        if spec == "german" {
+            return self.add_german();
+        }
+        let mut chars = spec.chars();
+        match (chars.next(), chars.next()) {
+            (Some(ch), Some('=')) => self.expand(ch, chars.as_str()),
+            _ => Err(EncodeError::MappingError(format!("invalid expansion '{}', expected <char>=<letters>", spec)))
+        }
+    }
+
+    /// Calls `on_digit` with each digit of an ASCII word, looking the bytes up in a table,
+    /// which is much faster than [`Mapping::encode_char`].
+    ///
+    /// Returns `false`, without calling `on_digit`, if the word is not ASCII, if there
+    /// are expansions, or if some ASCII letters are not mapped (all of which must go
+    /// through [`Mapping::encode_char`]).
+    #[inline]
+    pub fn encode_ascii<F: FnMut(u32)>(&self, word: &str, mut on_digit: F) -> bool {
+        if !self.expansions.is_empty() || self.ascii_gaps || !word.is_ascii() {
+            return false;
+        }
+        for byte in word.bytes() {
+            let digit = self.letters[byte as usize];
+            if digit != NOT_A_LETTER {
+                on_digit(digit as u32);
+            }
+        }
+        true
+    }
+
+    /// The first letter of a word that is neither mapped to a digit nor expanded,
+    /// which [`Mapping::encode_char`] cannot encode.
+    ///
+    /// Only words that are not ASCII need to be checked, unless [`Mapping::maps_ascii`] is false.
+    pub fn unmapped_letter(&self, word: &str) -> Option<char> {
+        word.chars().find(|&ch| ch.is_alphabetic()
+            && self.letter_to_digit(ch).is_none()
+            && !self.expansions.contains_key(&ch)
+            && !ch.to_lowercase().next().is_some_and(|lower| self.expansions.contains_key(&lower)))
+    }
+
+    /// Calls `on_digit` with each digit `ch` encodes to (none for non-letters).
+    ///
+    /// Panics for a letter that is neither mapped to a digit nor expanded, see [`Mapping::unmapped_letter`].
+    #[inline]
+    pub fn encode_char<F: FnMut(u32)>(&self, ch: char, mut on_digit: F) {
+        if !self.expansions.is_empty() {
+            let expansion = self.expansions.get(&ch).or_else(|| ch.to_lowercase()
+                .next()
+                .and_then(|lower| self.expansions.get(&lower)));
+            if let Some(digits) = expansion {
+                digits.iter().for_each(|d| on_digit(*d));
+                return;
+            }
+        }
+        if ch.is_alphabetic() {
+            on_digit(self.digit_of(ch));
+        }
+    }
+}
+
+const NOT_A_LETTER: u8 = u8::MAX;
+
+/// The lower case of a letter, if it is a single letter (as for all but a few letters).
+fn lower_case(letter: char) -> char {
+    let mut lower = letter.to_lowercase();
+    match (lower.next(), lower.next()) {
+        (Some(lower), None) => lower,
+        _ => letter,
+    }
+}
+
+/// The digit of each ASCII letter in the default mapping, [`NOT_A_LETTER`] for other ASCII characters.
+const ASCII_DIGITS: [u8; 128] = letter_table(&KEYMAPS[0].1);
+
+/// The digit of each ASCII letter, in either case, given the (ASCII) letters of each digit.
+const fn letter_table(digits: &[&str; 10]) -> [u8; 128] {
+    let mut table = [NOT_A_LETTER; 128];
+    let mut digit = 0;
+    while digit < 10 {
+        let letters = digits[digit].as_bytes();
+        let mut i = 0;
+        while i < letters.len() {
+            table[letters[i].to_ascii_lowercase() as usize] = digit as u8;
+            table[letters[i].to_ascii_uppercase() as usize] = digit as u8;
+            i += 1;
+        }
+        digit += 1;
+    }
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::search::word_to_number;
+
+    fn digits(word: &str, mapping: &Mapping) -> alloc::string::String {
+        word_to_number(word, mapping).to_string()[1..].into()
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn expands_characters_to_several_letters() {
+        let german = Mapping::german();
+        // ä is encoded as ae: 5 for a, 0 for e
+        assert_eq!(digits("Bär", &german), digits("Baer", &Mapping::default()));
+        assert_eq!(digits("Bär", &german), "7502");
+        assert_eq!(digits("BÄR", &german), "7502");
+        assert_eq!(digits("Fuß", &german), "4733");
+        assert_eq!(Mapping::default().unmapped_letter("Bär"), Some('ä'));
+        assert_eq!(german.unmapped_letter("Bär"), None);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn parses_expansions() {
+        let mut mapping = Mapping::new();
+        mapping.expand_spec("german").unwrap();
+        assert_eq!(mapping, Mapping::german());
+        let mut mapping = Mapping::new();
+        mapping.expand_spec("ø=oe").unwrap();
+        assert_eq!(digits("Øl", &mapping), "808");
+        assert!(mapping.expand_spec("ø").is_err());
+        // the letters must be mapped, which the digits of t9 are not
+        assert!(Mapping::t9().expand('ø', "o1").is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn rejects_german_expansions_without_their_letters() {
+        let mut mapping = Mapping::parse_keymap("2 = \"абв\"\n").unwrap();
+        let error = mapping.expand_spec("german").unwrap_err();
+        assert!(matches!(error, EncodeError::MappingError(_)));
+        assert_eq!(error.to_string(), "cannot expand 'ß' to 'ss': 's' is not mapped to a digit");
+        // the keypads with Latin letters have them all
+        assert!(Mapping::t9().expand_spec("german").is_ok());
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn bypasses_the_ascii_table_with_expansions() {
+        let mut calls = 0;
+        assert!(Mapping::default().encode_ascii("Tor", |_| calls += 1));
+        assert_eq!(calls, 3);
+        // as an expansion may replace an ASCII letter too
+        assert!(!Mapping::german().encode_ascii("Tor", |_| calls += 1));
+        assert_eq!(calls, 3);
+        assert_eq!(digits("Tor", &Mapping::german()), digits("Tor", &Mapping::default()));
+    }
+}
@@ -0,0 +1,112 @@
+//! Tests of the safe core (the search, mapping and filter modules, which only need `alloc`),
+//! small enough to run under Miri, which checks them for undefined behaviour:
+//!
+//! ```text
+//! cargo +nightly miri test --features miri-compat miri::
+//! ```
+//!
+//! They read no files and use no clock, and only the modules the crate does not let use
+//! `unsafe` run, so what Miri finds is in them or in their dependencies.
+
+use std::collections::BTreeMap;
+
+use num_bigint::BigUint;
+
+use crate::aho_corasick::{AhoCorasick, AhoCorasickDp};
+use crate::dp::DpCount;
+use crate::filter::{AcceptAll, Rules, SolutionFilter};
+use crate::iterative::IterativeDfs;
+use crate::mapping::Mapping;
+use crate::search::{words_from_bytes, Limits, Lookup, RecursiveDfs, SearchGuard, SearchStrategy, Word, WordOrDigit};
+use crate::split::MeetInTheMiddle;
+use crate::trie::{Trie, TrieWalk};
+use crate::verify::verify_solution;
+
+/// The dictionary of the problem statement.
+const WORDS: &[u8] = b"an\nblau\nBo\"\nBoot\nbo\"s\nda\nFee\nfern\nFest\nfort\nje\njemand\nmir\nMix\nMixer\nName\nneu\no\"d\nOrt\nso\nTor\nTorf\nWasser\n";
+
+fn dict() -> BTreeMap<BigUint, Vec<Word>> {
+    words_from_bytes(WORDS, &Mapping::default())
+}
+
+fn digits(num: &str) -> Vec<char> {
+    num.chars().filter(char::is_ascii_digit).collect()
+}
+
+/// The solutions of a search, in the order found, each checked with [`verify_solution`].
+fn solutions<S: SearchStrategy, D: Lookup + ?Sized>(
+    strategy: &S,
+    num: &str,
+    dict: &D,
+    filter: &dyn SolutionFilter,
+) -> Vec<String> {
+    let digits = digits(num);
+    let mut found = Vec::new();
+    strategy.search(&digits, dict, filter, Limits::solutions(usize::MAX), &mut |parts: &[WordOrDigit]| {
+        assert_eq!(verify_solution(&digits, parts, dict, filter), Ok(()));
+        found.push(parts.iter().map(ToString::to_string).collect::<Vec<_>>().join(" "));
+        Ok::<(), ()>(())
+    }, &mut |_, _| Ok(())).unwrap();
+    found
+}
+
+fn sorted(mut solutions: Vec<String>) -> Vec<String> {
+    solutions.sort();
+    solutions
+}
+
+#[test]
+fn finds_the_solutions_of_the_problem_statement() {
+    let dict = dict();
+    assert_eq!(sorted(solutions(&RecursiveDfs, "5624-82", &dict, &AcceptAll)), ["Mix Tor", "mir Tor"]);
+    assert_eq!(sorted(solutions(&RecursiveDfs, "4824", &dict, &AcceptAll)), ["Tor 4", "Torf", "fort"]);
+    assert_eq!(sorted(solutions(&RecursiveDfs, "10/783--5", &dict, &AcceptAll)), ["je Bo\" da", "je bo\"s 5", "neu o\"d 5"]);
+    assert_eq!(solutions(&RecursiveDfs, "381482", &dict, &AcceptAll), ["so 1 Tor"]);
+    assert_eq!(sorted(solutions(&RecursiveDfs, "04824", &dict, &AcceptAll)), ["0 Tor 4", "0 Torf", "0 fort"]);
+    assert!(solutions(&RecursiveDfs, "1078-913-5", &dict, &AcceptAll).is_empty());
+}
+
+#[test]
+fn strategies_find_the_same_solutions() {
+    let dict = dict();
+    let trie = Trie::new(&dict);
+    let automaton = AhoCorasick::new(&dict);
+    let rules = Rules::parse("all").unwrap();
+    for filter in [&AcceptAll as &dyn SolutionFilter, &rules] {
+        for num in ["5624-82", "4824", "10/783--5", "381482", "04824", "0721/608-4067"] {
+            let expected = sorted(solutions(&RecursiveDfs, num, &dict, filter));
+            assert_eq!(sorted(solutions(&IterativeDfs, num, &dict, filter)), expected, "{}", num);
+            assert_eq!(sorted(solutions(&DpCount, num, &dict, filter)), expected, "{}", num);
+            assert_eq!(sorted(solutions(&TrieWalk, num, &trie, filter)), expected, "{}", num);
+            assert_eq!(sorted(solutions(&MeetInTheMiddle, num, &dict, filter)), expected, "{}", num);
+            // the search of DpCount with the words found otherwise, so in the same order
+            assert_eq!(solutions(&AhoCorasickDp, num, &automaton, filter), solutions(&DpCount, num, &dict, filter), "{}", num);
+        }
+    }
+}
+
+/// The guard a search within `limits` was abandoned at, if any.
+fn guard<S: SearchStrategy, D: Lookup + ?Sized>(strategy: &S, num: &str, dict: &D, limits: Limits) -> Option<SearchGuard> {
+    let stats = strategy.search(&digits(num), dict, &AcceptAll, limits, &mut |_| Ok::<(), ()>(()), &mut |_, _| Ok(()));
+    stats.unwrap().guard
+}
+
+#[test]
+fn strategies_abandon_the_search_beyond_the_guards() {
+    let dict = dict();
+    let trie = Trie::new(&dict);
+    let automaton = AhoCorasick::new(&dict);
+    // Mix Tor and mir Tor: two parts, after one of two words
+    let shallow = Limits { depth: 1, ..Limits::solutions(usize::MAX) };
+    let narrow = Limits { branching: 1, ..Limits::solutions(usize::MAX) };
+    for (limits, expected) in [(shallow, SearchGuard::Depth(1)), (narrow, SearchGuard::Branching(1))] {
+        assert_eq!(guard(&RecursiveDfs, "5624-82", &dict, limits), Some(expected));
+        assert_eq!(guard(&IterativeDfs, "5624-82", &dict, limits), Some(expected));
+        assert_eq!(guard(&DpCount, "5624-82", &dict, limits), Some(expected));
+        assert_eq!(guard(&TrieWalk, "5624-82", &trie, limits), Some(expected));
+        assert_eq!(guard(&MeetInTheMiddle, "5624-82", &dict, limits), Some(expected));
+        assert_eq!(guard(&AhoCorasickDp, "5624-82", &automaton, limits), Some(expected));
+    }
+    let within = Limits { depth: 2, branching: 2, ..Limits::solutions(usize::MAX) };
+    assert_eq!(guard(&RecursiveDfs, "5624-82", &dict, within), None);
+}
@@ -0,0 +1,242 @@
+//! Normalizing phone numbers to the digits that are encoded.
+//!
+//! Per the original problem, a phone number is made of digits, with dashes (`-`) and
+//! slashes (`/`) in between that are not encoded; spaces are accepted as separators too.
+//! By default, other characters that are neither letters nor digits are ignored like
+//! separators, as they always were; in strict mode, they make the number invalid.
+
+use alloc::vec::Vec;
+use core::fmt;
+use core::hash::{Hash, Hasher};
+use core::ops::{Deref, Range};
+
+/// How many digits a [`DigitString`] holds without allocating: more than phone numbers have.
+pub const INLINE_DIGITS: usize = 50;
+
+/// The digits of a phone number, without its separators: only `0` to `9`.
+///
+/// Up to [`INLINE_DIGITS`] digits are stored inline, so that encoding a number allocates
+/// nothing for its digits. It dereferences to the `&[char]` taken by the search.
+#[derive(Clone)]
+pub struct DigitString {
+    storage: Storage,
+}
+
+#[derive(Clone)]
+enum Storage {
+    Inline { digits: [char; INLINE_DIGITS], len: usize },
+    Heap(Vec<char>),
+}
+
+impl DigitString {
+    pub fn new() -> DigitString {
+        DigitString { storage: Storage::Inline { digits: ['0'; INLINE_DIGITS], len: 0 } }
+    }
+
+    /// The digits of `digits`, if it only contains the digits `0` to `9`.
+    pub fn from_digits(digits: &str) -> Option<DigitString> {
+        digits.bytes().all(|byte| byte.is_ascii_digit())
+            .then(|| DigitString::from_checked(digits))
+    }
+
+    /// The digits of a number already checked with [`check_number`], skipping the others.
+    pub(crate) fn from_checked(num: &str) -> DigitString {
+        let mut digits = DigitString::new();
+        if num.len() > INLINE_DIGITS {
+            digits.storage = Storage::Heap(Vec::with_capacity(num.len()));
+        }
+        for digit in num.chars().filter(char::is_ascii_digit) {
+            digits.push(digit);
+        }
+        digits
+    }
+
+    fn push(&mut self, digit: char) {
+        match &mut self.storage {
+            Storage::Inline { digits, len } if *len < INLINE_DIGITS => {
+                digits[*len] = digit;
+                *len += 1;
+            }
+            Storage::Inline { digits, len } => {
+                let mut heap = Vec::with_capacity(*len * 2);
+                heap.extend_from_slice(&digits[..*len]);
+                heap.push(digit);
+                self.storage = Storage::Heap(heap);
+            }
+            Storage::Heap(digits) => digits.push(digit),
+        }
+    }
+
+    pub fn as_chars(&self) -> &[char] {
+        match &self.storage {
+            Storage::Inline { digits, len } => &digits[..*len],
+            Storage::Heap(digits) => digits,
+        }
+    }
+
+    /// The value of the digit at `index`.
+    pub fn digit(&self, index: usize) -> u8 {
+        self.as_chars()[index] as u8 - b'0'
+    }
+
+    /// The digits in `range`, which must be within the digits, like when indexing a slice.
+    pub fn slice(&self, range: Range<usize>) -> DigitString {
+        let mut digits = DigitString::new();
+        for digit in &self.as_chars()[range] {
+            digits.push(*digit);
+        }
+        digits
+    }
+
+    /// The first `len` digits (all of them if there are fewer).
+    pub fn prefix(&self, len: usize) -> DigitString {
+        self.slice(0..len.min(self.len()))
+    }
+
+    /// The digits from `start` on (none if there are fewer).
+    pub fn suffix(&self, start: usize) -> DigitString {
+        self.slice(start.min(self.len())..self.len())
+    }
+}
+
+impl Default for DigitString {
+    fn default() -> DigitString {
+        DigitString::new()
+    }
+}
+
+impl Deref for DigitString {
+    type Target = [char];
+
+    fn deref(&self) -> &[char] {
+        self.as_chars()
+    }
+}
+
+impl PartialEq for DigitString {
+    fn eq(&self, other: &DigitString) -> bool {
+        self.as_chars() == other.as_chars()
+    }
+}
+
+impl Eq for DigitString {}
+
+impl Hash for DigitString {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_chars().hash(state)
+    }
+}
+
+impl fmt::Debug for DigitString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "DigitString({:?})", alloc::string::ToString::to_string(self))
+    }
+}
+
+impl fmt::Display for DigitString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.as_chars().iter().try_for_each(|digit| fmt::Write::write_char(f, *digit))
+    }
+}
+
+/// A phone number contains a character that is neither a digit nor an allowed separator.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct InvalidNumber {
+    pub char: char,
+}
+
+impl fmt::Display for InvalidNumber {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid character {:?} in phone number", self.char)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for InvalidNumber {}
+
+/// Whether a character separates the digits of a phone number: `/`, `-` or a space.
+fn is_separator(ch: char) -> bool {
+    matches!(ch, '/' | '-' | ' ')
+}
+
+/// Checks that a phone number only contains digits and separators, or with `strict`
+/// unset, any characters but letters and digits other than `0` to `9`.
+#[inline]
+pub fn check_number(num: &str, strict: bool) -> Result<(), InvalidNumber> {
+    let invalid = if strict {
+        num.chars().find(|ch| !ch.is_ascii_digit() && !is_separator(*ch))
+    } else if num.is_ascii() {
+        // checking bytes is much cheaper than decoding and classifying chars
+        num.bytes().find(u8::is_ascii_alphabetic).map(char::from)
+    } else {
+        num.chars().find(|ch| ch.is_alphanumeric() && !ch.is_ascii_digit())
+    };
+    match invalid {
+        Some(char) => Err(InvalidNumber { char }),
+        None => Ok(()),
+    }
+}
+
+/// The digits of a phone number, after checking it with [`check_number`].
+pub fn normalize_number(num: &str, strict: bool) -> Result<DigitString, InvalidNumber> {
+    check_number(num, strict)?;
+    Ok(DigitString::from_checked(num))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn digits(num: &str, strict: bool) -> Result<String, InvalidNumber> {
+        normalize_number(num, strict).map(|digits| digits.to_string())
+    }
+
+    #[test]
+    fn removes_separators() {
+        for strict in [false, true] {
+            assert_eq!(digits("5624-82", strict), Ok("562482".into()));
+            assert_eq!(digits("/78698-/5 3", strict), Ok("7869853".into()));
+            assert_eq!(digits("", strict), Ok("".into()));
+            assert_eq!(digits("--/", strict), Ok("".into()));
+        }
+    }
+
+    #[test]
+    fn rejects_letters() {
+        for strict in [false, true] {
+            assert_eq!(digits("56a4", strict), Err(InvalidNumber { char: 'a' }));
+            assert_eq!(digits("4-\u{e4}", strict), Err(InvalidNumber { char: '\u{e4}' }));
+        }
+    }
+
+    #[test]
+    fn rejects_non_ascii_digits() {
+        // an Arabic-Indic digit three
+        assert_eq!(digits("12\u{663}", false), Err(InvalidNumber { char: '\u{663}' }));
+    }
+
+    #[test]
+    fn stores_long_numbers_on_the_heap() {
+        let num: String = (0..INLINE_DIGITS * 2 + 1).map(|i| char::from(b'0' + (i % 10) as u8)).collect();
+        let digits = DigitString::from_digits(&num).unwrap();
+        assert_eq!(digits.len(), INLINE_DIGITS * 2 + 1);
+        assert_eq!(digits.to_string(), num);
+        assert_eq!(digits.prefix(3), DigitString::from_digits("012").unwrap());
+        assert_eq!(digits.suffix(INLINE_DIGITS * 2), DigitString::from_digits("0").unwrap());
+        assert_eq!(digits.digit(7), 7);
+    }
+
+    #[test]
+    fn from_digits_only_accepts_digits() {
+        assert_eq!(DigitString::from_digits("0123"), Some(DigitString::from_checked("0-1/23")));
+        assert_eq!(DigitString::from_digits("01-23"), None);
+        assert_eq!(DigitString::from_digits(""), Some(DigitString::new()));
+    }
+
+    #[test]
+    fn ignores_other_characters_unless_strict() {
+        assert_eq!(digits("(030) 1234.56", false), Ok("030123456".into()));
+        assert_eq!(digits("(030) 1234.56", true), Err(InvalidNumber { char: '(' }));
+        assert_eq!(digits("12\t3", true), Err(InvalidNumber { char: '\t' }));
+    }
+}
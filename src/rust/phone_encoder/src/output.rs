@@ -0,0 +1,234 @@
+//! The files the solutions are written to.
+
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+use crate::platform;
+
+/// How output files are created and written.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct OutputOptions {
+    /// space allocated on the disk for each file before writing it, in bytes, so that the
+    /// file is not fragmented as it grows (on Linux; the space not written is released at the end).
+    pub preallocate: Option<u64>,
+    /// write the files with direct I/O, bypassing the page cache, so that the output does not
+    /// evict everything else from it (on Linux, where the file system supports it). Only
+    /// whole blocks are written directly: flushing the writer before its end writes the rest
+    /// without direct I/O, which the file then keeps to, so it should only be flushed once.
+    pub direct: bool,
+}
+
+/// Creates the file at `path`, returning a writer of it (see [`file_writer`]).
+pub fn create_output(path: &Path, options: &OutputOptions) -> io::Result<Box<dyn Write + Send>> {
+    let mut open = OpenOptions::new();
+    open.write(true).create(true).truncate(true);
+    let direct = options.direct && platform::open_direct(&mut open);
+    let file = open.open(path)?;
+    let Some(len) = options.preallocate else { return Ok(writer(file, direct)) };
+    platform::preallocate(&file, len)?;
+    Ok(Box::new(Preallocated { file: file.try_clone()?, out: writer(file, direct), len }))
+}
+
+fn writer(file: File, direct: bool) -> Box<dyn Write + Send> {
+    if direct {
+        return Box::new(DirectWriter::new(file));
+    }
+    file_writer(file)
+}
+
+/// A writer of `file`, which is written with io_uring if it is a regular file, on Linux with
+/// the `uring` feature (see [`platform::uring_writer`]), or else buffered.
+pub fn file_writer(file: File) -> Box<dyn Write + Send> {
+    // or else, e.g. if io_uring is disabled, buffered
+    platform::uring_writer(&file).unwrap_or_else(|| Box::new(BufWriter::new(file)))
+}
+
+/// Writes a file with space allocated beyond its end, releasing the space not written
+/// once dropped.
+struct Preallocated {
+    file: File,
+    out: Box<dyn Write + Send>,
+    /// the space allocated.
+    len: u64,
+}
+
+impl Write for Preallocated {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.out.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+}
+
+impl Drop for Preallocated {
+    fn drop(&mut self) {
+        if self.out.flush().is_err() {
+            return;
+        }
+        // truncating a file to its size frees the blocks allocated past its end
+        if let Ok(metadata) = self.file.metadata() {
+            if metadata.len() < self.len {
+                let _ = self.file.set_len(metadata.len());
+            }
+        }
+    }
+}
+
+/// The alignment of the buffers, sizes and offsets of direct I/O: the logical block size of
+/// most disks, or a multiple of it.
+const DIRECT_ALIGNMENT: usize = 4096;
+
+/// The size of the buffer of a [`DirectWriter`].
+const DIRECT_BUFFER_SIZE: usize = 1024 * 1024;
+
+/// Writes a file opened for direct I/O (see [`platform::open_direct`]) from an aligned buffer, in whole buffers.
+///
+/// When flushed, the whole blocks of the buffer are written directly, and the rest without
+/// direct I/O, which is no longer used for the file from then on, as its end is no longer
+/// aligned.
+struct DirectWriter {
+    file: File,
+    /// the buffer starts at `start`, the first aligned byte of the allocation.
+    buffer: Vec<u8>,
+    start: usize,
+    len: usize,
+    direct: bool,
+}
+
+impl DirectWriter {
+    fn new(file: File) -> DirectWriter {
+        let buffer = vec![0; DIRECT_BUFFER_SIZE + DIRECT_ALIGNMENT];
+        let start = buffer.as_ptr().align_offset(DIRECT_ALIGNMENT);
+        DirectWriter { file, buffer, start, len: 0, direct: true }
+    }
+
+    fn write_buffer(&mut self, len: usize) -> io::Result<()> {
+        self.file.write_all(&self.buffer[self.start..self.start + len])?;
+        self.buffer.copy_within(self.start + len..self.start + self.len, self.start);
+        self.len -= len;
+        Ok(())
+    }
+}
+
+impl Write for DirectWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = buf.len().min(DIRECT_BUFFER_SIZE - self.len);
+        let at = self.start + self.len;
+        self.buffer[at..at + n].copy_from_slice(&buf[..n]);
+        self.len += n;
+        if self.len == DIRECT_BUFFER_SIZE {
+            self.write_buffer(DIRECT_BUFFER_SIZE)?;
+        }
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if self.direct {
+            self.write_buffer(self.len / DIRECT_ALIGNMENT * DIRECT_ALIGNMENT)?;
+            if self.len == 0 {
+                return Ok(());
+            }
+            platform::end_direct(&self.file)?;
+            self.direct = false;
+        }
+        self.write_buffer(self.len)
+    }
+}
+
+impl Drop for DirectWriter {
+    fn drop(&mut self) {
+        let _ = self.flush();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs;
+    use std::path::PathBuf;
+    use std::process;
+
+    use super::*;
+
+    /// A file of its own for a test, deleted when dropped.
+    struct TempFile(PathBuf);
+
+    impl TempFile {
+        fn new(name: &str) -> TempFile {
+            TempFile(env::temp_dir().join(format!("phone_encoder-output-{}-{}", name, process::id())))
+        }
+    }
+
+    impl Drop for TempFile {
+        fn drop(&mut self) {
+            let _ = fs::remove_file(&self.0);
+        }
+    }
+
+    fn payload(len: usize) -> Vec<u8> {
+        (0..len).map(|i| (i % 251) as u8).collect()
+    }
+
+    fn write(path: &Path, options: &OutputOptions, payload: &[u8]) {
+        let mut out = create_output(path, options).unwrap();
+        for piece in payload.chunks(10_007) {
+            out.write_all(piece).unwrap();
+        }
+        out.flush().unwrap();
+    }
+
+    #[test]
+    fn writes_unaligned_lengths_with_direct_io() {
+        let path = TempFile::new("direct");
+        let options = OutputOptions { direct: true, ..OutputOptions::default() };
+        // a whole buffer and some, ending within a block
+        for len in [0, 100, DIRECT_ALIGNMENT, 3 * DIRECT_ALIGNMENT + 1000, DIRECT_BUFFER_SIZE + 5000] {
+            let payload = payload(len);
+            write(&path.0, &options, &payload);
+            assert_eq!(fs::read(&path.0).unwrap(), payload, "{}", len);
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn writes_directly_until_the_end_is_not_aligned() {
+        use rustix::fs::{fcntl_getfl, OFlags};
+
+        let path = TempFile::new("direct-flag");
+        let mut open = OpenOptions::new();
+        open.write(true).create(true).truncate(true);
+        platform::open_direct(&mut open);
+        let file = open.open(&path.0).unwrap();
+        let direct = || fcntl_getfl(&file).unwrap().contains(OFlags::DIRECT);
+        let mut out = DirectWriter::new(file.try_clone().unwrap());
+        let payload = payload(2 * DIRECT_ALIGNMENT + 100);
+        out.write_all(&payload[..2 * DIRECT_ALIGNMENT]).unwrap();
+        out.flush().unwrap();
+        assert!(direct());
+        out.write_all(&payload[2 * DIRECT_ALIGNMENT..]).unwrap();
+        out.flush().unwrap();
+        // the last 100 bytes were written without direct I/O
+        assert!(!direct());
+        out.write_all(b"more").unwrap();
+        drop(out);
+        let written = fs::read(&path.0).unwrap();
+        assert_eq!(written.len(), payload.len() + 4);
+        assert!(written.starts_with(&payload) && written.ends_with(b"more"));
+    }
+
+    #[test]
+    fn releases_the_space_preallocated_but_not_written() {
+        let path = TempFile::new("preallocated");
+        for direct in [false, true] {
+            let options = OutputOptions { preallocate: Some(4 * DIRECT_BUFFER_SIZE as u64), direct };
+            let payload = payload(DIRECT_BUFFER_SIZE + 3 * DIRECT_ALIGNMENT + 1234);
+            write(&path.0, &options, &payload);
+            assert_eq!(fs::metadata(&path.0).unwrap().len(), payload.len() as u64);
+            assert_eq!(fs::read(&path.0).unwrap(), payload);
+        }
+    }
+}
@@ -0,0 +1,361 @@
+use std::io::{self, Write};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::{sync_channel, SyncSender};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::encoder::{EncodeStats, Encoder};
+
+/// How many numbers are encoded on the current thread to estimate the cost of a number
+/// when the number of threads is chosen automatically.
+const CALIBRATION_SAMPLE: usize = 100;
+
+/// The size of the blocks of output sent by each thread to the writer.
+const OUTPUT_BLOCK_SIZE: usize = 64 * 1024;
+
+/// How many blocks of output each thread may have queued before it has to wait for the writer.
+const OUTPUT_QUEUE_BLOCKS: usize = 16;
+
+/// The least amount of work that makes it worth spawning one more thread.
+const MIN_WORK_PER_THREAD: Duration = Duration::from_millis(20);
+
+/// The number of threads used to encode phone numbers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Jobs {
+    /// pick the number of threads based on the cost of encoding a sample of the numbers.
+    Auto,
+    Count(usize),
+}
+
+impl Jobs {
+    /// Parses either `auto` or a positive number.
+    pub fn parse(value: &str) -> Option<Jobs> {
+        match value {
+            "auto" => Some(Jobs::Auto),
+            _ => value.parse().ok().filter(|n| *n > 0).map(Jobs::Count),
+        }
+    }
+}
+
+/// Encodes the given phone numbers using several threads, writing the solutions to `out`
+/// in the same order as [`Encoder::encode`] would.
+///
+/// The numbers are partitioned into contiguous ranges, one per thread, so that each thread
+/// works on its own region of memory.
+///
+/// This may be called once for each chunk of a large input: if `jobs` is [`Jobs::Auto`],
+/// it is replaced with the number of threads chosen for the first chunk.
+///
+/// With [`EncodeOptions::max_total`](crate::EncodeOptions::max_total), the threads use up
+/// the budget as they go, so that as many solutions are written, but not necessarily the
+/// first ones [`Encoder::encode`] would write: those of later numbers may take the place of
+/// some of earlier ones.
+pub fn encode_parallel(
+    encoder: &Encoder,
+    numbers: &[String],
+    jobs: &mut Jobs,
+    out: &mut dyn Write,
+) -> io::Result<EncodeStats> {
+    let mut stats = EncodeStats::default();
+    let (jobs, numbers) = match *jobs {
+        Jobs::Count(n) => (n, numbers),
+        Jobs::Auto => {
+            // the calibration sample is part of the output, so it's not wasted
+            let (sample, rest) = numbers.split_at(numbers.len().min(CALIBRATION_SAMPLE));
+            let start_time = Instant::now();
+            for num in sample {
+                stats += encoder.encode_to(num, out)?;
+            }
+            let count = auto_jobs(start_time.elapsed(), sample.len(), rest.len());
+            *jobs = Jobs::Count(count);
+            (count, rest)
+        }
+    };
+    if numbers.is_empty() {
+        return Ok(stats);
+    }
+
+    let partition_size = numbers.len().div_ceil(jobs);
+    let (partition_stats, write_time) = thread::scope(|scope| {
+        let mut handles = Vec::with_capacity(jobs);
+        let mut receivers = Vec::with_capacity(jobs);
+        for partition in numbers.chunks(partition_size) {
+            let (sender, receiver) = sync_channel(OUTPUT_QUEUE_BLOCKS);
+            receivers.push(receiver);
+            handles.push(scope.spawn(move || {
+                let mut out = BlockSender { block: Vec::with_capacity(OUTPUT_BLOCK_SIZE), sender };
+                let mut stats = EncodeStats::default();
+                for num in partition {
+                    stats += encoder.encode_to(num, &mut out)?;
+                }
+                out.flush()?;
+                Ok(stats)
+            }));
+        }
+
+        // partitions are written in order: the threads working on later partitions block
+        // once their queue is full, so memory stays bounded however slow `out` is
+        let mut write_time = Duration::ZERO;
+        let mut write_result = Ok(());
+        for receiver in receivers {
+            for block in receiver {
+                let start_time = Instant::now();
+                write_result = out.write_all(&block);
+                write_time += start_time.elapsed();
+                if write_result.is_err() {
+                    break;
+                }
+            }
+            if write_result.is_err() {
+                // dropping the remaining receivers makes the threads stop
+                break;
+            }
+        }
+        let partition_stats: io::Result<Vec<EncodeStats>> = handles.into_iter()
+            .map(|handle| handle.join().expect("encoder thread panicked"))
+            .collect();
+        write_result.and(partition_stats).map(|partition_stats| (partition_stats, write_time))
+    })?;
+
+    for partition_stats in partition_stats {
+        stats += partition_stats;
+    }
+    // threads format solutions concurrently, so only the final write is reported
+    stats.write_time = write_time;
+    Ok(stats)
+}
+
+/// Calls `f` on each of the items using `jobs` threads (for [`Jobs::Auto`], one per CPU),
+/// returning the results in the order of the items, or the first error.
+///
+/// Each thread takes the next item as soon as it is done with the previous one, so that
+/// items of very different costs, like whole numbers files, still keep all threads busy.
+pub fn map_parallel<T, R, F>(items: &[T], jobs: Jobs, f: F) -> io::Result<Vec<R>>
+where
+    T: Sync,
+    R: Send,
+    F: Fn(&T) -> io::Result<R> + Sync,
+{
+    let jobs = match jobs {
+        Jobs::Auto => thread::available_parallelism().map(|n| n.get()).unwrap_or(1),
+        Jobs::Count(n) => n,
+    };
+    let next = AtomicUsize::new(0);
+    let failed = AtomicBool::new(false);
+    let mut results: Vec<(usize, R)> = thread::scope(|scope| {
+        let handles: Vec<_> = (0..jobs.min(items.len()))
+            .map(|_| scope.spawn(|| {
+                let mut results = Vec::new();
+                while !failed.load(Ordering::Relaxed) {
+                    let index = next.fetch_add(1, Ordering::Relaxed);
+                    let Some(item) = items.get(index) else { break };
+                    match f(item) {
+                        Ok(result) => results.push((index, result)),
+                        Err(e) => {
+                            // the other threads stop after their current item
+                            failed.store(true, Ordering::Relaxed);
+                            return Err(e);
+                        }
+                    }
+                }
+                Ok(results)
+            }))
+            .collect();
+        let mut results = Vec::with_capacity(items.len());
+        for handle in handles {
+            results.extend(handle.join().expect("worker thread panicked")?);
+        }
+        Ok::<_, io::Error>(results)
+    })?;
+    results.sort_unstable_by_key(|(index, _)| *index);
+    Ok(results.into_iter().map(|(_, result)| result).collect())
+}
+
+/// Buffers the output of an encoder thread, sending it to the writer in blocks.
+struct BlockSender {
+    block: Vec<u8>,
+    sender: SyncSender<Vec<u8>>,
+}
+
+impl Write for BlockSender {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.block.extend_from_slice(buf);
+        if self.block.len() >= OUTPUT_BLOCK_SIZE {
+            self.flush()?;
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if self.block.is_empty() {
+            return Ok(());
+        }
+        let block = std::mem::replace(&mut self.block, Vec::with_capacity(OUTPUT_BLOCK_SIZE));
+        self.sender.send(block)
+            .map_err(|_| io::Error::new(io::ErrorKind::BrokenPipe, "output writer has stopped"))
+    }
+}
+
+fn auto_jobs(sample_time: Duration, sample_len: usize, remaining: usize) -> usize {
+    let max_jobs = thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+    if sample_len == 0 {
+        return 1;
+    }
+    let estimated = sample_time.as_secs_f64() / sample_len as f64 * remaining as f64;
+    let jobs = (estimated / MIN_WORK_PER_THREAD.as_secs_f64()) as usize;
+    jobs.clamp(1, max_jobs)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::filter::{Rejection, SolutionFilter};
+    use crate::search::{words_from_bytes, WordOrDigit};
+    use crate::Mapping;
+
+    use super::*;
+
+    fn encoder() -> Encoder {
+        Encoder::new(words_from_bytes(b"a\nM\nam\nma\nmam\n", &Mapping::default()).into_iter().collect())
+    }
+
+    /// Numbers of a few to a few thousand solutions, so that some have several blocks of
+    /// output and many fit in one.
+    fn numbers(count: usize) -> Vec<String> {
+        (0..count).map(|i| match i % 3 {
+            0 => "5".repeat(1 + i % 8),
+            1 => format!("5-{}", "5".repeat(i % 5)),
+            _ => "123".into(),
+        }).collect()
+    }
+
+    fn sequential(encoder: &Encoder, numbers: &[String]) -> (Vec<u8>, EncodeStats) {
+        let mut out = Vec::new();
+        let mut stats = EncodeStats::default();
+        for num in numbers {
+            stats += encoder.encode_to(num, &mut out).unwrap();
+        }
+        (out, stats)
+    }
+
+    #[test]
+    fn writes_the_solutions_in_the_order_of_the_numbers() {
+        let encoder = encoder();
+        let numbers = numbers(300);
+        let (expected, expected_stats) = sequential(&encoder, &numbers);
+        assert!(expected.len() > 4 * OUTPUT_BLOCK_SIZE);
+        // more threads than numbers too
+        for jobs in [Jobs::Count(1), Jobs::Count(3), Jobs::Count(8), Jobs::Count(1000), Jobs::Auto] {
+            let mut out = Vec::new();
+            let mut chosen = jobs;
+            let stats = encode_parallel(&encoder, &numbers, &mut chosen, &mut out).unwrap();
+            assert!(out == expected, "{:?}", jobs);
+            assert_eq!(stats.solutions, expected_stats.solutions);
+            assert!(matches!(chosen, Jobs::Count(n) if n > 0));
+        }
+        let mut out = Vec::new();
+        let stats = encode_parallel(&encoder, &numbers[..2], &mut Jobs::Count(8), &mut out).unwrap();
+        let (expected, expected_stats) = sequential(&encoder, &numbers[..2]);
+        assert_eq!(out, expected);
+        assert_eq!(stats.solutions, expected_stats.solutions);
+        assert_eq!(encode_parallel(&encoder, &[], &mut Jobs::Count(8), &mut out).unwrap().solutions, 0);
+    }
+
+    #[test]
+    fn stops_at_the_first_error_of_the_writer() {
+        struct Failing;
+
+        impl Write for Failing {
+            fn write(&mut self, _buf: &[u8]) -> io::Result<usize> {
+                Err(io::Error::new(io::ErrorKind::StorageFull, "disk full"))
+            }
+
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let error = encode_parallel(&encoder(), &numbers(300), &mut Jobs::Count(4), &mut Failing).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::StorageFull);
+    }
+
+    #[test]
+    fn maps_the_items_in_order() {
+        let items: Vec<usize> = (0..1000).collect();
+        for jobs in [Jobs::Count(1), Jobs::Count(7), Jobs::Count(5000), Jobs::Auto] {
+            let squares = map_parallel(&items, jobs, |&i| Ok(i * i)).unwrap();
+            assert_eq!(squares, items.iter().map(|i| i * i).collect::<Vec<_>>());
+        }
+        assert!(map_parallel(&[] as &[usize], Jobs::Count(4), |&i| Ok(i)).unwrap().is_empty());
+        let error = map_parallel(&items, Jobs::Count(4), |&i| match i {
+            500 => Err(io::Error::other("item 500")),
+            i => Ok(i),
+        });
+        assert_eq!(error.unwrap_err().to_string(), "item 500");
+    }
+
+    /// Counts the candidate solutions checked, accepting them all.
+    struct Counting(Arc<AtomicUsize>);
+
+    impl SolutionFilter for Counting {
+        fn check(&self, _digits: &[char], _parts: &[WordOrDigit]) -> Result<(), Rejection> {
+            self.0.fetch_add(1, Ordering::Relaxed);
+            Ok(())
+        }
+    }
+
+    /// A writer taking its time with each block, or failing after some of them.
+    struct Writer {
+        out: Vec<u8>,
+        delay: Duration,
+        blocks_left: usize,
+    }
+
+    impl Write for Writer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            if self.blocks_left == 0 {
+                return Err(io::Error::new(io::ErrorKind::BrokenPipe, "closed"));
+            }
+            self.blocks_left -= 1;
+            thread::sleep(self.delay);
+            // a short write, for the rest of the block to be written again
+            let n = buf.len().min(OUTPUT_BLOCK_SIZE / 2 + 1);
+            self.out.extend_from_slice(&buf[..n]);
+            Ok(n)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn waits_for_a_slow_writer_without_losing_blocks() {
+        let encoder = encoder();
+        let numbers = numbers(600);
+        let (expected, _) = sequential(&encoder, &numbers);
+        let mut out = Writer { out: Vec::new(), delay: Duration::from_millis(1), blocks_left: usize::MAX };
+        encode_parallel(&encoder, &numbers, &mut Jobs::Count(4), &mut out).unwrap();
+        assert!(out.out == expected);
+    }
+
+    #[test]
+    fn threads_stop_once_the_writer_fails() {
+        let checked = Arc::new(AtomicUsize::new(0));
+        let encoder = encoder().with_filter(Counting(checked.clone()));
+        // enough output for each thread to fill its queue many times over
+        let numbers = numbers(6000);
+        let mut out = Writer { out: Vec::new(), delay: Duration::ZERO, blocks_left: usize::MAX };
+        let stats = encode_parallel(&encoder, &numbers, &mut Jobs::Count(4), &mut out).unwrap();
+        let blocks = out.out.len() / OUTPUT_BLOCK_SIZE;
+        assert!(blocks > 16 * (OUTPUT_QUEUE_BLOCKS + 1), "{}", blocks);
+        assert_eq!(checked.swap(0, Ordering::Relaxed), stats.solutions);
+
+        let mut out = Writer { out: Vec::new(), delay: Duration::from_millis(1), blocks_left: 3 };
+        let error = encode_parallel(&encoder, &numbers, &mut Jobs::Count(4), &mut out).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::BrokenPipe);
+        // at most the blocks queued and being filled by each thread were encoded
+        assert!(checked.load(Ordering::Relaxed) < stats.solutions / 3, "{}", checked.load(Ordering::Relaxed));
+    }
+}
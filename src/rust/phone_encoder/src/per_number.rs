@@ -0,0 +1,199 @@
+//! `--one-file-per-number`: writing the solutions of each phone number to its own file.
+
+use std::fmt::Write;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
+
+use phone_encoder::input::LineReader;
+use phone_encoder::parallel::{map_parallel, Jobs};
+use phone_encoder::sha256::{hex, sha256};
+use phone_encoder::{EncodeStats, Encoder, FileContext, FileRole};
+
+use crate::cli::{NumberPolicy, Options};
+use crate::run::{number, Totals};
+
+/// The longest file name written, well below the limit of common file systems (255 bytes).
+const MAX_NAME_LEN: usize = 200;
+
+/// The number of the next partial file written, so that threads writing the file of the
+/// same number (given twice) each write their own.
+static NEXT_PART: AtomicUsize = AtomicUsize::new(0);
+
+/// Encodes the numbers of a file, from its contents if it was preloaded, writing the solutions
+/// of each number to its own file in `output_dir`.
+///
+/// The solutions of a number are formatted in memory, so that each thread has at most one
+/// file open at a time, however many numbers there are.
+pub fn encode_per_number(
+    options: &Options,
+    encoder: &Encoder,
+    jobs: Option<Jobs>,
+    input_file: &Path,
+    preloaded: Option<&[u8]>,
+    output_dir: &Path,
+) -> io::Result<Totals> {
+    let mut total = Totals::default();
+    let mut lines = LineReader::open_or_preloaded(input_file, preloaded).in_file(FileRole::Numbers, input_file)?
+        .select(&options.selection)
+        .detect_format(&options.number_field)
+        .fail_on_invalid(options.number_policy == NumberPolicy::Strict);
+    let mut chunk = Vec::with_capacity(options.chunk_lines.min(1024));
+    loop {
+        let start_time = Instant::now();
+        chunk.clear();
+        while let Some(num) = lines.next_line().in_file(FileRole::Numbers, input_file)? {
+            chunk.push(number(num).to_string());
+            if chunk.len() == options.chunk_lines {
+                break;
+            }
+        }
+        let read_time = Instant::now();
+        total.timings.read += read_time - start_time;
+        if chunk.is_empty() {
+            break;
+        }
+        let encode = |num: &String| encode_to_file(encoder, num, output_dir);
+        let chunk_stats = match jobs {
+            Some(jobs) => map_parallel(&chunk, jobs, encode)?,
+            None => chunk.iter().map(encode).collect::<io::Result<_>>()?,
+        };
+        let mut stats = EncodeStats::default();
+        for number_stats in chunk_stats {
+            stats += number_stats;
+        }
+        // with several threads, the formatting times overlap
+        total.timings.search += read_time.elapsed().saturating_sub(stats.write_time);
+        total.timings.write += stats.write_time;
+        total.numbers += chunk.len();
+        total.stats += stats;
+        if total.stats.truncated {
+            break;
+        }
+    }
+    Ok(total)
+}
+
+fn encode_to_file(encoder: &Encoder, num: &str, output_dir: &Path) -> io::Result<EncodeStats> {
+    let mut solutions = Vec::new();
+    encoder.write_header(&mut solutions)?;
+    let stats = encoder.encode_to(num, &mut solutions)?;
+    let path = number_file(output_dir, num);
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).in_file(FileRole::Output, dir)?;
+    }
+    // written whole, then renamed, so that the file is never seen partly written
+    let partial = path.with_extension(format!("{}.{}.part", process::id(), NEXT_PART.fetch_add(1, Ordering::Relaxed)));
+    fs::write(&partial, solutions).in_file(FileRole::Output, &partial)?;
+    fs::rename(&partial, &path).in_file(FileRole::Output, &path)?;
+    Ok(stats)
+}
+
+/// The file of a phone number: the number, with each byte of the characters other than
+/// digits and `-` escaped as `_` and two hexadecimal digits (so that `/` does not start a
+/// directory, and different numbers have different files), and `.out`. A name longer than
+/// [`MAX_NAME_LEN`] is cut short, and ends with `~` and the start of the SHA-256 of the number.
+///
+/// It is in a subdirectory named after the last two digits of the number, so that no
+/// directory has more than a hundredth of the files: many file systems slow down with
+/// large directories.
+fn number_file(output_dir: &Path, num: &str) -> PathBuf {
+    let mut name = String::with_capacity(num.len());
+    for ch in num.chars() {
+        if ch.is_ascii_digit() || ch == '-' {
+            name.push(ch);
+        } else {
+            for byte in ch.encode_utf8(&mut [0; 4]).bytes() {
+                let _ = write!(name, "_{:02x}", byte);
+            }
+        }
+    }
+    if name.len() > MAX_NAME_LEN {
+        // the name is ASCII
+        name.truncate(MAX_NAME_LEN - 17);
+        name.push('~');
+        name.push_str(&hex(&sha256(num.as_bytes())[..8]));
+    }
+    let digits: Vec<char> = num.chars().filter(char::is_ascii_digit).collect();
+    let mut shard: String = digits[digits.len().saturating_sub(2)..].iter().collect();
+    if shard.is_empty() {
+        shard.push('_');
+    }
+    name.push_str(".out");
+    output_dir.join(shard).join(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use phone_encoder::search::words_from_bytes;
+    use phone_encoder::Mapping;
+
+    use super::*;
+
+    /// A directory of its own for the files of a test, deleted when dropped.
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> TempDir {
+            let dir = env::temp_dir().join(format!("phone_encoder-per_number-{}-{}", name, process::id()));
+            let _ = fs::remove_dir_all(&dir);
+            fs::create_dir_all(&dir).unwrap();
+            TempDir(dir)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn name(num: &str) -> String {
+        let path = number_file(Path::new("out"), num);
+        path.strip_prefix("out").unwrap().to_str().unwrap().to_string()
+    }
+
+    #[test]
+    fn numbers_have_files_of_their_own() {
+        assert_eq!(name("5624-82"), "82/5624-82.out");
+        assert_eq!(name("5624/82"), "82/5624_2f82.out");
+        assert_eq!(name("5624 82"), "82/5624_2082.out");
+        assert_eq!(name("5624_82"), "82/5624_5f82.out");
+        assert_eq!(name("-/-"), "_/-_2f-.out");
+        assert_eq!(name("5€"), "5/5_e2_82_ac.out");
+    }
+
+    #[test]
+    fn long_numbers_end_with_a_hash() {
+        let long = "5".repeat(300);
+        let longer = format!("{}6", long);
+        let (a, b) = (name(&long), name(&longer));
+        assert_ne!(a, b);
+        let a = a.strip_prefix("55/").unwrap();
+        assert_eq!(a.len(), MAX_NAME_LEN + ".out".len());
+        assert_eq!(a[MAX_NAME_LEN - 17..MAX_NAME_LEN - 16].to_string(), "~");
+        assert_eq!(name(&"5".repeat(MAX_NAME_LEN)), format!("55/{}.out", "5".repeat(MAX_NAME_LEN)));
+    }
+
+    #[test]
+    fn writes_the_solutions_of_each_number() {
+        let dir = TempDir::new("write");
+        let encoder = Encoder::new(words_from_bytes(b"mir\nMix\nTor\nTorf\nfort\n", &Mapping::default()).into_iter().collect());
+        // the same number in several threads, and numbers that used to share a file
+        let numbers: Vec<String> = ["5624/82", "5624 82", "4824", "4824", "4824", "4824"].map(String::from).into();
+        let stats = map_parallel(&numbers, Jobs::Count(4), |num| encode_to_file(&encoder, num, &dir.0)).unwrap();
+        assert_eq!(stats.iter().map(|stats| stats.solutions).collect::<Vec<_>>(), [2, 2, 3, 3, 3, 3]);
+        let read = |name: &str| fs::read_to_string(dir.0.join(name)).unwrap();
+        assert_eq!(read("82/5624_2f82.out"), "5624/82: mir Tor\n5624/82: Mix Tor\n");
+        assert_eq!(read("82/5624_2082.out"), "5624 82: mir Tor\n5624 82: Mix Tor\n");
+        assert_eq!(read("24/4824.out"), "4824: Tor 4\n4824: Torf\n4824: fort\n");
+        // no partial file is left
+        let files = fs::read_dir(dir.0.join("24")).unwrap().count() + fs::read_dir(dir.0.join("82")).unwrap().count();
+        assert_eq!(files, 3);
+    }
+}
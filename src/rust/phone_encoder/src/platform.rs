@@ -0,0 +1,93 @@
+//! What the crate does differently on each platform, in one place, so that the modules using
+//! it build for every target (Linux, the other Unix systems, Windows and WASI) without
+//! attributes of their own.
+//!
+//! Only Linux has direct I/O and preallocated files, io_uring (with the `uring` feature) and
+//! systemd, whose notifications other Unix systems can receive too. Elsewhere, these do
+//! nothing, and files are written as usual.
+
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+
+/// Opens files for direct I/O (`O_DIRECT`), bypassing the page cache, returning whether
+/// the platform has it.
+#[cfg(target_os = "linux")]
+pub fn open_direct(open: &mut OpenOptions) -> bool {
+    use std::os::unix::fs::OpenOptionsExt;
+    open.custom_flags(rustix::fs::OFlags::DIRECT.bits() as i32);
+    true
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn open_direct(_open: &mut OpenOptions) -> bool {
+    false
+}
+
+/// Writes a file opened by [`open_direct`] without direct I/O from then on.
+#[cfg(target_os = "linux")]
+pub fn end_direct(file: &File) -> io::Result<()> {
+    let flags = rustix::fs::fcntl_getfl(file)?;
+    rustix::fs::fcntl_setfl(file, flags - rustix::fs::OFlags::DIRECT)?;
+    Ok(())
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn end_direct(_file: &File) -> io::Result<()> {
+    Ok(())
+}
+
+/// Allocates `len` bytes on the disk for `file`, without changing its size.
+#[cfg(target_os = "linux")]
+pub fn preallocate(file: &File, len: u64) -> io::Result<()> {
+    rustix::fs::fallocate(file, rustix::fs::FallocateFlags::KEEP_SIZE, 0, len)?;
+    Ok(())
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn preallocate(_file: &File, _len: u64) -> io::Result<()> {
+    Ok(())
+}
+
+/// A writer of `file` with io_uring, if it is a regular file and io_uring can be used (it
+/// may be disabled, and Miri cannot run it).
+#[cfg(all(feature = "uring", target_os = "linux", not(miri)))]
+pub fn uring_writer(file: &File) -> Option<Box<dyn Write + Send>> {
+    if !file.metadata().is_ok_and(|metadata| metadata.is_file()) {
+        return None;
+    }
+    let writer = file.try_clone().and_then(crate::uring::UringWriter::new).ok()?;
+    Some(Box::new(writer))
+}
+
+#[cfg(not(all(feature = "uring", target_os = "linux", not(miri))))]
+pub fn uring_writer(_file: &File) -> Option<Box<dyn Write + Send>> {
+    None
+}
+
+/// Sends `state` to systemd (like `sd_notify`) if this process was started by it as a
+/// service of type `notify`, which gives the socket to send it to in `NOTIFY_SOCKET`.
+#[cfg(unix)]
+pub fn notify_service(state: &str) -> io::Result<()> {
+    use std::os::unix::net::UnixDatagram;
+
+    let Some(path) = std::env::var_os("NOTIFY_SOCKET") else { return Ok(()) };
+    let socket = UnixDatagram::unbound()?;
+    let path = path.to_string_lossy();
+    match path.strip_prefix('@') {
+        #[cfg(target_os = "linux")]
+        Some(name) => {
+            use std::os::linux::net::SocketAddrExt;
+            let address = std::os::unix::net::SocketAddr::from_abstract_name(name)?;
+            socket.send_to_addr(state.as_bytes(), &address)?;
+        }
+        _ => {
+            socket.send_to(state.as_bytes(), path.as_ref())?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+pub fn notify_service(_state: &str) -> io::Result<()> {
+    Ok(())
+}
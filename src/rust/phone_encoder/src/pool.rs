@@ -0,0 +1,180 @@
+//! Formatting solutions into buffers leased from a pool provided by the caller, so that an
+//! embedding application can hand them over to its own writes (e.g. io_uring or socket
+//! writes) without copying them, and reuse them once written.
+
+use std::io::{self, Write};
+use std::mem;
+use std::ops::Deref;
+use std::sync::{Arc, Mutex};
+
+/// A pool of buffers of (at least) the same size, shared by its clones.
+#[derive(Clone)]
+pub struct BufferPool {
+    inner: Arc<PoolInner>,
+}
+
+struct PoolInner {
+    buffer_size: usize,
+    /// the buffers given back, empty, reused before allocating new ones.
+    free: Mutex<Vec<Vec<u8>>>,
+}
+
+impl BufferPool {
+    /// A pool of buffers of `buffer_size` bytes, allocated as they are first leased.
+    pub fn new(buffer_size: usize) -> BufferPool {
+        BufferPool { inner: Arc::new(PoolInner { buffer_size: buffer_size.max(1), free: Mutex::new(Vec::new()) }) }
+    }
+
+    pub fn buffer_size(&self) -> usize {
+        self.inner.buffer_size
+    }
+
+    /// How many buffers were given back and not leased again yet.
+    pub fn free_buffers(&self) -> usize {
+        self.inner.free.lock().unwrap().len()
+    }
+
+    /// An empty buffer, reusing one that was given back if any.
+    pub fn lease(&self) -> Lease {
+        let buffer = self.inner.free.lock().unwrap().pop()
+            .unwrap_or_else(|| Vec::with_capacity(self.inner.buffer_size));
+        Lease { buffer, pool: self.clone() }
+    }
+}
+
+/// A buffer leased from a [`BufferPool`], given back to it when dropped.
+pub struct Lease {
+    buffer: Vec<u8>,
+    pool: BufferPool,
+}
+
+impl Lease {
+    /// Takes the buffer out of the pool for good.
+    pub fn into_vec(mut self) -> Vec<u8> {
+        mem::take(&mut self.buffer)
+    }
+}
+
+impl Deref for Lease {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        &self.buffer
+    }
+}
+
+impl AsRef<[u8]> for Lease {
+    fn as_ref(&self) -> &[u8] {
+        &self.buffer
+    }
+}
+
+impl Drop for Lease {
+    fn drop(&mut self) {
+        // buffers taken with into_vec, or grown past the size of the pool by a long line, are not kept
+        if self.buffer.capacity() == 0 || self.buffer.capacity() > 2 * self.pool.inner.buffer_size {
+            return;
+        }
+        let mut buffer = mem::take(&mut self.buffer);
+        buffer.clear();
+        self.pool.inner.free.lock().unwrap().push(buffer);
+    }
+}
+
+/// Writes into buffers leased from a pool, giving each buffer to `on_buffer` once full.
+///
+/// Buffers only hold whole lines: a buffer is given when a line ends past its size, with
+/// the lines that ended before, so a line longer than a buffer grows it.
+pub struct PoolWriter<F: FnMut(Lease) -> io::Result<()>> {
+    pool: BufferPool,
+    lease: Lease,
+    on_buffer: F,
+}
+
+impl<F: FnMut(Lease) -> io::Result<()>> PoolWriter<F> {
+    pub fn new(pool: &BufferPool, on_buffer: F) -> PoolWriter<F> {
+        PoolWriter { pool: pool.clone(), lease: pool.lease(), on_buffer }
+    }
+
+    /// Gives the last buffer, unless empty.
+    pub fn finish(mut self) -> io::Result<()> {
+        self.flush()
+    }
+}
+
+impl<F: FnMut(Lease) -> io::Result<()>> Write for PoolWriter<F> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let buffer = &mut self.lease.buffer;
+        buffer.extend_from_slice(buf);
+        if buffer.len() >= self.pool.buffer_size() {
+            if let Some(end) = buffer.iter().rposition(|&byte| byte == b'\n') {
+                // the start of the last line, if not complete, goes to the next buffer
+                let mut next = self.pool.lease();
+                next.buffer.extend_from_slice(&buffer[end + 1..]);
+                buffer.truncate(end + 1);
+                (self.on_buffer)(mem::replace(&mut self.lease, next))?;
+            }
+        }
+        Ok(buf.len())
+    }
+
+    /// Gives the current buffer, unless empty, even if it ends with an incomplete line.
+    fn flush(&mut self) -> io::Result<()> {
+        if self.lease.is_empty() {
+            return Ok(());
+        }
+        let next = self.pool.lease();
+        (self.on_buffer)(mem::replace(&mut self.lease, next))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::search::words_from_bytes;
+    use crate::{Encoder, Mapping};
+
+    use super::*;
+
+    #[test]
+    fn dropped_leases_give_their_buffer_back_cleared() {
+        let pool = BufferPool::new(16);
+        let mut lease = pool.lease();
+        lease.buffer.extend_from_slice(b"5624-82: mir Tor\n");
+        let allocation = lease.buffer.as_ptr();
+        drop(lease);
+        assert_eq!(pool.free_buffers(), 1);
+        let lease = pool.lease();
+        assert_eq!(pool.free_buffers(), 0);
+        assert!(lease.is_empty());
+        assert_eq!(lease.buffer.as_ptr(), allocation);
+
+        // nor the buffers taken, nor those grown too large, are given back
+        assert!(lease.into_vec().capacity() >= pool.buffer_size());
+        assert_eq!(pool.free_buffers(), 0);
+        let mut lease = pool.lease();
+        lease.buffer.extend_from_slice(&[b'5'; 100]);
+        drop(lease);
+        assert_eq!(pool.free_buffers(), 0);
+    }
+
+    #[test]
+    fn leased_buffers_hold_the_output_of_the_encoder_in_whole_lines() {
+        let encoder = Encoder::new(words_from_bytes(b"a\nM\nam\nma\nmam\n", &Mapping::default()).into_iter().collect());
+        let mut expected = Vec::new();
+        let expected_stats = encoder.encode_to("5555555", &mut expected).unwrap();
+        let pool = BufferPool::new(100);
+        let mut buffers = Vec::new();
+        let stats = encoder.encode_leased("5555555", &pool, &mut |lease| {
+            buffers.push(lease);
+            Ok(())
+        }).unwrap();
+        assert_eq!(stats.solutions, expected_stats.solutions);
+        assert!(buffers.len() > 10);
+        assert!(buffers.iter().all(|buffer| buffer.ends_with(b"\n") && buffer.len() < 2 * pool.buffer_size()));
+        assert_eq!(buffers.iter().map(Lease::as_ref).collect::<Vec<_>>().concat(), expected);
+        // given back once written
+        let count = buffers.len();
+        drop(buffers);
+        assert!(pool.free_buffers() >= count);
+    }
+}
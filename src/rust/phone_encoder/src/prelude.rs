@@ -0,0 +1,26 @@
+//! The items most programs using the crate need, to be imported all at once:
+//!
+//! ```
+//! use phone_encoder::prelude::*;
+//! ```
+//!
+//! Only items covered by the stability of the crate are exported here, so that a minor
+//! release adds to the prelude without breaking the programs importing it. Without the `std`
+//! feature, only the search, mapping and filter items are.
+
+pub use crate::filter::{Rejection, Rules, SolutionFilter};
+pub use crate::mapping::Mapping;
+pub use crate::number::{normalize_number, DigitString, InvalidNumber};
+pub use crate::search::{
+    segments, Algorithm, EncodeStats, Limits, SearchGuard, SearchStrategy, Segment, Word, WordOrDigit,
+};
+pub use crate::verify::{explain, verify_solution, Explanation, VerifyError};
+
+#[cfg(feature = "std")]
+pub use crate::dict::{load_dict, load_dicts, DictOptions, Dictionary, WordsFile};
+#[cfg(feature = "std")]
+pub use crate::encoder::{EncodeOptions, Encoder};
+#[cfg(feature = "std")]
+pub use crate::error::{EncodeError, FileContext, FileRole};
+#[cfg(feature = "std")]
+pub use crate::format::{Format, OutputFormat, Solution};
@@ -0,0 +1,176 @@
+//! Keeping the phone numbers, which are personal data, out of the outputs and the audit logs
+//! (`--redact`): each number is written as a hash of its digits, or only its first digits.
+//!
+//! Only the numbers are redacted: the words of the solutions still encode them.
+
+use std::borrow::Cow;
+
+use crate::sha256::{hex, sha256};
+
+/// How many digits [`Redaction::Truncate`] leaves.
+pub const TRUNCATED_DIGITS: usize = 3;
+
+/// How many hexadecimal digits of the hash [`Redaction::Hash`] writes.
+const HASH_LEN: usize = 16;
+
+/// How phone numbers are redacted.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Redaction {
+    /// `#` and the start of the SHA-256 hash of the digits, the same for a number however it
+    /// is written (`5624-82` and `562/482`), so that the numbers can still be told apart.
+    ///
+    /// As there are few numbers of each length, a number can be found from its hash by
+    /// hashing all of them: this keeps numbers from being read, not from being recovered.
+    Hash,
+    /// the first [`TRUNCATED_DIGITS`] digits, the others replaced with `x` (e.g. `562x-xx`).
+    Truncate,
+}
+
+impl Redaction {
+    pub fn parse(name: &str) -> Option<Redaction> {
+        match name {
+            "hash" => Some(Redaction::Hash),
+            "truncate" => Some(Redaction::Truncate),
+            _ => None,
+        }
+    }
+
+    /// The number as written once redacted.
+    pub fn apply(self, number: &str) -> String {
+        match self {
+            Redaction::Hash => {
+                let digits: String = number.chars().filter(char::is_ascii_digit).collect();
+                let mut hash = hex(&sha256(digits.as_bytes()));
+                hash.truncate(HASH_LEN);
+                format!("#{}", hash)
+            }
+            Redaction::Truncate => {
+                let mut digits = 0;
+                number.chars()
+                    .map(|c| match c {
+                        '0'..='9' if digits < TRUNCATED_DIGITS => {
+                            digits += 1;
+                            c
+                        }
+                        '0'..='9' => 'x',
+                        c => c,
+                    })
+                    .collect()
+            }
+        }
+    }
+}
+
+/// The number as written with the redaction, if any.
+pub fn redacted(redaction: Option<Redaction>, number: &str) -> Cow<'_, str> {
+    match redaction {
+        Some(redaction) => Cow::Owned(redaction.apply(number)),
+        None => Cow::Borrowed(number),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+
+    use crate::search::words_from_bytes;
+    use crate::{EncodeOptions, Encoder, Format, Mapping};
+
+    use super::*;
+
+    const NUMBERS: [&str; 3] = ["5624-82", "10/783--5", "4824"];
+
+    /// The digits of a number, which must not be written either.
+    fn digits(number: &str) -> String {
+        number.chars().filter(char::is_ascii_digit).collect()
+    }
+
+    #[test]
+    fn redacts_numbers() {
+        assert_eq!(Redaction::parse("hash"), Some(Redaction::Hash));
+        assert_eq!(Redaction::parse("truncate"), Some(Redaction::Truncate));
+        assert_eq!(Redaction::parse("none"), None);
+
+        let hash = Redaction::Hash.apply("5624-82");
+        assert_eq!(hash, format!("#{}", &hex(&sha256(b"562482"))[..HASH_LEN]));
+        assert_eq!(Redaction::Hash.apply("562/482"), hash);
+        assert_ne!(Redaction::Hash.apply("5624-83"), hash);
+
+        assert_eq!(Redaction::Truncate.apply("5624-82"), "562x-xx");
+        assert_eq!(Redaction::Truncate.apply("0721/608-4067"), "072x/xxx-xxxx");
+        assert_eq!(Redaction::Truncate.apply("1-2"), "1-2");
+        assert_eq!(redacted(None, "5624-82"), "5624-82");
+    }
+
+    #[test]
+    fn solutions_and_errors_do_not_show_the_numbers() {
+        let dict = words_from_bytes(b"mir\nMix\nTor\nTorf\nfort\nje\nneu\no\"d\nbo\"s\nBo\"\nda\n", &Mapping::default());
+        // json only with the formats-json feature
+        let formats: Vec<_> = ["plain", "csv", "tsv", "grouped", "json"].into_iter().filter_map(Format::parse).collect();
+        for redaction in [Redaction::Hash, Redaction::Truncate] {
+            for format in formats.iter().copied() {
+                let options = EncodeOptions { redact: Some(redaction), format, ..EncodeOptions::default() };
+                let encoder = Encoder::new(dict.clone().into_iter().collect()).with_options(options);
+                let mut out = Vec::new();
+                for number in NUMBERS {
+                    assert!(encoder.encode_to(number, &mut out).unwrap().solutions > 0);
+                }
+                let out = String::from_utf8(out).unwrap();
+                for number in NUMBERS {
+                    assert!(out.contains(&redaction.apply(number)), "{:?} {:?}", redaction, format);
+                    assert!(!out.contains(number) && !out.contains(&digits(number)), "{:?} {:?}: {}", redaction, format, out);
+                }
+                let error = encoder.encode_to("5624-82a", &mut io::sink()).unwrap_err().to_string();
+                assert!(!error.contains("5624") && error.contains(&redaction.apply("5624-82a")), "{}", error);
+            }
+        }
+    }
+
+    #[cfg(feature = "server")]
+    #[test]
+    fn the_audit_log_does_not_show_the_numbers() {
+        use std::io::Write;
+        use std::sync::{Arc, Mutex};
+        use std::time::Duration;
+
+        use crate::audit::{AuditEntry, AuditLog};
+
+        #[derive(Clone, Default)]
+        struct Shared(Arc<Mutex<Vec<u8>>>);
+
+        impl Write for Shared {
+            fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+                self.0.lock().unwrap().write(buf)
+            }
+
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+
+        for redaction in [Redaction::Hash, Redaction::Truncate] {
+            let out = Shared::default();
+            let log = AuditLog::new(out.clone(), Some(redaction));
+            for number in NUMBERS {
+                let entry = AuditEntry {
+                    server: "resp",
+                    client: Some("127.0.0.1:51234"),
+                    command: "ENCODE",
+                    number,
+                    outcome: "ok",
+                    solutions: 2,
+                    cached: false,
+                    duration: Duration::from_millis(1),
+                };
+                log.record(&entry).unwrap();
+            }
+            let out = String::from_utf8(out.0.lock().unwrap().clone()).unwrap();
+            assert_eq!(out.lines().count(), NUMBERS.len());
+            for number in NUMBERS {
+                assert!(out.contains(&format!("\"number\":\"{}\"", redaction.apply(number))), "{}", out);
+                assert!(!out.contains(number) && !out.contains(&digits(number)), "{}", out);
+            }
+        }
+    }
+}
@@ -0,0 +1,207 @@
+//! Dictionaries shared by the threads of an embedding application, e.g. through the JNI
+//! bindings: each distinct words file is loaded once, however many callers load it, and
+//! stays loaded until all of them unloaded it.
+
+use std::collections::hash_map::{DefaultHasher, Entry};
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::path::Path;
+use std::sync::{Arc, Mutex, OnceLock};
+
+use crate::dict::{DictOptions, WordsFile, EMBEDDED_PATH, EMBEDDED_WORDS};
+use crate::encoder::Encoder;
+use crate::error::EncodeError;
+
+/// Identifies a dictionary of a [`DictionaryRegistry`] by the contents of its words file,
+/// so that it can be handed to other languages as a number.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct DictionaryId(u64);
+
+impl DictionaryId {
+    pub fn from_u64(id: u64) -> DictionaryId {
+        DictionaryId(id)
+    }
+
+    pub fn as_u64(self) -> u64 {
+        self.0
+    }
+}
+
+/// The dictionaries loaded with the same [`DictOptions`], each shared by everyone who loaded
+/// its words file, as an immutable [`Encoder`] (with the default encoding options).
+pub struct DictionaryRegistry {
+    options: DictOptions,
+    loaded: Mutex<HashMap<DictionaryId, Loaded>>,
+}
+
+struct Loaded {
+    encoder: Arc<Encoder>,
+    /// how many times it was loaded and not unloaded yet.
+    references: usize,
+}
+
+impl DictionaryRegistry {
+    pub fn new(options: DictOptions) -> DictionaryRegistry {
+        DictionaryRegistry { options, loaded: Mutex::new(HashMap::new()) }
+    }
+
+    /// The registry of the process, with the default dictionary options.
+    pub fn global() -> &'static DictionaryRegistry {
+        static GLOBAL: OnceLock<DictionaryRegistry> = OnceLock::new();
+        GLOBAL.get_or_init(|| DictionaryRegistry::new(DictOptions::default()))
+    }
+
+    /// Loads the words file at `path`, unless a file with the same contents is loaded already,
+    /// which is then shared (with the sources of its words).
+    ///
+    /// Each load must be matched by an [`unload`](DictionaryRegistry::unload).
+    pub fn load<P: AsRef<Path>>(&self, path: P) -> Result<DictionaryId, EncodeError> {
+        let mut file = WordsFile::new(path.as_ref());
+        file.preload()?;
+        let mut hasher = DefaultHasher::new();
+        match &file.contents {
+            Some(contents) => contents.hash(&mut hasher),
+            None => (EMBEDDED_PATH, EMBEDDED_WORDS).hash(&mut hasher),
+        }
+        let id = DictionaryId(hasher.finish());
+        if self.acquire(id) {
+            return Ok(id);
+        }
+        // loaded without holding the lock, so that other dictionaries stay usable meanwhile
+        let encoder = Arc::new(Encoder::load_all(&[file], &self.options)?);
+        match self.lock().entry(id) {
+            // loaded by another thread meanwhile
+            Entry::Occupied(mut loaded) => loaded.get_mut().references += 1,
+            Entry::Vacant(vacant) => {
+                vacant.insert(Loaded { encoder, references: 1 });
+            }
+        }
+        Ok(id)
+    }
+
+    /// The encoder of a loaded dictionary, which stays usable after the dictionary is unloaded,
+    /// until dropped.
+    pub fn get(&self, id: DictionaryId) -> Option<Arc<Encoder>> {
+        self.lock().get(&id).map(|loaded| loaded.encoder.clone())
+    }
+
+    /// Unloads a dictionary once for each time it was loaded, returning `false` if it was
+    /// not loaded.
+    pub fn unload(&self, id: DictionaryId) -> bool {
+        let mut loaded = self.lock();
+        let Some(dictionary) = loaded.get_mut(&id) else { return false };
+        dictionary.references -= 1;
+        if dictionary.references == 0 {
+            loaded.remove(&id);
+        }
+        true
+    }
+
+    /// How many dictionaries are loaded.
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Counts another load of the dictionary, if it is loaded.
+    fn acquire(&self, id: DictionaryId) -> bool {
+        match self.lock().get_mut(&id) {
+            Some(loaded) => {
+                loaded.references += 1;
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<DictionaryId, Loaded>> {
+        // the map is consistent even if a thread panicked while holding the lock
+        self.loaded.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use std::{env, fs, process, thread};
+
+    use super::*;
+
+    /// A directory of its own for the files of a test, deleted when dropped.
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> TempDir {
+            let dir = env::temp_dir().join(format!("phone_encoder-registry-{}-{}", name, process::id()));
+            let _ = fs::remove_dir_all(&dir);
+            fs::create_dir_all(&dir).unwrap();
+            TempDir(dir)
+        }
+
+        fn file(&self, name: &str, contents: &str) -> PathBuf {
+            let path = self.0.join(name);
+            fs::write(&path, contents).unwrap();
+            path
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn shares_the_dictionaries_of_the_same_contents() {
+        let dir = TempDir::new("share");
+        let registry = DictionaryRegistry::new(DictOptions::default());
+        let a = registry.load(dir.file("a.txt", "mir\nTor\n")).unwrap();
+        let b = registry.load(dir.file("b.txt", "mir\nTor\n")).unwrap();
+        let c = registry.load(dir.file("c.txt", "mir\nTorf\n")).unwrap();
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+        assert_eq!(registry.len(), 2);
+        assert!(Arc::ptr_eq(&registry.get(a).unwrap(), &registry.get(b).unwrap()));
+        assert_eq!(DictionaryId::from_u64(a.as_u64()), a);
+    }
+
+    #[test]
+    fn evicts_a_dictionary_once_unloaded_as_many_times_as_loaded() {
+        let dir = TempDir::new("release");
+        let registry = DictionaryRegistry::new(DictOptions::default());
+        let path = dir.file("words.txt", "mir\nTor\n");
+        let id = registry.load(&path).unwrap();
+        assert_eq!(registry.load(&path).unwrap(), id);
+        let encoder = registry.get(id).unwrap();
+        assert!(registry.unload(id));
+        assert!(registry.get(id).is_some());
+        assert!(registry.unload(id));
+        assert!(registry.get(id).is_none() && registry.is_empty());
+        assert!(!registry.unload(id));
+        // the encoder still works until dropped
+        assert!(encoder.is_encodable("5624-82"));
+        assert_eq!(Arc::strong_count(&encoder), 1);
+    }
+
+    #[test]
+    fn counts_the_loads_of_threads_loading_the_same_file() {
+        let dir = TempDir::new("threads");
+        let registry = DictionaryRegistry::new(DictOptions::default());
+        let path = dir.file("words.txt", "mir\nTor\n");
+        let ids: Vec<_> = thread::scope(|scope| {
+            let handles: Vec<_> = (0..8).map(|_| scope.spawn(|| registry.load(&path).unwrap())).collect();
+            handles.into_iter().map(|handle| handle.join().unwrap()).collect()
+        });
+        assert!(ids.iter().all(|id| *id == ids[0]));
+        assert_eq!(registry.len(), 1);
+        for id in &ids[1..] {
+            assert!(registry.unload(*id));
+        }
+        assert_eq!(registry.len(), 1);
+        assert!(registry.unload(ids[0]));
+        assert!(registry.is_empty());
+    }
+}
@@ -0,0 +1,168 @@
+//! The `report` command: turning the statistics written by `--stats-json` into a single HTML
+//! page, with its styles inline so that it can be mailed or attached to a CI run as it is.
+
+use std::fmt::Write as _;
+use std::fs;
+use std::io::{self, Write};
+
+use phone_encoder::search::SolutionCounts;
+use phone_encoder::{FileContext, FileRole};
+use serde_json::Value;
+
+const STYLE: &str = "
+body { font-family: sans-serif; margin: 2em auto; max-width: 60em; color: #222; }
+h1 { font-size: 1.5em; }
+h2 { font-size: 1.2em; margin-top: 2em; border-bottom: 1px solid #ccc; }
+table { border-collapse: collapse; }
+th, td { padding: 0.2em 0.8em; text-align: left; }
+td.n { text-align: right; font-variant-numeric: tabular-nums; }
+tr:nth-child(even) { background: #f4f4f4; }
+.bar { background: #4a7ebb; height: 1em; min-width: 1px; }
+.bars td:last-child { width: 30em; }
+";
+
+/// Writes the report of the stats file to `output`, or stdout.
+pub fn report(stats_file: &str, output: Option<&str>) -> io::Result<()> {
+    let contents = fs::read_to_string(stats_file).in_file(FileRole::Stats, stats_file)?;
+    let lines = contents.lines().filter(|line| !line.trim().is_empty())
+        .enumerate()
+        .map(|(index, line)| serde_json::from_str::<Value>(line)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("line {}: {}", index + 1, e))))
+        .collect::<io::Result<Vec<_>>>()
+        .in_file(FileRole::Stats, stats_file)?;
+    // the line of the whole run comes after those of the files
+    let (run, files) = lines.split_last()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "no statistics"))
+        .in_file(FileRole::Stats, stats_file)?;
+    let html = render(stats_file, run, files);
+    match output {
+        Some(file) => Ok(fs::write(file, html).in_file(FileRole::Output, file)?),
+        None => io::stdout().lock().write_all(html.as_bytes()),
+    }
+}
+
+/// The page, with the statistics of the `run` and of each of its `files` (if encoded in sections).
+fn render(title: &str, run: &Value, files: &[Value]) -> String {
+    let mut html = String::new();
+    let title = escape(title);
+    let _ = write!(html, "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n\
+                          <title>phone_encoder report: {}</title>\n<style>{}</style>\n</head>\n<body>\n\
+                          <h1>phone_encoder report: {}</h1>\n", title, STYLE, title);
+    summary(&mut html, run);
+    histogram(&mut html, run);
+    timings(&mut html, run);
+    top_numbers(&mut html, run);
+    if !files.is_empty() {
+        per_file(&mut html, files);
+    }
+    html.push_str("</body>\n</html>\n");
+    html
+}
+
+fn summary(html: &mut String, run: &Value) {
+    let numbers = count(run, "numbers");
+    let total_ms = millis(run, "total");
+    let mut rows = vec![
+        ("numbers", numbers.to_string()),
+        ("solutions", count(run, "solutions").to_string()),
+        ("rejected solutions", count(run, "rejected").to_string()),
+        ("numbers without digits", count(run, "empty_numbers").to_string()),
+        ("partial solutions extended", count(run, "nodes").to_string()),
+        ("dictionary lookups", count(run, "lookups").to_string()),
+        ("candidates checked", count(run, "candidates").to_string()),
+        ("total time", format!("{:.3} ms", total_ms)),
+    ];
+    if total_ms > 0.0 {
+        rows.push(("numbers per second", format!("{:.0}", numbers as f64 * 1000.0 / total_ms)));
+    }
+    html.push_str("<h2>Summary</h2>\n<table>\n");
+    for (name, value) in rows {
+        let _ = writeln!(html, "<tr><th>{}</th><td class=\"n\">{}</td></tr>", name, value);
+    }
+    html.push_str("</table>\n");
+}
+
+/// How many numbers had how many solutions, as bars.
+fn histogram(html: &mut String, run: &Value) {
+    let counts: Vec<u64> = SolutionCounts::LABELS.iter()
+        .map(|label| run["solution_counts"][label].as_u64().unwrap_or(0))
+        .collect();
+    let numbers: u64 = counts.iter().sum();
+    let max = counts.iter().copied().max().unwrap_or(0);
+    html.push_str("<h2>Numbers by count of solutions</h2>\n<table class=\"bars\">\n\
+                   <tr><th>solutions</th><th>numbers</th><th>share</th><th></th></tr>\n");
+    for (label, count) in SolutionCounts::LABELS.iter().zip(counts) {
+        bar_row(html, label, &count.to_string(), share(count as f64, numbers as f64), share(count as f64, max as f64));
+    }
+    html.push_str("</table>\n");
+}
+
+/// The time spent in each phase, as bars.
+fn timings(html: &mut String, run: &Value) {
+    let total = millis(run, "total");
+    html.push_str("<h2>Timings</h2>\n<table class=\"bars\">\n<tr><th>phase</th><th>time</th><th>share</th><th></th></tr>\n");
+    for (phase, name) in [("load", "dictionary load"), ("read", "read"), ("search", "search"), ("write", "format/write")] {
+        let time = millis(run, phase);
+        let share = share(time, total);
+        bar_row(html, name, &format!("{:.3} ms", time), share, share);
+    }
+    let _ = writeln!(html, "<tr><th>total</th><td class=\"n\">{:.3} ms</td><td></td><td></td></tr>", total);
+    html.push_str("</table>\n");
+}
+
+fn top_numbers(html: &mut String, run: &Value) {
+    let Some(top) = run["top_numbers"].as_array().filter(|top| !top.is_empty()) else { return };
+    html.push_str("<h2>Top numbers by encodings</h2>\n<table>\n<tr><th>number</th><th>solutions</th></tr>\n");
+    for entry in top {
+        let _ = writeln!(html, "<tr><td>{}</td><td class=\"n\">{}</td></tr>",
+                         escape(entry["number"].as_str().unwrap_or_default()), count(entry, "solutions"));
+    }
+    html.push_str("</table>\n");
+}
+
+fn per_file(html: &mut String, files: &[Value]) {
+    html.push_str("<h2>Files</h2>\n<table>\n\
+                   <tr><th>file</th><th>numbers</th><th>solutions</th><th>search</th><th>total</th></tr>\n");
+    for file in files {
+        let _ = writeln!(html, "<tr><td>{}</td><td class=\"n\">{}</td><td class=\"n\">{}</td>\
+                                <td class=\"n\">{:.3} ms</td><td class=\"n\">{:.3} ms</td></tr>",
+                         escape(file["file"].as_str().unwrap_or_default()), count(file, "numbers"),
+                         count(file, "solutions"), millis(file, "search"), millis(file, "total"));
+    }
+    html.push_str("</table>\n");
+}
+
+/// A row of a table of bars, `width` being the share of the widest bar.
+fn bar_row(html: &mut String, label: &str, value: &str, percent: f64, width: f64) {
+    let _ = writeln!(html, "<tr><th>{}</th><td class=\"n\">{}</td><td class=\"n\">{:.1}%</td>\
+                            <td><div class=\"bar\" style=\"width: {:.1}%\"></div></td></tr>",
+                     escape(label), value, percent, width);
+}
+
+/// `part` as a percentage of `whole`.
+fn share(part: f64, whole: f64) -> f64 {
+    if whole > 0.0 { 100.0 * part / whole } else { 0.0 }
+}
+
+fn count(stats: &Value, name: &str) -> u64 {
+    stats[name].as_u64().unwrap_or(0)
+}
+
+/// A time of the `timings` of the statistics, in milliseconds.
+fn millis(stats: &Value, phase: &str) -> f64 {
+    stats["timings"][phase].as_f64().unwrap_or(0.0)
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
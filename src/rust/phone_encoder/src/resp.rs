@@ -0,0 +1,342 @@
+//! A server speaking a tiny subset of the Redis protocol
+//! ([RESP](https://redis.io/docs/latest/develop/reference/protocol-spec/)), so that the Redis
+//! client library of any language can query an encoder kept in memory, with commands like
+//! `redis-cli -p 6380 ENCODE 5624-82`.
+//!
+//! Commands (case-insensitive), as arrays of bulk strings or as inline commands:
+//!
+//! * `ENCODE <number>` - an array of the solutions, e.g. `["mir Tor", "Mix Tor"]`.
+//! * `COUNT <number>` - the number of solutions, as an integer.
+//! * `EXPLAIN <number> <part>...` - whether the parts (a single digit is a digit, anything else
+//!   a word) are a solution, or every rule they break, e.g. `part 1 is a digit where a word fits`.
+//! * `PING [<message>]` - `PONG`, or the message, as most clients check the connection with it.
+//! * `INFO [<section>]` - the hits and misses of the [`SolutionCache`], like the `INFO` of Redis.
+//! * `QUIT` - `OK`, then the connection is closed.
+//!
+//! Other commands, and invalid numbers, get an error reply, as well as the requests beyond
+//! the [`ServerLimits`]: `BUSY` if too many are waiting already, `TIMEOUT` if they time out.
+//!
+//! With a [`SolutionCache`], the solutions of the numbers requested again are served from it,
+//! without waiting for their turn. With an [`AuditLog`], each `ENCODE`, `COUNT` and `EXPLAIN`
+//! is written to it.
+
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Write};
+use std::net::TcpListener;
+use std::sync::Arc;
+use std::thread;
+use std::time::Instant;
+
+use crate::admission::{Admission, Refused, ServerLimits};
+use crate::audit::{AuditEntry, AuditLog};
+use crate::cache::{SolutionCache, Solutions};
+use crate::encoder::{EncodeStats, Encoder, WordOrDigit};
+
+/// The address the server listens on by default (next to the port of Redis itself).
+pub const DEFAULT_ADDRESS: &str = "127.0.0.1:6380";
+
+/// The longest bulk string or array accepted, so that a client cannot have the server
+/// allocate any amount of memory.
+const MAX_LEN: usize = 64 * 1024;
+
+/// Serves the connections accepted by `listener`, each in its own thread, until accepting
+/// a connection fails.
+pub fn serve(
+    encoder: Arc<Encoder>,
+    listener: TcpListener,
+    limits: ServerLimits,
+    cache: Option<Arc<SolutionCache>>,
+    audit: Option<Arc<AuditLog>>,
+) -> io::Result<()> {
+    let admission = Admission::new(limits);
+    for stream in listener.incoming() {
+        let stream = stream?;
+        let encoder = encoder.clone();
+        let admission = admission.clone();
+        let cache = cache.clone();
+        let audit = audit.clone();
+        thread::spawn(move || {
+            let client = stream.peer_addr()?.to_string();
+            let input = BufReader::new(stream.try_clone()?);
+            handle(&encoder, &admission, cache.as_deref(), audit.as_deref(), &client, input, BufWriter::new(stream))
+        });
+    }
+    Ok(())
+}
+
+/// Replies to the commands read from `input` until it is closed, or the client quits or
+/// breaks the protocol, encoding the numbers when admitted (or taking their solutions from
+/// the `cache`), and writing them to the `audit` log as requested by the `client`.
+pub fn handle<R: BufRead, W: Write>(
+    encoder: &Encoder,
+    admission: &Admission,
+    cache: Option<&SolutionCache>,
+    audit: Option<&AuditLog>,
+    client: &str,
+    mut input: R,
+    mut output: W,
+) -> io::Result<()> {
+    loop {
+        let command = match read_command(&mut input) {
+            Ok(Some(command)) => command,
+            Ok(None) => return Ok(()),
+            Err(e) if e.kind() == io::ErrorKind::InvalidData => {
+                write_error(&mut output, "ERR", &format!("Protocol error: {}", e))?;
+                return output.flush();
+            }
+            Err(e) => return Err(e),
+        };
+        let Some((name, args)) = command.split_first() else { continue };
+        match (name.to_ascii_uppercase().as_str(), args) {
+            ("ENCODE", [number]) => {
+                let start_time = Instant::now();
+                let result = solutions(encoder, admission, cache, number);
+                let outcome = result.as_ref().map(|(solutions, cached)| (solutions.len(), *cached));
+                record(audit, client, "ENCODE", number, outcome.map_err(|(code, _)| *code), start_time)?;
+                match result {
+                    Ok((solutions, _)) => {
+                        write!(output, "*{}\r\n", solutions.len())?;
+                        for solution in solutions.iter() {
+                            write_bulk(&mut output, &solution.join(" "))?;
+                        }
+                    }
+                    Err((code, message)) => write_error(&mut output, code, &message)?,
+                }
+            }
+            ("COUNT", [number]) => {
+                let start_time = Instant::now();
+                let count = match cache {
+                    Some(_) => solutions(encoder, admission, cache, number).map(|(solutions, cached)| (solutions.len(), cached)),
+                    None => encode(encoder, admission, number, &mut |_| Ok(())).map(|stats| (stats.solutions, false)),
+                };
+                record(audit, client, "COUNT", number, count.as_ref().copied().map_err(|(code, _)| *code), start_time)?;
+                match count {
+                    Ok((count, _)) => write!(output, ":{}\r\n", count)?,
+                    Err((code, message)) => write_error(&mut output, code, &message)?,
+                }
+            }
+            ("EXPLAIN", [number, parts @ ..]) if !parts.is_empty() => {
+                let start_time = Instant::now();
+                let parts: Vec<WordOrDigit> = parts.iter().map(|part| WordOrDigit::parse(part)).collect();
+                let explanation = encoder.explain(number, &parts);
+                record(audit, client, "EXPLAIN", number, Ok((explanation.is_solution() as usize, false)), start_time)?;
+                write_bulk(&mut output, &explanation.to_string())?;
+            }
+            ("PING", []) => output.write_all(b"+PONG\r\n")?,
+            ("PING", [message]) => write_bulk(&mut output, message)?,
+            ("INFO", [] | [_]) => write_bulk(&mut output, &info(cache))?,
+            ("QUIT", []) => {
+                output.write_all(b"+OK\r\n")?;
+                return output.flush();
+            }
+            ("ENCODE" | "COUNT" | "EXPLAIN" | "PING" | "INFO" | "QUIT", _) => {
+                write_error(&mut output, "ERR", &format!("wrong number of arguments for '{}' command", name.to_ascii_lowercase()))?;
+            }
+            _ => write_error(&mut output, "ERR", &format!("unknown command '{}'", name))?,
+        }
+        output.flush()?;
+    }
+}
+
+/// The solutions of a number, from the cache if there, or else encoded once admitted (and
+/// then cached unless the request timed out), with whether they came from the cache.
+fn solutions(
+    encoder: &Encoder,
+    admission: &Admission,
+    cache: Option<&SolutionCache>,
+    number: &str,
+) -> Result<(Solutions, bool), (&'static str, String)> {
+    // invalid numbers are not cached, but get their error from the encoder
+    let key = cache.and_then(|cache| Some((cache, encoder.cache_key(number).ok()?)));
+    if let Some(solutions) = key.as_ref().and_then(|(cache, key)| cache.get(key)) {
+        return Ok((solutions, true));
+    }
+    let mut solutions = Vec::new();
+    encode(encoder, admission, number, &mut |parts| {
+        solutions.push(parts.iter().map(ToString::to_string).collect());
+        Ok(())
+    })?;
+    let solutions = Arc::new(solutions);
+    if let Some((cache, key)) = key {
+        cache.insert(key, solutions.clone());
+    }
+    Ok((solutions, false))
+}
+
+/// Writes a request to the audit log, if any, with the number of solutions and whether they
+/// came from the cache, or else the code of its error.
+fn record(
+    audit: Option<&AuditLog>,
+    client: &str,
+    command: &str,
+    number: &str,
+    outcome: Result<(usize, bool), &str>,
+    start_time: Instant,
+) -> io::Result<()> {
+    let Some(audit) = audit else { return Ok(()) };
+    let (solutions, cached) = outcome.unwrap_or_default();
+    audit.record(&AuditEntry {
+        server: "resp",
+        client: Some(client),
+        command,
+        number,
+        outcome: outcome.map_or_else(|code| code, |_| "ok"),
+        solutions,
+        cached,
+        duration: start_time.elapsed(),
+    })
+}
+
+/// The reply to `INFO`, in the format of Redis: a section of `field:value` lines.
+fn info(cache: Option<&SolutionCache>) -> String {
+    let mut info = String::from("# Cache\r\n");
+    match cache {
+        Some(cache) => {
+            let stats = cache.stats();
+            info += &format!(
+                "cache_enabled:1\r\ncache_capacity:{}\r\ncache_entries:{}\r\ncache_hits:{}\r\ncache_misses:{}\r\n",
+                cache.options().capacity, stats.entries, stats.hits, stats.misses,
+            );
+        }
+        None => info += "cache_enabled:0\r\n",
+    }
+    info
+}
+
+/// Encodes a number once admitted, returning the code and message of the error reply if it
+/// is not admitted, invalid, or not encoded before the deadline of the request.
+fn encode(
+    encoder: &Encoder,
+    admission: &Admission,
+    number: &str,
+    on_solution: &mut dyn FnMut(&[WordOrDigit]) -> io::Result<()>,
+) -> Result<EncodeStats, (&'static str, String)> {
+    let deadline = admission.deadline();
+    let _permit = admission.admit(deadline).map_err(|refused| match refused {
+        Refused::Saturated => ("BUSY", "too many requests, try again later".to_string()),
+        Refused::TimedOut => ("TIMEOUT", "timed out waiting for other requests".to_string()),
+    })?;
+    let stats = encoder.for_each_solution_until(number, deadline, on_solution).map_err(|e| ("ERR", e.to_string()))?;
+    if stats.interrupted {
+        return Err(("TIMEOUT", format!("timed out after {} solutions", stats.solutions)));
+    }
+    Ok(stats)
+}
+
+/// Reads a command, as an array of bulk strings, or else an inline command (words separated
+/// by spaces, on a line), returning `None` at the end of the input.
+fn read_command<R: BufRead>(input: &mut R) -> io::Result<Option<Vec<String>>> {
+    let Some(line) = read_line(input)? else { return Ok(None) };
+    let Some(count) = line.strip_prefix('*') else {
+        return Ok(Some(line.split_whitespace().map(str::to_string).collect()));
+    };
+    let count = parse_len(count)?;
+    let mut command = Vec::with_capacity(count);
+    for _ in 0..count {
+        let header = read_line(input)?.ok_or_else(|| invalid("unexpected end of the command"))?;
+        let len = parse_len(header.strip_prefix('$').ok_or_else(|| invalid("expected '$'"))?)?;
+        let mut bulk = vec![0; len + 2];
+        input.read_exact(&mut bulk)?;
+        if !bulk.ends_with(b"\r\n") {
+            return Err(invalid("expected CRLF after a bulk string"));
+        }
+        bulk.truncate(len);
+        command.push(String::from_utf8(bulk).map_err(|_| invalid("invalid UTF-8"))?);
+    }
+    Ok(Some(command))
+}
+
+/// Reads a line ending with CRLF (or LF, like inline commands typed into telnet).
+fn read_line<R: BufRead>(input: &mut R) -> io::Result<Option<String>> {
+    let mut line = String::new();
+    if input.by_ref().take(MAX_LEN as u64).read_line(&mut line)? == 0 {
+        return Ok(None);
+    }
+    if !line.ends_with('\n') {
+        return Err(invalid(if line.len() < MAX_LEN { "unexpected end of the command" } else { "line too long" }));
+    }
+    let trimmed = line.trim_end_matches(['\r', '\n']).len();
+    line.truncate(trimmed);
+    Ok(Some(line))
+}
+
+fn parse_len(len: &str) -> io::Result<usize> {
+    len.parse().ok().filter(|&len| len <= MAX_LEN).ok_or_else(|| invalid("invalid length"))
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+fn write_bulk<W: Write>(output: &mut W, text: &str) -> io::Result<()> {
+    write!(output, "${}\r\n{}\r\n", text.len(), text)
+}
+
+/// Writes an error reply, starting with its code like those of Redis, on a single line as
+/// the protocol requires.
+fn write_error<W: Write>(output: &mut W, code: &str, message: &str) -> io::Result<()> {
+    write!(output, "-{} {}\r\n", code, message.replace(['\r', '\n'], " "))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    fn read(input: &[u8]) -> io::Result<Option<Vec<String>>> {
+        read_command(&mut Cursor::new(input))
+    }
+
+    fn error(input: &[u8]) -> io::ErrorKind {
+        read(input).expect_err("not a valid command").kind()
+    }
+
+    #[test]
+    fn reads_arrays_of_bulk_strings() {
+        let mut input = Cursor::new(&b"*2\r\n$6\r\nENCODE\r\n$7\r\n5624-82\r\n*1\r\n$4\r\nPING\r\n"[..]);
+        assert_eq!(read_command(&mut input).unwrap(), Some(vec!["ENCODE".to_string(), "5624-82".to_string()]));
+        assert_eq!(read_command(&mut input).unwrap(), Some(vec!["PING".to_string()]));
+        assert_eq!(read_command(&mut input).unwrap(), None);
+        // bulk strings may hold spaces and line breaks
+        assert_eq!(read(b"*1\r\n$5\r\na b\r\n\r\n").unwrap(), Some(vec!["a b\r\n".to_string()]));
+        assert_eq!(read(b"*0\r\n").unwrap(), Some(vec![]));
+    }
+
+    #[test]
+    fn reads_inline_commands() {
+        let mut input = Cursor::new(&b"ENCODE  5624-82\r\nPING\n"[..]);
+        assert_eq!(read_command(&mut input).unwrap(), Some(vec!["ENCODE".to_string(), "5624-82".to_string()]));
+        assert_eq!(read_command(&mut input).unwrap(), Some(vec!["PING".to_string()]));
+        assert_eq!(read_command(&mut input).unwrap(), None);
+    }
+
+    #[test]
+    fn rejects_lengths_beyond_the_limit() {
+        assert_eq!(error(format!("*{}\r\n", MAX_LEN + 1).as_bytes()), io::ErrorKind::InvalidData);
+        assert_eq!(error(format!("*1\r\n${}\r\n", MAX_LEN + 1).as_bytes()), io::ErrorKind::InvalidData);
+        let long_line = format!("ENCODE {}\r\n", "5".repeat(MAX_LEN));
+        assert_eq!(read(long_line.as_bytes()).unwrap_err().to_string(), "line too long");
+    }
+
+    #[test]
+    fn rejects_malformed_commands() {
+        for input in [
+            &b"*x\r\n"[..],
+            b"*-1\r\n",
+            b"*1\r\n:5\r\n",
+            b"*1\r\n$-1\r\n",
+            b"*1\r\n$3\r\nabcde\r\n",
+            b"*1\r\n$2\r\n\xff\xfe\r\n",
+        ] {
+            assert_eq!(error(input), io::ErrorKind::InvalidData, "{:?}", String::from_utf8_lossy(input));
+        }
+    }
+
+    #[test]
+    fn rejects_truncated_commands() {
+        assert_eq!(error(b"*2\r\n$6\r\nENCODE\r\n"), io::ErrorKind::InvalidData);
+        assert_eq!(error(b"*1\r\n$6\r\nENC"), io::ErrorKind::UnexpectedEof);
+        assert_eq!(error(b"*1\r\n$6"), io::ErrorKind::InvalidData);
+        assert_eq!(read(b"PING").unwrap_err().to_string(), "unexpected end of the command");
+    }
+}
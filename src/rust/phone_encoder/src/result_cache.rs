@@ -0,0 +1,119 @@
+//! `--cache-dir`: keeping the solutions written by a run in a directory, so that running
+//! again with the same numbers files, words and options writes them from there, without
+//! loading the words or searching anything.
+//!
+//! The solutions are kept under the SHA-256 of what they depend on: the numbers files (their
+//! paths, which head the sections of a directory, and their digests), the dictionary (see
+//! [`Manifest::dictionary`]) and the options changing what is written, as parsed, with the
+//! version of the program. The other options, like `--jobs`, `--timings` or `--preload`, can
+//! change between the runs. Nothing is ever removed from the directory, which can be emptied
+//! at any time.
+
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+
+use phone_encoder::manifest::Manifest;
+use phone_encoder::sha256::{hex, sha256};
+use phone_encoder::{FileContext, FileRole, WordsFile};
+
+use crate::cli::Options;
+
+/// Where the solutions of a run are kept in the cache directory.
+pub struct CachedRun {
+    path: PathBuf,
+}
+
+impl CachedRun {
+    /// Hashes the words and numbers files of a run, and its options.
+    pub fn new(dir: &Path, options: &Options, words_files: &[WordsFile], input_files: &[PathBuf]) -> io::Result<CachedRun> {
+        let manifest = Manifest::new(Vec::new(), words_files, input_files, &options.dict.mapping, options.rules.unwrap_or_default(), 0)?;
+        let mut key = format!("options {}\ndictionary {}\n", hex(&options_digest(options)), hex(&manifest.dictionary()));
+        for file in &manifest.numbers_files {
+            key += &format!("numbers {} {}\n", hex(&file.sha256), file.path.display());
+        }
+        fs::create_dir_all(dir).in_file(FileRole::CachedResults, dir)?;
+        Ok(CachedRun { path: dir.join(hex(&sha256(key.as_bytes()))) })
+    }
+
+    /// Writes the solutions of a previous run with the same key to `out`, returning whether
+    /// there was one.
+    pub fn replay(&self, out: &mut dyn Write) -> io::Result<bool> {
+        let mut file = match File::open(&self.path) {
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(false),
+            file => file.in_file(FileRole::CachedResults, &self.path)?,
+        };
+        io::copy(&mut file, out)?;
+        out.flush()?;
+        Ok(true)
+    }
+
+    /// A writer copying what is written to `out` into the cache, which keeps it once the
+    /// run [`finish`](Recording::finish)es.
+    pub fn record<W: Write>(&self, out: W) -> io::Result<Recording<W>> {
+        // another run with the same key may be writing at the same time
+        let mut name = self.path.file_name().unwrap_or_default().to_os_string();
+        name.push(format!(".{}.tmp", process::id()));
+        let temp = self.path.with_file_name(name);
+        let copy = BufWriter::new(File::create(&temp).in_file(FileRole::CachedResults, &temp)?);
+        Ok(Recording { out, copy, temp, path: self.path.clone() })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+/// The solutions of a run being written, and copied into the cache.
+pub struct Recording<W: Write> {
+    out: W,
+    copy: BufWriter<File>,
+    temp: PathBuf,
+    path: PathBuf,
+}
+
+impl<W: Write> Recording<W> {
+    /// Keeps the solutions written in the cache, once they all were.
+    pub fn finish(mut self) -> io::Result<()> {
+        self.out.flush()?;
+        self.copy.flush().in_file(FileRole::CachedResults, &self.temp)?;
+        fs::rename(&self.temp, &self.path).in_file(FileRole::CachedResults, &self.path)?;
+        Ok(())
+    }
+}
+
+impl<W: Write> Write for Recording<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.out.write(buf)?;
+        self.copy.write_all(&buf[..written])?;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.out.flush()?;
+        self.copy.flush()
+    }
+}
+
+/// The solutions of a run that failed are not kept.
+impl<W: Write> Drop for Recording<W> {
+    fn drop(&mut self) {
+        // already renamed if the run finished
+        let _ = fs::remove_file(&self.temp);
+    }
+}
+
+/// The digest of the options changing the solutions written: those of the dictionary and
+/// of the encoding, but not `--timings`, the `--rules`, which numbers are read and how, and
+/// whether they are sorted or the files merged.
+fn options_digest(options: &Options) -> [u8; 32] {
+    let mut encode = options.encode.clone();
+    encode.timings = false;
+    let options = format!(
+        "{}\n{:?}\n{:?}\n{:?}\n{:?}\n{:?}\n{:?}\n{}\n{}",
+        env!("CARGO_PKG_VERSION"), options.dict, encode, options.rules, options.selection,
+        options.number_policy, options.number_field, options.sort.is_some(), options.merge,
+    );
+    sha256(options.as_bytes())
+}
@@ -0,0 +1,558 @@
+//! Running the command given on the command line, with the `cli` feature.
+
+use std::env;
+#[cfg(feature = "server")]
+use std::fs::OpenOptions;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+#[cfg(feature = "server")]
+use std::net::TcpListener;
+use std::ops::AddAssign;
+use std::path::{Path, PathBuf};
+#[cfg(feature = "server")]
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+#[cfg(feature = "server")]
+use phone_encoder::audit::AuditLog;
+#[cfg(feature = "server")]
+use phone_encoder::cache::SolutionCache;
+use phone_encoder::dict_report::DictReport;
+use phone_encoder::generate::generate_dict;
+use phone_encoder::input::{input_files, LineReader};
+use phone_encoder::manifest::Manifest;
+use phone_encoder::output::create_output;
+use phone_encoder::parallel::{encode_parallel, map_parallel, Jobs};
+use phone_encoder::scramble::Scrambler;
+use phone_encoder::sort::ExternalSort;
+use phone_encoder::stats::{stats_json, Timings};
+use phone_encoder::vanity::VanityFinder;
+use phone_encoder::{EncodeStats, Encoder, FileContext, FileRole, NoOutput, WordsFile};
+
+use crate::bench::bench;
+use crate::cli::{Command, NumberPolicy, Options, Schedule};
+use crate::per_number::encode_per_number;
+use crate::result_cache::CachedRun;
+use crate::service;
+#[cfg(feature = "server")]
+use crate::service::Priority;
+use crate::{bench_dict, compare, fetch, follow, keymaps, report, trace};
+
+pub fn run(options: &Options) -> io::Result<()> {
+    if let Some(unit_file) = &options.install_service {
+        return service::install(options, unit_file);
+    }
+    match &options.command {
+        Command::Encode { words_files, input_file } if !options.keymaps.is_empty() => {
+            keymaps::encode_keymaps(options, words_files, input_file)?
+        }
+        Command::Encode { words_files, input_file } => encode(options, words_files, input_file)?,
+        Command::Complete { prefix, words_files } => {
+            let encoder = Encoder::load_all(words_files, &options.dict)?;
+            for word in encoder.words_with_prefix(prefix) {
+                println!("{}", word);
+            }
+        }
+        Command::Lookup { digits, words_files } => {
+            let encoder = Encoder::load_all(words_files, &options.dict)?;
+            for word in encoder.lookup(digits) {
+                println!("{}", word);
+            }
+        }
+        Command::Contains { digits, words_files } => {
+            let encoder = Encoder::load_all(words_files, &options.dict)?;
+            let mut out = BufWriter::new(io::stdout().lock());
+            for (span, word) in encoder.contained_words(digits) {
+                writeln!(out, "{}..{} {}", span.start, span.end, word)?;
+            }
+            out.flush()?;
+        }
+        Command::Bench { words_files, input_file } => bench(options, words_files, input_file)?,
+        Command::BenchDict { words_files, input_file } => bench_dict::bench_dict(options, words_files, input_file)?,
+        Command::Follow { words_files } => follow::follow(options, words_files)?,
+        Command::Report { stats, output } => report::report(stats, output.as_deref())?,
+        Command::Scramble { input_file, key } => scramble(input_file, key)?,
+        Command::GenerateDict { shape } => {
+            let generated = generate_dict(shape, &options.dict.mapping);
+            let mut out = BufWriter::new(io::stdout().lock());
+            for word in &generated.words {
+                writeln!(out, "{}", word)?;
+            }
+            out.flush()?;
+            eprintln!("{} words encoded by {} different digits ({:.2} words each on average, at most {})",
+                      generated.words.len(), generated.buckets,
+                      generated.words.len() as f64 / generated.buckets.max(1) as f64, generated.largest_bucket);
+        }
+        Command::Find { words, input_file } => {
+            let mut finder = VanityFinder::new(words, &options.dict.mapping, options.position);
+            let mut out = io::stdout().lock();
+            let mut lines = LineReader::open(input_file).in_file(FileRole::Numbers, input_file)?
+                .select(&options.selection)
+                .detect_format(&options.number_field)
+                .fail_on_invalid(options.number_policy == NumberPolicy::Strict);
+            while let Some(num) = lines.next_line().in_file(FileRole::Numbers, input_file)? {
+                let num = number(num);
+                for word in finder.matches(num) {
+                    writeln!(out, "{}: {}", num, word)?;
+                }
+            }
+        }
+        Command::Compare { other, words_file, input_file, args } => compare::compare(other, words_file, input_file, args)?,
+        Command::Keymap if options.keymaps.is_empty() => print!("{}", options.dict.mapping.keymap_toml()),
+        Command::Keymap => {
+            for (index, (name, mapping)) in options.keymaps.iter().enumerate() {
+                if index > 0 {
+                    println!();
+                }
+                print!("# {}\n{}", name, mapping.keymap_toml());
+            }
+        }
+        Command::FetchInputs { sources, data_dir } => fetch::fetch_inputs(sources, data_dir)?,
+        #[cfg(feature = "server")]
+        Command::JsonRpc { words_files } => {
+            let load = |files: &[WordsFile]| Ok(load(options, files)?);
+            phone_encoder::jsonrpc::serve(words_files.clone(), load, io::stdin().lock(), io::stdout().lock())?
+        }
+        #[cfg(feature = "server")]
+        Command::Resp { words_files, address } => {
+            let encoder = load(options, words_files)?;
+            let listener = TcpListener::bind(address)?;
+            service::log(Priority::Info, format_args!("Serving ENCODE and COUNT on {}", listener.local_addr()?));
+            service::notify_ready();
+            phone_encoder::resp::serve(Arc::new(encoder), listener, options.server_limits, solution_cache(options), audit_log(options)?)?
+        }
+        Command::Trace { number, words_files } => trace::trace(options, words_files, number)?,
+        Command::Unencodable { words_files, input_file } => {
+            let encoder = load(options, words_files)?;
+            let mut out = io::stdout().lock();
+            let mut lines = LineReader::open(input_file).in_file(FileRole::Numbers, input_file)?
+                .select(&options.selection)
+                .detect_format(&options.number_field)
+                .fail_on_invalid(options.number_policy == NumberPolicy::Strict);
+            while let Some(num) = lines.next_line().in_file(FileRole::Numbers, input_file)? {
+                let num = number(num);
+                if !encoder.is_encodable(num) {
+                    writeln!(out, "{}", num)?;
+                }
+            }
+        }
+        #[cfg(feature = "grpc")]
+        Command::GrpcServe { words_files, address } => grpc_serve(options, words_files, address)?,
+        #[cfg(feature = "grpc")]
+        Command::GrpcClient { input_file, address } => grpc_client(options, input_file, address)?,
+        #[cfg(feature = "kafka")]
+        Command::Kafka { words_files, kafka } => kafka_encode(options, words_files, kafka)?,
+    }
+    Ok(())
+}
+
+/// Loads the words files into an encoder configured by the options, reporting its words
+/// with `--dict-report`.
+pub fn load(options: &Options, words_files: &[WordsFile]) -> io::Result<Encoder> {
+    let encoder = Encoder::load_all(words_files, &options.dict)?;
+    if let Some(file) = &options.dict_report {
+        let report = DictReport::new(encoder.dictionary(), options.dict_report_top);
+        match file {
+            Some(file) => fs::write(file, report.to_string()).in_file(FileRole::DictReport, file)?,
+            None => eprint!("{}", report),
+        }
+    }
+    Ok(configure(options, encoder))
+}
+
+/// Writes the manifest of a run encoding the `input_files` with the words files, with
+/// `--manifest`.
+pub fn write_manifest(options: &Options, words_files: &[WordsFile], input_files: &[PathBuf]) -> io::Result<()> {
+    let Some(file) = &options.manifest else { return Ok(()) };
+    let rules = options.rules.unwrap_or_default();
+    let manifest = Manifest::new(options.args.clone(), words_files, input_files, &options.dict.mapping, rules, options.selection.seed)?;
+    let out = File::create(file).in_file(FileRole::Manifest, file)?;
+    manifest.write(BufWriter::new(out)).in_file(FileRole::Manifest, file)?;
+    Ok(())
+}
+
+/// Applies the encoding options given on the command line to the encoder.
+pub fn configure(options: &Options, encoder: Encoder) -> Encoder {
+    let mut encoder = encoder.with_options(options.encode.clone());
+    if options.encode.timeout_per_number.is_some() {
+        encoder = encoder.with_skipped_output(io::stderr());
+    }
+    match options.rules {
+        Some(rules) => encoder.with_rules(rules),
+        None => encoder,
+    }
+}
+
+/// Writes the lines of a numbers file to stdout with the digits of each number scrambled,
+/// and the columns after it, if any, as they are.
+fn scramble(input_file: &str, key: &str) -> io::Result<()> {
+    let scrambler = Scrambler::new(key.as_bytes());
+    let file = File::open(input_file).in_file(FileRole::Numbers, input_file)?;
+    let mut out = BufWriter::new(io::stdout().lock());
+    for line in BufReader::new(file).lines() {
+        let line = line.in_file(FileRole::Numbers, input_file)?;
+        match line.split_once('\t') {
+            Some((number, columns)) => writeln!(out, "{}\t{}", scrambler.scramble(number), columns)?,
+            None => writeln!(out, "{}", scrambler.scramble(&line))?,
+        }
+    }
+    out.flush()
+}
+
+/// The cache of the solutions requested from a server, with `--cache-size`.
+#[cfg(feature = "server")]
+fn solution_cache(options: &Options) -> Option<Arc<SolutionCache>> {
+    options.cache.map(|cache| Arc::new(SolutionCache::new(cache)))
+}
+
+/// The audit log of a server, with `--audit-log`, appended to the file.
+#[cfg(feature = "server")]
+fn audit_log(options: &Options) -> io::Result<Option<Arc<AuditLog>>> {
+    let Some(file) = &options.audit_log else { return Ok(None) };
+    let out = OpenOptions::new().create(true).append(true).open(file).in_file(FileRole::Output, file)?;
+    Ok(Some(Arc::new(AuditLog::new(BufWriter::new(out), options.encode.redact))))
+}
+
+fn encode(options: &Options, words_files: &[WordsFile], input: &str) -> io::Result<()> {
+    let input_files = input_files(input).in_file(FileRole::Numbers, input)?;
+    // the files of a directory or glob are encoded in sections, unless merged
+    let sections = !options.merge && input_files != [Path::new(input)];
+    let mut timings = Timings::default();
+
+    let (words_files, preloaded) = preload(options, words_files, &input_files)?;
+    write_manifest(options, &words_files, &input_files)?;
+    let cached_run = match &options.cache_dir {
+        Some(dir) => Some(CachedRun::new(dir, options, &words_files, &input_files)?),
+        None => None,
+    };
+    if let Some(cached_run) = &cached_run {
+        if cached_run.replay(&mut io::stdout().lock())? {
+            eprintln!("Wrote the solutions of a previous run, kept in {}", cached_run.path().display());
+            return Ok(());
+        }
+    }
+    let start_time = Instant::now();
+    let mut encoder = load(options, &words_files)?;
+    timings.load = start_time.elapsed();
+    if options.stats_json.is_some() {
+        encoder = encoder.with_top_numbers(TOP_NUMBERS);
+    }
+
+    match &options.show_rejected {
+        Some(Some(file)) => encoder = encoder.with_rejected_output(BufWriter::new(File::create(file).in_file(FileRole::Rejected, file)?)),
+        Some(None) => encoder = encoder.with_rejected_output(io::stderr()),
+        None => {}
+    }
+    // invalid numbers are skipped instead of stopping the run
+    encoder = match &options.rejects_file {
+        Some(file) => encoder.with_skipped_output(BufWriter::new(File::create(file).in_file(FileRole::Skipped, file)?)),
+        None => encoder.with_skipped_output(io::stderr()),
+    };
+
+    let mut total = Totals { timings, ..Totals::default() };
+    let mut files = Vec::new();
+    if let Some(output_dir) = &options.output_dir {
+        // each numbers file is encoded into its own output file, so they can be encoded in parallel
+        fs::create_dir_all(output_dir).in_file(FileRole::Output, output_dir)?;
+        let inputs: Vec<_> = input_files.iter().zip(&preloaded).collect();
+        let encode_into_file = |(input_file, preloaded): (&PathBuf, &Option<Vec<u8>>), jobs: &mut Option<Jobs>| {
+            if options.one_file_per_number {
+                return encode_per_number(options, &encoder, *jobs, input_file, preloaded.as_deref(), Path::new(output_dir));
+            }
+            let mut out = output_file(options, Path::new(output_dir), input_file)?;
+            encoder.write_header(&mut out)?;
+            let file = encode_file(options, &encoder, jobs, input_file, preloaded.as_deref(), &mut out)?;
+            encoder.write_summary(&mut out, file.numbers, &file.stats)?;
+            out.flush()?;
+            Ok(file)
+        };
+        let file_totals = match options.schedule {
+            Schedule::Files => map_parallel(&inputs, options.jobs.unwrap_or(Jobs::Auto), |&input| encode_into_file(input, &mut None))?,
+            Schedule::Numbers => {
+                let mut jobs = options.jobs;
+                inputs.iter().map(|&input| encode_into_file(input, &mut jobs)).collect::<io::Result<_>>()?
+            }
+        };
+        for ((input_file, _), file) in inputs.into_iter().zip(file_totals) {
+            total += file;
+            files.push((input_file, file));
+        }
+        return finish(options, &total, &files, &encoder.top_numbers());
+    }
+
+    let mut jobs = options.jobs;
+    // stdout itself flushes every line: the solutions are written in blocks instead, or number by number
+    // with --line-buffered
+    let stdout = io::stdout().lock();
+    let mut encode_into = |out: &mut dyn Write| {
+        encoder.write_header(out)?;
+        for (index, (input_file, preloaded)) in input_files.iter().zip(&preloaded).enumerate() {
+            if sections {
+                if index > 0 {
+                    writeln!(out)?;
+                }
+                writeln!(out, "==> {} <==", input_file.display())?;
+            }
+            let file = encode_file(options, &encoder, &mut jobs, input_file, preloaded.as_deref(), out)?;
+            total += file;
+            if sections {
+                files.push((input_file, file));
+            }
+            if total.stats.truncated {
+                break;
+            }
+        }
+        encoder.write_summary(out, total.numbers, &total.stats)?;
+        out.flush()
+    };
+    match &cached_run {
+        Some(cached_run) => {
+            let mut out = BufWriter::new(cached_run.record(stdout)?);
+            encode_into(&mut out)?;
+            out.into_inner()?.finish()?;
+        }
+        None => encode_into(&mut BufWriter::new(stdout))?,
+    }
+    finish(options, &total, &files, &encoder.top_numbers())
+}
+
+/// The file the solutions of a numbers file are written to with `--output-dir`, named after it.
+pub fn output_file(options: &Options, output_dir: &Path, input_file: &Path) -> io::Result<Box<dyn Write + Send>> {
+    let mut name = input_file.file_name().unwrap_or(input_file.as_os_str()).to_os_string();
+    name.push(".out");
+    let path = output_dir.join(name);
+    Ok(create_output(&path, &options.output).in_file(FileRole::Output, &path)?)
+}
+
+/// The counts and timings of encoding the numbers of one or more files.
+#[derive(Clone, Copy, Default)]
+pub struct Totals {
+    pub numbers: usize,
+    pub stats: EncodeStats,
+    pub timings: Timings,
+}
+
+impl AddAssign for Totals {
+    fn add_assign(&mut self, other: Totals) {
+        self.numbers += other.numbers;
+        self.stats += other.stats;
+        self.timings += other.timings;
+    }
+}
+
+/// Encodes the numbers of a file, from its contents if it was preloaded, writing the solutions to `out`.
+pub fn encode_file(
+    options: &Options,
+    encoder: &Encoder,
+    jobs: &mut Option<Jobs>,
+    input_file: &Path,
+    preloaded: Option<&[u8]>,
+    out: &mut dyn Write,
+) -> io::Result<Totals> {
+    let mut total = Totals::default();
+    let timings = &mut total.timings;
+    let mut lines = numbers(options, input_file, preloaded)?;
+
+    if let Some(jobs) = jobs {
+        // only one chunk of the input is kept in memory at a time
+        let mut chunk = Vec::with_capacity(options.chunk_lines.min(1024));
+        loop {
+            let start_time = Instant::now();
+            chunk.clear();
+            while let Some(num) = lines.next_line().in_file(FileRole::Numbers, input_file)? {
+                chunk.push(row(options, num).to_string());
+                if chunk.len() == options.chunk_lines {
+                    break;
+                }
+            }
+            let read_time = Instant::now();
+            timings.read += read_time - start_time;
+            if chunk.is_empty() {
+                break;
+            }
+            let stats = encode_parallel(encoder, &chunk, jobs, out)?;
+            timings.search += read_time.elapsed() - stats.write_time;
+            timings.write += stats.write_time;
+            if options.line_buffered {
+                timings.write += flush(out)?;
+            }
+            total.numbers += chunk.len();
+            total.stats += stats;
+            if total.stats.truncated {
+                break;
+            }
+        }
+        return Ok(total);
+    }
+
+    loop {
+        let start_time = Instant::now();
+        let Some(num) = lines.next_line().in_file(FileRole::Numbers, input_file)? else { break };
+        let read_time = Instant::now();
+        timings.read += read_time - start_time;
+        let stats = encoder.encode_to(row(options, num), out)?;
+        timings.search += read_time.elapsed() - stats.write_time;
+        timings.write += stats.write_time;
+        if options.line_buffered {
+            timings.write += flush(out)?;
+        }
+        total.numbers += 1;
+        total.stats += stats;
+        if total.stats.truncated {
+            break;
+        }
+    }
+    Ok(total)
+}
+
+/// Flushes `out`, returning how long it took.
+pub fn flush(out: &mut dyn Write) -> io::Result<Duration> {
+    let start_time = Instant::now();
+    out.flush()?;
+    Ok(start_time.elapsed())
+}
+
+/// The lines of a numbers file picked by the `--skip`, `--take` and `--sample` options,
+/// sorted with `--sort`, spilling them to temporary files if there are too many to sort in memory.
+fn numbers<'a>(options: &Options, input_file: &Path, preloaded: Option<&'a [u8]>) -> io::Result<LineReader<Box<dyn BufRead + 'a>>> {
+    let mut lines = LineReader::open_or_preloaded(input_file, preloaded).in_file(FileRole::Numbers, input_file)?
+        .select(&options.selection)
+        .detect_format(&options.number_field)
+        .fail_on_invalid(options.number_policy == NumberPolicy::Strict);
+    let Some(run_bytes) = options.sort else { return Ok(lines) };
+    let mut sort = ExternalSort::new(run_bytes, env::temp_dir());
+    while let Some(line) = lines.next_line().in_file(FileRole::Numbers, input_file)? {
+        sort.push(line)?;
+    }
+    Ok(LineReader::new(Box::new(sort.finish()?)))
+}
+
+/// The contents of each numbers file, if it was read into memory by `--preload`.
+type Preloaded = Vec<Option<Vec<u8>>>;
+
+/// Reads the words and numbers files into memory if `--preload` is given, returning the words
+/// files with their contents and the contents of each numbers file.
+pub fn preload(options: &Options, words_files: &[WordsFile], input_files: &[PathBuf]) -> io::Result<(Vec<WordsFile>, Preloaded)> {
+    let mut words_files = words_files.to_vec();
+    if !options.preload {
+        return Ok((words_files, vec![None; input_files.len()]));
+    }
+    for file in &mut words_files {
+        file.preload()?;
+    }
+    let contents = input_files.iter()
+        .map(|file| fs::read(file).in_file(FileRole::Numbers, file).map(Some))
+        .collect::<Result<_, _>>()?;
+    Ok((words_files, contents))
+}
+
+/// The phone number on a line of the numbers file, without the second, tab-separated
+/// column (the reason a number is listed in a `--rejects-file`).
+pub fn number(line: &str) -> &str {
+    line.split_once('\t').map_or(line, |(num, _)| num)
+}
+
+/// What the encoder is given for a line of the numbers file: with `--join`, the whole line,
+/// to copy its columns into the output, or else the number.
+pub fn row<'a>(options: &Options, line: &'a str) -> &'a str {
+    if options.encode.columns.is_empty() { number(line) } else { line }
+}
+
+/// How many of the numbers with the most solutions are listed by `--stats-json`.
+const TOP_NUMBERS: usize = 20;
+
+/// Reports the timings, the statistics of the search and whether the output was truncated,
+/// if requested, also for each of the `files` encoded in sections, with the `top_numbers`
+/// of the run by solutions.
+pub fn finish(options: &Options, total: &Totals, files: &[(&PathBuf, Totals)], top_numbers: &[(String, usize)]) -> io::Result<()> {
+    if total.stats.truncated {
+        if let Some(max_total) = options.encode.max_total {
+            eprintln!("Stopped after printing {} solutions (--max-total)", max_total);
+        }
+    }
+    if options.histogram {
+        eprintln!("{}", total.stats.solution_counts);
+    }
+    if options.encode.no_output == Some(NoOutput::Checksum) {
+        eprintln!("checksum of the {} solutions: {:016x}", total.stats.solutions, total.stats.checksum);
+    }
+    if options.encode.timings {
+        for (file, totals) in files {
+            eprintln!("{}:\n{}", file.display(), totals.timings);
+        }
+        eprintln!("{}", total.timings);
+    }
+    // one line for each file, then one for the whole run
+    let json = || {
+        let mut lines = String::new();
+        for (file, totals) in files {
+            let mut json = stats_json(totals.numbers, &totals.stats, &totals.timings);
+            json["file"] = file.display().to_string().into();
+            lines += &(json.to_string() + "\n");
+        }
+        let mut json = stats_json(total.numbers, &total.stats, &total.timings);
+        if !top_numbers.is_empty() {
+            json["top_numbers"] = top_numbers.iter()
+                .map(|(number, solutions)| serde_json::json!({"number": number, "solutions": solutions}))
+                .collect();
+        }
+        lines + &json.to_string() + "\n"
+    };
+    match &options.stats_json {
+        Some(Some(file)) => fs::write(file, json()).in_file(FileRole::Stats, file)?,
+        Some(None) => eprint!("{}", json()),
+        None => {}
+    }
+    Ok(())
+}
+
+#[cfg(feature = "grpc")]
+fn grpc_serve(options: &Options, words_files: &[WordsFile], address: &str) -> io::Result<()> {
+    let address = address.parse()
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, format!("invalid address: {}", e)))?;
+    let encoder = load(options, words_files)?;
+    service::log(Priority::Info, format_args!("Serving PhoneEncoding on {}", address));
+    service::notify_ready();
+    let audit = audit_log(options)?;
+    tokio::runtime::Runtime::new()?
+        .block_on(phone_encoder::grpc::serve(encoder, address, options.server_limits, solution_cache(options), audit))
+        .map_err(io::Error::other)
+}
+
+/// Sends the numbers in the input file to a gRPC server, printing the solutions like the encoder does.
+#[cfg(feature = "grpc")]
+fn grpc_client(options: &Options, input_file: &str, address: &str) -> io::Result<()> {
+    let mut numbers = Vec::new();
+    let mut lines = LineReader::open(input_file).in_file(FileRole::Numbers, input_file)?
+        .select(&options.selection)
+        .detect_format(&options.number_field)
+        .fail_on_invalid(options.number_policy == NumberPolicy::Strict);
+    while let Some(num) = lines.next_line().in_file(FileRole::Numbers, input_file)? {
+        numbers.push(num.to_string());
+    }
+    let address = if address.contains("://") { address.to_string() } else { format!("http://{}", address) };
+    tokio::runtime::Runtime::new()?
+        .block_on(phone_encoder::grpc::encode_remote(address, numbers, |solution| {
+            if solution.words.is_empty() {
+                println!("{}:", solution.number);
+            } else {
+                println!("{}: {}", solution.number, solution.words.join(" "));
+            }
+        }))
+        .map_err(|e| io::Error::other(e.to_string()))
+}
+
+/// Encodes the numbers of a Kafka topic into another, until it fails.
+#[cfg(feature = "kafka")]
+fn kafka_encode(options: &Options, words_files: &[WordsFile], kafka: &phone_encoder::kafka::KafkaOptions) -> io::Result<()> {
+    // invalid numbers are listed instead of stopping the stream, and get no solutions
+    let encoder = load(options, words_files)?.with_skipped_output(io::stderr());
+    service::log(Priority::Info, format_args!("Encoding the numbers of {} into {} ({})", kafka.input_topic, kafka.output_topic, kafka.brokers));
+    service::notify_ready();
+    let mut total = Totals::default();
+    let audit = audit_log(options)?;
+    phone_encoder::kafka::run(&encoder, kafka, audit.as_deref(), |numbers, stats| {
+        total += Totals { numbers, stats: *stats, timings: Timings::default() };
+        service::log(Priority::Info, format_args!("Committed {} numbers ({} solutions), {} in total", numbers, stats.solutions, total.numbers));
+    })
+}
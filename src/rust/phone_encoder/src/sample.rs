@@ -0,0 +1,206 @@
+//! Selecting some of the numbers of an input: a slice of its lines, and a random sample of
+//! them, for quick runs on enormous numbers files without preprocessing them; and keeping
+//! a few example solutions when only counting them.
+//!
+//! Samples are drawn with a small seeded generator rather than an external crate, so that
+//! the same seed gives the same sample on every platform and in every version.
+
+use alloc::vec::Vec;
+
+/// A fast pseudo-random generator (SplitMix64), good enough for sampling, not for security.
+#[derive(Clone, Debug)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Rng {
+        Rng { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// A number in `[0, 1)`.
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+/// Which lines of an input are used: after skipping the first `skip` lines, each line is
+/// kept with probability `sample`, until `take` lines were kept.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Selection {
+    pub skip: usize,
+    pub take: Option<usize>,
+    /// the fraction of the lines to keep, from 0 to 1 (all lines if `None`).
+    pub sample: Option<f64>,
+    pub seed: u64,
+}
+
+impl Selection {
+    /// Whether all lines are kept.
+    pub fn is_all(&self) -> bool {
+        self.skip == 0 && self.take.is_none() && self.sample.is_none()
+    }
+
+    /// Starts selecting the lines of an input.
+    #[cfg(feature = "std")]
+    pub(crate) fn selector(&self) -> Selector {
+        Selector { selection: *self, skipped: 0, taken: 0, rng: Rng::new(self.seed) }
+    }
+}
+
+/// Whether a line is kept, see [`Selector::select`].
+#[cfg(feature = "std")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Select {
+    Keep,
+    Drop,
+    /// no more lines will be kept, so the rest of the input need not be read.
+    Done,
+}
+
+/// Selects the lines of an input, one after the other.
+#[cfg(feature = "std")]
+#[derive(Clone, Debug)]
+pub(crate) struct Selector {
+    selection: Selection,
+    skipped: usize,
+    taken: usize,
+    rng: Rng,
+}
+
+#[cfg(feature = "std")]
+impl Selector {
+    /// Whether the next line of the input is kept.
+    pub(crate) fn select(&mut self) -> Select {
+        if self.selection.take.is_some_and(|take| self.taken >= take) {
+            return Select::Done;
+        }
+        if self.skipped < self.selection.skip {
+            self.skipped += 1;
+            return Select::Drop;
+        }
+        if let Some(fraction) = self.selection.sample {
+            if self.rng.next_f64() >= fraction {
+                return Select::Drop;
+            }
+        }
+        self.taken += 1;
+        Select::Keep
+    }
+}
+
+/// A uniform random sample of at most `size` of the items offered to it, however many
+/// there are, keeping only the sample in memory (Vitter's algorithm R).
+#[derive(Clone, Debug)]
+pub struct Reservoir<T> {
+    size: usize,
+    offered: u64,
+    items: Vec<T>,
+    rng: Rng,
+}
+
+impl<T> Reservoir<T> {
+    pub fn new(size: usize, seed: u64) -> Reservoir<T> {
+        Reservoir { size, offered: 0, items: Vec::new(), rng: Rng::new(seed) }
+    }
+
+    /// Offers the item made by `item`, which is only called if the item is kept.
+    pub fn offer<F: FnOnce() -> T>(&mut self, item: F) {
+        self.offered += 1;
+        if self.items.len() < self.size {
+            self.items.push(item());
+            return;
+        }
+        let index = self.rng.next_u64() % self.offered;
+        if index < self.size as u64 {
+            self.items[index as usize] = item();
+        }
+    }
+
+    /// How many items were offered.
+    pub fn offered(&self) -> u64 {
+        self.offered
+    }
+
+    /// The sample, in the order the items were kept in.
+    pub fn into_items(self) -> Vec<T> {
+        self.items
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+
+    /// The indexes of the lines kept of an input of `lines` lines.
+    fn select(selection: Selection, lines: usize) -> Vec<usize> {
+        let mut selector = selection.selector();
+        let mut kept = Vec::new();
+        for line in 0..lines {
+            match selector.select() {
+                Select::Keep => kept.push(line),
+                Select::Drop => {}
+                Select::Done => break,
+            }
+        }
+        kept
+    }
+
+    fn sample(fraction: f64, seed: u64) -> Selection {
+        Selection { sample: Some(fraction), seed, ..Selection::default() }
+    }
+
+    #[test]
+    fn keeps_all_lines_by_default() {
+        assert!(Selection::default().is_all());
+        assert_eq!(select(Selection::default(), 5), [0, 1, 2, 3, 4]);
+        assert!(!Selection { skip: 1, ..Selection::default() }.is_all());
+        assert!(!sample(1.0, 0).is_all());
+    }
+
+    #[test]
+    fn skips_then_takes() {
+        let selection = Selection { skip: 3, take: Some(4), ..Selection::default() };
+        assert_eq!(select(selection, 10), [3, 4, 5, 6]);
+        // the input need not be read after the lines taken
+        let mut selector = selection.selector();
+        let selected: Vec<_> = (0..8).map(|_| selector.select()).collect();
+        assert_eq!(selected[..3], [Select::Drop; 3]);
+        assert_eq!(selected[3..7], [Select::Keep; 4]);
+        assert_eq!(selected[7], Select::Done);
+
+        assert_eq!(select(Selection { skip: 8, take: Some(5), ..Selection::default() }, 10), [8, 9]);
+        assert!(select(Selection { skip: 20, ..Selection::default() }, 10).is_empty());
+        assert_eq!(Selection { take: Some(0), ..Selection::default() }.selector().select(), Select::Done);
+    }
+
+    #[test]
+    fn samples_are_reproducible_for_a_seed() {
+        let kept = select(sample(0.25, 42), 10_000);
+        assert_eq!(select(sample(0.25, 42), 10_000), kept);
+        assert_ne!(select(sample(0.25, 43), 10_000), kept);
+        assert!((2300..2700).contains(&kept.len()), "{}", kept.len());
+        assert!(select(sample(0.0, 42), 100).is_empty());
+        assert_eq!(select(sample(1.0, 42), 100), (0..100).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn samples_the_lines_after_those_skipped_until_enough_are_taken() {
+        let kept = select(sample(0.5, 7), 1000);
+        // the skipped lines do not draw from the generator, so the sample is the same
+        let skipped = select(Selection { skip: 100, ..sample(0.5, 7) }, 1100);
+        assert_eq!(skipped, kept.iter().map(|line| line + 100).collect::<Vec<_>>());
+        let taken = select(Selection { take: Some(10), ..sample(0.5, 7) }, 1000);
+        assert_eq!(taken, kept[..10]);
+        let both = select(Selection { skip: 100, take: Some(10), ..sample(0.5, 7) }, 1100);
+        assert_eq!(both, skipped[..10]);
+    }
+}
@@ -0,0 +1,144 @@
+//! Pseudonymizing numbers files (`scramble`), so that realistic datasets can be published as
+//! benchmarks: the digits of each number are replaced with others by a permutation keyed with
+//! a secret, which keeps what the benchmark depends on.
+//!
+//! * Each number keeps its length and its separators (`5624-82` may become `0913-47`).
+//! * The same number is always scrambled the same way, and different numbers differently, so
+//!   the duplicates of a file are kept, and no others are made.
+//!
+//! The permutation is a Feistel network on the decimal digits of a number (like the FF1
+//! format-preserving encryption), whose rounds add digits derived from the other half of the
+//! number with HMAC-SHA256. Without the key, the numbers cannot be recovered, except by
+//! guessing it, so it should be long and random.
+
+use crate::sha256::hmac_sha256;
+
+/// The rounds of the Feistel network, as many as FF1.
+const ROUNDS: u8 = 10;
+
+/// Scrambles the digits of numbers with a keyed permutation.
+pub struct Scrambler {
+    key: Vec<u8>,
+}
+
+impl Scrambler {
+    pub fn new(key: &[u8]) -> Scrambler {
+        Scrambler { key: key.to_vec() }
+    }
+
+    /// The number with its digits permuted, and its other characters as they are.
+    pub fn scramble(&self, number: &str) -> String {
+        replace_digits(number, |digits| self.permute(digits))
+    }
+
+    /// The number a number was scrambled from with the same key.
+    pub fn unscramble(&self, number: &str) -> String {
+        replace_digits(number, |digits| self.unpermute(digits))
+    }
+
+    /// Permutes the sequences of `digits.len()` digits: each round replaces one half with
+    /// the other, and the other with the sum, digit by digit modulo 10, of the first and of
+    /// digits derived from the second, which the next round can subtract again.
+    fn permute(&self, digits: &[u8]) -> Vec<u8> {
+        let (first, second) = digits.split_at(digits.len() / 2);
+        let (mut a, mut b) = (first.to_vec(), second.to_vec());
+        for round in 0..ROUNDS {
+            let sum = a.iter().zip(self.round_digits(round, digits.len(), &b)).map(|(x, y)| (x + y) % 10).collect();
+            a = std::mem::replace(&mut b, sum);
+        }
+        // after an even number of rounds, a and b have the lengths of first and second again
+        a.extend(b);
+        a
+    }
+
+    /// The inverse of [`Scrambler::permute`], undoing its rounds from the last.
+    fn unpermute(&self, digits: &[u8]) -> Vec<u8> {
+        let (first, second) = digits.split_at(digits.len() / 2);
+        let (mut a, mut b) = (first.to_vec(), second.to_vec());
+        for round in (0..ROUNDS).rev() {
+            let difference = b.iter().zip(self.round_digits(round, digits.len(), &a)).map(|(x, y)| (x + 10 - y) % 10).collect();
+            b = std::mem::replace(&mut a, difference);
+        }
+        a.extend(b);
+        a
+    }
+
+    /// Digits derived from `half` by a round of the permutation of numbers of `len` digits,
+    /// as many as needed (one HMAC for each 8 digits, 4 bytes for each).
+    fn round_digits(&self, round: u8, len: usize, half: &[u8]) -> impl Iterator<Item = u8> + '_ {
+        let mut message = vec![round];
+        message.extend_from_slice(&(len as u32).to_be_bytes());
+        message.extend_from_slice(half);
+        (0u32..).flat_map(move |block| {
+            let mut message = message.clone();
+            message.extend_from_slice(&block.to_be_bytes());
+            let mac = hmac_sha256(&self.key, &message);
+            (0..8).map(move |i| (u32::from_be_bytes([mac[4 * i], mac[4 * i + 1], mac[4 * i + 2], mac[4 * i + 3]]) % 10) as u8)
+        })
+    }
+}
+
+/// The number with its digits replaced by those `replace` makes of them.
+fn replace_digits<F: FnOnce(&[u8]) -> Vec<u8>>(number: &str, replace: F) -> String {
+    let digits: Vec<u8> = number.bytes().filter(u8::is_ascii_digit).map(|byte| byte - b'0').collect();
+    let mut replaced = replace(&digits).into_iter();
+    number.chars()
+        .map(|c| match c {
+            '0'..='9' => char::from(b'0' + replaced.next().unwrap_or_default()),
+            c => c,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+
+    const NUMBERS: [&str; 6] = ["5624-82", "4824", "10/783--5", "0721/608-4067", "3", "04824047610396184205"];
+
+    #[test]
+    fn permutes_all_numbers_of_a_length() {
+        let scrambler = Scrambler::new(b"secret");
+        for len in 1..=3 {
+            let numbers: Vec<String> = (0..10u32.pow(len)).map(|n| format!("{:0width$}", n, width = len as usize)).collect();
+            let scrambled: HashSet<String> = numbers.iter().map(|number| scrambler.scramble(number)).collect();
+            assert_eq!(scrambled.len(), numbers.len());
+            assert!(scrambled.iter().all(|number| number.len() == len as usize && number.bytes().all(|b| b.is_ascii_digit())));
+        }
+    }
+
+    #[test]
+    fn keeps_the_length_and_the_separators() {
+        let scrambler = Scrambler::new(b"secret");
+        for number in NUMBERS {
+            let scrambled = scrambler.scramble(number);
+            assert_eq!(scrambled.len(), number.len());
+            let separators = |number: &str| number.char_indices().filter(|(_, c)| !c.is_ascii_digit()).collect::<Vec<_>>();
+            assert_eq!(separators(&scrambled), separators(number), "{}", number);
+            assert_eq!(scrambler.scramble(number), scrambled);
+        }
+    }
+
+    #[test]
+    fn unscrambles_what_it_scrambled() {
+        let scrambler = Scrambler::new(b"secret");
+        for number in NUMBERS {
+            assert_eq!(scrambler.unscramble(&scrambler.scramble(number)), number);
+            assert_eq!(scrambler.scramble(&scrambler.unscramble(number)), number);
+        }
+        assert_eq!(scrambler.scramble(""), "");
+    }
+
+    #[test]
+    fn scrambles_differently_with_another_key() {
+        let (scrambler, other) = (Scrambler::new(b"secret"), Scrambler::new(b"secreT"));
+        let scrambled: Vec<String> = NUMBERS.iter().map(|number| scrambler.scramble(number)).collect();
+        let other_scrambled: Vec<String> = NUMBERS.iter().map(|number| other.scramble(number)).collect();
+        assert_ne!(scrambled, other_scrambled);
+        // only numbers of a digit may be scrambled the same, as there are only 10 of them
+        assert!(scrambled.iter().zip(&other_scrambled).filter(|(a, b)| a != b).count() >= NUMBERS.len() - 1);
+        assert_ne!(scrambled, NUMBERS);
+    }
+}
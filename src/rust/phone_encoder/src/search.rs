@@ -0,0 +1,957 @@
+//! The search for the encodings of a phone number.
+//!
+//! This module only needs `alloc`, so it is available without the `std` feature,
+//! e.g. to run the encoder on a microcontroller with a dictionary embedded in the
+//! program as a static byte slice (see [`words_from_bytes`]).
+
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::cell::RefCell;
+use core::fmt;
+use core::ops::{AddAssign, Deref, Range};
+use core::time::Duration;
+
+use num_bigint::BigUint;
+
+use crate::aho_corasick::AhoCorasick;
+use crate::filter::{Rejection, SolutionFilter};
+use crate::mapping::Mapping;
+use crate::trie::Trie;
+
+/// A dictionary word.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Word {
+    /// the word as it is printed.
+    pub text: String,
+    /// the priority of the word within its entry, given in the optional second,
+    /// tab-separated column of the words file (`0` by default).
+    pub weight: f64,
+    /// the source of the words file the word was loaded from.
+    pub source: Option<Arc<str>>,
+    /// the word of the words file this one is a variant of, printed differently but encoded
+    /// by the same digits (see `DictOptions::expand_case`), if it is one.
+    pub match_form: Option<Arc<str>>,
+}
+
+impl Word {
+    pub fn new(text: String) -> Word {
+        Word { text, weight: 0.0, source: None, match_form: None }
+    }
+
+    /// The word as it is matched: that of the words file, which a variant is printed
+    /// differently from.
+    pub fn matched(&self) -> &str {
+        self.match_form.as_deref().unwrap_or(&self.text)
+    }
+
+    /// Whether the word is only a variant of a word of the words file.
+    pub fn is_variant(&self) -> bool {
+        self.match_form.is_some()
+    }
+}
+
+/// A part of a solution: either a dictionary word or a digit copied from the phone number.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WordOrDigit<'a> {
+    Word(&'a str),
+    Digit(char),
+}
+
+impl<'a> WordOrDigit<'a> {
+    /// The part printed as `text`: a single digit is a digit, anything else a word.
+    pub fn parse(text: &'a str) -> WordOrDigit<'a> {
+        match text.as_bytes() {
+            [digit @ b'0'..=b'9'] => WordOrDigit::Digit(char::from(*digit)),
+            _ => WordOrDigit::Word(text),
+        }
+    }
+
+    pub fn is_digit(&self) -> bool {
+        matches!(self, WordOrDigit::Digit(_))
+    }
+
+    /// Whether the part is a digit of the number kept as it is, because no word encodes the
+    /// digits from there: the only digits of a solution are these.
+    pub fn is_fallback_digit(&self) -> bool {
+        self.is_digit()
+    }
+
+    /// The length of the part in letters (user-perceived characters, not bytes): combining
+    /// marks are not counted, so that `ä` is one letter whether it is written as one
+    /// character or as an `a` followed by a combining diaeresis.
+    pub fn len(&self) -> usize {
+        match self {
+            WordOrDigit::Word(word) if word.is_ascii() => word.len(),
+            WordOrDigit::Word(word) => word.chars().filter(|ch| !is_combining_mark(*ch)).count(),
+            WordOrDigit::Digit(_) => 1,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Whether a character is a combining mark of one of the blocks of combining diacritics,
+/// which is part of the letter before it.
+fn is_combining_mark(ch: char) -> bool {
+    matches!(ch, '\u{300}'..='\u{36f}' | '\u{483}'..='\u{489}' | '\u{1ab0}'..='\u{1aff}'
+        | '\u{1dc0}'..='\u{1dff}' | '\u{20d0}'..='\u{20ff}' | '\u{fe20}'..='\u{fe2f}')
+}
+
+impl fmt::Display for WordOrDigit<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WordOrDigit::Word(word) => f.write_str(word),
+            WordOrDigit::Digit(digit) => write!(f, "{}", digit),
+        }
+    }
+}
+
+/// A part is serialized as it is printed, a string, and read back with [`WordOrDigit::parse`]
+/// (borrowing the string, which must then have no escapes).
+#[cfg(feature = "serde")]
+impl serde::Serialize for WordOrDigit<'_> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            WordOrDigit::Word(word) => serializer.serialize_str(word),
+            WordOrDigit::Digit(digit) => serializer.serialize_char(*digit),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for WordOrDigit<'de> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        <&'de str>::deserialize(deserializer).map(WordOrDigit::parse)
+    }
+}
+
+/// A part of a solution with the positions of the digits of the number it encodes, counting
+/// only the digits (not the separators of the number), as [`segments`] finds them.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Segment<'a> {
+    pub part: WordOrDigit<'a>,
+    pub span: Range<usize>,
+}
+
+impl Segment<'_> {
+    /// See [`WordOrDigit::is_fallback_digit`].
+    pub fn is_fallback_digit(&self) -> bool {
+        self.part.is_fallback_digit()
+    }
+}
+
+/// `{"part": "mir", "span": {"start": 0, "end": 3}, "fallback_digit": false}`
+#[cfg(feature = "serde")]
+impl serde::Serialize for Segment<'_> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        let mut segment = serializer.serialize_struct("Segment", 3)?;
+        segment.serialize_field("part", &self.part)?;
+        segment.serialize_field("span", &self.span)?;
+        segment.serialize_field("fallback_digit", &self.is_fallback_digit())?;
+        segment.end()
+    }
+}
+
+/// Counts of what happened while encoding a phone number.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct EncodeStats {
+    /// solutions printed.
+    pub solutions: usize,
+    /// candidate solutions rejected by the [`SolutionFilter`].
+    pub rejected: usize,
+    /// time spent formatting and writing solutions, or handling them in
+    /// `Encoder::for_each_solution` (only measured if enabled in `EncodeOptions`).
+    pub write_time: Duration,
+    /// whether no more solutions may be printed, as `EncodeOptions::max_total` was reached.
+    pub truncated: bool,
+    /// whether the search was abandoned when [`Limits::interrupt`] returned `true`.
+    pub interrupted: bool,
+    /// the guard the search was abandoned at, if it went beyond [`Limits::depth`] or
+    /// [`Limits::branching`] (for several numbers, that of the first one).
+    pub guard: Option<SearchGuard>,
+    /// partial solutions extended by the search.
+    pub nodes: u64,
+    /// lookups of digits in the dictionary (steps down the trie for [`Algorithm::Trie`], and
+    /// steps of the automaton, one for each digit, for [`Algorithm::AhoCorasick`]).
+    pub lookups: u64,
+    /// complete candidate solutions checked by the [`SolutionFilter`].
+    pub candidates: u64,
+    /// how many numbers had how many solutions (only counted by `Encoder::for_each_solution`).
+    pub solution_counts: SolutionCounts,
+    /// numbers without any digit, e.g. `-/-` (only counted by `Encoder::for_each_solution`).
+    pub empty_numbers: u64,
+    /// with `NoOutput::Checksum`, the checksum of the solutions, adding up like the counts.
+    pub checksum: u64,
+}
+
+impl AddAssign for EncodeStats {
+    fn add_assign(&mut self, other: EncodeStats) {
+        self.solutions += other.solutions;
+        self.rejected += other.rejected;
+        self.write_time += other.write_time;
+        self.truncated |= other.truncated;
+        self.interrupted |= other.interrupted;
+        self.guard = self.guard.or(other.guard);
+        self.nodes += other.nodes;
+        self.lookups += other.lookups;
+        self.candidates += other.candidates;
+        self.solution_counts += other.solution_counts;
+        self.empty_numbers += other.empty_numbers;
+        self.checksum = self.checksum.wrapping_add(other.checksum);
+    }
+}
+
+/// A limit on the shape of a search, beyond which it is abandoned (see [`Limits`]), so that
+/// the same numbers are abandoned in every run, unlike with a timeout.
+///
+/// Where a search is abandoned still depends on the [`Algorithm`], as they do not all go
+/// through the same partial solutions, or in the same order.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum SearchGuard {
+    /// a partial solution had more parts than this.
+    Depth(usize),
+    /// a partial solution could go on in more ways than this.
+    Branching(usize),
+}
+
+impl fmt::Display for SearchGuard {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SearchGuard::Depth(depth) => write!(f, "a partial solution of more than {} parts", depth),
+            SearchGuard::Branching(branching) => write!(f, "more than {} ways to go on from a partial solution", branching),
+        }
+    }
+}
+
+/// The default of [`Limits::depth`] for the encoder: no phone number needs that many parts,
+/// but a search that deep would run out of stack.
+pub const DEFAULT_MAX_DEPTH: usize = 1000;
+
+/// The default of [`Limits::branching`] for the encoder: the words the digits from one
+/// position may start with, which only a dictionary of generated words has that many of.
+pub const DEFAULT_MAX_BRANCHING: usize = 100_000;
+
+/// How many phone numbers had no solution, one, a few or many, showing how well
+/// a dictionary covers the numbers.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SolutionCounts {
+    /// the numbers with as many solutions as the corresponding [`SolutionCounts::LABELS`].
+    pub buckets: [u64; 5],
+}
+
+impl SolutionCounts {
+    pub const LABELS: [&'static str; 5] = ["0", "1", "2-10", "11-100", ">100"];
+
+    /// Counts a number with the given number of solutions.
+    pub fn record(&mut self, solutions: usize) {
+        let bucket = match solutions {
+            0 => 0,
+            1 => 1,
+            2..=10 => 2,
+            11..=100 => 3,
+            _ => 4,
+        };
+        self.buckets[bucket] += 1;
+    }
+
+    pub fn numbers(&self) -> u64 {
+        self.buckets.iter().sum()
+    }
+}
+
+impl AddAssign for SolutionCounts {
+    fn add_assign(&mut self, other: SolutionCounts) {
+        for (count, other) in self.buckets.iter_mut().zip(other.buckets) {
+            *count += other;
+        }
+    }
+}
+
+/// The numbers of digits from which [`Algorithm::Auto`] uses the meet-in-the-middle search.
+pub const SPLIT_THRESHOLD: usize = 40;
+
+/// The algorithms available to search for the encodings of a phone number,
+/// each implemented by a [`SearchStrategy`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Algorithm {
+    /// [`Algorithm::Split`] for numbers with at least [`SPLIT_THRESHOLD`] digits,
+    /// [`Algorithm::Dfs`] otherwise.
+    #[default]
+    Auto,
+    /// the recursive depth-first search, [`RecursiveDfs`].
+    Dfs,
+    /// the same search with an explicit stack, [`IterativeDfs`](crate::iterative::IterativeDfs).
+    Iterative,
+    /// counting the encodings of each suffix first, [`DpCount`](crate::dp::DpCount).
+    DpCount,
+    /// walking a trie of the dictionary, [`TrieWalk`](crate::trie::TrieWalk).
+    Trie,
+    /// the meet-in-the-middle search, [`MeetInTheMiddle`](crate::split::MeetInTheMiddle).
+    Split,
+    /// counting the encodings like [`Algorithm::DpCount`], with the words found in a single
+    /// pass over the digits, [`AhoCorasickDp`](crate::aho_corasick::AhoCorasickDp).
+    AhoCorasick,
+}
+
+impl Algorithm {
+    /// Parses `auto`, `dfs`, `iterative`, `dp-count`, `trie`, `split` or `aho-corasick`.
+    pub fn parse(name: &str) -> Option<Algorithm> {
+        match name {
+            "auto" => Some(Algorithm::Auto),
+            "dfs" => Some(Algorithm::Dfs),
+            "iterative" => Some(Algorithm::Iterative),
+            "dp-count" => Some(Algorithm::DpCount),
+            "trie" => Some(Algorithm::Trie),
+            "split" => Some(Algorithm::Split),
+            "aho-corasick" => Some(Algorithm::AhoCorasick),
+            _ => None,
+        }
+    }
+
+    /// The algorithm used for a number with the given number of digits (never `Auto`).
+    pub fn resolve(self, digits: usize) -> Algorithm {
+        match self {
+            Algorithm::Auto if digits >= SPLIT_THRESHOLD => Algorithm::Split,
+            Algorithm::Auto => Algorithm::Dfs,
+            algorithm => algorithm,
+        }
+    }
+}
+
+/// Words keyed by their encoding (see [`word_to_number`]).
+pub trait Lookup {
+    /// The words whose encoding is `key`, if any.
+    fn words(&self, key: &BigUint) -> Option<&[Word]>;
+
+    /// The words encoding exactly `digits`, all of them as a single word rather than a
+    /// solution of several, ignoring the other characters (like the separators of a number).
+    fn exact(&self, digits: &str) -> &[Word] {
+        let key = digits.chars()
+            .filter_map(|ch| ch.to_digit(10))
+            .fold(BigUint::from(1u32), |key, digit| key * 10u32 + digit);
+        self.words(&key).unwrap_or_default()
+    }
+
+    /// This dictionary as a [`Trie`], if it is one (used by [`TrieWalk`](crate::trie::TrieWalk)).
+    fn as_trie(&self) -> Option<&Trie> {
+        None
+    }
+
+    /// This dictionary as an [`AhoCorasick`] automaton, if it is one (used by
+    /// [`AhoCorasickDp`](crate::aho_corasick::AhoCorasickDp)).
+    fn as_aho_corasick(&self) -> Option<&AhoCorasick> {
+        None
+    }
+
+    /// The words encoding each prefix of `digits`, with the length of the prefix, shortest
+    /// prefixes first: the words a solution of the digits may start with.
+    ///
+    /// These are not the words whose encoding starts with the digits, as T9-style completion
+    /// needs: those are only found in a trie, by [`Trie::completions`].
+    fn matches_at<'a>(&'a self, digits: &'a [char]) -> Matches<'a, Self> {
+        let walk = match self.as_trie() {
+            Some(trie) => MatchWalk::Trie { trie, node: 0 },
+            None => MatchWalk::Keys { dict: self, key: BigUint::from(1u32) },
+        };
+        Matches { digits, len: 0, walk }
+    }
+}
+
+/// The iterator of [`Lookup::matches_at`].
+pub struct Matches<'a, D: ?Sized> {
+    digits: &'a [char],
+    /// the length of the prefix looked up last.
+    len: usize,
+    walk: MatchWalk<'a, D>,
+}
+
+enum MatchWalk<'a, D: ?Sized> {
+    /// looking up the encoding of each prefix, as it may be the key of a word even if no
+    /// word has the encoding of a shorter prefix.
+    Keys { dict: &'a D, key: BigUint },
+    /// walking down a trie, until no word starts with the prefix.
+    Trie { trie: &'a Trie, node: usize },
+}
+
+impl<'a, D: Lookup + ?Sized> Iterator for Matches<'a, D> {
+    type Item = (usize, &'a [Word]);
+
+    fn next(&mut self) -> Option<(usize, &'a [Word])> {
+        while self.len < self.digits.len() {
+            let digit = self.digits[self.len];
+            self.len += 1;
+            let words = match &mut self.walk {
+                MatchWalk::Keys { dict, key } => {
+                    *key = core::mem::take(key) * 10u32 + digit.to_digit(10)?;
+                    dict.words(key)
+                }
+                MatchWalk::Trie { trie, node } => {
+                    *node = trie.child(*node, digit)?;
+                    trie.words_of(*node)
+                }
+            };
+            if let Some(words) = words {
+                return Some((self.len, words));
+            }
+        }
+        None
+    }
+}
+
+#[cfg(feature = "std")]
+impl<S: core::hash::BuildHasher> Lookup for std::collections::HashMap<BigUint, Vec<Word>, S> {
+    #[inline]
+    fn words(&self, key: &BigUint) -> Option<&[Word]> {
+        self.get(key).map(Vec::as_slice)
+    }
+}
+
+impl Lookup for BTreeMap<BigUint, Vec<Word>> {
+    #[inline]
+    fn words(&self, key: &BigUint) -> Option<&[Word]> {
+        self.get(key).map(Vec::as_slice)
+    }
+}
+
+/// The words file compiled into the program, if the `PHONE_ENCODER_EMBED_WORDS` environment
+/// variable was set to its path when building it.
+#[cfg(embedded_words)]
+pub const EMBEDDED_WORDS: Option<&[u8]> = Some(include_bytes!(concat!(env!("OUT_DIR"), "/embedded_words.txt")));
+#[cfg(not(embedded_words))]
+pub const EMBEDDED_WORDS: Option<&[u8]> = None;
+
+/// Loads the words of a words file given as a byte slice, e.g. with `include_bytes!`.
+///
+/// Each line holds one word; empty lines, lines that are not valid UTF-8 and words with a
+/// letter the mapping cannot encode (see [`Mapping::unmapped_letter`]) are skipped.
+pub fn words_from_bytes(bytes: &[u8], mapping: &Mapping) -> BTreeMap<BigUint, Vec<Word>> {
+    let mut dict: BTreeMap<BigUint, Vec<Word>> = BTreeMap::new();
+    for line in bytes.split(|b| *b == b'\n') {
+        let Ok(line) = core::str::from_utf8(line) else { continue };
+        let word = line.trim();
+        if word.is_empty() || mapping.unmapped_letter(word).is_some() {
+            continue;
+        }
+        dict.entry(word_to_number(word, mapping)).or_default().push(Word::new(word.into()));
+    }
+    dict
+}
+
+/// How many digits [`word_to_number`] collects in a `u64` before adding them to the number.
+const CHUNK_DIGITS: u32 = 18;
+
+/// Encodes a word as a number starting with a `1` (so that leading zeroes are not lost).
+///
+/// Panics if the word has a letter the mapping cannot encode, which
+/// [`Mapping::unmapped_letter`] finds beforehand.
+pub fn word_to_number(word: &str, mapping: &Mapping) -> BigUint {
+    // the digits are collected in a u64, which only gets added to the (much slower)
+    // BigUint when it cannot hold more digits
+    let mut n = BigUint::default();
+    let mut chunk: u64 = 1;
+    let mut chunk_digits = 0;
+    let mut push = |digit: u32| {
+        chunk = chunk * 10 + u64::from(digit);
+        chunk_digits += 1;
+        if chunk_digits == CHUNK_DIGITS {
+            n = core::mem::take(&mut n) * 10u64.pow(CHUNK_DIGITS) + chunk;
+            chunk = 0;
+            chunk_digits = 0;
+        }
+    };
+    if !mapping.encode_ascii(word, &mut push) {
+        for ch in word.chars() {
+            mapping.encode_char(ch, &mut push);
+        }
+    }
+    n * 10u64.pow(chunk_digits) + chunk
+}
+
+/// Appends the characters of a phone number that are encoded (the digits) to `digits`,
+/// skipping separators like `-` and `/`.
+#[inline]
+pub fn extract_digits<E: Extend<char>>(num: &str, digits: &mut E) {
+    if num.is_ascii() {
+        // checking bytes is much cheaper than decoding and classifying chars
+        digits.extend(num.bytes().filter(u8::is_ascii_alphanumeric).map(char::from));
+    } else {
+        digits.extend(num.chars().filter(|ch| ch.is_alphanumeric()));
+    }
+}
+
+/// The parts of a solution for the given digits, each with the digits it encodes (see
+/// [`Segment`]).
+pub fn segments<'a, D: Lookup + ?Sized>(digits: &[char], parts: &[WordOrDigit<'a>], dict: &D) -> Vec<Segment<'a>> {
+    parts.iter()
+        .zip(digit_spans(digits, parts, dict))
+        .map(|(part, span)| Segment { part: *part, span })
+        .collect()
+}
+
+/// The positions of the digits encoded by each part of a solution for the given digits.
+///
+/// The search does not keep track of them, as they are only needed to annotate solutions,
+/// so the digits of each word are found again by looking up the digits after the previous part.
+pub(crate) fn digit_spans<D: Lookup + ?Sized>(digits: &[char], parts: &[WordOrDigit], dict: &D) -> Vec<Range<usize>> {
+    let mut spans = Vec::with_capacity(parts.len());
+    let mut start = 0;
+    for part in parts {
+        let end = match part {
+            WordOrDigit::Digit(_) => start + 1,
+            WordOrDigit::Word(text) => {
+                let mut n = BigUint::from(1u32);
+                (start..digits.len())
+                    .map_while(|i| Some((i, nth_digit(digits, i)?)))
+                    .find(|&(_, digit)| {
+                        n = core::mem::take(&mut n) * 10u32 + digit;
+                        dict.words(&n).is_some_and(|words| words.iter().any(|word| word.text == *text))
+                    })
+                    .map_or(start, |(i, _)| i + 1)
+            }
+        };
+        spans.push(start..end);
+        start = end;
+    }
+    spans
+}
+
+/// Called with each solution accepted by the [`SolutionFilter`] during a search.
+pub type OnSolution<'w, E> = dyn FnMut(&[WordOrDigit]) -> Result<(), E> + 'w;
+
+/// Called with each candidate solution rejected by the [`SolutionFilter`] during a search.
+pub type OnRejected<'w, E> = dyn FnMut(&[WordOrDigit], &Rejection) -> Result<(), E> + 'w;
+
+/// An algorithm searching for the encodings of a phone number.
+///
+/// All implementations find the same solutions, in the same order.
+pub trait SearchStrategy {
+    /// Finds the encodings of the digits of a phone number, calling `on_solution` with each
+    /// one accepted by the filter and `on_rejected` with the others, within the given limits.
+    fn search<D: Lookup + ?Sized, E>(
+        &self,
+        digits: &[char],
+        dict: &D,
+        filter: &dyn SolutionFilter,
+        limits: Limits,
+        on_solution: &mut OnSolution<E>,
+        on_rejected: &mut OnRejected<E>,
+    ) -> Result<EncodeStats, E>;
+
+    /// Like [`SearchStrategy::search`], also telling `observer` what the search does.
+    ///
+    /// Unless a strategy reports more, only the solutions accepted and rejected are reported.
+    #[allow(clippy::too_many_arguments)]
+    fn search_observed<D: Lookup + ?Sized, E, O: SearchObserver + ?Sized>(
+        &self,
+        digits: &[char],
+        dict: &D,
+        filter: &dyn SolutionFilter,
+        limits: Limits,
+        observer: &mut O,
+        on_solution: &mut OnSolution<E>,
+        on_rejected: &mut OnRejected<E>,
+    ) -> Result<EncodeStats, E> {
+        let observer = RefCell::new(observer);
+        self.search(digits, dict, filter, limits, &mut |words| {
+            observer.borrow_mut().solution_accepted(words);
+            on_solution(words)
+        }, &mut |words, rejection| {
+            observer.borrow_mut().solution_rejected(words, rejection);
+            on_rejected(words, rejection)
+        })
+    }
+}
+
+/// Watches a search as it goes, e.g. to trace it or to show it step by step.
+///
+/// Every method does nothing by default, and the searches without an observer use
+/// [`NoObserver`], for which the calls compile to nothing.
+pub trait SearchObserver {
+    /// The search extends the partial solution `parts`, covering the digits before `start`.
+    fn node_entered(&mut self, _start: usize, _parts: &[WordOrDigit]) {}
+
+    /// A word encodes the digits from `start` to `end` (excluded).
+    fn word_matched(&mut self, _start: usize, _end: usize, _word: &str) {}
+
+    /// The digit at `position` is kept as it is, as no word encodes the digits from there.
+    fn digit_inserted(&mut self, _position: usize, _digit: char) {}
+
+    /// A complete candidate solution is accepted by the [`SolutionFilter`].
+    fn solution_accepted(&mut self, _solution: &[WordOrDigit]) {}
+
+    /// A complete candidate solution is rejected by the [`SolutionFilter`].
+    fn solution_rejected(&mut self, _solution: &[WordOrDigit], _rejection: &Rejection) {}
+}
+
+/// The observer of the searches that are not observed, doing nothing.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct NoObserver;
+
+impl SearchObserver for NoObserver {}
+
+impl<O: SearchObserver + ?Sized> SearchObserver for &mut O {
+    #[inline]
+    fn node_entered(&mut self, start: usize, parts: &[WordOrDigit]) {
+        (**self).node_entered(start, parts)
+    }
+
+    #[inline]
+    fn word_matched(&mut self, start: usize, end: usize, word: &str) {
+        (**self).word_matched(start, end, word)
+    }
+
+    #[inline]
+    fn digit_inserted(&mut self, position: usize, digit: char) {
+        (**self).digit_inserted(position, digit)
+    }
+
+    #[inline]
+    fn solution_accepted(&mut self, solution: &[WordOrDigit]) {
+        (**self).solution_accepted(solution)
+    }
+
+    #[inline]
+    fn solution_rejected(&mut self, solution: &[WordOrDigit], rejection: &Rejection) {
+        (**self).solution_rejected(solution, rejection)
+    }
+}
+
+/// Limits on a search.
+#[derive(Clone, Copy)]
+pub struct Limits<'a> {
+    /// the maximum number of solutions to find.
+    pub solutions: usize,
+    /// called from time to time during the search, which is abandoned if it returns `true`.
+    pub interrupt: Option<&'a dyn Fn() -> bool>,
+    /// the most parts (words and digits) of a partial solution, beyond which the search is
+    /// abandoned (see [`SearchGuard::Depth`]).
+    pub depth: usize,
+    /// the most ways to go on from a partial solution, i.e. words encoding the digits that
+    /// follow it, beyond which the search is abandoned (see [`SearchGuard::Branching`]).
+    pub branching: usize,
+}
+
+impl Limits<'_> {
+    /// Finding at most the given number of solutions, without interruptions or guards.
+    pub fn solutions(solutions: usize) -> Limits<'static> {
+        Limits { solutions, interrupt: None, depth: usize::MAX, branching: usize::MAX }
+    }
+}
+
+impl fmt::Debug for Limits<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Limits")
+            .field("solutions", &self.solutions)
+            .field("interrupt", &self.interrupt.is_some())
+            .field("depth", &self.depth)
+            .field("branching", &self.branching)
+            .finish()
+    }
+}
+
+/// How many times [`Solutions::is_full`] is called between checks of [`Limits::interrupt`].
+const INTERRUPT_CHECK_INTERVAL: u32 = 1024;
+
+/// Checks the complete candidate solutions found by a [`SearchStrategy`],
+/// passing them on and counting them.
+pub(crate) struct Solutions<'a, 'w, E, O = NoObserver> {
+    digits: &'a [char],
+    filter: &'a dyn SolutionFilter,
+    on_solution: &'w mut OnSolution<'w, E>,
+    on_rejected: &'w mut OnRejected<'w, E>,
+    /// how many more solutions may be found.
+    remaining: usize,
+    interrupt: Option<&'a dyn Fn() -> bool>,
+    /// calls of `is_full` left until the next check of `interrupt`.
+    until_interrupt_check: u32,
+    max_depth: usize,
+    max_branching: usize,
+    pub(crate) stats: EncodeStats,
+    pub(crate) observer: O,
+}
+
+impl<'a, 'w, E> Solutions<'a, 'w, E> {
+    pub(crate) fn new(
+        digits: &'a [char],
+        filter: &'a dyn SolutionFilter,
+        limits: Limits<'a>,
+        on_solution: &'w mut OnSolution<'w, E>,
+        on_rejected: &'w mut OnRejected<'w, E>,
+    ) -> Solutions<'a, 'w, E> {
+        Solutions::observed(digits, filter, limits, NoObserver, on_solution, on_rejected)
+    }
+}
+
+impl<'a, 'w, E, O: SearchObserver> Solutions<'a, 'w, E, O> {
+    pub(crate) fn observed(
+        digits: &'a [char],
+        filter: &'a dyn SolutionFilter,
+        limits: Limits<'a>,
+        observer: O,
+        on_solution: &'w mut OnSolution<'w, E>,
+        on_rejected: &'w mut OnRejected<'w, E>,
+    ) -> Solutions<'a, 'w, E, O> {
+        Solutions {
+            digits,
+            filter,
+            on_solution,
+            on_rejected,
+            remaining: limits.solutions,
+            interrupt: limits.interrupt,
+            until_interrupt_check: INTERRUPT_CHECK_INTERVAL,
+            max_depth: limits.depth,
+            max_branching: limits.branching,
+            stats: EncodeStats::default(),
+            observer,
+        }
+    }
+
+    /// Whether as many solutions as allowed were found, or the search was interrupted,
+    /// so the search must stop.
+    #[inline]
+    pub(crate) fn is_full(&mut self) -> bool {
+        if self.remaining == 0 {
+            return true;
+        }
+        if let Some(interrupt) = self.interrupt {
+            self.until_interrupt_check -= 1;
+            if self.until_interrupt_check == 0 {
+                self.until_interrupt_check = INTERRUPT_CHECK_INTERVAL;
+                if interrupt() {
+                    self.stats.interrupted = true;
+                    self.remaining = 0;
+                    return true;
+                }
+            }
+        }
+        false
+    }
+
+    /// Whether a partial solution of `depth` parts is beyond [`Limits::depth`], which
+    /// abandons the search.
+    #[inline]
+    pub(crate) fn too_deep(&mut self, depth: usize) -> bool {
+        depth > self.max_depth && self.abandon(SearchGuard::Depth(self.max_depth))
+    }
+
+    /// Whether the `branches`th way found to go on from a partial solution is beyond
+    /// [`Limits::branching`], which abandons the search.
+    #[inline]
+    pub(crate) fn too_wide(&mut self, branches: usize) -> bool {
+        branches > self.max_branching && self.abandon(SearchGuard::Branching(self.max_branching))
+    }
+
+    #[cold]
+    fn abandon(&mut self, guard: SearchGuard) -> bool {
+        self.stats.guard = Some(guard);
+        self.remaining = 0;
+        true
+    }
+
+    /// Counts a partial solution extended by the search, with the dictionary lookups it took.
+    #[inline]
+    pub(crate) fn visit(&mut self, lookups: usize) {
+        self.stats.nodes += 1;
+        self.stats.lookups += lookups as u64;
+    }
+
+    pub(crate) fn check(&mut self, words: &[WordOrDigit]) -> Result<(), E> {
+        self.stats.candidates += 1;
+        match self.filter.check(self.digits, words) {
+            Ok(()) => {
+                self.remaining -= 1;
+                self.stats.solutions += 1;
+                self.observer.solution_accepted(words);
+                (self.on_solution)(words)
+            }
+            Err(rejection) => {
+                self.stats.rejected += 1;
+                self.observer.solution_rejected(words, &rejection);
+                (self.on_rejected)(words, &rejection)
+            }
+        }
+    }
+}
+
+/// The recursive depth-first search ported from Norvig's solution.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RecursiveDfs;
+
+impl SearchStrategy for RecursiveDfs {
+    fn search<D: Lookup + ?Sized, E>(
+        &self,
+        digits: &[char],
+        dict: &D,
+        filter: &dyn SolutionFilter,
+        limits: Limits,
+        on_solution: &mut OnSolution<E>,
+        on_rejected: &mut OnRejected<E>,
+    ) -> Result<EncodeStats, E> {
+        search(digits, dict, filter, limits, on_solution, on_rejected)
+    }
+
+    /// Reports everything the search does.
+    #[allow(clippy::too_many_arguments)]
+    fn search_observed<D: Lookup + ?Sized, E, O: SearchObserver + ?Sized>(
+        &self,
+        digits: &[char],
+        dict: &D,
+        filter: &dyn SolutionFilter,
+        limits: Limits,
+        observer: &mut O,
+        on_solution: &mut OnSolution<E>,
+        on_rejected: &mut OnRejected<E>,
+    ) -> Result<EncodeStats, E> {
+        search_observed(digits, dict, filter, limits, observer, on_solution, on_rejected)
+    }
+}
+
+/// Finds the encodings of the digits of a phone number with the [`RecursiveDfs`].
+pub fn search<D: Lookup + ?Sized, E>(
+    digits: &[char],
+    dict: &D,
+    filter: &dyn SolutionFilter,
+    limits: Limits,
+    on_solution: &mut OnSolution<E>,
+    on_rejected: &mut OnRejected<E>,
+) -> Result<EncodeStats, E> {
+    let mut search = Search {
+        digits,
+        dict,
+        solutions: Solutions::new(digits, filter, limits, on_solution, on_rejected),
+    };
+    find_translations(&mut search, 0, &mut Vec::with_capacity(digits.len()))?;
+    Ok(search.solutions.stats)
+}
+
+/// Like [`search`], telling `observer` about every step of the search.
+pub fn search_observed<D: Lookup + ?Sized, E, O: SearchObserver + ?Sized>(
+    digits: &[char],
+    dict: &D,
+    filter: &dyn SolutionFilter,
+    limits: Limits,
+    observer: &mut O,
+    on_solution: &mut OnSolution<E>,
+    on_rejected: &mut OnRejected<E>,
+) -> Result<EncodeStats, E> {
+    let mut search = Search {
+        digits,
+        dict,
+        solutions: Solutions::observed(digits, filter, limits, observer, on_solution, on_rejected),
+    };
+    find_translations(&mut search, 0, &mut Vec::with_capacity(digits.len()))?;
+    Ok(search.solutions.stats)
+}
+
+/// Like [`search`], but allocates the partial solutions in the given arena,
+/// which may be reset once the search is done.
+#[cfg(feature = "arena")]
+pub fn search_in<D: Lookup + ?Sized, E>(
+    arena: &bumpalo::Bump,
+    digits: &[char],
+    dict: &D,
+    filter: &dyn SolutionFilter,
+    limits: Limits,
+    on_solution: &mut OnSolution<E>,
+    on_rejected: &mut OnRejected<E>,
+) -> Result<EncodeStats, E> {
+    let mut search = Search {
+        digits,
+        dict,
+        solutions: Solutions::new(digits, filter, limits, on_solution, on_rejected),
+    };
+    find_translations(&mut search, 0, &mut bumpalo::collections::Vec::with_capacity_in(digits.len(), arena))?;
+    Ok(search.solutions.stats)
+}
+
+/// The parts of a solution found so far, pushed as the search goes deeper and popped as it
+/// backtracks, so that it does not allocate for each solution once as long as the number.
+trait Parts<'a>: Deref<Target = [WordOrDigit<'a>]> {
+    fn push(&mut self, part: WordOrDigit<'a>);
+    fn pop(&mut self);
+}
+
+impl<'a> Parts<'a> for Vec<WordOrDigit<'a>> {
+    #[inline]
+    fn push(&mut self, part: WordOrDigit<'a>) {
+        Vec::push(self, part)
+    }
+
+    #[inline]
+    fn pop(&mut self) {
+        Vec::pop(self);
+    }
+}
+
+#[cfg(feature = "arena")]
+impl<'a> Parts<'a> for bumpalo::collections::Vec<'_, WordOrDigit<'a>> {
+    #[inline]
+    fn push(&mut self, part: WordOrDigit<'a>) {
+        bumpalo::collections::Vec::push(self, part)
+    }
+
+    #[inline]
+    fn pop(&mut self) {
+        bumpalo::collections::Vec::pop(self);
+    }
+}
+
+/// State of the search for the encodings of a single phone number.
+struct Search<'a, 'w, D: ?Sized, E, O = NoObserver> {
+    digits: &'a [char],
+    dict: &'a D,
+    solutions: Solutions<'a, 'w, E, O>,
+}
+
+fn find_translations<'a, D: Lookup + ?Sized, E, O: SearchObserver, P: Parts<'a>>(
+    search: &mut Search<'a, '_, D, E, O>,
+    start: usize,
+    words: &mut P,
+) -> Result<(), E> {
+    if search.solutions.is_full() || search.solutions.too_deep(words.len()) {
+        return Ok(());
+    }
+    let digits = search.digits;
+    if start >= digits.len() {
+        return search.solutions.check(words);
+    }
+    let dict = search.dict;
+    search.solutions.visit(digits.len() - start);
+    search.solutions.observer.node_entered(start, words);
+    let mut branches = 0;
+    for (len, found_words) in dict.matches_at(&digits[start..]) {
+        for word in found_words {
+            branches += 1;
+            if search.solutions.too_wide(branches) {
+                return Ok(());
+            }
+            search.solutions.observer.word_matched(start, start + len, &word.text);
+            words.push(WordOrDigit::Word(&word.text));
+            find_translations(search, start + len, words)?;
+            words.pop();
+        }
+    }
+    if branches == 0 && !words.last().map(|w| w.is_digit()).unwrap_or(false) {
+        search.solutions.observer.digit_inserted(start, digits[start]);
+        words.push(WordOrDigit::Digit(digits[start]));
+        let result = find_translations(search, start + 1, words);
+        words.pop();
+        result
+    } else {
+        Ok(())
+    }
+}
+
+/// The value of the digit at `i`, if there is a digit there.
+pub(crate) fn nth_digit(digits: &[char], i: usize) -> Option<u32> {
+    digits.get(i)?.to_digit(10)
+}
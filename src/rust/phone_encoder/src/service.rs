@@ -0,0 +1,89 @@
+//! Running the servers (`--resp`, `grpc-serve`, `--kafka-in`) as systemd services: installing
+//! a unit for them (`--install-service`), telling systemd when they are ready to serve, and
+//! logging with the priorities of the journal.
+
+use std::env;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use phone_encoder::platform;
+use phone_encoder::{FileContext, FileRole};
+
+use crate::cli::Options;
+
+/// Where `--install-service` writes the unit by default.
+pub const DEFAULT_UNIT: &str = "/etc/systemd/system/phone_encoder.service";
+
+/// The priority of a message, as understood by the journal.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Priority {
+    Error = 3,
+    Info = 6,
+}
+
+/// Writes a message to stderr, prefixed with its priority if stderr goes to the journal (a
+/// service started by systemd), which then keeps it with the message instead of the prefix.
+pub fn log(priority: Priority, message: fmt::Arguments) {
+    if env::var_os("JOURNAL_STREAM").is_some() {
+        // each line is a message of its own
+        for line in message.to_string().lines() {
+            eprintln!("<{}>{}", priority as u8, line);
+        }
+    } else {
+        eprintln!("{}", message);
+    }
+}
+
+/// Tells systemd that the service is ready (`sd_notify(0, "READY=1")`), if it was started by
+/// systemd as a service of type `notify`, and does nothing otherwise.
+pub fn notify_ready() {
+    if let Err(e) = platform::notify_service("READY=1") {
+        log(Priority::Error, format_args!("Cannot notify systemd: {}", e));
+    }
+}
+
+/// Writes a unit running this program with the arguments of `options` as a service to
+/// `unit_file`, and prints how to start it.
+pub fn install(options: &Options, unit_file: &Path) -> io::Result<()> {
+    let program = env::current_exe()?;
+    let directory = env::current_dir()?;
+    let unit = format!(
+        "[Unit]\n\
+         Description=phone_encoder {}\n\
+         After=network.target\n\
+         \n\
+         [Service]\n\
+         Type=notify\n\
+         # the relative paths of the arguments are relative to this directory\n\
+         WorkingDirectory={}\n\
+         ExecStart={}\n\
+         Restart=on-failure\n\
+         \n\
+         [Install]\n\
+         WantedBy=multi-user.target\n",
+        options.service_args.join(" ").replace('%', "%%"),
+        directory.display(),
+        std::iter::once(program.to_string_lossy().into_owned())
+            .chain(options.service_args.iter().cloned())
+            .map(|arg| quote(&arg))
+            .collect::<Vec<_>>()
+            .join(" "),
+    );
+    fs::write(unit_file, unit).in_file(FileRole::Output, unit_file)?;
+    let name = unit_file.file_name().map_or_else(String::new, |name| name.to_string_lossy().into_owned());
+    println!("Wrote {}, start the service with:\n    systemctl daemon-reload && systemctl enable --now {}",
+             unit_file.display(), name);
+    Ok(())
+}
+
+/// Quotes an argument of `ExecStart` if needed, with the escapes of systemd (where `%` and
+/// `$` are also special).
+fn quote(arg: &str) -> String {
+    let arg = arg.replace('%', "%%").replace('$', "$$");
+    if !arg.is_empty() && !arg.contains(|c: char| c.is_whitespace() || matches!(c, '"' | '\'' | '\\' | ';')) {
+        return arg;
+    }
+    format!("\"{}\"", arg.replace('\\', "\\\\").replace('"', "\\\""))
+}
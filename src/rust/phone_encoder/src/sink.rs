@@ -0,0 +1,115 @@
+//! What is done with the solutions the search of an [`Encoder`](crate::Encoder) finds: it
+//! hands each one over as a [`SolutionRecord`], the parts of the solution as found in the
+//! dictionary and the number, and a [`SolutionSink`] formats it, or only counts or checksums
+//! it, so that the modes not writing the solutions skip formatting altogether (see
+//! [`NoOutput`](crate::NoOutput)).
+
+use std::io::{self, Write};
+use std::ops::Range;
+
+use crate::dict::Dictionary;
+use crate::format::{OutputFormat, Solution};
+use crate::search::{digit_spans, WordOrDigit};
+
+/// A solution as the search found it, before it is formatted.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SolutionRecord<'a> {
+    /// the index of the solution among those of the number, from 0.
+    pub index: usize,
+    pub parts: &'a [WordOrDigit<'a>],
+}
+
+/// Consumes the solutions of a number, in the order they are found.
+pub trait SolutionSink {
+    fn solution(&mut self, record: &SolutionRecord) -> io::Result<()>;
+}
+
+/// Writes the solutions of a number with an [`OutputFormat`].
+pub struct Formatter<'a> {
+    format: &'a dyn OutputFormat,
+    out: &'a mut dyn Write,
+    /// the number, as written in the solutions.
+    number: &'a str,
+    columns: &'a [&'a str],
+    /// the digits of the number and the dictionary, to annotate the solutions with the
+    /// digits of each part.
+    annotated: Option<(&'a [char], &'a Dictionary)>,
+}
+
+impl<'a> Formatter<'a> {
+    pub fn new(format: &'a dyn OutputFormat, out: &'a mut dyn Write, number: &'a str, columns: &'a [&'a str]) -> Formatter<'a> {
+        Formatter { format, out, number, columns, annotated: None }
+    }
+
+    /// Annotates the solutions with the digits of each part, found again in the dictionary.
+    pub fn annotated(mut self, digits: &'a [char], dict: &'a Dictionary) -> Formatter<'a> {
+        self.annotated = Some((digits, dict));
+        self
+    }
+}
+
+impl SolutionSink for Formatter<'_> {
+    fn solution(&mut self, record: &SolutionRecord) -> io::Result<()> {
+        let (digits, spans): (&[char], Vec<Range<usize>>) = match self.annotated {
+            Some((digits, dict)) => (digits, digit_spans(digits, record.parts, dict)),
+            None => (&[], Vec::new()),
+        };
+        let solution = Solution {
+            number: self.number,
+            parts: record.parts,
+            index: record.index,
+            digits,
+            spans: &spans,
+            columns: self.columns,
+        };
+        self.format.render_solution(self.out, &solution)
+    }
+}
+
+/// Discards the solutions, which the search still counts.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Discard;
+
+impl SolutionSink for Discard {
+    fn solution(&mut self, _record: &SolutionRecord) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Checksums the solutions of a number instead of writing them: `sum` adds up a hash of
+/// each solution with the number, so that the sums of all numbers add up to the same
+/// checksum whatever the order they were encoded in (e.g. with several threads), and any
+/// solution found or missed changes it.
+#[derive(Clone, Debug)]
+pub struct Checksum<'a> {
+    number: &'a str,
+    pub sum: u64,
+}
+
+impl<'a> Checksum<'a> {
+    pub fn new(number: &'a str) -> Checksum<'a> {
+        Checksum { number, sum: 0 }
+    }
+}
+
+impl SolutionSink for Checksum<'_> {
+    fn solution(&mut self, record: &SolutionRecord) -> io::Result<()> {
+        let mut hash = fnv1a(FNV_OFFSET, self.number.as_bytes());
+        for part in record.parts {
+            hash = fnv1a(hash, b" ");
+            hash = match part {
+                WordOrDigit::Word(word) => fnv1a(hash, word.as_bytes()),
+                WordOrDigit::Digit(digit) => fnv1a(hash, &[*digit as u8]),
+            };
+        }
+        self.sum = self.sum.wrapping_add(hash);
+        Ok(())
+    }
+}
+
+const FNV_OFFSET: u64 = 0xcbf29ce484222325;
+
+/// Continues the 64-bit FNV-1a hash `hash` with `bytes`.
+fn fnv1a(hash: u64, bytes: &[u8]) -> u64 {
+    bytes.iter().fold(hash, |hash, byte| (hash ^ u64::from(*byte)).wrapping_mul(0x100000001b3))
+}
@@ -0,0 +1,109 @@
+//! Copy-on-write snapshots of a value shared by concurrent readers, like the [`Encoder`]
+//! of a server, so that updates never block the searches in progress.
+//!
+//! [`Encoder`]: crate::encoder::Encoder
+
+use std::sync::{Arc, Mutex, RwLock};
+
+/// A value that readers take a snapshot of, and that writers replace with a new version.
+///
+/// A reader keeps using its snapshot for as long as it needs (e.g. for a whole request),
+/// even if a new version is published meanwhile. The lock is only held to clone or swap
+/// the [`Arc`], never while the value is used.
+pub struct Snapshot<T> {
+    current: RwLock<Arc<T>>,
+    /// serializes [`Snapshot::update`]s, so that none of them is lost.
+    writer: Mutex<()>,
+}
+
+impl<T> Snapshot<T> {
+    pub fn new(value: T) -> Snapshot<T> {
+        Snapshot { current: RwLock::new(Arc::new(value)), writer: Mutex::new(()) }
+    }
+
+    /// The current version.
+    pub fn load(&self) -> Arc<T> {
+        self.current.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// Publishes a new version, returning the previous one.
+    pub fn store(&self, value: T) -> Arc<T> {
+        let value = Arc::new(value);
+        std::mem::replace(&mut *self.current.write().unwrap_or_else(|e| e.into_inner()), value)
+    }
+
+    /// Publishes the version made by `update` from the current one, returning it.
+    ///
+    /// Readers go on using the current version while `update` runs.
+    pub fn update<F: FnOnce(&T) -> T>(&self, update: F) -> Arc<T> {
+        let _writer = self.writer.lock().unwrap_or_else(|e| e.into_inner());
+        let value = Arc::new(update(&self.load()));
+        *self.current.write().unwrap_or_else(|e| e.into_inner()) = value.clone();
+        value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc;
+    use std::thread;
+
+    use crate::search::words_from_bytes;
+    use crate::{Encoder, Mapping};
+
+    use super::*;
+
+    #[test]
+    fn readers_keep_their_snapshot_once_another_is_published() {
+        let snapshot = Snapshot::new(vec![1]);
+        let before = snapshot.load();
+        assert_eq!(*snapshot.store(vec![1, 2]), [1]);
+        assert_eq!(*before, [1]);
+        assert_eq!(*snapshot.load(), [1, 2]);
+        let updated = snapshot.update(|value| [value.as_slice(), &[3]].concat());
+        assert_eq!(*updated, [1, 2, 3]);
+        assert_eq!(*before, [1]);
+        assert!(Arc::ptr_eq(&snapshot.load(), &updated));
+    }
+
+    #[test]
+    fn readers_go_on_while_an_update_runs() {
+        let snapshot = Snapshot::new(1);
+        let (loaded, on_loaded) = mpsc::channel();
+        thread::scope(|scope| {
+            snapshot.update(|value| {
+                // a reader is not blocked by the update in progress, and sees the current version
+                scope.spawn(|| loaded.send(*snapshot.load()).unwrap());
+                assert_eq!(on_loaded.recv().unwrap(), 1);
+                value + 1
+            });
+        });
+        assert_eq!(*snapshot.load(), 2);
+    }
+
+    #[test]
+    fn no_update_is_lost() {
+        let snapshot = Snapshot::new(0);
+        thread::scope(|scope| {
+            for _ in 0..8 {
+                scope.spawn(|| {
+                    for _ in 0..100 {
+                        snapshot.update(|value| value + 1);
+                    }
+                });
+            }
+        });
+        assert_eq!(*snapshot.load(), 800);
+    }
+
+    #[test]
+    fn searches_keep_the_dictionary_they_started_with() {
+        let words = |bytes: &[u8]| words_from_bytes(bytes, &Mapping::default()).into_iter().collect();
+        let snapshot = Snapshot::new(Encoder::new(words(b"mir\nTor\n")));
+        let encoder = snapshot.load();
+        snapshot.update(|encoder| encoder.with_dictionary(words(b"mir\nTor\nMix\n")));
+        let count = |encoder: &Encoder| encoder.for_each_solution("5624-82", &mut |_| Ok(())).unwrap().solutions;
+        assert_eq!(count(&encoder), 1);
+        assert_eq!(count(&snapshot.load()), 2);
+    }
+}
@@ -0,0 +1,240 @@
+//! Sorting more lines than fit in memory: runs of lines are sorted in memory and spilled
+//! to temporary files, then merged.
+//!
+//! The encoder writes the solutions of each number together, so the output is sorted by
+//! number when the numbers are: only the numbers need sorting, not the (much larger) output.
+
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::input::read_line;
+
+/// The default memory used for the lines of a run, in bytes.
+pub const DEFAULT_RUN_BYTES: usize = 256 * 1024 * 1024;
+
+/// Tells apart the temporary files of the sorts of a process.
+static RUN_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+/// Sorts lines, in the byte order of their UTF-8 encoding, using at most about `run_bytes`
+/// of memory for them.
+pub struct ExternalSort {
+    run_bytes: usize,
+    temp_dir: PathBuf,
+    lines: Vec<String>,
+    /// the size of `lines`, counting the bookkeeping of each line.
+    bytes: usize,
+    runs: Vec<Run>,
+}
+
+/// A sorted run of lines spilled to a temporary file, deleted when dropped.
+struct Run {
+    path: PathBuf,
+    reader: BufReader<File>,
+}
+
+impl Drop for Run {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+impl ExternalSort {
+    /// Spills runs of lines to `temp_dir` once they take more than `run_bytes` of memory.
+    pub fn new<P: AsRef<Path>>(run_bytes: usize, temp_dir: P) -> ExternalSort {
+        ExternalSort { run_bytes, temp_dir: temp_dir.as_ref().to_path_buf(), lines: Vec::new(), bytes: 0, runs: Vec::new() }
+    }
+
+    pub fn push(&mut self, line: &str) -> io::Result<()> {
+        self.bytes += line.len() + size_of::<String>();
+        self.lines.push(line.to_string());
+        if self.bytes > self.run_bytes {
+            self.spill()?;
+        }
+        Ok(())
+    }
+
+    fn spill(&mut self) -> io::Result<()> {
+        self.lines.sort_unstable();
+        let name = format!("phone_encoder-sort-{}-{}", process::id(), RUN_COUNT.fetch_add(1, Ordering::Relaxed));
+        let path = self.temp_dir.join(name);
+        match write_run(&path, self.lines.drain(..)).and_then(|()| File::open(&path)) {
+            Ok(file) => self.runs.push(Run { path, reader: BufReader::new(file) }),
+            Err(e) => {
+                let _ = fs::remove_file(&path);
+                return Err(io::Error::new(e.kind(), format!("temporary file '{}': {}", path.display(), e)));
+            }
+        }
+        self.bytes = 0;
+        Ok(())
+    }
+
+    /// The lines, sorted, merging the runs spilled to temporary files with those still in memory.
+    pub fn finish(mut self) -> io::Result<SortedLines> {
+        self.lines.sort_unstable();
+        let mut merged = SortedLines {
+            heap: BinaryHeap::with_capacity(self.runs.len() + 1),
+            runs: self.runs,
+            memory: self.lines.into_iter(),
+            line: Vec::new(),
+            pos: 0,
+        };
+        for index in 0..=merged.runs.len() {
+            merged.refill(index)?;
+        }
+        Ok(merged)
+    }
+}
+
+/// The lines of an [`ExternalSort`], in order, each followed by `\n`, to be read
+/// like a file (e.g. with a [`LineReader`](crate::input::LineReader)).
+pub struct SortedLines {
+    /// the next line of each run, with the index of its run: the runs spilled to files, then
+    /// the one in memory, whose index is `runs.len()`.
+    heap: BinaryHeap<Reverse<(String, usize)>>,
+    runs: Vec<Run>,
+    memory: std::vec::IntoIter<String>,
+    /// the line being read, with its `\n`, and how much of it was read.
+    line: Vec<u8>,
+    pos: usize,
+}
+
+impl SortedLines {
+    /// Puts the next line of the run at `index` on the heap, if any.
+    fn refill(&mut self, index: usize) -> io::Result<()> {
+        let next = match self.runs.get_mut(index) {
+            Some(run) => {
+                let mut line = Vec::new();
+                if read_line(&mut run.reader, &mut line)? {
+                    // the runs are written from strings
+                    Some(String::from_utf8(line).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?)
+                } else {
+                    None
+                }
+            }
+            None => self.memory.next(),
+        };
+        if let Some(line) = next {
+            self.heap.push(Reverse((line, index)));
+        }
+        Ok(())
+    }
+}
+
+impl Read for SortedLines {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let available = self.fill_buf()?;
+        let len = available.len().min(buf.len());
+        buf[..len].copy_from_slice(&available[..len]);
+        self.consume(len);
+        Ok(len)
+    }
+}
+
+impl BufRead for SortedLines {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        if self.pos == self.line.len() {
+            if let Some(Reverse((line, index))) = self.heap.pop() {
+                self.line = line.into_bytes();
+                self.line.push(b'\n');
+                self.pos = 0;
+                self.refill(index)?;
+            }
+        }
+        Ok(&self.line[self.pos..])
+    }
+
+    fn consume(&mut self, amount: usize) {
+        self.pos = (self.pos + amount).min(self.line.len());
+    }
+}
+
+fn write_run(path: &Path, lines: impl Iterator<Item=String>) -> io::Result<()> {
+    let mut out = BufWriter::new(File::create(path)?);
+    for line in lines {
+        out.write_all(line.as_bytes())?;
+        out.write_all(b"\n")?;
+    }
+    out.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use super::*;
+
+    /// A temporary directory of its own for the runs of a test, deleted when dropped.
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> TempDir {
+            let dir = env::temp_dir().join(format!("phone_encoder-sort-test-{}-{}", process::id(), name));
+            fs::create_dir_all(&dir).unwrap();
+            TempDir(dir)
+        }
+
+        fn files(&self) -> usize {
+            fs::read_dir(&self.0).unwrap().count()
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn sorted(sort: ExternalSort) -> String {
+        let mut text = String::new();
+        sort.finish().unwrap().read_to_string(&mut text).unwrap();
+        text
+    }
+
+    #[test]
+    fn merges_several_runs_with_duplicates() {
+        let dir = TempDir::new("runs");
+        // a run of at most two lines
+        let mut sort = ExternalSort::new(2 * size_of::<String>() + 8, &dir.0);
+        for line in ["5624-82", "4824", "112", "04824", "4824", "10/783--5", "112", "381482", "0721/608-4067"] {
+            sort.push(line).unwrap();
+        }
+        assert!(sort.runs.len() >= 3, "{} runs", sort.runs.len());
+        assert_eq!(dir.files(), sort.runs.len());
+        assert_eq!(sorted(sort), "04824\n0721/608-4067\n10/783--5\n112\n112\n381482\n4824\n4824\n5624-82\n");
+        // the runs are deleted once read
+        assert_eq!(dir.files(), 0);
+    }
+
+    #[test]
+    fn sorts_in_the_byte_order_of_utf8() {
+        let dir = TempDir::new("utf8");
+        let mut sort = ExternalSort::new(1, &dir.0);
+        for line in ["ö", "z", "Z", "", "a"] {
+            sort.push(line).unwrap();
+        }
+        assert_eq!(sorted(sort), "\nZ\na\nz\nö\n");
+    }
+
+    #[test]
+    fn sorts_an_empty_input() {
+        let dir = TempDir::new("empty");
+        assert_eq!(sorted(ExternalSort::new(1, &dir.0)), "");
+        assert_eq!(dir.files(), 0);
+    }
+
+    #[test]
+    fn sorts_in_memory_below_the_run_size() {
+        let dir = TempDir::new("memory");
+        let mut sort = ExternalSort::new(DEFAULT_RUN_BYTES, &dir.0);
+        for line in ["4824", "112", "4824"] {
+            sort.push(line).unwrap();
+        }
+        assert!(sort.runs.is_empty());
+        assert_eq!(sorted(sort), "112\n4824\n4824\n");
+    }
+}
@@ -0,0 +1,257 @@
+//! Meet-in-the-middle search for long phone numbers.
+//!
+//! The depth-first [`search`](crate::search::search) explores the encodings of the end of
+//! a phone number again for each encoding of its beginning, which explodes for numbers with
+//! 40 or more digits and dense dictionaries. This search splits the digits in two halves:
+//! the encodings of the second half are enumerated only once for each position (and kind of
+//! part) a solution may cross the middle at, then joined with each encoding of the first half.
+//!
+//! The solutions are the same, and found in the same order, as with the depth-first search.
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use num_bigint::BigUint;
+
+use crate::filter::SolutionFilter;
+use crate::search::{Limits, nth_digit, EncodeStats, Lookup, OnRejected, OnSolution, SearchStrategy, Solutions, WordOrDigit};
+
+/// Encodings of the digits from some position to the end.
+type Suffixes<'a> = Vec<Vec<WordOrDigit<'a>>>;
+
+/// The meet-in-the-middle search.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct MeetInTheMiddle;
+
+impl SearchStrategy for MeetInTheMiddle {
+    fn search<D: Lookup + ?Sized, E>(
+        &self,
+        digits: &[char],
+        dict: &D,
+        filter: &dyn SolutionFilter,
+        limits: Limits,
+        on_solution: &mut OnSolution<E>,
+        on_rejected: &mut OnRejected<E>,
+    ) -> Result<EncodeStats, E> {
+        search(digits, dict, filter, limits, on_solution, on_rejected)
+    }
+}
+
+/// Like [`search`](crate::search::search), but joins the encodings of the two halves
+/// of the digits instead of searching the second half again for each encoding of the first.
+pub fn search<D: Lookup + ?Sized, E>(
+    digits: &[char],
+    dict: &D,
+    filter: &dyn SolutionFilter,
+    limits: Limits,
+    on_solution: &mut OnSolution<E>,
+    on_rejected: &mut OnRejected<E>,
+) -> Result<EncodeStats, E> {
+    let mut split = Split {
+        digits,
+        dict,
+        middle: digits.len() / 2,
+        suffixes: vec![[None, None]; digits.len() + 1],
+        solutions: Solutions::new(digits, filter, limits, on_solution, on_rejected),
+    };
+    find_prefixes(&mut split, 0, Vec::new())?;
+    Ok(split.solutions.stats)
+}
+
+/// State of the search for the encodings of a single phone number.
+struct Split<'a, 'w, D: ?Sized, E> {
+    digits: &'a [char],
+    dict: &'a D,
+    middle: usize,
+    /// the encodings of the digits from each position in the second half, after a word
+    /// (index `0`) or after a digit (index `1`), computed when first needed.
+    suffixes: Vec<[Option<Suffixes<'a>>; 2]>,
+    solutions: Solutions<'a, 'w, E>,
+}
+
+fn find_prefixes<'a, D: Lookup + ?Sized, E>(
+    split: &mut Split<'a, '_, D, E>,
+    start: usize,
+    words: Vec<WordOrDigit<'a>>,
+) -> Result<(), E> {
+    if split.solutions.is_full() || split.solutions.too_deep(words.len()) {
+        return Ok(());
+    }
+    let after_digit = words.last().map(|w| w.is_digit()).unwrap_or(false);
+    if start >= split.middle {
+        return join(split, start, after_digit, &words);
+    }
+    let digits = split.digits;
+    let dict = split.dict;
+    split.solutions.visit(digits.len() - start);
+    let mut branches = 0;
+    for (len, found_words) in dict.matches_at(&digits[start..]) {
+        for word in found_words {
+            branches += 1;
+            if split.solutions.too_wide(branches) {
+                return Ok(());
+            }
+            let mut partial_solution = words.clone();
+            partial_solution.push(WordOrDigit::Word(&word.text));
+            find_prefixes(split, start + len, partial_solution)?;
+        }
+    }
+    if branches == 0 && !after_digit {
+        let mut partial_solution = words;
+        partial_solution.push(WordOrDigit::Digit(digits[start]));
+        find_prefixes(split, start + 1, partial_solution)
+    } else {
+        Ok(())
+    }
+}
+
+/// Completes the encoding of the digits before `start` with each encoding of the rest.
+fn join<'a, D: Lookup + ?Sized, E>(
+    split: &mut Split<'a, '_, D, E>,
+    start: usize,
+    after_digit: bool,
+    prefix: &[WordOrDigit<'a>],
+) -> Result<(), E> {
+    compute_suffixes(split, start, after_digit);
+    let suffixes = split.suffixes[start][after_digit as usize].take().unwrap_or_default();
+    let mut result = Ok(());
+    let mut words = Vec::with_capacity(prefix.len() + 8);
+    for suffix in &suffixes {
+        if split.solutions.is_full() || split.solutions.too_deep(prefix.len() + suffix.len()) {
+            break;
+        }
+        words.clear();
+        words.extend_from_slice(prefix);
+        words.extend_from_slice(suffix);
+        result = split.solutions.check(&words);
+        if result.is_err() {
+            break;
+        }
+    }
+    split.suffixes[start][after_digit as usize] = Some(suffixes);
+    result
+}
+
+/// Enumerates the encodings of the digits from `start` to the end, following the same
+/// rules, and in the same order, as the depth-first search.
+///
+/// Once the search must stop (e.g. interrupted, as there may be millions of suffixes), the
+/// suffixes are left incomplete, as they are not used anymore.
+fn compute_suffixes<'a, D: Lookup + ?Sized, E>(split: &mut Split<'a, '_, D, E>, start: usize, after_digit: bool) {
+    if split.suffixes[start][after_digit as usize].is_some() {
+        return;
+    }
+    let digits = split.digits;
+    let dict = split.dict;
+    let mut suffixes = Vec::new();
+    if start >= digits.len() {
+        suffixes.push(Vec::new());
+    } else {
+        split.solutions.visit(digits.len() - start);
+        let mut n = BigUint::from(1u32);
+        let mut branches = 0;
+        'words: for i in start..digits.len() {
+            // like the depth-first search, no word goes on past a character other than a digit
+            let Some(digit) = nth_digit(digits, i) else { break };
+            n = n * 10u32 + digit;
+            if let Some(found_words) = dict.words(&n) {
+                compute_suffixes(split, i + 1, false);
+                let rest = split.suffixes[i + 1][0].as_ref().expect("suffixes were computed");
+                for word in found_words {
+                    branches += 1;
+                    if split.solutions.too_wide(branches) {
+                        break 'words;
+                    }
+                    for suffix in rest {
+                        if split.solutions.is_full() {
+                            break;
+                        }
+                        let mut words = Vec::with_capacity(suffix.len() + 1);
+                        words.push(WordOrDigit::Word(word.text.as_str()));
+                        words.extend_from_slice(suffix);
+                        suffixes.push(words);
+                    }
+                }
+            }
+        }
+        if branches == 0 && !after_digit {
+            compute_suffixes(split, start + 1, true);
+            let rest = split.suffixes[start + 1][1].as_ref().expect("suffixes were computed");
+            for suffix in rest {
+                if split.solutions.is_full() {
+                    break;
+                }
+                let mut words = Vec::with_capacity(suffix.len() + 1);
+                words.push(WordOrDigit::Digit(digits[start]));
+                words.extend_from_slice(suffix);
+                suffixes.push(words);
+            }
+        }
+    }
+    split.suffixes[start][after_digit as usize] = Some(suffixes);
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::collections::BTreeMap;
+    use alloc::format;
+    use alloc::string::{String, ToString};
+
+    use super::*;
+    use crate::filter::AcceptAll;
+    use crate::sample::Rng;
+    use crate::search::{RecursiveDfs, Word};
+
+    /// A dictionary with words for a third of the 2-digit keys and a tenth of the 3-digit ones,
+    /// some with two words, so that long numbers have many solutions.
+    fn dense_dict(rng: &mut Rng) -> BTreeMap<BigUint, Vec<Word>> {
+        let mut dict = BTreeMap::new();
+        for key in (100u32..200).chain(1000..2000) {
+            let share = if key < 200 { 3 } else { 10 };
+            if !rng.next_u64().is_multiple_of(share) {
+                continue;
+            }
+            let words = (0..1 + rng.next_u64() % 2).map(|i| Word::new(format!("w{}{}", key, i))).collect();
+            dict.insert(BigUint::from(key), words);
+        }
+        dict
+    }
+
+    /// The first solutions found, in order, as the search of the longest numbers is too long
+    /// to finish.
+    fn solutions<S: SearchStrategy>(strategy: &S, digits: &[char], dict: &BTreeMap<BigUint, Vec<Word>>) -> (Vec<String>, EncodeStats) {
+        let mut found = Vec::new();
+        let stats = strategy.search(digits, dict, &AcceptAll, Limits::solutions(5_000), &mut |parts: &[WordOrDigit]| {
+            found.push(parts.iter().map(ToString::to_string).collect::<Vec<_>>().join(" "));
+            Ok::<(), ()>(())
+        }, &mut |_, _| Ok(())).unwrap();
+        (found, stats)
+    }
+
+    #[test]
+    fn finds_the_solutions_of_the_depth_first_search_for_long_numbers() {
+        let mut rng = Rng::new(122);
+        let dict = dense_dict(&mut rng);
+        let keys: Vec<String> = dict.keys().map(|key| key.to_string()[1..].to_string()).collect();
+        let mut solved = 0;
+        for _ in 0..20 {
+            // the digits of words, so that there are solutions, and a few more
+            let len = 40 + rng.next_u64() as usize % 21;
+            let mut digits = Vec::new();
+            while digits.len() < len {
+                match rng.next_u64() % 8 {
+                    0 => digits.push(char::from(b'0' + (rng.next_u64() % 10) as u8)),
+                    _ => digits.extend(keys[rng.next_u64() as usize % keys.len()].chars()),
+                }
+            }
+            digits.truncate(len);
+            let (expected, stats) = solutions(&RecursiveDfs, &digits, &dict);
+            let (found, split_stats) = solutions(&MeetInTheMiddle, &digits, &dict);
+            let number: String = digits.iter().collect();
+            assert_eq!(found, expected, "{}", number);
+            assert_eq!(split_stats.solutions, stats.solutions, "{}", number);
+            solved += usize::from(!expected.is_empty());
+        }
+        assert!(solved > 0);
+    }
+}
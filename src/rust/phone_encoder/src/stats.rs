@@ -0,0 +1,184 @@
+use std::fmt;
+use std::ops::AddAssign;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+#[cfg(feature = "formats-json")]
+use serde_json::{json, Value};
+
+#[cfg(feature = "formats-json")]
+use crate::search::EncodeStats;
+use crate::search::SolutionCounts;
+
+/// Time spent in each phase of a run.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Timings {
+    /// loading the dictionary.
+    pub load: Duration,
+    /// reading the phone numbers.
+    pub read: Duration,
+    /// searching for solutions, excluding formatting and writing them.
+    pub search: Duration,
+    /// formatting and writing solutions.
+    pub write: Duration,
+}
+
+impl Timings {
+    pub fn total(&self) -> Duration {
+        self.load + self.read + self.search + self.write
+    }
+}
+
+impl AddAssign for Timings {
+    fn add_assign(&mut self, other: Timings) {
+        self.load += other.load;
+        self.read += other.read;
+        self.search += other.search;
+        self.write += other.write;
+    }
+}
+
+impl fmt::Display for Timings {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let total = self.total();
+        let phases = [
+            ("dictionary load", self.load),
+            ("read", self.read),
+            ("search", self.search),
+            ("format/write", self.write),
+        ];
+        for (name, time) in phases {
+            let percent = if total.is_zero() { 0.0 } else { 100.0 * time.as_secs_f64() / total.as_secs_f64() };
+            writeln!(f, "{:<16} {:>10.3} ms {:>6.1}%", name, millis(time), percent)?;
+        }
+        write!(f, "{:<16} {:>10.3} ms", "total", millis(total))
+    }
+}
+
+/// The mean and spread of the times of several runs.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Summary {
+    pub runs: usize,
+    /// in milliseconds, like the other fields.
+    pub mean: f64,
+    /// the sample standard deviation (`0` for a single run).
+    pub std_dev: f64,
+    pub min: f64,
+    pub max: f64,
+}
+
+impl Summary {
+    pub fn of(times: &[Duration]) -> Summary {
+        let millis: Vec<f64> = times.iter().copied().map(millis).collect();
+        let runs = millis.len();
+        if runs == 0 {
+            return Summary::default();
+        }
+        let mean = millis.iter().sum::<f64>() / runs as f64;
+        let variance = if runs > 1 {
+            millis.iter().map(|t| (t - mean).powi(2)).sum::<f64>() / (runs - 1) as f64
+        } else {
+            0.0
+        };
+        Summary {
+            runs,
+            mean,
+            std_dev: variance.sqrt(),
+            min: millis.iter().copied().fold(f64::INFINITY, f64::min),
+            max: millis.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+        }
+    }
+}
+
+impl fmt::Display for Summary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} runs: mean {:.3} ms, std dev {:.3} ms, min {:.3} ms, max {:.3} ms",
+               self.runs, self.mean, self.std_dev, self.min, self.max)
+    }
+}
+
+/// A table of how many numbers had how many solutions, with their share of all numbers.
+impl fmt::Display for SolutionCounts {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let numbers = self.numbers();
+        write!(f, "{:<10} {:>10}", "solutions", "numbers")?;
+        for (label, count) in SolutionCounts::LABELS.iter().zip(self.buckets) {
+            let percent = if numbers == 0 { 0.0 } else { 100.0 * count as f64 / numbers as f64 };
+            write!(f, "\n{:<10} {:>10} {:>6.1}%", label, count, percent)?;
+        }
+        Ok(())
+    }
+}
+
+/// The numbers of a run with the most solutions, at most `capacity` of them, recorded by the
+/// threads encoding the numbers.
+pub struct TopNumbers {
+    capacity: usize,
+    /// once there are `capacity` numbers, the fewest solutions of them, which a number must
+    /// exceed to be recorded (checked without taking the lock).
+    threshold: AtomicUsize,
+    /// the numbers with their solutions, most first.
+    numbers: Mutex<Vec<(String, usize)>>,
+}
+
+impl TopNumbers {
+    pub fn new(capacity: usize) -> TopNumbers {
+        TopNumbers { capacity, threshold: AtomicUsize::new(0), numbers: Mutex::new(Vec::new()) }
+    }
+
+    /// Records a number, if it has more solutions than the ones recorded already (or some
+    /// solutions, while there are fewer than `capacity` numbers).
+    pub fn record(&self, number: &str, solutions: usize) {
+        if solutions <= self.threshold.load(Ordering::Relaxed) || self.capacity == 0 {
+            return;
+        }
+        let mut numbers = self.numbers.lock().unwrap_or_else(|e| e.into_inner());
+        // after the numbers with as many solutions, which were recorded first
+        let at = numbers.partition_point(|&(_, count)| count >= solutions);
+        if at == self.capacity {
+            return;
+        }
+        numbers.insert(at, (number.to_string(), solutions));
+        numbers.truncate(self.capacity);
+        if numbers.len() == self.capacity {
+            self.threshold.store(numbers[self.capacity - 1].1, Ordering::Relaxed);
+        }
+    }
+
+    /// The numbers recorded, with their solutions, most first.
+    pub fn numbers(&self) -> Vec<(String, usize)> {
+        self.numbers.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+}
+
+/// A duration in milliseconds.
+pub fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+/// The statistics of a run encoding `numbers` phone numbers, as written by `--stats-json`,
+/// with the times in milliseconds.
+#[cfg(feature = "formats-json")]
+pub fn stats_json(numbers: usize, stats: &EncodeStats, timings: &Timings) -> Value {
+    json!({
+        "numbers": numbers,
+        "solutions": stats.solutions,
+        "rejected": stats.rejected,
+        "nodes": stats.nodes,
+        "lookups": stats.lookups,
+        "candidates": stats.candidates,
+        "empty_numbers": stats.empty_numbers,
+        "solution_counts": SolutionCounts::LABELS.iter()
+            .zip(stats.solution_counts.buckets)
+            .map(|(label, count)| (label.to_string(), Value::from(count)))
+            .collect::<serde_json::Map<_, _>>(),
+        "timings": {
+            "load": millis(timings.load),
+            "read": millis(timings.read),
+            "search": millis(timings.search),
+            "write": millis(timings.write),
+            "total": millis(timings.total()),
+        },
+    })
+}
@@ -0,0 +1,127 @@
+//! Printing each step of the search for the encodings of a number (`--trace <number>`), to
+//! follow how the depth-first search finds them, e.g. when teaching the algorithm:
+//!
+//! ```text
+//! 4824 (digits 4824)
+//!   4824:
+//!     Tor for 482
+//!       4:
+//!         no word, keeping the digit 4
+//!           solution: Tor 4
+//!     fort for 4824
+//!       solution: fort
+//! ```
+
+use std::fmt;
+use std::io::{self, Write};
+
+use phone_encoder::{Rejection, SearchObserver, WordOrDigit, WordsFile};
+
+use crate::cli::Options;
+use crate::run::load;
+
+/// Prints the trace of the search for the encodings of `number` to stdout.
+pub fn trace(options: &Options, words_files: &[WordsFile], number: &str) -> io::Result<()> {
+    let encoder = load(options, words_files)?;
+    let digits: Vec<char> = number.chars().filter(char::is_ascii_digit).collect();
+    let mut out = io::stdout().lock();
+    writeln!(out, "{} (digits {})", number, digits.iter().collect::<String>())?;
+    let mut tracer = Tracer { out: &mut out, digits, nodes: Vec::new(), at_node: false, error: None };
+    let stats = encoder.observe(number, &mut tracer);
+    tracer.backtrack(usize::MAX);
+    if let Some(e) = tracer.error {
+        return Err(e);
+    }
+    let stats = stats?;
+    writeln!(out, "{} solutions, {} rejected, {} partial solutions extended", stats.solutions, stats.rejected, stats.nodes)
+}
+
+/// Prints the steps of a search, each indented by the depth of the recursion.
+struct Tracer<'a> {
+    out: &'a mut dyn Write,
+    digits: Vec<char>,
+    /// where the partial solutions being extended end, from the first one.
+    nodes: Vec<usize>,
+    /// whether nothing happened since the last partial solution was entered, which is a
+    /// dead end if the search goes back from it.
+    at_node: bool,
+    /// the first error writing the trace, which stops it.
+    error: Option<io::Error>,
+}
+
+impl Tracer<'_> {
+    fn line(&mut self, indent: usize, text: fmt::Arguments) {
+        if self.error.is_none() {
+            if let Err(e) = writeln!(self.out, "{:indent$}{}", "", text, indent = indent) {
+                self.error = Some(e);
+            }
+        }
+    }
+
+    /// The indentation of the words matched at the partial solution that was entered last.
+    fn step_indent(&self) -> usize {
+        4 * self.nodes.len()
+    }
+
+    /// Goes back to the partial solution ending at `start`, noting a dead end if the search
+    /// goes back from a partial solution it could not extend.
+    fn backtrack(&mut self, start: usize) {
+        if self.at_node && self.nodes.last() != Some(&start) {
+            let indent = self.step_indent();
+            self.line(indent, format_args!("dead end: no word, and a digit cannot follow a digit"));
+        }
+        self.at_node = false;
+        while self.nodes.len() > 1 && self.nodes.last() != Some(&start) {
+            self.nodes.pop();
+        }
+    }
+}
+
+impl SearchObserver for Tracer<'_> {
+    fn node_entered(&mut self, start: usize, parts: &[WordOrDigit]) {
+        self.nodes.truncate(parts.len());
+        self.nodes.push(start);
+        self.at_node = true;
+        let rest: String = self.digits[start..].iter().collect();
+        let indent = self.step_indent() - 2;
+        self.line(indent, format_args!("{}:", rest));
+    }
+
+    fn word_matched(&mut self, start: usize, end: usize, word: &str) {
+        self.backtrack(start);
+        let encoded: String = self.digits[start..end].iter().collect();
+        let indent = self.step_indent();
+        self.line(indent, format_args!("{} for {}", word, encoded));
+    }
+
+    fn digit_inserted(&mut self, position: usize, digit: char) {
+        self.backtrack(position);
+        let indent = self.step_indent();
+        self.line(indent, format_args!("no word, keeping the digit {}", digit));
+    }
+
+    fn solution_accepted(&mut self, solution: &[WordOrDigit]) {
+        let indent = self.step_indent() + 2;
+        self.line(indent, format_args!("solution: {}", Text(solution)));
+    }
+
+    fn solution_rejected(&mut self, solution: &[WordOrDigit], rejection: &Rejection) {
+        let indent = self.step_indent() + 2;
+        self.line(indent, format_args!("rejected ({}): {}", rejection, Text(solution)));
+    }
+}
+
+/// The parts of a solution, separated by spaces.
+struct Text<'a>(&'a [WordOrDigit<'a>]);
+
+impl fmt::Display for Text<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, part) in self.0.iter().enumerate() {
+            if i > 0 {
+                f.write_str(" ")?;
+            }
+            write!(f, "{}", part)?;
+        }
+        Ok(())
+    }
+}
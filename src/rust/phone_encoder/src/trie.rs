@@ -0,0 +1,214 @@
+//! Depth-first search walking a trie of the dictionary.
+
+use alloc::collections::VecDeque;
+use alloc::vec::Vec;
+
+use num_bigint::BigUint;
+
+use crate::filter::SolutionFilter;
+use crate::search::{
+    Limits, search, EncodeStats, Lookup, OnRejected, OnSolution, SearchStrategy, Solutions, Word, WordOrDigit,
+};
+
+/// A dictionary stored as a trie of the digits of the words' encodings.
+///
+/// Looking up all the words encoding a prefix of some digits takes a single walk down the
+/// trie, which stops as soon as no word starts with the digits walked so far, instead of
+/// computing and hashing a number for each prefix.
+#[derive(Clone, Debug)]
+pub struct Trie {
+    nodes: Vec<Node>,
+}
+
+#[derive(Clone, Debug, Default)]
+struct Node {
+    /// the index of the child node for each digit (`0`, the root, for none).
+    children: [u32; 10],
+    words: Vec<Word>,
+}
+
+impl Trie {
+    /// Builds a trie with the words of a dictionary, given as its entries.
+    pub fn new<'d, I: IntoIterator<Item = (&'d BigUint, &'d Vec<Word>)>>(entries: I) -> Trie {
+        let mut trie = Trie { nodes: alloc::vec![Node::default()] };
+        for (key, words) in entries {
+            // the key is the encoding with a leading `1`, which is not part of the trie
+            let key = key.to_radix_be(10);
+            let mut node = 0;
+            for digit in &key[1..] {
+                node = match trie.nodes[node].children[*digit as usize] {
+                    0 => {
+                        trie.nodes.push(Node::default());
+                        let child = trie.nodes.len() - 1;
+                        trie.nodes[node].children[*digit as usize] = child as u32;
+                        child
+                    }
+                    child => child as usize,
+                };
+            }
+            trie.nodes[node].words.extend(words.iter().cloned());
+        }
+        trie
+    }
+
+    /// The bytes taken by the nodes of the trie and the vectors of their words (not counting
+    /// the text of the words).
+    pub fn memory_size(&self) -> usize {
+        self.nodes.capacity() * size_of::<Node>()
+            + self.nodes.iter().map(|node| node.words.capacity() * size_of::<Word>()).sum::<usize>()
+    }
+
+    /// The words whose encoding starts with some digits, shortest encodings first, and in the
+    /// order of their digits for encodings of the same length (T9-style completion).
+    ///
+    /// Only the nodes below the node of the digits are visited, breadth first. No word starts
+    /// with a character other than `0` to `9`.
+    pub fn completions(&self, digits: &[char]) -> Vec<&Word> {
+        let mut node = 0;
+        for ch in digits {
+            let Some(child) = self.child(node, *ch) else { return Vec::new() };
+            node = child;
+        }
+        let mut words = Vec::new();
+        let mut queue = VecDeque::from([node]);
+        while let Some(node) = queue.pop_front() {
+            let node = &self.nodes[node];
+            words.extend(&node.words);
+            queue.extend(node.children.iter().filter(|child| **child != 0).map(|child| *child as usize));
+        }
+        words
+    }
+
+    /// The child of a node for a character of a phone number, if any.
+    #[inline]
+    pub(crate) fn child(&self, node: usize, ch: char) -> Option<usize> {
+        let digit = ch.to_digit(10)?;
+        match self.nodes[node].children[digit as usize] {
+            0 => None,
+            child => Some(child as usize),
+        }
+    }
+
+    /// The words of a node, if it has any.
+    #[inline]
+    pub(crate) fn words_of(&self, node: usize) -> Option<&[Word]> {
+        Some(self.nodes[node].words.as_slice()).filter(|words| !words.is_empty())
+    }
+}
+
+impl Lookup for Trie {
+    fn words(&self, key: &BigUint) -> Option<&[Word]> {
+        let key = key.to_radix_be(10);
+        let mut node = 0;
+        for digit in &key[1..] {
+            node = match self.nodes[node].children[*digit as usize] {
+                0 => return None,
+                child => child as usize,
+            };
+        }
+        self.words_of(node)
+    }
+
+    fn as_trie(&self) -> Option<&Trie> {
+        Some(self)
+    }
+}
+
+/// The depth-first search of [`RecursiveDfs`](crate::search::RecursiveDfs), walking a
+/// [`Trie`] to find the words encoding each prefix of the remaining digits.
+///
+/// If the dictionary is not a [`Trie`], this is the same as the [`RecursiveDfs`](crate::search::RecursiveDfs).
+/// Characters of the phone number other than `0` to `9` never match a word in a trie.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct TrieWalk;
+
+impl SearchStrategy for TrieWalk {
+    fn search<D: Lookup + ?Sized, E>(
+        &self,
+        digits: &[char],
+        dict: &D,
+        filter: &dyn SolutionFilter,
+        limits: Limits,
+        on_solution: &mut OnSolution<E>,
+        on_rejected: &mut OnRejected<E>,
+    ) -> Result<EncodeStats, E> {
+        let Some(trie) = dict.as_trie() else {
+            return search(digits, dict, filter, limits, on_solution, on_rejected);
+        };
+        let mut walk = Walk {
+            digits,
+            trie,
+            solutions: Solutions::new(digits, filter, limits, on_solution, on_rejected),
+        };
+        find_translations(&mut walk, 0, &mut Vec::with_capacity(digits.len()))?;
+        Ok(walk.solutions.stats)
+    }
+}
+
+struct Walk<'a, 'w, E> {
+    digits: &'a [char],
+    trie: &'a Trie,
+    solutions: Solutions<'a, 'w, E>,
+}
+
+fn find_translations<'a, E>(walk: &mut Walk<'a, '_, E>, start: usize, words: &mut Vec<WordOrDigit<'a>>) -> Result<(), E> {
+    if walk.solutions.is_full() || walk.solutions.too_deep(words.len()) {
+        return Ok(());
+    }
+    let digits = walk.digits;
+    if start >= digits.len() {
+        return walk.solutions.check(words);
+    }
+    let trie = walk.trie;
+    let mut node = 0;
+    let mut branches = 0;
+    let mut steps = 0;
+    for (i, ch) in digits.iter().enumerate().skip(start) {
+        steps += 1;
+        let Some(child) = trie.child(node, *ch) else { break };
+        node = child;
+        for word in &trie.nodes[node].words {
+            branches += 1;
+            if walk.solutions.too_wide(branches) {
+                return Ok(());
+            }
+            words.push(WordOrDigit::Word(&word.text));
+            find_translations(walk, i + 1, words)?;
+            words.pop();
+        }
+    }
+    walk.solutions.visit(steps);
+    if branches == 0 && !words.last().map(|w| w.is_digit()).unwrap_or(false) {
+        words.push(WordOrDigit::Digit(digits[start]));
+        let result = find_translations(walk, start + 1, words);
+        words.pop();
+        result
+    } else {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::collections::BTreeMap;
+
+    use super::*;
+    use crate::mapping::Mapping;
+    use crate::search::words_from_bytes;
+
+    fn texts<'a>(words: &[&'a Word]) -> Vec<&'a str> {
+        words.iter().map(|w| w.text.as_str()).collect()
+    }
+
+    #[test]
+    fn completes_shortest_encodings_first() {
+        let dict: BTreeMap<BigUint, Vec<Word>> = words_from_bytes(b"Torf\nfort\nTor\nFee\nFest\nmir\n", &Mapping::default());
+        let trie = Trie::new(&dict);
+        // Tor is 482, Fee 400, Fest 4034, Torf and fort 4824
+        assert_eq!(texts(&trie.completions(&['4'])), ["Fee", "Tor", "Fest", "Torf", "fort"]);
+        assert_eq!(texts(&trie.completions(&['4', '8', '2'])), ["Tor", "Torf", "fort"]);
+        assert_eq!(texts(&trie.completions(&[])).len(), 6);
+        assert!(trie.completions(&['4', '8', '3']).is_empty());
+        assert!(trie.completions(&['-']).is_empty());
+    }
+}
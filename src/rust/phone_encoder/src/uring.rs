@@ -0,0 +1,382 @@
+//! Writing output files with io_uring, on Linux with the `uring` feature: solutions are
+//! written into buffers registered with the kernel, and each buffer, once full, is written
+//! by the kernel while the next ones are filled, instead of blocking in a `write` system
+//! call for each.
+
+use std::ffi::c_void;
+use std::fs::File;
+use std::io::{self, Seek, Write};
+use std::os::fd::{AsRawFd, OwnedFd};
+use std::ptr::{self, null_mut};
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use rustix::io::{pwrite, Errno};
+use rustix::io_uring::{
+    addr_or_splice_off_in_union, buf_union, io_uring_cqe, io_uring_enter, io_uring_params, io_uring_ptr,
+    io_uring_register, io_uring_setup, io_uring_sqe, io_uring_user_data, iovec, len_union, off_or_addr2_union,
+    IoringEnterFlags, IoringFeatureFlags, IoringOp, IoringRegisterOp, IORING_OFF_CQ_RING, IORING_OFF_SQES,
+    IORING_OFF_SQ_RING,
+};
+use rustix::mm::{mmap, munmap, MapFlags, ProtFlags};
+
+/// The size of each buffer.
+const BUFFER_SIZE: usize = 1024 * 1024;
+
+/// How many buffers there are: one being filled while the others are written.
+const BUFFERS: usize = 8;
+
+/// Writes a regular file with io_uring, from its current position.
+///
+/// The writes are done at explicit offsets, so the position of the file itself is not moved.
+pub struct UringWriter {
+    // dropped before the buffers, which the kernel may be writing until the ring is closed
+    ring: Ring,
+    file: File,
+    /// the registered buffers, one after the other (allocated once, so they never move).
+    buffers: Vec<u8>,
+    /// the buffer being filled, and how much of it is.
+    current: usize,
+    len: usize,
+    /// the buffers not being written.
+    free: Vec<usize>,
+    /// the offset and the length of the write of each buffer being written.
+    writes: [(u64, usize); BUFFERS],
+    in_flight: usize,
+    /// where the next buffer is written in the file.
+    offset: u64,
+    /// whether a write failed, after which the buffers may still be in use by the kernel,
+    /// so that nothing more is written.
+    failed: bool,
+}
+
+impl UringWriter {
+    /// Fails if io_uring is not available, e.g. on kernels older than 5.1 or where it is
+    /// disabled, so that the caller can fall back to another writer.
+    pub fn new(mut file: File) -> io::Result<UringWriter> {
+        let offset = file.stream_position()?;
+        let ring = Ring::new(BUFFERS as u32)?;
+        let mut buffers = vec![0u8; BUFFERS * BUFFER_SIZE];
+        let iovecs: Vec<iovec> = buffers.chunks_exact_mut(BUFFER_SIZE)
+            .map(|buffer| iovec { iov_base: buffer.as_mut_ptr().cast(), iov_len: BUFFER_SIZE })
+            .collect();
+        // SAFETY: the buffers live as long as the ring, as it is dropped first
+        unsafe {
+            io_uring_register(&ring.fd, IoringRegisterOp::RegisterBuffers, iovecs.as_ptr().cast(), BUFFERS as u32)?;
+        }
+        Ok(UringWriter {
+            ring,
+            file,
+            buffers,
+            current: 0,
+            len: 0,
+            free: (1..BUFFERS).rev().collect(),
+            writes: [(0, 0); BUFFERS],
+            in_flight: 0,
+            offset,
+            failed: false,
+        })
+    }
+
+    fn buffer(&self, index: usize) -> &[u8] {
+        &self.buffers[index * BUFFER_SIZE..(index + 1) * BUFFER_SIZE]
+    }
+
+    /// Has the kernel write the current buffer, continuing with a free one, once there is one.
+    fn submit(&mut self) -> io::Result<()> {
+        let (index, len) = (self.current, self.len);
+        let sqe = io_uring_sqe {
+            opcode: IoringOp::WriteFixed,
+            fd: self.file.as_raw_fd(),
+            off_or_addr2: off_or_addr2_union { off: self.offset },
+            addr_or_splice_off_in: addr_or_splice_off_in_union {
+                addr: io_uring_ptr::new(self.buffer(index).as_ptr() as *mut c_void),
+            },
+            len: len_union { len: len as u32 },
+            buf: buf_union { buf_index: index as u16 },
+            user_data: io_uring_user_data::from_u64(index as u64),
+            ..Default::default()
+        };
+        self.ring.submit(sqe)?;
+        self.writes[index] = (self.offset, len);
+        self.in_flight += 1;
+        self.offset += len as u64;
+
+        if self.free.is_empty() {
+            self.complete()?;
+        }
+        self.current = self.free.pop().expect("a buffer was written");
+        self.len = 0;
+        Ok(())
+    }
+
+    /// Waits for a buffer to be written, finishing the write itself if the kernel wrote
+    /// only part of the buffer.
+    fn complete(&mut self) -> io::Result<()> {
+        let (index, res) = self.ring.complete()?;
+        self.in_flight -= 1;
+        self.free.push(index);
+        if res < 0 {
+            return Err(Errno::from_raw_os_error(-res).into());
+        }
+        let (offset, len) = self.writes[index];
+        let written = (res as usize).min(len);
+        write_at(&self.file, &self.buffer(index)[written..len], offset + written as u64)
+    }
+
+    /// Fails once a write failed, or else marks the writer as failed if `result` is an error.
+    fn checked<T>(&mut self, result: impl FnOnce(&mut Self) -> io::Result<T>) -> io::Result<T> {
+        if self.failed {
+            return Err(io::Error::other("an earlier write of the file failed"));
+        }
+        let result = result(self);
+        self.failed = result.is_err();
+        result
+    }
+}
+
+/// Writes all of `buf` at `offset` in the file, without io_uring.
+fn write_at(file: &File, mut buf: &[u8], mut offset: u64) -> io::Result<()> {
+    while !buf.is_empty() {
+        match pwrite(file, buf, offset)? {
+            0 => return Err(io::ErrorKind::WriteZero.into()),
+            n => {
+                buf = &buf[n..];
+                offset += n as u64;
+            }
+        }
+    }
+    Ok(())
+}
+
+impl Write for UringWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.checked(|writer| {
+            let start = writer.current * BUFFER_SIZE + writer.len;
+            let n = buf.len().min(BUFFER_SIZE - writer.len);
+            writer.buffers[start..start + n].copy_from_slice(&buf[..n]);
+            writer.len += n;
+            if writer.len == BUFFER_SIZE {
+                writer.submit()?;
+            }
+            Ok(n)
+        })
+    }
+
+    /// Writes the current buffer and waits for all buffers to be written.
+    fn flush(&mut self) -> io::Result<()> {
+        self.checked(|writer| {
+            if writer.len > 0 {
+                writer.submit()?;
+            }
+            while writer.in_flight > 0 {
+                writer.complete()?;
+            }
+            Ok(())
+        })
+    }
+}
+
+impl Drop for UringWriter {
+    fn drop(&mut self) {
+        if !self.failed {
+            let _ = self.flush();
+        }
+    }
+}
+
+/// An io_uring instance: its file descriptor and its submission and completion queues,
+/// shared with the kernel.
+struct Ring {
+    fd: OwnedFd,
+    // only kept to be unmapped with the ring, the queues being accessed through the pointers below
+    _sq_ring: Mapping,
+    _cq_ring: Option<Mapping>,
+    sqes: Mapping,
+    sq_tail: *const AtomicU32,
+    sq_mask: u32,
+    sq_array: *mut u32,
+    cq_head: *const AtomicU32,
+    cq_tail: *const AtomicU32,
+    cq_mask: u32,
+    cqes: *const io_uring_cqe,
+}
+
+// SAFETY: the queues are only accessed through the ring, which is not shared between threads
+unsafe impl Send for Ring {}
+
+impl Ring {
+    fn new(entries: u32) -> io::Result<Ring> {
+        let mut params = io_uring_params::default();
+        // SAFETY: the parameters are initialized
+        let fd = unsafe { io_uring_setup(entries, &mut params)? };
+        let (sq, cq) = (params.sq_off, params.cq_off);
+        let sq_len = sq.array as usize + params.sq_entries as usize * size_of::<u32>();
+        let cq_len = cq.cqes as usize + params.cq_entries as usize * size_of::<io_uring_cqe>();
+        // since Linux 5.4, both queues are in a single mapping
+        let single = params.features.contains(IoringFeatureFlags::SINGLE_MMAP);
+        let sq_ring = Mapping::new(&fd, if single { sq_len.max(cq_len) } else { sq_len }, IORING_OFF_SQ_RING)?;
+        let cq_ring = if single { None } else { Some(Mapping::new(&fd, cq_len, IORING_OFF_CQ_RING)?) };
+        let sqes = Mapping::new(&fd, params.sq_entries as usize * size_of::<io_uring_sqe>(), IORING_OFF_SQES)?;
+        let cq_base = cq_ring.as_ref().unwrap_or(&sq_ring).ptr;
+        // SAFETY: the offsets given by the kernel are within the mappings
+        unsafe {
+            let at = |base: *mut c_void, offset: u32| base.cast::<u8>().add(offset as usize);
+            Ok(Ring {
+                sq_tail: at(sq_ring.ptr, sq.tail).cast(),
+                sq_mask: *at(sq_ring.ptr, sq.ring_mask).cast::<u32>(),
+                sq_array: at(sq_ring.ptr, sq.array).cast(),
+                cq_head: at(cq_base, cq.head).cast(),
+                cq_tail: at(cq_base, cq.tail).cast(),
+                cq_mask: *at(cq_base, cq.ring_mask).cast::<u32>(),
+                cqes: at(cq_base, cq.cqes).cast(),
+                fd,
+                _sq_ring: sq_ring,
+                _cq_ring: cq_ring,
+                sqes,
+            })
+        }
+    }
+
+    /// Submits an entry, of which there are never more than the queue holds.
+    fn submit(&mut self, sqe: io_uring_sqe) -> io::Result<()> {
+        // SAFETY: only this process writes the tail of the submission queue, and the entry
+        // at the tail is not used by the kernel until the tail is moved past it
+        unsafe {
+            let tail = (*self.sq_tail).load(Ordering::Relaxed);
+            let index = tail & self.sq_mask;
+            ptr::write(self.sqes.ptr.cast::<io_uring_sqe>().add(index as usize), sqe);
+            *self.sq_array.add(index as usize) = index;
+            (*self.sq_tail).store(tail.wrapping_add(1), Ordering::Release);
+            io_uring_enter(&self.fd, 1, 0, IoringEnterFlags::empty())?;
+        }
+        Ok(())
+    }
+
+    /// Waits for the next completion, returning its user data and result.
+    fn complete(&mut self) -> io::Result<(usize, i32)> {
+        loop {
+            // SAFETY: only this process moves the head of the completion queue, and the
+            // entries before the tail are written by the kernel before moving it
+            unsafe {
+                let head = (*self.cq_head).load(Ordering::Relaxed);
+                if head != (*self.cq_tail).load(Ordering::Acquire) {
+                    let cqe = &*self.cqes.add((head & self.cq_mask) as usize);
+                    let completion = (cqe.user_data.u64_() as usize, cqe.res);
+                    (*self.cq_head).store(head.wrapping_add(1), Ordering::Release);
+                    return Ok(completion);
+                }
+                match io_uring_enter(&self.fd, 0, 1, IoringEnterFlags::GETEVENTS) {
+                    Ok(_) | Err(Errno::INTR) => {}
+                    Err(e) => return Err(e.into()),
+                }
+            }
+        }
+    }
+}
+
+/// A memory mapping of the queues of a ring, unmapped when dropped.
+struct Mapping {
+    ptr: *mut c_void,
+    len: usize,
+}
+
+impl Mapping {
+    fn new(fd: &OwnedFd, len: usize, offset: u64) -> io::Result<Mapping> {
+        // SAFETY: a new mapping, not aliasing any memory of the process
+        let ptr = unsafe {
+            mmap(null_mut(), len, ProtFlags::READ | ProtFlags::WRITE, MapFlags::SHARED | MapFlags::POPULATE, fd, offset)?
+        };
+        Ok(Mapping { ptr, len })
+    }
+}
+
+impl Drop for Mapping {
+    fn drop(&mut self) {
+        // SAFETY: the mapping is not used after the ring is dropped
+        unsafe {
+            let _ = munmap(self.ptr, self.len);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs::{self, OpenOptions};
+    use std::io::SeekFrom;
+    use std::path::PathBuf;
+    use std::process;
+
+    use super::*;
+
+    /// A file of its own for a test, deleted when dropped.
+    struct TempFile(PathBuf);
+
+    impl TempFile {
+        fn new(name: &str) -> TempFile {
+            TempFile(env::temp_dir().join(format!("phone_encoder-uring-{}-{}", name, process::id())))
+        }
+    }
+
+    impl Drop for TempFile {
+        fn drop(&mut self) {
+            let _ = fs::remove_file(&self.0);
+        }
+    }
+
+    /// Bytes that differ from one buffer to the next, so that a buffer written twice or
+    /// in the wrong place shows.
+    fn payload(len: usize) -> Vec<u8> {
+        (0..len).map(|i| (i % 251) as u8 ^ (i / BUFFER_SIZE) as u8).collect()
+    }
+
+    #[test]
+    fn writes_many_buffers_in_order() {
+        let path = TempFile::new("round-trip");
+        let mut file = File::create(&path.0).unwrap();
+        file.write_all(b"head").unwrap();
+        let mut writer = UringWriter::new(file).unwrap();
+        // more buffers than there are, written in pieces not aligned with them
+        let payload = payload(BUFFERS * BUFFER_SIZE * 2 + 12345);
+        for piece in payload.chunks(100_003) {
+            writer.write_all(piece).unwrap();
+        }
+        writer.flush().unwrap();
+        writer.write_all(b"tail").unwrap();
+        drop(writer);
+        let written = fs::read(&path.0).unwrap();
+        assert_eq!(written.len(), 4 + payload.len() + 4);
+        assert!(written[..4] == *b"head" && written[4..4 + payload.len()] == payload && written.ends_with(b"tail"));
+    }
+
+    #[test]
+    fn writes_the_rest_of_a_short_write() {
+        let path = TempFile::new("short");
+        let mut file = OpenOptions::new().read(true).write(true).create(true).truncate(true).open(&path.0).unwrap();
+        let payload = payload(10_000);
+        // as if the kernel wrote only the first 1000 bytes at 10
+        file.seek(SeekFrom::Start(10)).unwrap();
+        file.write_all(&payload[..1000]).unwrap();
+        write_at(&file, &payload[1000..], 1010).unwrap();
+        let written = fs::read(&path.0).unwrap();
+        assert_eq!(written[..10], [0; 10]);
+        assert_eq!(written[10..], payload);
+    }
+
+    #[test]
+    fn fails_for_good_after_a_failed_write() {
+        let path = TempFile::new("error");
+        File::create(&path.0).unwrap();
+        // the kernel cannot write a file opened for reading
+        let mut writer = UringWriter::new(File::open(&path.0).unwrap()).unwrap();
+        let buffer = vec![1; BUFFER_SIZE];
+        // the writes fail when a buffer is needed back, once all were written
+        let writes = (0..=BUFFERS).take_while(|_| writer.write_all(&buffer).is_ok()).count();
+        assert_eq!(writes, BUFFERS - 1);
+        // the buffer the kernel was writing is not written over, nor written again
+        assert!(writer.write(b"more").is_err());
+        assert!(writer.flush().is_err());
+        drop(writer);
+        assert_eq!(fs::metadata(&path.0).unwrap().len(), 0);
+    }
+}
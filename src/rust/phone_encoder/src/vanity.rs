@@ -0,0 +1,107 @@
+//! Vanity-number search: finding the phone numbers that spell given words.
+
+use crate::dict::key_digits;
+use crate::mapping::Mapping;
+use crate::search::{extract_digits, word_to_number};
+
+/// Where the digits of a word must appear in a phone number.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Position {
+    /// at the start of the number.
+    Prefix,
+    /// at the end of the number.
+    Suffix,
+    /// anywhere in the number.
+    #[default]
+    Anywhere,
+}
+
+impl Position {
+    pub fn parse(name: &str) -> Option<Position> {
+        match name {
+            "prefix" => Some(Position::Prefix),
+            "suffix" => Some(Position::Suffix),
+            "anywhere" => Some(Position::Anywhere),
+            _ => None,
+        }
+    }
+}
+
+/// Finds the phone numbers containing the digits of some target words.
+#[derive(Clone, Debug)]
+pub struct VanityFinder {
+    /// the target words with their digits.
+    targets: Vec<(String, String)>,
+    position: Position,
+    /// the digits of the last number checked.
+    digits: String,
+}
+
+impl VanityFinder {
+    /// Looks for the given words, encoded with `mapping`. Words without any letter
+    /// of the mapping have no digits and are ignored, as are words with a letter it
+    /// cannot encode.
+    pub fn new<I: IntoIterator<Item = S>, S: Into<String>>(words: I, mapping: &Mapping, position: Position) -> VanityFinder {
+        let targets = words.into_iter()
+            .map(Into::into)
+            .filter(|word: &String| mapping.unmapped_letter(word).is_none())
+            .map(|word| {
+                let digits = key_digits(&word_to_number(&word, mapping));
+                (word, digits)
+            })
+            .filter(|(_, digits)| !digits.is_empty())
+            .collect();
+        VanityFinder { targets, position, digits: String::new() }
+    }
+
+    /// The target words whose digits appear in the given phone number, ignoring
+    /// the characters of the number other than digits.
+    pub fn matches(&mut self, num: &str) -> impl Iterator<Item = &str> {
+        self.digits.clear();
+        extract_digits(num, &mut self.digits);
+        let (digits, position) = (&self.digits, self.position);
+        self.targets.iter()
+            .filter(move |(_, word_digits)| match position {
+                Position::Prefix => digits.starts_with(word_digits.as_str()),
+                Position::Suffix => digits.ends_with(word_digits.as_str()),
+                Position::Anywhere => digits.contains(word_digits.as_str()),
+            })
+            .map(|(word, _)| word.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use super::*;
+
+    fn matches<'a>(finder: &'a mut VanityFinder, num: &str) -> Vec<&'a str> {
+        finder.matches(num).collect()
+    }
+
+    #[test]
+    fn finds_the_numbers_containing_the_digits_of_the_words() {
+        // ß cannot be encoded, and - has no digits
+        let mut finder = VanityFinder::new(["mir", "Tor", "Fuß", "-"], &Mapping::default(), Position::Anywhere);
+        assert_eq!(matches(&mut finder, "5624-82"), ["mir", "Tor"]);
+        // whatever the separators between the digits
+        assert_eq!(matches(&mut finder, "56/24 8-2"), ["mir", "Tor"]);
+        assert_eq!(matches(&mut finder, "1-4/8-2"), ["Tor"]);
+        assert!(matches(&mut finder, "5-6").is_empty());
+        assert!(matches(&mut finder, "").is_empty());
+    }
+
+    #[test]
+    fn finds_the_digits_where_asked() {
+        let words = ["mir", "Tor"];
+        let mut prefix = VanityFinder::new(words, &Mapping::default(), Position::parse("prefix").unwrap());
+        assert_eq!(matches(&mut prefix, "5624-82"), ["mir"]);
+        assert!(matches(&mut prefix, "0-5624-82").is_empty());
+        let mut suffix = VanityFinder::new(words, &Mapping::default(), Position::parse("suffix").unwrap());
+        assert_eq!(matches(&mut suffix, "5624-82"), ["Tor"]);
+        assert!(matches(&mut suffix, "5624-82/0").is_empty());
+        assert_eq!(Position::parse("anywhere"), Some(Position::Anywhere));
+        assert_eq!(Position::parse("middle"), None);
+    }
+}
@@ -0,0 +1,342 @@
+//! Checking proposed solutions against a phone number, independently of the search.
+
+use alloc::vec::Vec;
+use core::fmt;
+use core::ops::Range;
+
+use num_bigint::BigUint;
+
+use crate::filter::{Rejection, SolutionFilter};
+use crate::number::InvalidNumber;
+use crate::search::{nth_digit, Lookup, WordOrDigit};
+
+/// Why a proposed solution is not a valid encoding of a phone number.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum VerifyError {
+    /// the digit at `index` is not the next digit of the number.
+    WrongDigit { index: usize, expected: char },
+    /// the word at `index` is not in the dictionary as an encoding of the next digits.
+    WrongWord { index: usize },
+    /// the digit at `index` is used where a word of the dictionary fits.
+    DigitWhereWordFits { index: usize },
+    /// the parts at `index - 1` and `index` are both digits.
+    ConsecutiveDigits { index: usize },
+    /// the parts up to `index` already encode all digits of the number.
+    TooLong { index: usize },
+    /// the parts only encode the first `encoded` digits of the number.
+    Incomplete { encoded: usize },
+    /// the solution is rejected by the filter.
+    Rejected(Rejection),
+    /// the phone number itself is invalid.
+    InvalidNumber(InvalidNumber),
+}
+
+impl fmt::Display for VerifyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VerifyError::WrongDigit { index, expected } =>
+                write!(f, "part {} should be the digit {}", index, expected),
+            VerifyError::WrongWord { index } =>
+                write!(f, "part {} is not a word encoding the next digits", index),
+            VerifyError::DigitWhereWordFits { index } =>
+                write!(f, "part {} is a digit where a word fits", index),
+            VerifyError::ConsecutiveDigits { index } =>
+                write!(f, "consecutive digits at parts {} and {}", index - 1, index),
+            VerifyError::TooLong { index } =>
+                write!(f, "all digits are encoded before part {}", index),
+            VerifyError::Incomplete { encoded } => write!(f, "only the first {} digits are encoded", encoded),
+            VerifyError::Rejected(rejection) => write!(f, "rejected: {}", rejection),
+            VerifyError::InvalidNumber(invalid) => write!(f, "{}", invalid),
+        }
+    }
+}
+
+impl VerifyError {
+    /// The name of the rule broken, for programs: `wrong_digit`, `wrong_word`,
+    /// `digit_where_word_fits`, `consecutive_digits`, `too_long`, `incomplete`, `rejected`
+    /// or `invalid_number`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            VerifyError::WrongDigit { .. } => "wrong_digit",
+            VerifyError::WrongWord { .. } => "wrong_word",
+            VerifyError::DigitWhereWordFits { .. } => "digit_where_word_fits",
+            VerifyError::ConsecutiveDigits { .. } => "consecutive_digits",
+            VerifyError::TooLong { .. } => "too_long",
+            VerifyError::Incomplete { .. } => "incomplete",
+            VerifyError::Rejected(_) => "rejected",
+            VerifyError::InvalidNumber(_) => "invalid_number",
+        }
+    }
+
+    /// The index of the part breaking the rule, if the rule is about a part.
+    pub fn part(&self) -> Option<usize> {
+        match self {
+            VerifyError::WrongDigit { index, .. }
+            | VerifyError::WrongWord { index }
+            | VerifyError::DigitWhereWordFits { index }
+            | VerifyError::ConsecutiveDigits { index }
+            | VerifyError::TooLong { index } => Some(*index),
+            VerifyError::Rejected(Rejection::ConsecutiveDigits { index }) => Some(*index),
+            VerifyError::Incomplete { .. } | VerifyError::Rejected(_) | VerifyError::InvalidNumber(_) => None,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for VerifyError {}
+
+/// Checks that `parts` is a solution for the digits of a phone number: that the parts encode
+/// exactly its digits with words of the dictionary, following the encoding rules (a digit
+/// only where no word fits, and never two digits in a row), and that the filter accepts it.
+///
+/// Fails with the first rule broken, in the order of the parts (see [`explain`] for all).
+pub fn verify_solution<D: Lookup + ?Sized>(
+    digits: &[char],
+    parts: &[WordOrDigit],
+    dict: &D,
+    filter: &dyn SolutionFilter,
+) -> Result<(), VerifyError> {
+    explain(digits, parts, dict, filter).into_result()
+}
+
+/// What [`explain`] finds out about a candidate solution.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Explanation {
+    /// the digits of the number encoded by each part, as far as the parts can be followed:
+    /// up to a part that is not a word encoding the next digits, or the end of the number.
+    pub spans: Vec<Range<usize>>,
+    /// every rule the candidate breaks, in the order of its parts (none if it is a solution).
+    pub violations: Vec<VerifyError>,
+}
+
+impl Explanation {
+    pub fn is_solution(&self) -> bool {
+        self.violations.is_empty()
+    }
+
+    /// The first rule broken, if any.
+    pub fn into_result(self) -> Result<(), VerifyError> {
+        self.violations.into_iter().next().map_or(Ok(()), Err)
+    }
+}
+
+/// `a solution`, or the rules broken, separated by `; `.
+impl fmt::Display for Explanation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.violations.is_empty() {
+            return f.write_str("a solution");
+        }
+        for (i, violation) in self.violations.iter().enumerate() {
+            if i > 0 {
+                f.write_str("; ")?;
+            }
+            write!(f, "{}", violation)?;
+        }
+        Ok(())
+    }
+}
+
+/// Explains why `parts` is or is not a solution for the digits of a phone number, listing
+/// every rule of [`verify_solution`] it breaks instead of only the first.
+///
+/// A wrong digit is taken to stand for the digit it replaces, so that the parts after it are
+/// still checked, but a wrong word ends the explanation, as the digits it was meant to
+/// encode are unknown. The filter is only asked once the parts break no other rule.
+///
+/// Digits with a character other than `0` to `9` are only an [`VerifyError::InvalidNumber`].
+pub fn explain<D: Lookup + ?Sized>(
+    digits: &[char],
+    parts: &[WordOrDigit],
+    dict: &D,
+    filter: &dyn SolutionFilter,
+) -> Explanation {
+    let mut explanation = Explanation::default();
+    let violations = &mut explanation.violations;
+    if let Some(&char) = digits.iter().find(|ch| !ch.is_ascii_digit()) {
+        violations.push(VerifyError::InvalidNumber(InvalidNumber { char }));
+        return explanation;
+    }
+    let mut start = 0;
+    for (index, part) in parts.iter().enumerate() {
+        if start >= digits.len() {
+            violations.push(VerifyError::TooLong { index });
+            return explanation;
+        }
+        let end = match part {
+            WordOrDigit::Digit(digit) => {
+                if *digit != digits[start] {
+                    violations.push(VerifyError::WrongDigit { index, expected: digits[start] });
+                }
+                if index > 0 && parts[index - 1].is_digit() {
+                    violations.push(VerifyError::ConsecutiveDigits { index });
+                }
+                if word_fits(digits, start, dict) {
+                    violations.push(VerifyError::DigitWhereWordFits { index });
+                }
+                start + 1
+            }
+            WordOrDigit::Word(text) => {
+                let mut n = BigUint::from(1u32);
+                let end = (start..digits.len())
+                    .map_while(|i| Some((i, nth_digit(digits, i)?)))
+                    .find(|&(_, digit)| {
+                        n = core::mem::take(&mut n) * 10u32 + digit;
+                        dict.words(&n).is_some_and(|words| words.iter().any(|word| word.text == *text))
+                    });
+                match end {
+                    Some((end, _)) => end + 1,
+                    None => {
+                        violations.push(VerifyError::WrongWord { index });
+                        return explanation;
+                    }
+                }
+            }
+        };
+        explanation.spans.push(start..end);
+        start = end;
+    }
+    if start < digits.len() {
+        violations.push(VerifyError::Incomplete { encoded: start });
+    }
+    if violations.is_empty() {
+        if let Err(rejection) = filter.check(digits, parts) {
+            violations.push(VerifyError::Rejected(rejection));
+        }
+    }
+    explanation
+}
+
+/// Whether any word of the dictionary encodes the digits from `start`.
+fn word_fits<D: Lookup + ?Sized>(digits: &[char], start: usize, dict: &D) -> bool {
+    let mut n = BigUint::from(1u32);
+    (start..digits.len()).map_while(|i| nth_digit(digits, i)).any(|digit| {
+        n = core::mem::take(&mut n) * 10u32 + digit;
+        dict.words(&n).is_some()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::collections::BTreeMap;
+
+    use super::*;
+    use crate::filter::{AcceptAll, Rules};
+    use crate::mapping::Mapping;
+    use crate::search::{words_from_bytes, Word};
+
+    use WordOrDigit::{Digit, Word as W};
+
+    fn dict() -> BTreeMap<BigUint, Vec<Word>> {
+        words_from_bytes(b"mir\nMix\nTor\nTorf\nfort\nOrt\n", &Mapping::default())
+    }
+
+    fn verify(digits: &str, parts: &[WordOrDigit]) -> Result<(), VerifyError> {
+        let digits: Vec<char> = digits.chars().collect();
+        verify_solution(&digits, parts, &dict(), &AcceptAll)
+    }
+
+    #[test]
+    fn accepts_a_solution() {
+        assert_eq!(verify("562482", &[W("mir"), W("Tor")]), Ok(()));
+        assert_eq!(verify("4824", &[W("Tor"), Digit('4')]), Ok(()));
+    }
+
+    #[test]
+    fn rejects_a_wrong_digit() {
+        assert_eq!(verify("4824", &[W("Tor"), Digit('5')]), Err(VerifyError::WrongDigit { index: 1, expected: '4' }));
+    }
+
+    #[test]
+    fn rejects_a_wrong_word() {
+        assert_eq!(verify("562482", &[W("Tor"), W("mir")]), Err(VerifyError::WrongWord { index: 0 }));
+        // a word of the dictionary, but not of these digits
+        assert_eq!(verify("562482", &[W("mir"), W("Ort")]), Err(VerifyError::WrongWord { index: 1 }));
+    }
+
+    #[test]
+    fn rejects_a_digit_where_a_word_fits() {
+        assert_eq!(verify("562", &[Digit('5'), Digit('6'), Digit('2')]), Err(VerifyError::DigitWhereWordFits { index: 0 }));
+    }
+
+    #[test]
+    fn rejects_consecutive_digits() {
+        assert_eq!(verify("66", &[Digit('6'), Digit('6')]), Err(VerifyError::ConsecutiveDigits { index: 1 }));
+    }
+
+    #[test]
+    fn rejects_parts_past_the_end() {
+        assert_eq!(verify("482", &[W("Tor"), Digit('4')]), Err(VerifyError::TooLong { index: 1 }));
+    }
+
+    #[test]
+    fn rejects_parts_short_of_the_end() {
+        assert_eq!(verify("4824", &[W("Tor")]), Err(VerifyError::Incomplete { encoded: 3 }));
+        assert_eq!(verify("4824", &[]), Err(VerifyError::Incomplete { encoded: 0 }));
+    }
+
+    #[test]
+    fn rejects_what_the_filter_rejects() {
+        let digits: Vec<char> = "5624824562482".chars().collect();
+        let parts = [W("mir"), W("Torf"), W("mir"), W("Tor")];
+        assert_eq!(verify_solution(&digits, &parts, &dict(), &AcceptAll), Ok(()));
+        let rules = Rules { same_word_lengths: true, ..Rules::default() };
+        assert_eq!(verify_solution(&digits, &parts, &dict(), &rules),
+                   Err(VerifyError::Rejected(Rejection::MixedWordLengths)));
+    }
+
+    #[test]
+    fn rejects_an_invalid_number() {
+        assert_eq!(verify("56a", &[W("mir")]), Err(VerifyError::InvalidNumber(InvalidNumber { char: 'a' })));
+    }
+
+    fn explained(digits: &str, parts: &[WordOrDigit]) -> Explanation {
+        let digits: Vec<char> = digits.chars().collect();
+        explain(&digits, parts, &dict(), &AcceptAll)
+    }
+
+    #[test]
+    fn explains_a_solution() {
+        let explanation = explained("4824", &[W("Tor"), Digit('4')]);
+        assert!(explanation.is_solution());
+        assert_eq!(explanation.spans, [0..3, 3..4]);
+        assert_eq!(explanation.to_string(), "a solution");
+    }
+
+    #[test]
+    fn explains_every_rule_broken_with_its_part() {
+        // Tor fits where the 4 is, and Ort where the 9 is, which should be an 8
+        let explanation = explained("4824", &[Digit('4'), Digit('9')]);
+        assert_eq!(explanation.violations, [
+            VerifyError::DigitWhereWordFits { index: 0 },
+            VerifyError::WrongDigit { index: 1, expected: '8' },
+            VerifyError::ConsecutiveDigits { index: 1 },
+            VerifyError::DigitWhereWordFits { index: 1 },
+            VerifyError::Incomplete { encoded: 2 },
+        ]);
+        let parts: Vec<_> = explanation.violations.iter().map(VerifyError::part).collect();
+        assert_eq!(parts, [Some(0), Some(1), Some(1), Some(1), None]);
+        assert_eq!(explanation.spans, [0..1, 1..2]);
+        assert_eq!(explanation.clone().into_result(), Err(VerifyError::DigitWhereWordFits { index: 0 }));
+    }
+
+    #[test]
+    fn stops_explaining_after_a_wrong_word() {
+        // what follows mir is not checked, as the digits it stands for are unknown
+        let explanation = explained("4824562", &[Digit('5'), W("mir"), Digit('9'), Digit('9')]);
+        assert_eq!(explanation.violations, [
+            VerifyError::WrongDigit { index: 0, expected: '4' },
+            VerifyError::DigitWhereWordFits { index: 0 },
+            VerifyError::WrongWord { index: 1 },
+        ]);
+        assert_eq!(explanation.spans, alloc::vec![0..1]);
+    }
+
+    #[test]
+    fn asks_the_filter_only_without_other_violations() {
+        let digits: Vec<char> = "4824".chars().collect();
+        let rules = Rules { no_consecutive_digits: true, ..Rules::default() };
+        let explanation = explain(&digits, &[W("Tor"), Digit('4'), Digit('5')], &dict(), &rules);
+        assert_eq!(explanation.violations, [VerifyError::TooLong { index: 2 }]);
+    }
+}
@@ -0,0 +1,47 @@
+//! Runs the encoder built for WASI (`wasm32-wasip1`) under wasmtime, checking that it finds
+//! the solutions of the original problem, as the encoder built for the host does:
+//!
+//! ```text
+//! rustup target add wasm32-wasip1
+//! cargo test --test wasi -- --ignored
+//! ```
+//!
+//! with `wasmtime` on the `PATH`. The encoder is built with the command line and the
+//! compressed files, to run more of the crate than the default build.
+
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+#[test]
+#[ignore = "needs wasmtime and the wasm32-wasip1 target"]
+fn encodes_the_numbers_under_wasmtime() {
+    let manifest_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+    // a target directory of its own, so as not to wait for the lock of the one running the tests
+    let target_dir = manifest_dir.join("target/wasi");
+    let status = Command::new(env!("CARGO"))
+        .args(["build", "--target", "wasm32-wasip1", "--bin", "phone_encoder", "--features", "cli,compress"])
+        .arg("--target-dir").arg(&target_dir)
+        .current_dir(manifest_dir)
+        .status()
+        .expect("cannot run cargo");
+    assert!(status.success(), "cannot build for wasm32-wasip1");
+
+    // the program only sees the root of the repository, as its root directory
+    let root = manifest_dir.join("../../..").canonicalize().unwrap();
+    let output = Command::new("wasmtime")
+        .arg("run")
+        .arg("--dir").arg(format!("{}::/", root.display()))
+        .arg(target_dir.join("wasm32-wasip1/debug/phone_encoder.wasm"))
+        .args(["/dictionary.txt", "/input.txt"])
+        .output()
+        .expect("cannot run wasmtime");
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+    // the solutions of a number may be printed in any order
+    let mut solutions: Vec<String> = String::from_utf8(output.stdout).unwrap().lines().map(str::to_string).collect();
+    let mut expected: Vec<String> = fs::read_to_string(root.join("output.txt")).unwrap().lines().map(str::to_string).collect();
+    solutions.sort();
+    expected.sort();
+    assert_eq!(solutions, expected);
+}
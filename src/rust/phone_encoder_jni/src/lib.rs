@@ -0,0 +1,127 @@
+//! JNI bindings for the Rust phone encoder, so that the Java harness
+//! (see `src/java/RustPhoneEncoder.java`) can call it directly.
+//!
+//! Dictionaries are kept in the [`DictionaryRegistry::global`] registry, so that the threads
+//! of the JVM loading the same words file share a single copy of it.
+
+use std::sync::Mutex;
+
+use jni::objects::{JClass, JObject, JString};
+use jni::sys::{jlong, jobjectArray};
+use jni::JNIEnv;
+use phone_encoder::registry::{DictionaryId, DictionaryRegistry};
+
+/// The dictionary loaded by `loadDictionary`, used by `encode`.
+static DEFAULT: Mutex<Option<DictionaryId>> = Mutex::new(None);
+
+/// `static native void loadDictionary(String path)`: loads the dictionary used by `encode`.
+#[no_mangle]
+pub extern "system" fn Java_RustPhoneEncoder_loadDictionary(mut env: JNIEnv, _class: JClass, path: JString) {
+    if let Err(message) = load_dictionary(&mut env, &path) {
+        throw(&mut env, "java/lang/IllegalArgumentException", message);
+    }
+}
+
+/// `static native String[] encode(String number)`: returns all encodings of the phone number,
+/// each as the words (or digits) separated by spaces.
+#[no_mangle]
+pub extern "system" fn Java_RustPhoneEncoder_encode<'local>(
+    mut env: JNIEnv<'local>,
+    _class: JClass<'local>,
+    number: JString<'local>,
+) -> jobjectArray {
+    match encode(&mut env, None, &number) {
+        Ok(solutions) => solutions,
+        Err(message) => {
+            throw(&mut env, "java/lang/IllegalStateException", message);
+            std::ptr::null_mut()
+        }
+    }
+}
+
+/// `static native long acquireDictionary(String path)`: loads a dictionary, or shares the one
+/// loaded already from a file with the same contents, returning its handle for `encodeWith`.
+#[no_mangle]
+pub extern "system" fn Java_RustPhoneEncoder_acquireDictionary(mut env: JNIEnv, _class: JClass, path: JString) -> jlong {
+    match acquire(&mut env, &path) {
+        Ok(id) => id.as_u64() as jlong,
+        Err(message) => {
+            throw(&mut env, "java/lang/IllegalArgumentException", message);
+            0
+        }
+    }
+}
+
+/// `static native void releaseDictionary(long dictionary)`: releases a dictionary acquired with
+/// `acquireDictionary`, which is unloaded once released as many times as it was acquired.
+#[no_mangle]
+pub extern "system" fn Java_RustPhoneEncoder_releaseDictionary(mut env: JNIEnv, _class: JClass, dictionary: jlong) {
+    if !DictionaryRegistry::global().unload(DictionaryId::from_u64(dictionary as u64)) {
+        throw(&mut env, "java/lang/IllegalArgumentException", format!("no dictionary {}", dictionary));
+    }
+}
+
+/// `static native String[] encodeWith(long dictionary, String number)`: like `encode`, with a
+/// dictionary acquired with `acquireDictionary`.
+#[no_mangle]
+pub extern "system" fn Java_RustPhoneEncoder_encodeWith<'local>(
+    mut env: JNIEnv<'local>,
+    _class: JClass<'local>,
+    dictionary: jlong,
+    number: JString<'local>,
+) -> jobjectArray {
+    match encode(&mut env, Some(dictionary), &number) {
+        Ok(solutions) => solutions,
+        Err(message) => {
+            throw(&mut env, "java/lang/IllegalStateException", message);
+            std::ptr::null_mut()
+        }
+    }
+}
+
+fn acquire(env: &mut JNIEnv, path: &JString) -> Result<DictionaryId, String> {
+    let path: String = env.get_string(path).map_err(|e| e.to_string())?.into();
+    DictionaryRegistry::global().load(&path)
+        .map_err(|e| format!("cannot load dictionary {}: {}", path, e))
+}
+
+fn load_dictionary(env: &mut JNIEnv, path: &JString) -> Result<(), String> {
+    let id = acquire(env, path)?;
+    let previous = DEFAULT.lock().unwrap_or_else(|e| e.into_inner()).replace(id);
+    if let Some(previous) = previous {
+        DictionaryRegistry::global().unload(previous);
+    }
+    Ok(())
+}
+
+/// Encodes a number with the given dictionary, or else the one loaded by `loadDictionary`.
+fn encode(env: &mut JNIEnv, dictionary: Option<jlong>, number: &JString) -> Result<jobjectArray, String> {
+    let number: String = env.get_string(number).map_err(|e| e.to_string())?.into();
+    let encoder = match dictionary {
+        Some(dictionary) => DictionaryRegistry::global().get(DictionaryId::from_u64(dictionary as u64))
+            .ok_or_else(|| format!("no dictionary {}", dictionary))?,
+        None => DEFAULT.lock().unwrap_or_else(|e| e.into_inner())
+            .and_then(|id| DictionaryRegistry::global().get(id))
+            .ok_or("no dictionary loaded, call loadDictionary first")?,
+    };
+    let mut solutions = Vec::new();
+    encoder.for_each_solution(&number, &mut |parts| {
+        solutions.push(parts.iter().map(ToString::to_string).collect::<Vec<_>>().join(" "));
+        Ok(())
+    }).map_err(|e| e.to_string())?;
+
+    let array = env.new_object_array(solutions.len() as i32, "java/lang/String", JObject::null())
+        .map_err(|e| e.to_string())?;
+    for (i, solution) in solutions.iter().enumerate() {
+        let solution = env.new_string(solution).map_err(|e| e.to_string())?;
+        env.set_object_array_element(&array, i as i32, solution).map_err(|e| e.to_string())?;
+    }
+    Ok(array.into_raw())
+}
+
+/// Throws a Java exception, unless one is already pending (e.g. thrown by a failed JNI call).
+fn throw(env: &mut JNIEnv, class: &str, message: String) {
+    if !env.exception_check().unwrap_or(false) {
+        let _ = env.throw_new(class, message);
+    }
+}
@@ -0,0 +1,32 @@
+//! Node.js bindings for the Rust phone encoder, so that JavaScript tools can call it
+//! natively instead of spawning a process for each run.
+
+use std::sync::{Arc, RwLock};
+
+use napi::{Error, Result};
+use napi_derive::napi;
+use phone_encoder::{DictOptions, Encoder};
+
+static ENCODER: RwLock<Option<Arc<Encoder>>> = RwLock::new(None);
+
+/// Loads the dictionary used by `encode` from the given words file.
+#[napi(js_name = "loadDictionary")]
+pub fn load_dictionary(path: String) -> Result<()> {
+    let encoder = Encoder::load(&path, &DictOptions::default())
+        .map_err(|e| Error::from_reason(format!("cannot load dictionary {}: {}", path, e)))?;
+    *ENCODER.write().unwrap_or_else(|e| e.into_inner()) = Some(Arc::new(encoder));
+    Ok(())
+}
+
+/// Returns all encodings of the phone number, each as the words (or digits) separated by spaces.
+#[napi]
+pub fn encode(number: String) -> Result<Vec<String>> {
+    let encoder = ENCODER.read().unwrap_or_else(|e| e.into_inner()).clone()
+        .ok_or_else(|| Error::from_reason("no dictionary loaded, call loadDictionary first"))?;
+    let mut solutions = Vec::new();
+    encoder.for_each_solution(&number, &mut |parts| {
+        solutions.push(parts.iter().map(ToString::to_string).collect::<Vec<_>>().join(" "));
+        Ok(())
+    }).map_err(|e| Error::from_reason(e.to_string()))?;
+    Ok(solutions)
+}